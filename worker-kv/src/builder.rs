@@ -1,10 +1,21 @@
+use std::time::Duration;
+
 use js_sys::{ArrayBuffer, Function, Object, Promise, Uint8Array};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
+use web_sys::ReadableStream;
+
+use crate::{consistency, record_operation, KvError, ListResponse};
 
-use crate::{KvError, ListResponse};
+/// The shortest expiration KV supports, in seconds. Keys must either have a TTL of at least this
+/// long or expire at least this far in the future.
+pub const MIN_EXPIRATION_TTL: u64 = 60;
+
+/// The shortest `cacheTtl` KV supports, in seconds, which gives the freshest reads it can: KV
+/// always caches the values it reads for at least this long.
+pub const MIN_CACHE_TTL: u64 = 60;
 
 /// A builder to configure put requests.
 #[derive(Debug, Clone, Serialize)]
@@ -39,6 +50,11 @@ impl PutOptionsBuilder {
         self.expiration_ttl = Some(expiration_ttl);
         self
     }
+    /// How long until the key value pair will expire. KV only has a granularity of seconds, any
+    /// fractional part of `ttl` is dropped.
+    pub fn ttl(self, ttl: Duration) -> Self {
+        self.expiration_ttl(ttl.as_secs())
+    }
     /// Metadata to be stored with the key value pair.
     pub fn metadata<T: Serialize>(mut self, metadata: T) -> Result<Self, KvError> {
         self.metadata = Some(serde_json::to_value(metadata)?);
@@ -46,15 +62,41 @@ impl PutOptionsBuilder {
     }
     /// Puts the value in the kv store.
     pub async fn execute(self) -> Result<(), KvError> {
-        let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
+        self.validate()?;
+        // The metadata is JSON, and KV expects its objects to be plain objects rather than `Map`s.
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        let options_object = self.serialize(&serializer).map_err(JsValue::from)?;
+        record_operation();
         let promise: Promise = self
             .put_function
             .call3(&self.this, &self.name, &self.value, &options_object)?
             .into();
-        JsFuture::from(promise)
-            .await
-            .map(|_| ())
-            .map_err(KvError::from)
+        JsFuture::from(promise).await?;
+        consistency::record_write(&self.this, &self.name, None);
+        Ok(())
+    }
+}
+
+impl PutOptionsBuilder {
+    /// Check the expiration options against the limits of KV, which would otherwise only be
+    /// reported as a generic error by the runtime.
+    fn validate(&self) -> Result<(), KvError> {
+        if let Some(ttl) = self.expiration_ttl {
+            if ttl < MIN_EXPIRATION_TTL {
+                return Err(KvError::InvalidExpiration(format!(
+                    "expiration TTL must be at least {MIN_EXPIRATION_TTL} seconds, got {ttl}"
+                )));
+            }
+        }
+        if let Some(expiration) = self.expiration {
+            let now = (js_sys::Date::now() / 1000.0) as u64;
+            if expiration < now + MIN_EXPIRATION_TTL {
+                return Err(KvError::InvalidExpiration(format!(
+                    "expiration must be at least {MIN_EXPIRATION_TTL} seconds in the future, got {expiration} (now: {now})"
+                )));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -94,6 +136,7 @@ impl ListOptionsBuilder {
     /// Lists the key value pairs in the kv store.
     pub async fn execute(self) -> Result<ListResponse, KvError> {
         let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
+        record_operation();
         let promise: Promise = self
             .list_function
             .call1(&self.this, &options_object)?
@@ -121,6 +164,8 @@ pub struct GetOptionsBuilder {
     pub(crate) cache_ttl: Option<u64>,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub(crate) value_type: Option<GetValueType>,
+    #[serde(skip)]
+    pub(crate) check_consistency: bool,
 }
 
 impl GetOptionsBuilder {
@@ -136,13 +181,35 @@ impl GetOptionsBuilder {
         self
     }
 
+    /// Don't warn that the read may be stale when it follows a write of this isolate, e.g. when
+    /// reading a key back is expected to race with writes made elsewhere anyway.
+    pub fn skip_consistency_check(mut self) -> Self {
+        self.check_consistency = false;
+        self
+    }
+
     fn value_type(mut self, value_type: GetValueType) -> Self {
         self.value_type = Some(value_type);
         self
     }
 
+    /// Gets the value with the given type, as the JavaScript value returned by KV. Returns `None`
+    /// if there is no value for the key.
+    pub async fn raw(self, value_type: GetValueType) -> Result<Option<JsValue>, KvError> {
+        let value = self.value_type(value_type).get().await?;
+        Ok(if value.is_null() || value.is_undefined() {
+            None
+        } else {
+            Some(value)
+        })
+    }
+
     async fn get(self) -> Result<JsValue, KvError> {
         let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
+        if self.check_consistency {
+            consistency::check_read(&self.this, &self.name);
+        }
+        record_operation();
         let promise: Promise = self
             .get_function
             .call2(&self.this, &self.name, &options_object)?
@@ -181,11 +248,22 @@ impl GetOptionsBuilder {
         }
     }
 
+    /// Gets the value as a stream of bytes, without buffering it in memory. This is useful for
+    /// passing large values on to a response.
+    pub async fn stream(self) -> Result<Option<ReadableStream>, KvError> {
+        let value = self.raw(GetValueType::Stream).await?;
+        Ok(value.map(ReadableStream::unchecked_from_js))
+    }
+
     async fn get_with_metadata<M>(&self) -> Result<(JsValue, Option<M>), KvError>
     where
         M: DeserializeOwned,
     {
         let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
+        if self.check_consistency {
+            consistency::check_read(&self.this, &self.name);
+        }
+        record_operation();
         let promise: Promise = self
             .get_with_meta_function
             .call2(&self.this, &self.name, &options_object)?
@@ -257,10 +335,16 @@ impl GetOptionsBuilder {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// The type a value is read as, see [`GetOptionsBuilder::raw`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) enum GetValueType {
+pub enum GetValueType {
+    /// A string, decoded as UTF-8.
     Text,
+    /// An `ArrayBuffer` with the value as is.
     ArrayBuffer,
+    /// The value parsed as JSON.
     Json,
+    /// A `ReadableStream` of the value.
+    Stream,
 }
@@ -0,0 +1,195 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use js_sys::{global, Function, Object, Reflect};
+use serde::de::DeserializeOwned;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{KvError, KvStore, ToRawKvValue};
+
+/// How long a write may take to be visible to reads, in seconds. Writes are usually visible
+/// immediately in the location they were made in, and within 60 seconds everywhere else, but reads
+/// of a cached value only see writes once it expires.
+pub const CONSISTENCY_WINDOW: u64 = 60;
+
+/// A write made by this isolate, kept for the [`CONSISTENCY_WINDOW`].
+struct RecentWrite {
+    namespace: Object,
+    at: f64,
+    /// The value written through [`ReadYourWrites`], `Some(None)` if it deleted the key. It isn't
+    /// known for writes made through a plain [`KvStore`].
+    value: Option<Option<JsValue>>,
+    warned: bool,
+}
+
+thread_local! {
+    static RECENT_WRITES: RefCell<HashMap<String, Vec<RecentWrite>>> = RefCell::new(HashMap::new());
+}
+
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Forget the writes which are older than the consistency window.
+fn prune(writes: &mut HashMap<String, Vec<RecentWrite>>, now: f64) {
+    let oldest = now - CONSISTENCY_WINDOW as f64 * 1000.0;
+    writes.retain(|_, writes| {
+        writes.retain(|write| write.at > oldest);
+        !writes.is_empty()
+    });
+}
+
+pub(crate) fn record_write(namespace: &Object, name: &JsValue, value: Option<Option<JsValue>>) {
+    let name = match name.as_string() {
+        Some(name) => name,
+        None => return,
+    };
+    let now = now();
+    RECENT_WRITES.with(|writes| {
+        let mut writes = writes.borrow_mut();
+        prune(&mut writes, now);
+        let writes = writes.entry(name).or_default();
+        writes.retain(|write| !Object::is(&write.namespace, namespace));
+        writes.push(RecentWrite {
+            namespace: namespace.clone(),
+            at: now,
+            value,
+            warned: false,
+        });
+    });
+}
+
+/// Warn, once per write, that reading a key this isolate just wrote may not return the value it
+/// wrote.
+pub(crate) fn check_read(namespace: &Object, name: &JsValue) {
+    let name = match name.as_string() {
+        Some(name) => name,
+        None => return,
+    };
+    let now = now();
+    let warning = RECENT_WRITES.with(|writes| {
+        let mut writes = writes.borrow_mut();
+        prune(&mut writes, now);
+        let write = writes
+            .get_mut(&name)?
+            .iter_mut()
+            .find(|write| Object::is(&write.namespace, namespace) && !write.warned)?;
+        write.warned = true;
+        Some(format!(
+            "KV: reading \"{name}\" {:.0}s after writing it in this isolate may return a stale \
+             value, as KV is eventually consistent. Use `ReadYourWrites` to see this isolate's \
+             own writes.",
+            (now - write.at) / 1000.0
+        ))
+    });
+    if let Some(warning) = warning {
+        warn(&warning);
+    }
+}
+
+fn warn(message: &str) {
+    let warn = Reflect::get(&global(), &"console".into())
+        .and_then(|console| Ok((Reflect::get(&console, &"warn".into())?, console)));
+    if let Ok((warn, console)) = warn {
+        if let Some(warn) = warn.dyn_ref::<Function>() {
+            let _ = warn.call1(&console, &message.into());
+        }
+    }
+}
+
+/// The value this isolate wrote to `name` through [`ReadYourWrites`] within the consistency
+/// window: `Some(None)` if it deleted it.
+fn recent_value(namespace: &Object, name: &str) -> Option<Option<JsValue>> {
+    let now = now();
+    RECENT_WRITES.with(|writes| {
+        let mut writes = writes.borrow_mut();
+        prune(&mut writes, now);
+        writes
+            .get(name)?
+            .iter()
+            .find(|write| Object::is(&write.namespace, namespace))?
+            .value
+            .clone()
+    })
+}
+
+/// A [`KvStore`] whose reads return the values written through it by the same isolate during
+/// the last [`CONSISTENCY_WINDOW`], which KV may not return yet.
+///
+/// Other isolates, and other locations, still see writes with KV's delay: this only makes an
+/// isolate consistent with itself, e.g. so that a request reading a setting sees the change the
+/// previous request made.
+///
+/// ```ignore
+/// let kv = ReadYourWrites::new(env.kv("SETTINGS")?);
+/// kv.put("theme", "dark").await?;
+/// assert_eq!(kv.get_text("theme").await?.as_deref(), Some("dark"));
+/// ```
+#[derive(Clone)]
+pub struct ReadYourWrites {
+    kv: KvStore,
+}
+
+impl ReadYourWrites {
+    pub fn new(kv: KvStore) -> Self {
+        Self { kv }
+    }
+
+    pub fn inner(&self) -> &KvStore {
+        &self.kv
+    }
+
+    /// Write `value` to `name`, with the default options.
+    pub async fn put<T: ToRawKvValue>(&self, name: &str, value: T) -> Result<(), KvError> {
+        let put = self.kv.put(name, value)?;
+        let value = put.value.clone();
+        put.execute().await?;
+        record_write(&self.kv.this, &JsValue::from(name), Some(Some(value)));
+        Ok(())
+    }
+
+    /// Write `value` to `name` as bytes, with the default options.
+    pub async fn put_bytes(&self, name: &str, value: &[u8]) -> Result<(), KvError> {
+        let put = self.kv.put_bytes(name, value)?;
+        let value = put.value.clone();
+        put.execute().await?;
+        record_write(&self.kv.this, &JsValue::from(name), Some(Some(value)));
+        Ok(())
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<(), KvError> {
+        self.kv.delete(name).await?;
+        record_write(&self.kv.this, &JsValue::from(name), Some(None));
+        Ok(())
+    }
+
+    pub async fn get_text(&self, name: &str) -> Result<Option<String>, KvError> {
+        match recent_value(&self.kv.this, name) {
+            Some(value) => Ok(value.map(|value| match value.as_string() {
+                Some(text) => text,
+                None => String::from_utf8_lossy(&array_buffer_bytes(&value)).into_owned(),
+            })),
+            None => self.kv.get_fresh(name).text().await,
+        }
+    }
+
+    pub async fn get_bytes(&self, name: &str) -> Result<Option<Vec<u8>>, KvError> {
+        match recent_value(&self.kv.this, name) {
+            Some(value) => Ok(value.map(|value| match value.as_string() {
+                Some(text) => text.into_bytes(),
+                None => array_buffer_bytes(&value),
+            })),
+            None => self.kv.get_fresh(name).bytes().await,
+        }
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, KvError> {
+        match self.get_text(name).await? {
+            Some(text) => Ok(Some(serde_json::from_str(&text)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn array_buffer_bytes(value: &JsValue) -> Vec<u8> {
+    js_sys::Uint8Array::new(value).to_vec()
+}
@@ -11,14 +11,21 @@
 //!     .execute()
 //!     .await?;
 //!
-//! // NOTE: kv changes can take a minute to become visible to other workers.
+//! // NOTE: kv changes can take a minute to become visible to other workers, and reading a key
+//! // shortly after writing it logs a warning; see `ReadYourWrites`.
 //! // Get that same metadata.
 //! let (value, metadata) = kv.get("example_key").text_with_metadata::<Vec<usize>>().await?;
 //! ```
 #[forbid(missing_docs)]
 mod builder;
+mod consistency;
+mod transfer;
 
 pub use builder::*;
+pub use consistency::{ReadYourWrites, CONSISTENCY_WINDOW};
+pub use transfer::{export, import, ExportedEntry, ImportOptions, ImportReport};
+
+use std::cell::Cell;
 
 use js_sys::{global, Function, Object, Promise, Reflect, Uint8Array};
 use serde::{Deserialize, Serialize};
@@ -26,6 +33,20 @@ use serde_json::Value;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 
+thread_local! {
+    static OPERATIONS: Cell<u64> = Cell::new(0);
+}
+
+/// The number of KV operations (gets, puts, lists and deletes) started by this isolate, which is
+/// used to count the KV operations made while handling a request.
+pub fn operation_count() -> u64 {
+    OPERATIONS.with(Cell::get)
+}
+
+pub(crate) fn record_operation() {
+    OPERATIONS.with(|ops| ops.set(ops.get() + 1));
+}
+
 /// A binding to a Cloudflare KvStore.
 #[derive(Clone)]
 pub struct KvStore {
@@ -90,9 +111,38 @@ impl KvStore {
             name: JsValue::from(name),
             cache_ttl: None,
             value_type: None,
+            check_consistency: true,
         }
     }
 
+    /// Fetches the value with the shortest cache TTL, [`MIN_CACHE_TTL`]. KV can't skip its caches:
+    /// a value read in the last 60 seconds may still be returned, and writes made in other
+    /// locations may take 60 seconds or more to be visible. Use [`ReadYourWrites`] to see the
+    /// writes of this isolate right away.
+    pub fn get_fresh(&self, name: &str) -> GetOptionsBuilder {
+        self.get(name).cache_ttl(MIN_CACHE_TTL)
+    }
+
+    /// Fetches the value as bytes, without decoding it as text. Shorthand for
+    /// `get(name).bytes()`.
+    pub async fn get_bytes(&self, name: &str) -> Result<Option<Vec<u8>>, KvError> {
+        self.get(name).bytes().await
+    }
+
+    /// Fetches the value as a stream of bytes, e.g. to pass a large value on to a response.
+    /// Shorthand for `get(name).stream()`.
+    pub async fn get_stream(&self, name: &str) -> Result<Option<web_sys::ReadableStream>, KvError> {
+        self.get(name).stream().await
+    }
+
+    /// Fetches the value and deserializes it from JSON. Shorthand for `get(name).json()`.
+    pub async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, KvError> {
+        self.get(name).json().await
+    }
+
     /// Puts data into the kv store.
     pub fn put<T: ToRawKvValue>(&self, name: &str, value: T) -> Result<PutOptionsBuilder, KvError> {
         Ok(PutOptionsBuilder {
@@ -136,8 +186,10 @@ impl KvStore {
     /// Deletes a key in the kv store.
     pub async fn delete(&self, name: &str) -> Result<(), KvError> {
         let name = JsValue::from(name);
+        record_operation();
         let promise: Promise = self.delete_function.call1(&self.this, &name)?.into();
         JsFuture::from(promise).await?;
+        consistency::record_write(&self.this, &name, None);
         Ok(())
     }
 }
@@ -165,8 +217,17 @@ pub struct Key {
     pub metadata: Option<Value>,
 }
 
+impl Key {
+    /// When the key value pair will expire in the store, if it has an expiration.
+    pub fn expiration_date(&self) -> Option<js_sys::Date> {
+        self.expiration
+            .map(|secs| js_sys::Date::new(&JsValue::from_f64(secs as f64 * 1000.0)))
+    }
+}
+
 /// A simple error type that can occur during kv operations.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum KvError {
     #[error("js error: {0:?}")]
     JavaScript(JsValue),
@@ -174,6 +235,10 @@ pub enum KvError {
     Serialization(serde_json::Error),
     #[error("invalid kv store: {0}")]
     InvalidKvStore(String),
+    #[error("invalid expiration: {0}")]
+    InvalidExpiration(String),
+    #[error("invalid import: {0}")]
+    InvalidImport(String),
 }
 
 impl From<KvError> for JsValue {
@@ -184,6 +249,8 @@ impl From<KvError> for JsValue {
             KvError::InvalidKvStore(binding) => {
                 format!("KvError::InvalidKvStore: {binding}").into()
             }
+            KvError::InvalidExpiration(e) => format!("KvError::InvalidExpiration: {e}").into(),
+            KvError::InvalidImport(e) => format!("KvError::InvalidImport: {e}").into(),
         }
     }
 }
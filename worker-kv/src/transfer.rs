@@ -0,0 +1,251 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::{pin_mut, stream, Stream, StreamExt};
+use js_sys::{Function, Promise, Reflect};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{Key, KvError, KvStore, MIN_EXPIRATION_TTL};
+
+/// A key value pair as a line of the NDJSON written by [`export`] and read by [`import`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEntry {
+    /// The name of the key.
+    pub key: String,
+    /// The value, encoded as base64 so that any value can be exported.
+    pub value: String,
+    /// The metadata of the key, if it has any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// When the key expires, as a unix timestamp in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+}
+
+impl ExportedEntry {
+    /// The decoded value.
+    pub fn bytes(&self) -> Result<Vec<u8>, KvError> {
+        STANDARD.decode(&self.value).map_err(|e| {
+            KvError::InvalidImport(format!("the value of {:?} is not base64: {e}", self.key))
+        })
+    }
+}
+
+/// Stream every key starting with `prefix` in `store`, with its value, metadata and expiration,
+/// as NDJSON lines of [`ExportedEntry`], e.g. to back up a namespace to R2 or copy it to another
+/// one with [`import`].
+///
+/// The keys are listed a page at a time and their values read one at a time, so the export
+/// doesn't need to fit in memory. Keys which are deleted while the export runs are left out.
+///
+/// ```ignore
+/// let backup = worker_kv::export(&kv, "users:").map(|line| line.map_err(Error::from));
+/// bucket.put("backup.ndjson", Data::Stream(Box::pin(backup))).execute().await?;
+/// ```
+pub fn export(store: &KvStore, prefix: &str) -> impl Stream<Item = Result<Vec<u8>, KvError>> {
+    struct State {
+        store: KvStore,
+        prefix: String,
+        keys: VecDeque<Key>,
+        cursor: Option<String>,
+        complete: bool,
+    }
+
+    let state = State {
+        store: store.clone(),
+        prefix: prefix.into(),
+        keys: VecDeque::new(),
+        cursor: None,
+        complete: false,
+    };
+    stream::try_unfold(state, |mut state| async move {
+        loop {
+            if let Some(key) = state.keys.pop_front() {
+                let Some(value) = state.store.get_bytes(&key.name).await? else {
+                    continue;
+                };
+                let entry = ExportedEntry {
+                    key: key.name,
+                    value: STANDARD.encode(value),
+                    metadata: key.metadata,
+                    expiration: key.expiration,
+                };
+                let mut line = serde_json::to_vec(&entry)?;
+                line.push(b'\n');
+                return Ok(Some((line, state)));
+            }
+            if state.complete {
+                return Ok::<_, KvError>(None);
+            }
+            let mut list = state.store.list().prefix(state.prefix.clone());
+            if let Some(cursor) = state.cursor.take() {
+                list = list.cursor(cursor);
+            }
+            let page = list.execute().await?;
+            state.keys.extend(page.keys);
+            state.complete = page.list_complete || page.cursor.is_none();
+            state.cursor = page.cursor;
+        }
+    })
+}
+
+/// Options of [`import`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportOptions {
+    writes_per_second: u32,
+}
+
+impl ImportOptions {
+    /// Write up to 100 keys a second.
+    pub fn new() -> Self {
+        Self {
+            writes_per_second: 100,
+        }
+    }
+
+    /// Write up to `writes` keys a second, to stay within the write limits of the namespace.
+    pub fn writes_per_second(mut self, writes: u32) -> Self {
+        self.writes_per_second = writes.max(1);
+        self
+    }
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What [`import`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The number of keys written.
+    pub imported: usize,
+    /// The number of keys which expired since they were exported, and weren't written.
+    pub skipped: usize,
+}
+
+/// Write the keys of NDJSON `lines` written by [`export`] to `store`, with their metadata and
+/// expirations. `lines` can be chunked anyhow, e.g. the body of a request or an R2 object.
+///
+/// The writes are spread out according to [`ImportOptions::writes_per_second`]. Keys which expire
+/// within a minute are given a minute, the shortest expiration KV supports.
+///
+/// ```ignore
+/// let backup = bucket.get("backup.ndjson").execute().await?.unwrap();
+/// let lines = backup.body().unwrap().stream()?;
+/// let report = worker_kv::import(&kv, lines, ImportOptions::new()).await?;
+/// ```
+pub async fn import<S, B, E>(
+    store: &KvStore,
+    lines: S,
+    options: ImportOptions,
+) -> Result<ImportReport, KvError>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    pin_mut!(lines);
+    let interval = 1000.0 / options.writes_per_second as f64;
+    let mut next_write = js_sys::Date::now();
+    let mut report = ImportReport::default();
+    let mut buffer = Vec::new();
+    let mut done = false;
+    while !done {
+        match lines.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(|e| KvError::InvalidImport(e.to_string()))?;
+                buffer.extend_from_slice(chunk.as_ref());
+            }
+            None => {
+                buffer.push(b'\n');
+                done = true;
+            }
+        }
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let Some(entry) = parse_line(&line)? else {
+                continue;
+            };
+            let before = js_sys::Date::now();
+            if next_write > before {
+                sleep(next_write - before).await?;
+            }
+            next_write = next_write.max(before) + interval;
+
+            // The clock is read again after the throttle so that the expiration is still valid
+            // when the put is validated.
+            let expiration = match entry.expiration {
+                Some(expiration) => match clamp_expiration(expiration, js_sys::Date::now()) {
+                    Some(expiration) => Some(expiration),
+                    None => {
+                        report.skipped += 1;
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            let mut put = store.put_bytes(&entry.key, &entry.bytes()?)?;
+            if let Some(expiration) = expiration {
+                put = put.expiration(expiration);
+            }
+            if let Some(metadata) = entry.metadata {
+                put = put.metadata(metadata)?;
+            }
+            put.execute().await?;
+            report.imported += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// The expiration to import a key with at `now` (in milliseconds), or `None` if it has already
+/// expired.
+fn clamp_expiration(expiration: u64, now: f64) -> Option<u64> {
+    if (expiration as f64) * 1000.0 <= now {
+        return None;
+    }
+    Some(expiration.max((now / 1000.0) as u64 + MIN_EXPIRATION_TTL))
+}
+
+/// The entry of a line, or `None` if it's blank.
+fn parse_line(line: &[u8]) -> Result<Option<ExportedEntry>, KvError> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_slice(line)?))
+}
+
+async fn sleep(ms: f64) -> Result<(), KvError> {
+    let set_timeout: Function =
+        Reflect::get(&js_sys::global(), &"setTimeout".into())?.dyn_into()?;
+    let promise = Promise::new(&mut |resolve, _| {
+        let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from_f64(ms));
+    });
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_expiration_after_delay() {
+        let start = 1_700_000_000_000.0;
+        let expiration = 1_700_000_000 + MIN_EXPIRATION_TTL;
+        assert_eq!(clamp_expiration(expiration, start), Some(expiration));
+
+        // After a throttle wait the minimum is measured from the later clock.
+        let later = start + 5_000.0;
+        assert_eq!(clamp_expiration(expiration, later), Some(expiration + 5));
+
+        // Keys which expire during the wait are skipped.
+        assert_eq!(clamp_expiration(1_700_000_003, later), None);
+    }
+}
@@ -0,0 +1,79 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ext::IdentExt, spanned::Spanned, Data, DeriveInput, Error, Fields, LitStr};
+
+pub fn expand_macro(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "`Bindings` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`Bindings` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut loads = Vec::new();
+    let mut idents = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+
+        let mut binding = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("binding")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    binding = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported binding attribute, expected `name`"))
+                }
+            })?;
+        }
+        // Bindings are conventionally SCREAMING_SNAKE_CASE in wrangler.toml.
+        let binding = binding.unwrap_or_else(|| ident.unraw().to_string().to_uppercase());
+
+        loads.push(quote! {
+            let #ident = match <#ty as ::worker::FromEnv>::from_env(env, #binding) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    errors.push(format!("`{}`: {}", #binding, e));
+                    None
+                }
+            };
+        });
+        idents.push(ident);
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::worker::Bindings for #name #ty_generics #where_clause {
+            fn load(env: &::worker::Env) -> ::worker::Result<Self> {
+                #[allow(unused_mut)]
+                let mut errors: Vec<String> = Vec::new();
+                #(#loads)*
+
+                if !errors.is_empty() {
+                    return Err(::worker::Error::RustError(format!(
+                        "failed to load bindings: {}",
+                        errors.join(", ")
+                    )));
+                }
+
+                Ok(Self {
+                    #(#idents: #idents.unwrap(),)*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,170 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ext::IdentExt, spanned::Spanned, Data, DeriveInput, Error, Fields, LitStr};
+
+pub fn expand_macro(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "`D1Entity` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`D1Entity` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut table = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("d1")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported d1 attribute, expected `table`"))
+            }
+        })?;
+    }
+    let table = table.unwrap_or_else(|| snake_case(&name.to_string()));
+
+    let mut columns = Vec::new();
+    let mut primary_key = None;
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("d1")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("primary_key") {
+                    if primary_key.is_some() {
+                        return Err(meta.error("only one field can be the primary key"));
+                    }
+                    primary_key = Some(ident);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported d1 attribute, expected `primary_key`"))
+                }
+            })?;
+        }
+        columns.push(ident);
+    }
+    let primary_key = match primary_key.or_else(|| columns.iter().copied().find(|c| *c == "id")) {
+        Some(primary_key) => primary_key,
+        None => {
+            return Err(Error::new(
+                input.span(),
+                "`D1Entity` needs an `id` field or a field marked with `#[d1(primary_key)]`",
+            ))
+        }
+    };
+    let others: Vec<_> = columns
+        .iter()
+        .copied()
+        .filter(|c| *c != primary_key)
+        .collect();
+
+    // The statements are built here so that they're constants of the implementation.
+    let column_names: Vec<_> = columns.iter().map(|c| c.unraw().to_string()).collect();
+    let column_list = column_names
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let table_name = quote_ident(&table);
+    let primary_key_name = primary_key.unraw().to_string();
+    let key = quote_ident(&primary_key_name);
+    let placeholders = (1..=columns.len())
+        .map(|i| format!("?{i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let assignments = others
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{} = ?{}", quote_ident(&c.unraw().to_string()), i + 1))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let insert = format!("INSERT INTO {table_name} ({column_list}) VALUES ({placeholders})");
+    let select = format!("SELECT {column_list} FROM {table_name} WHERE {key} = ?1");
+    let update = if others.is_empty() {
+        // Nothing but the key to update, which leaves the row as it is.
+        format!("UPDATE {table_name} SET {key} = {key} WHERE {key} = ?1")
+    } else {
+        format!(
+            "UPDATE {table_name} SET {assignments} WHERE {key} = ?{}",
+            others.len() + 1
+        )
+    };
+    let delete = format!("DELETE FROM {table_name} WHERE {key} = ?1");
+
+    Ok(quote! {
+        impl #impl_generics ::worker::d1::D1Entity for #name #ty_generics #where_clause {
+            const TABLE: &'static str = #table;
+            const COLUMNS: &'static [&'static str] = &[#(#column_names),*];
+            const PRIMARY_KEY: &'static str = #primary_key_name;
+            const INSERT: &'static str = #insert;
+            const SELECT_BY_ID: &'static str = #select;
+            const UPDATE: &'static str = #update;
+            const DELETE: &'static str = #delete;
+
+            fn insert_values(&self) -> ::worker::Result<Vec<::worker::wasm_bindgen::JsValue>> {
+                Ok(vec![#(::worker::d1::to_d1_value(&self.#columns)?),*])
+            }
+
+            fn update_values(&self) -> ::worker::Result<Vec<::worker::wasm_bindgen::JsValue>> {
+                Ok(vec![
+                    #(::worker::d1::to_d1_value(&self.#others)?,)*
+                    ::worker::d1::to_d1_value(&self.#primary_key)?
+                ])
+            }
+
+            fn id_value(&self) -> ::worker::Result<::worker::wasm_bindgen::JsValue> {
+                ::worker::d1::to_d1_value(&self.#primary_key)
+            }
+        }
+    })
+}
+
+/// `UserProfile` as `user_profile`. A run of capitals is one word, so `HTTPRequest` is
+/// `http_request`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let after_word = i > 0 && !chars[i - 1].is_uppercase() && chars[i - 1] != '_';
+            let ends_run = i > 0
+                && chars[i - 1].is_uppercase()
+                && chars.get(i + 1).map_or(false, |next| next.is_lowercase());
+            if after_word || ends_run {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// An SQL identifier, quoted so that keywords can be used as table and column names.
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+#[test]
+fn snake_case_works() {
+    assert_eq!(snake_case("UserProfile"), "user_profile");
+    assert_eq!(snake_case("HTTPRequest"), "http_request");
+    assert_eq!(snake_case("UserID"), "user_id");
+    assert_eq!(snake_case("Oauth2Token"), "oauth2_token");
+    assert_eq!(snake_case("user_profile"), "user_profile");
+}
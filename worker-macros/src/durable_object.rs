@@ -1,6 +1,6 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{spanned::Spanned, Error, FnArg, ImplItem, Item, Type, TypePath, Visibility};
+use syn::{spanned::Spanned, Error, FnArg, ImplItem, Item, Signature, Type, TypePath, Visibility};
 
 pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
     let item = syn::parse2::<Item>(tokens)?;
@@ -30,6 +30,7 @@ pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
             }
 
             let mut optional_methods = OptionalMethods::default();
+            let mut typed_constructor = false;
 
             for item in items {
                 let impl_method = match item {
@@ -40,6 +41,26 @@ pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
                 let span = impl_method.sig.ident.span();
 
                 let tokens = match impl_method.sig.ident.to_string().as_str() {
+                    "new" if typed_env(&impl_method.sig).is_some() => {
+                        let env_ty = typed_env(&impl_method.sig);
+                        typed_constructor = true;
+
+                        let mut method = impl_method.clone();
+                        method.sig.ident = Ident::new("_new_typed", method.sig.ident.span());
+                        method.vis = Visibility::Inherited;
+
+                        // Load the bindings in the constructor, so that a missing binding fails the
+                        // construction of the object with a descriptive error.
+                        Ok(quote! {
+                            #pound[wasm_bindgen::prelude::wasm_bindgen(constructor)]
+                            pub fn _new(state: worker::worker_sys::DurableObjectState, env: ::worker::Env) -> ::std::result::Result<#struct_name, wasm_bindgen::JsValue> {
+                                let env = <#env_ty as ::worker::Bindings>::load(&env).map_err(wasm_bindgen::JsValue::from)?;
+                                Ok(Self::_new_typed(::worker::durable::State::from(state), env))
+                            }
+
+                            #method
+                        })
+                    },
                     "new" => {
                         let mut method = impl_method.clone();
                         method.sig.ident = Ident::new("_new", method.sig.ident.span());
@@ -244,6 +265,16 @@ pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
                 }
             });
 
+            let new_tokens = if typed_constructor {
+                quote! {
+                    Self::_new(state._inner(), env).unwrap_or_else(|e| wasm_bindgen::throw_val(e))
+                }
+            } else {
+                quote! {
+                    Self::_new(state._inner(), env)
+                }
+            };
+
             Ok(quote! {
                 #wasm_bindgen_attr
                 impl #struct_name {
@@ -253,7 +284,7 @@ pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
                 #pound[async_trait::async_trait(?Send)]
                 impl ::worker::durable::DurableObject for #struct_name {
                     fn new(state: ::worker::durable::State, env: ::worker::Env) -> Self {
-                        Self::_new(state._inner(), env)
+                        #new_tokens
                     }
 
                     async fn fetch(&mut self, req: ::worker::Request) -> ::worker::Result<worker::Response> {
@@ -287,3 +318,24 @@ pub fn expand_macro(tokens: TokenStream) -> syn::Result<TokenStream> {
         _ => Err(Error::new(item.span(), "Durable Object macro can only be applied to structs and their impl of DurableObject trait"))
     }
 }
+
+/// The type of the `env` argument of a `new` method when it's a typed struct of bindings instead
+/// of `Env`.
+fn typed_env(sig: &Signature) -> Option<&Type> {
+    match sig.inputs.iter().nth(1)? {
+        FnArg::Typed(pat) => match &*pat.ty {
+            Type::Path(path)
+                if path
+                    .path
+                    .segments
+                    .last()
+                    .map(|s| s.ident == "Env")
+                    .unwrap_or(false) =>
+            {
+                None
+            }
+            ty => Some(ty),
+        },
+        FnArg::Receiver(_) => None,
+    }
+}
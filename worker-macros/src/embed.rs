@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Error, LitStr};
+
+pub fn expand_macro(input: LitStr) -> syn::Result<TokenStream> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| Error::new(input.span(), "CARGO_MANIFEST_DIR is not set"))?;
+    let root = Path::new(&manifest_dir).join(input.value());
+    if !root.is_dir() {
+        return Err(Error::new(
+            input.span(),
+            format!("{} is not a directory", root.display()),
+        ));
+    }
+
+    let mut files = Vec::new();
+    collect_files(&root, &mut files).map_err(|e| {
+        Error::new(
+            input.span(),
+            format!("failed to read {}: {e}", root.display()),
+        )
+    })?;
+    files.sort();
+
+    let mut entries = Vec::new();
+    for file in files {
+        let contents = std::fs::read(&file).map_err(|e| {
+            Error::new(
+                input.span(),
+                format!("failed to read {}: {e}", file.display()),
+            )
+        })?;
+        // The path relative to the directory, as it appears in a URL.
+        let path = file
+            .strip_prefix(&root)
+            .expect("file is in the embedded directory")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .fold(String::new(), |path, component| path + "/" + &component);
+        let hash = fnv1a(&contents);
+        let etag = format!("\"{hash:016x}\"");
+        let fingerprinted = fingerprint(&path, hash);
+        let absolute = file.to_string_lossy().into_owned();
+        entries.push(quote! {
+            ::worker::assets::EmbeddedFile::new(
+                #path,
+                #fingerprinted,
+                include_bytes!(#absolute),
+                #etag,
+            )
+        });
+    }
+
+    Ok(quote! {
+        ::worker::assets::EmbeddedDir::new(&[#(#entries),*])
+    })
+}
+
+/// Every file below `dir`, skipping hidden files and directories.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `path` with the start of `hash` before the extension of its file name, e.g.
+/// `/css/main.1a2b3c4d.css`, so that it changes whenever the contents do.
+fn fingerprint(path: &str, hash: u64) -> String {
+    let hash = format!("{:08x}", hash >> 32);
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{dir}/{stem}.{hash}.{extension}"),
+        _ => format!("{dir}/{name}.{hash}"),
+    }
+}
+
+/// A hash of the contents which stays the same across builds, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
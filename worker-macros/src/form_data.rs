@@ -0,0 +1,78 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ext::IdentExt, spanned::Spanned, Data, DeriveInput, Error, Fields, LitStr};
+
+pub fn expand_macro(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "`FromFormData` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`FromFormData` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut reads = Vec::new();
+    let mut idents = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+
+        let mut field_name = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("form")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    field_name = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported form attribute, expected `name`"))
+                }
+            })?;
+        }
+        let field_name = field_name.unwrap_or_else(|| ident.unraw().to_string());
+
+        reads.push(quote! {
+            let #ident = match <#ty as ::worker::FromFormField>::from_form_field(form, #field_name) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    errors.push(format!("`{}`: {}", #field_name, e));
+                    None
+                }
+            };
+        });
+        idents.push(ident);
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::worker::FromFormData for #name #ty_generics #where_clause {
+            fn from_form_data(form: &::worker::FormData) -> ::worker::Result<Self> {
+                #[allow(unused_mut)]
+                let mut errors: Vec<String> = Vec::new();
+                #(#reads)*
+
+                if !errors.is_empty() {
+                    return Err(::worker::Error::RustError(format!(
+                        "invalid form: {}",
+                        errors.join(", ")
+                    )));
+                }
+
+                Ok(Self {
+                    #(#idents: #idents.unwrap(),)*
+                })
+            }
+        }
+    })
+}
@@ -1,8 +1,39 @@
+mod bindings;
+#[cfg(feature = "d1")]
+mod d1_entity;
 mod durable_object;
+#[cfg(feature = "embed")]
+mod embed;
 mod event;
+mod form_data;
+mod routes;
 mod send;
+mod worker;
 
 use proc_macro::TokenStream;
+use syn::{parse_macro_input, punctuated::Punctuated, DeriveInput};
+
+/// Derive [`worker::Bindings`] for a struct whose fields are bindings of the `Env`, read from the
+/// binding with the field's name in upper case unless overridden with `#[binding(name = "...")]`.
+#[proc_macro_derive(Bindings, attributes(binding))]
+pub fn bindings(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    bindings::expand_macro(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive [`worker::d1::D1Entity`] for a struct mapped to a D1 table, named after the struct in
+/// snake case unless overridden with `#[d1(table = "...")]`. The primary key is the `id` field
+/// or the field marked with `#[d1(primary_key)]`.
+#[cfg(feature = "d1")]
+#[proc_macro_derive(D1Entity, attributes(d1))]
+pub fn d1_entity(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    d1_entity::expand_macro(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
 #[proc_macro_attribute]
 pub fn durable_object(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -11,6 +42,28 @@ pub fn durable_object(_attr: TokenStream, item: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Embed the files of a directory, relative to the crate's `Cargo.toml`, into the binary as a
+/// [`worker::assets::EmbeddedDir`]. Files starting with a `.` are skipped. Each file also gets a
+/// fingerprinted path with a hash of its contents, e.g. `/main.1a2b3c4d.css` for `/main.css`.
+#[cfg(feature = "embed")]
+#[proc_macro]
+pub fn embed_dir(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::LitStr);
+    embed::expand_macro(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive [`worker::FromFormData`] for a struct whose fields are read from the form field with the
+/// field's name unless overridden with `#[form(name = "...")]`.
+#[proc_macro_derive(FromFormData, attributes(form))]
+pub fn from_form_data(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    form_data::expand_macro(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[cfg(feature = "http")]
 #[proc_macro_attribute]
 pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -23,6 +76,31 @@ pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
     event::expand_macro(attr, item, false)
 }
 
+/// Define a module of routes, with a constant holding the pattern of each route to register on
+/// a [`worker::Router`], and a function building the path of the route from its parameters, so
+/// that links and redirects can't point to a route which doesn't exist.
+///
+/// ```ignore
+/// worker::routes! {
+///     pub mod routes {
+///         home = "/";
+///         user_detail = "/users/:id";
+///         file = "/files/*path";
+///     }
+/// }
+///
+/// let router = Router::new().get(routes::USER_DETAIL, user_detail);
+/// assert_eq!(routes::user_detail(42), "/users/42");
+/// assert_eq!(routes::file("docs/a b.txt"), "/files/docs/a%20b.txt");
+/// ```
+#[proc_macro]
+pub fn routes(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as routes::Routes);
+    routes::expand_macro(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[proc_macro_attribute]
 /// Convert an async function which is `!Send` to be `Send`.
 ///
@@ -40,3 +118,17 @@ pub fn event(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn send(attr: TokenStream, stream: TokenStream) -> TokenStream {
     send::expand_macro(attr, stream)
 }
+
+/// Export the `fetch`, `scheduled` and `queue` handlers of a [`worker::Worker`] implementation,
+/// sharing one instance of it per isolate. Only the handlers defined in the `impl` block are
+/// exported; Durable Objects are still exported with [`#[durable_object]`](macro@durable_object).
+/// Like [`#[event(fetch)]`](macro@event), errors returned by `fetch` are turned into `500`
+/// responses with `#[worker(respond_with_errors)]`.
+#[proc_macro_attribute]
+pub fn worker(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attrs = parse_macro_input!(attr with Punctuated::parse_terminated);
+    let item = parse_macro_input!(item as syn::ItemImpl);
+    worker::expand_macro(attrs, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    Attribute, Error, Ident, LitStr, Token, Visibility,
+};
+
+/// `vis mod name { route = "/pattern"; ... }`
+pub struct Routes {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    routes: Vec<Route>,
+}
+
+struct Route {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    pattern: LitStr,
+}
+
+impl Parse for Routes {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut routes = Vec::new();
+        while !content.is_empty() {
+            let attrs = content.call(Attribute::parse_outer)?;
+            let name = content.parse()?;
+            content.parse::<Token![=]>()?;
+            let pattern = content.parse()?;
+            content.parse::<Token![;]>()?;
+            routes.push(Route {
+                attrs,
+                name,
+                pattern,
+            });
+        }
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            routes,
+        })
+    }
+}
+
+/// A part of a route pattern.
+enum Segment {
+    Literal(String),
+    /// `:name`, matching up to the next `/`.
+    Param(Ident),
+    /// `*name`, matching the rest of the path.
+    CatchAll(Ident),
+}
+
+fn parse_pattern(pattern: &LitStr) -> syn::Result<Vec<Segment>> {
+    let value = pattern.value();
+    if !value.starts_with('/') {
+        return Err(Error::new(
+            pattern.span(),
+            "route patterns must start with `/`",
+        ));
+    }
+
+    let mut segments = Vec::new();
+    let mut names = HashSet::new();
+    let mut rest = value.as_str();
+    while !rest.is_empty() {
+        let start = rest.find(|c| c == ':' || c == '*').unwrap_or(rest.len());
+        if start > 0 {
+            segments.push(Segment::Literal(rest[..start].to_string()));
+            rest = &rest[start..];
+            continue;
+        }
+        let catch_all = rest.starts_with('*');
+        let end = rest.find('/').unwrap_or(rest.len());
+        let name = &rest[1..end];
+        if catch_all && end != rest.len() {
+            return Err(Error::new(
+                pattern.span(),
+                format!("catch-all parameter `*{name}` must be at the end of the pattern"),
+            ));
+        }
+        let ident = syn::parse_str::<Ident>(name).map_err(|_| {
+            Error::new(
+                pattern.span(),
+                format!("route parameter `{name}` is not a valid identifier"),
+            )
+        })?;
+        if !names.insert(name.to_string()) {
+            return Err(Error::new(
+                pattern.span(),
+                format!("route parameter `{name}` is used more than once"),
+            ));
+        }
+        segments.push(if catch_all {
+            Segment::CatchAll(ident)
+        } else {
+            Segment::Param(ident)
+        });
+        rest = &rest[end..];
+    }
+    Ok(segments)
+}
+
+pub fn expand_macro(input: Routes) -> syn::Result<TokenStream> {
+    let Routes {
+        attrs: module_attrs,
+        vis,
+        name: module,
+        routes,
+    } = input;
+
+    let mut items = Vec::new();
+    for Route {
+        attrs,
+        name,
+        pattern,
+    } in routes
+    {
+        let segments = parse_pattern(&pattern)?;
+        let constant = format_ident!("{}", name.to_string().to_uppercase(), span = name.span());
+        // Not visible to the parameters, which can be called `path` too.
+        let path = Ident::new("path", Span::mixed_site());
+        let mut params = Vec::new();
+        let mut pushes = Vec::new();
+        for segment in &segments {
+            match segment {
+                Segment::Literal(literal) => pushes.push(quote! { #path.push_str(#literal); }),
+                Segment::Param(param) | Segment::CatchAll(param) => {
+                    let catch_all = matches!(segment, Segment::CatchAll(_));
+                    params.push(quote! { #param: impl ::std::fmt::Display });
+                    pushes.push(quote! {
+                        #path.push_str(&::worker::encode_route_param(
+                            &#param.to_string(),
+                            #catch_all,
+                        ));
+                    });
+                }
+            }
+        }
+        let const_doc = format!("The pattern of the route, `{}`.", pattern.value());
+        let fn_doc = format!(
+            "The path of the route `{}`, with its parameters percent-encoded.",
+            pattern.value()
+        );
+
+        items.push(quote! {
+            #(#attrs)*
+            #[doc = #const_doc]
+            pub const #constant: &str = #pattern;
+
+            #(#attrs)*
+            #[doc = #fn_doc]
+            pub fn #name(#(#params),*) -> ::std::string::String {
+                let mut #path = ::std::string::String::new();
+                #(#pushes)*
+                #path
+            }
+        });
+    }
+
+    Ok(quote! {
+        #(#module_attrs)*
+        #vis mod #module {
+            #(#items)*
+        }
+    })
+}
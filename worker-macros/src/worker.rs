@@ -0,0 +1,128 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, token::Comma, Error, Ident, ImplItem, ItemImpl};
+
+pub fn expand_macro(attrs: Punctuated<Ident, Comma>, item: ItemImpl) -> syn::Result<TokenStream> {
+    let mut respond_with_errors = false;
+    for attr in attrs {
+        match attr.to_string().as_str() {
+            "respond_with_errors" => respond_with_errors = true,
+            _ => return Err(Error::new(attr.span(), "Invalid attribute")),
+        }
+    }
+    if item.trait_.is_none() {
+        return Err(Error::new_spanned(
+            &item.self_ty,
+            "#[worker] must be applied to an `impl Worker for ...` block",
+        ));
+    }
+
+    let handlers: Vec<String> = item
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    let defines = |name: &str| handlers.iter().any(|handler| handler == name);
+    let mut entrypoints = Vec::new();
+
+    if defines("fetch") {
+        let error_handling = match respond_with_errors {
+            true => quote! { ::worker::Response::error(e.to_string(), 500).unwrap().into() },
+            false => quote! { panic!("{}", e) },
+        };
+        entrypoints.push(quote! {
+            pub async fn fetch(
+                req: ::worker::worker_sys::web_sys::Request,
+                env: ::worker::Env,
+                ctx: ::worker::worker_sys::Context
+            ) -> ::worker::worker_sys::web_sys::Response {
+                let ctx = ::worker::Context::new(ctx);
+                let result = match instance(&env) {
+                    Ok(worker) => worker.fetch(::worker::Request::from(req), env, ctx).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(res) => ::worker::worker_sys::web_sys::Response::from(res),
+                    Err(e) => {
+                        ::worker::console_error!("{}", &e);
+                        #error_handling
+                    }
+                }
+            }
+        });
+    }
+
+    if defines("scheduled") {
+        entrypoints.push(quote! {
+            pub async fn scheduled(
+                event: ::worker::worker_sys::ScheduledEvent,
+                env: ::worker::Env,
+                ctx: ::worker::worker_sys::ScheduleContext
+            ) {
+                let worker = match instance(&env) {
+                    Ok(worker) => worker,
+                    Err(e) => {
+                        ::worker::console_error!("{}", &e);
+                        panic!("{}", e);
+                    }
+                };
+                worker.scheduled(
+                    ::worker::ScheduledEvent::from(event),
+                    env,
+                    ::worker::ScheduleContext::from(ctx)
+                ).await
+            }
+        });
+    }
+
+    #[cfg(feature = "queue")]
+    if defines("queue") {
+        entrypoints.push(quote! {
+            pub async fn queue(
+                event: ::worker::worker_sys::MessageBatch,
+                env: ::worker::Env,
+                ctx: ::worker::worker_sys::Context
+            ) {
+                let ctx = ::worker::Context::new(ctx);
+                let result = match instance(&env) {
+                    Ok(worker) => worker.queue(::worker::MessageBatch::from(event), env, ctx).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    ::worker::console_log!("{}", &e);
+                    panic!("{}", e);
+                }
+            }
+        });
+    }
+
+    let entrypoints = entrypoints
+        .into_iter()
+        .map(|entrypoint| wasm_bindgen_macro_support::expand(TokenStream::new(), entrypoint))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::new_spanned(&item.self_ty, "wasm_bindgen macro failed to expand"))?;
+    let self_ty = &item.self_ty;
+
+    Ok(quote! {
+        #[::worker::async_trait::async_trait(?Send)]
+        #item
+
+        mod _worker {
+            use ::worker::{wasm_bindgen, wasm_bindgen_futures, Worker as _};
+            use super::*;
+
+            ::std::thread_local! {
+                static INSTANCE: ::worker::WorkerInstance<#self_ty> = ::worker::WorkerInstance::new();
+            }
+
+            fn instance(env: &::worker::Env) -> ::worker::Result<::std::rc::Rc<#self_ty>> {
+                INSTANCE.with(|instance| instance.get(env))
+            }
+
+            #(#entrypoints)*
+        }
+    })
+}
@@ -109,6 +109,7 @@ pub async fn handle_websocket(_req: Request, env: Env, _data: SomeSharedData) ->
                     WebsocketEvent::Close(_) => {
                         let _res = do_ws.close(Some(1000), Some("client closed".to_string()));
                     }
+                    _ => {}
                 },
                 "durable" => match event.expect("received error in websocket") {
                     WebsocketEvent::Message(msg) => {
@@ -119,6 +120,7 @@ pub async fn handle_websocket(_req: Request, env: Env, _data: SomeSharedData) ->
                     WebsocketEvent::Close(_) => {
                         let _res = server.close(Some(1000), Some("durable closed".to_string()));
                     }
+                    _ => {}
                 },
                 _ => unreachable!(),
             }
@@ -33,6 +33,7 @@ pub async fn handle_websocket(_req: Request, env: Env, _data: SomeSharedData) ->
                         .await
                         .unwrap();
                 }
+                _ => {}
             }
         }
     });
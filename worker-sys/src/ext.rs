@@ -3,8 +3,11 @@ mod abort_signal;
 mod cache_storage;
 mod headers;
 mod request;
+#[cfg(feature = "websocket")]
 mod response;
+#[cfg(feature = "websocket")]
 mod response_init;
+#[cfg(feature = "websocket")]
 mod websocket;
 
 pub use abort_controller::*;
@@ -12,6 +15,9 @@ pub use abort_signal::*;
 pub use cache_storage::*;
 pub use headers::*;
 pub use request::*;
+#[cfg(feature = "websocket")]
 pub use response::*;
+#[cfg(feature = "websocket")]
 pub use response_init::*;
+#[cfg(feature = "websocket")]
 pub use websocket::*;
@@ -1,31 +1,43 @@
 mod context;
 #[cfg(feature = "d1")]
 mod d1;
+#[cfg(feature = "crypto")]
+mod digest_stream;
 mod durable_object;
 mod dynamic_dispatcher;
 mod fetcher;
 mod fixed_length_stream;
+mod identity_transform_stream;
 mod incoming_request_cf_properties;
 #[cfg(feature = "queue")]
 mod queue;
+#[cfg(feature = "r2")]
 mod r2;
 mod schedule;
 mod socket;
 mod tls_client_auth;
+mod url_pattern;
+#[cfg(feature = "websocket")]
 mod websocket_pair;
 
 pub use context::*;
 #[cfg(feature = "d1")]
 pub use d1::*;
+#[cfg(feature = "crypto")]
+pub use digest_stream::*;
 pub use durable_object::*;
 pub use dynamic_dispatcher::*;
 pub use fetcher::*;
 pub use fixed_length_stream::*;
+pub use identity_transform_stream::*;
 pub use incoming_request_cf_properties::*;
 #[cfg(feature = "queue")]
 pub use queue::*;
+#[cfg(feature = "r2")]
 pub use r2::*;
 pub use schedule::*;
 pub use socket::*;
 pub use tls_client_auth::*;
+pub use url_pattern::*;
+#[cfg(feature = "websocket")]
 pub use websocket_pair::*;
@@ -0,0 +1,14 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends=web_sys::WritableStream, js_namespace=crypto)]
+    #[derive(Debug, Clone)]
+    pub type DigestStream;
+
+    #[wasm_bindgen(constructor, catch, js_namespace=crypto)]
+    pub fn new(algorithm: &str) -> Result<DigestStream, JsValue>;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn digest(this: &DigestStream) -> js_sys::Promise;
+}
@@ -15,7 +15,11 @@ extern "C" {
 
     #[wasm_bindgen(method, js_name=waitUntil)]
     pub fn wait_until(this: &DurableObjectState, promise: &js_sys::Promise);
+}
 
+#[cfg(feature = "websocket")]
+#[wasm_bindgen]
+extern "C" {
     #[wasm_bindgen(method, js_name=acceptWebSocket)]
     pub fn accept_websocket(this: &DurableObjectState, ws: &web_sys::WebSocket);
 
@@ -10,12 +10,26 @@ extern "C" {
     #[wasm_bindgen(method, catch)]
     pub fn get(this: &DurableObjectStorage, key: &str) -> Result<js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name=get)]
+    pub fn get_with_options(
+        this: &DurableObjectStorage,
+        key: &str,
+        options: js_sys::Object,
+    ) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(method, catch, js_name=get)]
     pub fn get_multiple(
         this: &DurableObjectStorage,
         keys: Vec<JsValue>,
     ) -> Result<js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name=get)]
+    pub fn get_multiple_with_options(
+        this: &DurableObjectStorage,
+        keys: Vec<JsValue>,
+        options: js_sys::Object,
+    ) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(method, catch)]
     pub fn put(
         this: &DurableObjectStorage,
@@ -23,21 +37,50 @@ extern "C" {
         value: JsValue,
     ) -> Result<js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name=put)]
+    pub fn put_with_options(
+        this: &DurableObjectStorage,
+        key: &str,
+        value: JsValue,
+        options: js_sys::Object,
+    ) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(method, catch, js_name=put)]
     pub fn put_multiple(
         this: &DurableObjectStorage,
         value: JsValue,
     ) -> Result<js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name=put)]
+    pub fn put_multiple_with_options(
+        this: &DurableObjectStorage,
+        value: JsValue,
+        options: js_sys::Object,
+    ) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(method, catch)]
     pub fn delete(this: &DurableObjectStorage, key: &str) -> Result<js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name=delete)]
+    pub fn delete_with_options(
+        this: &DurableObjectStorage,
+        key: &str,
+        options: js_sys::Object,
+    ) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(method, catch, js_name=delete)]
     pub fn delete_multiple(
         this: &DurableObjectStorage,
         keys: Vec<JsValue>,
     ) -> Result<js_sys::Promise, JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name=delete)]
+    pub fn delete_multiple_with_options(
+        this: &DurableObjectStorage,
+        keys: Vec<JsValue>,
+        options: js_sys::Object,
+    ) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(method, catch, js_name=deleteAll)]
     pub fn delete_all(this: &DurableObjectStorage) -> Result<js_sys::Promise, JsValue>;
 
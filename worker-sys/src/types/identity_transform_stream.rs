@@ -0,0 +1,11 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(extends=web_sys::TransformStream)]
+    #[derive(Debug, Clone)]
+    pub type IdentityTransformStream;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> IdentityTransformStream;
+}
@@ -0,0 +1,45 @@
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name=URLPattern)]
+    #[derive(Debug, Clone)]
+    pub type UrlPattern;
+
+    #[wasm_bindgen(constructor, catch, js_class=URLPattern)]
+    pub fn new(input: &JsValue) -> Result<UrlPattern, JsValue>;
+
+    #[wasm_bindgen(constructor, catch, js_class=URLPattern)]
+    pub fn new_with_base(input: &str, base_url: &str) -> Result<UrlPattern, JsValue>;
+
+    #[wasm_bindgen(method, catch, js_class=URLPattern)]
+    pub fn test(this: &UrlPattern, input: &str) -> Result<bool, JsValue>;
+
+    /// Returns `null` if the input doesn't match.
+    #[wasm_bindgen(method, catch, js_class=URLPattern)]
+    pub fn exec(this: &UrlPattern, input: &str) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn protocol(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn username(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn password(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn hostname(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn port(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn pathname(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn search(this: &UrlPattern) -> String;
+
+    #[wasm_bindgen(method, getter, js_class=URLPattern)]
+    pub fn hash(this: &UrlPattern) -> String;
+}
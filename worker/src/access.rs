@@ -0,0 +1,245 @@
+//! Validation of [Cloudflare Access](https://developers.cloudflare.com/cloudflare-one/identity/authorization-cookie/validating-json/)
+//! application tokens.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use futures_util::future::LocalBoxFuture;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    jwt::{JwkSet, Jwt},
+    Date, Error, Fetch, Middleware, Next, Request, Response, Result, RouteContext,
+};
+
+const ASSERTION_HEADER: &str = "Cf-Access-Jwt-Assertion";
+const AUTHORIZATION_COOKIE: &str = "CF_Authorization";
+/// How long fetched signing keys are trusted before they are fetched again.
+const CERTS_TTL_MS: u64 = 60 * 60 * 1000;
+/// The context of the errors of missing or invalid tokens.
+const REJECTED: &str = "the Cloudflare Access token was rejected";
+
+thread_local! {
+    static CERTS: RefCell<HashMap<String, (u64, Rc<JwkSet>)>> = RefCell::new(HashMap::new());
+}
+
+/// The identity of a user authenticated by Cloudflare Access, taken from the claims of a
+/// validated application token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccessIdentity {
+    /// The user's email address. Absent for service tokens.
+    pub email: Option<String>,
+    /// The user's unique identifier within Access.
+    pub sub: String,
+    /// Country the user connected from.
+    pub country: Option<String>,
+    /// Nonce which can be used to fetch the user's full identity from `/cdn-cgi/access/get-identity`.
+    pub identity_nonce: Option<String>,
+    /// The kind of token, e.g. `app`.
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// The service token's client ID, if the request was authenticated with a service token.
+    pub common_name: Option<String>,
+    /// Custom SAML or OIDC claims passed through by the identity provider.
+    pub custom: Option<Value>,
+}
+
+/// Route data which can receive the [`AccessIdentity`] of an authenticated request, allowing
+/// [`CloudflareAccess`] to be used as [`Middleware`] of a [`Router`](crate::Router).
+pub trait AccessIdentityData {
+    fn set_access_identity(&mut self, identity: AccessIdentity);
+}
+
+impl AccessIdentityData for Option<AccessIdentity> {
+    fn set_access_identity(&mut self, identity: AccessIdentity) {
+        *self = Some(identity);
+    }
+}
+
+/// Validates the Cloudflare Access token sent with a request against the signing keys of a team
+/// domain and one or more application audience (`AUD`) tags.
+///
+/// When registered as [`Middleware`] requests without a valid token are rejected with a `403`,
+/// while the identity of valid ones is stored in the route data and the
+/// [`RouteContext::extensions`]. Failures to fetch the signing keys of the team aren't the
+/// client's fault, so they're returned as errors rather than rejecting the request:
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(req: Request, env: Env) -> Result<Response> {
+/// let access = CloudflareAccess::new("my-team", "4714c1358e65fe4b408ad6d432a5f878f08194bdb4752441fd56faefa9b2b6f2");
+/// Router::with_data(None::<AccessIdentity>)
+///     .middleware(access)
+///     .get("/", |_, ctx| match &ctx.data {
+///         Some(AccessIdentity { email: Some(email), .. }) => Response::ok(format!("Hello {email}")),
+///         _ => Response::ok("Hello"),
+///     })
+///     .run(req, env)
+///     .await
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CloudflareAccess {
+    issuer: String,
+    audiences: Vec<String>,
+}
+
+impl CloudflareAccess {
+    /// Create a validator for the given team domain (either `my-team` or
+    /// `https://my-team.cloudflareaccess.com`) and application audience tag.
+    pub fn new(team_domain: &str, audience: &str) -> Self {
+        let team_domain = team_domain.trim_end_matches('/');
+        let issuer = if team_domain.starts_with("https://") {
+            team_domain.to_string()
+        } else if team_domain.contains('.') {
+            format!("https://{team_domain}")
+        } else {
+            format!("https://{team_domain}.cloudflareaccess.com")
+        };
+
+        Self {
+            issuer,
+            audiences: vec![audience.to_string()],
+        }
+    }
+
+    /// Also accept tokens issued for `audience`.
+    #[must_use]
+    pub fn audience(mut self, audience: &str) -> Self {
+        self.audiences.push(audience.to_string());
+        self
+    }
+
+    /// The token sent with the request, taken from the `Cf-Access-Jwt-Assertion` header or the
+    /// `CF_Authorization` cookie.
+    pub fn token(req: &Request) -> Result<Option<String>> {
+        if let Some(token) = req.headers().get(ASSERTION_HEADER)? {
+            return Ok(Some(token));
+        }
+
+        req.cookie(AUTHORIZATION_COOKIE)
+    }
+
+    /// Validate the token sent with `req`, returning the identity of the authenticated user.
+    ///
+    /// Fails with an error for which [`is_rejected`](Self::is_rejected) is `true` if the token is
+    /// missing or invalid, and with any other error if the signing keys couldn't be fetched.
+    pub async fn validate_request(&self, req: &Request) -> Result<AccessIdentity> {
+        let token = match Self::token(req) {
+            Ok(Some(token)) => token,
+            Ok(None) => {
+                return Err(rejected(Error::RustError(
+                    "missing Cloudflare Access token".into(),
+                )))
+            }
+            Err(e) => return Err(rejected(e)),
+        };
+        self.validate(&token).await
+    }
+
+    /// Validate the signature and claims of `token`, returning the identity of the
+    /// authenticated user. Fails like [`validate_request`](Self::validate_request).
+    pub async fn validate(&self, token: &str) -> Result<AccessIdentity> {
+        let jwt = Jwt::decode(token).map_err(rejected)?;
+        let Some(kid) = jwt.header().kid.clone() else {
+            return Err(rejected(Error::RustError("JWT has no key id".into())));
+        };
+
+        let mut certs = self.certs(false).await?;
+        if certs.find(&kid).is_none() {
+            // The signing keys are rotated regularly, so the key may be newer than our cache.
+            certs = self.certs(true).await?;
+        }
+        self.validate_with(&jwt, &certs, &kid)
+            .await
+            .map_err(rejected)
+    }
+
+    /// Whether `error` is the error of a missing or invalid token, which the client should be
+    /// denied access for, rather than a failure to fetch the signing keys.
+    pub fn is_rejected(error: &Error) -> bool {
+        matches!(error, Error::Context { context, .. } if context == REJECTED)
+    }
+
+    async fn validate_with(&self, jwt: &Jwt, certs: &JwkSet, kid: &str) -> Result<AccessIdentity> {
+        let jwk = certs
+            .find(kid)
+            .ok_or_else(|| Error::RustError(format!("unknown JWT key id: {kid}")))?;
+
+        if !jwt.verify(jwk).await? {
+            return Err(Error::RustError("invalid JWT signature".into()));
+        }
+
+        let claims = jwt.registered_claims()?;
+        if claims.iss.as_deref() != Some(self.issuer.as_str()) {
+            return Err(Error::RustError("JWT has an invalid issuer".into()));
+        }
+        if !self.audiences.iter().any(|aud| claims.aud.contains(aud)) {
+            return Err(Error::RustError("JWT has an invalid audience".into()));
+        }
+        jwt.validate_time()?;
+
+        jwt.claims()
+    }
+
+    async fn certs(&self, refresh: bool) -> Result<Rc<JwkSet>> {
+        let now = Date::now().as_millis();
+        let issuer = &self.issuer;
+
+        if !refresh {
+            let cached = CERTS.with(|certs| {
+                certs
+                    .borrow()
+                    .get(issuer)
+                    .filter(|(fetched_at, _)| now.saturating_sub(*fetched_at) < CERTS_TTL_MS)
+                    .map(|(_, set)| set.clone())
+            });
+            if let Some(set) = cached {
+                return Ok(set);
+            }
+        }
+
+        let url = format!("{issuer}/cdn-cgi/access/certs").parse()?;
+        let mut resp = Fetch::Url(url).send().await?;
+        if resp.status_code() != 200 {
+            return Err(Error::RustError(format!(
+                "failed to fetch Cloudflare Access certs: {}",
+                resp.status_code()
+            )));
+        }
+        let set = Rc::new(resp.json::<JwkSet>().await?);
+
+        CERTS.with(|certs| {
+            certs
+                .borrow_mut()
+                .insert(issuer.clone(), (now, set.clone()));
+        });
+        Ok(set)
+    }
+}
+
+impl<'a, D: AccessIdentityData + 'a> Middleware<'a, D> for CloudflareAccess {
+    fn handle(
+        &self,
+        req: Request,
+        mut ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> LocalBoxFuture<'a, Result<Response>> {
+        let access = self.clone();
+        Box::pin(async move {
+            match access.validate_request(&req).await {
+                Ok(identity) => {
+                    ctx.extensions_mut().insert(identity.clone());
+                    ctx.data.set_access_identity(identity);
+                    next.run(req, ctx).await
+                }
+                Err(e) if Self::is_rejected(&e) => Response::error("Forbidden", 403),
+                Err(e) => Err(e),
+            }
+        })
+    }
+}
+
+fn rejected(error: Error) -> Error {
+    error.context(REJECTED)
+}
@@ -0,0 +1,78 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use wasm_bindgen::JsValue;
+
+use crate::{Error, Fetch, FormData, Headers, Method, Request, RequestInit, Result};
+
+const API_BASE: &str = "https://api.cloudflare.com/client/v4";
+
+/// The body of a request to the Cloudflare API.
+pub(crate) enum ApiBody {
+    #[cfg_attr(not(feature = "queue"), allow(dead_code))]
+    Json(String),
+    Form(FormData),
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<ApiError>,
+    #[serde(default)]
+    result: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    code: u32,
+    message: String,
+}
+
+/// Send a request to the Cloudflare API at `path` (relative to `/client/v4/`), returning the
+/// `result` of its response. `service` names the API in error messages.
+pub(crate) async fn request<T: DeserializeOwned>(
+    service: &str,
+    api_token: &str,
+    method: Method,
+    path: &str,
+    body: Option<ApiBody>,
+) -> Result<T> {
+    let url = format!("{API_BASE}/{path}");
+
+    let mut headers = Headers::new();
+    headers.set("Authorization", &format!("Bearer {api_token}"))?;
+
+    let mut init = RequestInit::new();
+    init.with_method(method);
+    match body {
+        Some(ApiBody::Json(json)) => {
+            headers.set("Content-Type", "application/json")?;
+            init.with_body(Some(JsValue::from_str(&json)));
+        }
+        // The multipart boundary is added to the content type by `fetch`.
+        Some(ApiBody::Form(form)) => {
+            init.with_body(Some(form.into()));
+        }
+        None => {}
+    }
+    init.with_headers(headers);
+
+    let mut resp = Fetch::Request(Request::new_with_init(&url, &init)?)
+        .send()
+        .await?;
+    let status = resp.status_code();
+    let unexpected =
+        |e: Error| Error::RustError(format!("unexpected {service} API response ({status}): {e}"));
+    let api: ApiResponse = resp.json().await.map_err(unexpected)?;
+
+    if !api.success {
+        return Err(Error::RustError(format!(
+            "{service} API request failed ({status}): {}",
+            api.errors
+                .iter()
+                .map(|e| format!("{} (code {})", e.message, e.code))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+    serde_json::from_value(api.result).map_err(|e| unexpected(e.into()))
+}
@@ -0,0 +1,274 @@
+//! Static files embedded into the binary with [`embed_dir!`](crate::embed_dir), for Workers which
+//! serve a few small assets without the assets binding.
+//!
+//! ```ignore
+//! use worker::{assets::EmbeddedDir, *};
+//!
+//! static ASSETS: EmbeddedDir = embed_dir!("public");
+//!
+//! #[event(fetch)]
+//! async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
+//!     Router::new()
+//!         .get("/*path", |req, _| ASSETS.serve(&req))
+//!         .run(req, env)
+//!         .await
+//! }
+//! ```
+//!
+//! Each file can also be served from a fingerprinted path, which has a hash of its contents in
+//! the file name, e.g. `/css/main.1a2b3c4d.css` for `/css/main.css`. The fingerprints are
+//! computed when the Worker is compiled, so a fingerprinted path always serves the same contents
+//! and is cached by browsers and the CDN for a year with `Cache-Control: immutable`, while the
+//! plain paths, such as the `index.html` of a single-page app, are revalidated on every use.
+//! Pages link to the fingerprinted paths with [`EmbeddedDir::asset_path`].
+//!
+//! The files are read when the Worker is compiled, so they should be kept small to stay below
+//! the size limit of the script. Adding a file to the directory requires touching the source
+//! which embeds it for the change to be picked up.
+//!
+//! **Requires** `embed` feature.
+
+use crate::{Headers, Method, Request, Response, Result};
+
+/// A file embedded with [`embed_dir!`](crate::embed_dir).
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedFile {
+    path: &'static str,
+    fingerprinted_path: &'static str,
+    contents: &'static [u8],
+    etag: &'static str,
+}
+
+impl EmbeddedFile {
+    #[doc(hidden)]
+    pub const fn new(
+        path: &'static str,
+        fingerprinted_path: &'static str,
+        contents: &'static [u8],
+        etag: &'static str,
+    ) -> Self {
+        Self {
+            path,
+            fingerprinted_path,
+            contents,
+            etag,
+        }
+    }
+
+    /// The path of the file from the embedded directory, starting with `/`.
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// The path with a hash of the contents in the file name, e.g. `/css/main.1a2b3c4d.css`.
+    pub fn fingerprinted_path(&self) -> &'static str {
+        self.fingerprinted_path
+    }
+
+    pub fn contents(&self) -> &'static [u8] {
+        self.contents
+    }
+
+    /// A strong `ETag` derived from the contents.
+    pub fn etag(&self) -> &'static str {
+        self.etag
+    }
+
+    /// The `Content-Type` for the file's extension, `application/octet-stream` if it's unknown.
+    pub fn content_type(&self) -> &'static str {
+        content_type(self.path)
+    }
+}
+
+/// The files of a directory embedded with [`embed_dir!`](crate::embed_dir).
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedDir {
+    files: &'static [EmbeddedFile],
+}
+
+impl EmbeddedDir {
+    #[doc(hidden)]
+    pub const fn new(files: &'static [EmbeddedFile]) -> Self {
+        Self { files }
+    }
+
+    pub fn files(&self) -> &'static [EmbeddedFile] {
+        self.files
+    }
+
+    /// The file at `path`, e.g. `/css/main.css`.
+    pub fn get(&self, path: &str) -> Option<&'static EmbeddedFile> {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.files.iter().find(|file| file.path == path)
+    }
+
+    /// The fingerprinted path of the file at `path`, to link to it from pages, e.g.
+    /// `asset_path("/css/main.css")` is `Some("/css/main.1a2b3c4d.css")`.
+    pub fn asset_path(&self, path: &str) -> Option<&'static str> {
+        self.get(path).map(EmbeddedFile::fingerprinted_path)
+    }
+
+    /// The file at `path`, and whether `path` is its fingerprinted path.
+    fn find(&self, path: &str) -> Option<(&'static EmbeddedFile, bool)> {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.files.iter().find_map(|file| {
+            if file.path == path {
+                Some((file, false))
+            } else if file.fingerprinted_path == path {
+                Some((file, true))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Respond to a `GET` or `HEAD` request with the file at the request's path, or the
+    /// `index.html` file of the directory if the path ends with `/`. Responds with
+    /// `304 Not Modified` if the request's `If-None-Match` header matches the file's `ETag`, and
+    /// with `404 Not Found` if there is no such file.
+    ///
+    /// Files served from their fingerprinted path are cached for a year as `immutable`, the
+    /// others with `no-cache` so that they're revalidated with their `ETag`.
+    pub fn serve(&self, req: &Request) -> Result<Response> {
+        self.serve_path(req, &req.path())
+    }
+
+    /// Respond to `req` like [`serve`](EmbeddedDir::serve) with the file at `path` rather than
+    /// the request's path, e.g. the catch-all parameter of a route. `path` is percent-decoded,
+    /// as it is in a URL.
+    pub fn serve_path(&self, req: &Request, path: &str) -> Result<Response> {
+        let mut path = match percent_decode(path) {
+            Some(path) => path,
+            None => return Response::error("Not Found", 404),
+        };
+        if path.is_empty() || path.ends_with('/') {
+            path.push_str("index.html");
+        }
+        let (file, fingerprinted) = match self.find(&path) {
+            Some(found) => found,
+            None => return Response::error("Not Found", 404),
+        };
+
+        let mut headers = Headers::new();
+        headers.set("ETag", file.etag)?;
+        headers.set(
+            "Cache-Control",
+            if fingerprinted { IMMUTABLE } else { "no-cache" },
+        )?;
+        let matches = req
+            .headers()
+            .get("If-None-Match")?
+            .map(|tags| {
+                tags.split(',').any(|tag| {
+                    tag.trim().trim_start_matches("W/") == file.etag || tag.trim() == "*"
+                })
+            })
+            .unwrap_or(false);
+        if matches {
+            return Ok(Response::empty()?.with_status(304).with_headers(headers));
+        }
+
+        headers.set("Content-Type", file.content_type())?;
+        headers.set("Content-Length", &file.contents.len().to_string())?;
+        let resp = if req.method() == Method::Head {
+            Response::empty()?
+        } else {
+            Response::from_bytes(file.contents.to_vec())?
+        };
+        Ok(resp.with_headers(headers))
+    }
+}
+
+/// `path` with its `%XX` escapes decoded, or `None` if they aren't valid UTF-8.
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// The `Cache-Control` of fingerprinted files, which never change.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+fn content_type(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[test]
+fn content_type_works() {
+    assert_eq!(content_type("/index.html"), "text/html; charset=utf-8");
+    assert_eq!(content_type("/img/Logo.PNG"), "image/png");
+    assert_eq!(content_type("/LICENSE"), "application/octet-stream");
+}
+
+#[test]
+fn fingerprinted_paths_work() {
+    static FILES: [EmbeddedFile; 1] = [EmbeddedFile::new(
+        "/css/main.css",
+        "/css/main.1a2b3c4d.css",
+        b"body {}",
+        "\"1a2b3c4d5e6f7a8b\"",
+    )];
+    let dir = EmbeddedDir::new(&FILES);
+    assert_eq!(
+        dir.asset_path("css/main.css"),
+        Some("/css/main.1a2b3c4d.css")
+    );
+    assert!(matches!(
+        dir.find("/css/main.1a2b3c4d.css"),
+        Some((_, true))
+    ));
+    assert!(matches!(dir.find("/css/main.css"), Some((_, false))));
+    assert!(dir.find("/css/main.00000000.css").is_none());
+}
+
+#[test]
+fn percent_decode_works() {
+    assert_eq!(percent_decode("/a%20b.txt").as_deref(), Some("/a b.txt"));
+    assert_eq!(percent_decode("/caf%C3%A9").as_deref(), Some("/café"));
+    assert_eq!(percent_decode("/100%").as_deref(), Some("/100%"));
+    assert_eq!(percent_decode("/%zz%+f").as_deref(), Some("/%zz%+f"));
+    assert_eq!(percent_decode("/%FF"), None);
+}
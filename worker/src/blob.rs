@@ -0,0 +1,90 @@
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{ByteStream, Error, Result};
+
+/// A [Blob](https://developer.mozilla.org/en-US/docs/Web/API/Blob): immutable bytes with a media
+/// type, which can be used as the body of a fetch, a [`Response`](crate::Response) or an R2 object
+/// and appended to a [`FormData`](crate::FormData) without copying it into Wasm memory.
+#[derive(Debug, Clone)]
+pub struct Blob(web_sys::Blob);
+
+impl Blob {
+    /// Construct a new blob from a buffer, with the media type `type_`, which may be empty.
+    pub fn new(data: impl AsRef<[u8]>, type_: &str) -> Result<Self> {
+        let data = data.as_ref();
+        let arr = Uint8Array::new_with_length(data.len() as u32);
+        arr.copy_from(data);
+        let mut options = web_sys::BlobPropertyBag::new();
+        options.type_(type_);
+        let blob =
+            web_sys::Blob::new_with_u8_array_sequence_and_options(&Array::of1(&arr), &options)?;
+        Ok(Self(blob))
+    }
+
+    /// Get the blob size, in bytes.
+    pub fn size(&self) -> usize {
+        self.0.size() as usize
+    }
+
+    /// Get the blob media type, empty if it's unknown.
+    pub fn type_(&self) -> String {
+        self.0.type_()
+    }
+
+    /// A new blob with the bytes from `start` up to `end`, excluding `end`, with the same media
+    /// type.
+    pub fn slice(&self, start: usize, end: usize) -> Result<Blob> {
+        self.slice_with_type(start, end, &self.type_())
+    }
+
+    /// A new blob with the bytes from `start` up to `end`, excluding `end`, with the media type
+    /// `type_`.
+    pub fn slice_with_type(&self, start: usize, end: usize, type_: &str) -> Result<Blob> {
+        self.0
+            .slice_with_f64_and_f64_and_content_type(start as f64, end as f64, type_)
+            .map(Self)
+            .map_err(Error::from)
+    }
+
+    /// Get a stream of the bytes of the blob, without reading it in memory first.
+    pub fn stream(&self) -> ByteStream {
+        ByteStream::from_raw(self.0.stream().unchecked_into())
+    }
+
+    /// Read the blob as UTF-8 text.
+    pub async fn text(&self) -> Result<String> {
+        let text = JsFuture::from(self.0.text()).await?;
+        text.as_string()
+            .ok_or_else(|| Error::RustError("blob text isn't a string".into()))
+    }
+
+    /// Read the blob and get the resulting bytes.
+    pub async fn bytes(&self) -> Result<Vec<u8>> {
+        let buffer = JsFuture::from(self.0.array_buffer()).await?;
+        Ok(Uint8Array::new(&buffer).to_vec())
+    }
+
+    pub(crate) fn inner(&self) -> &web_sys::Blob {
+        &self.0
+    }
+}
+
+impl From<web_sys::Blob> for Blob {
+    fn from(blob: web_sys::Blob) -> Self {
+        Self(blob)
+    }
+}
+
+impl From<Blob> for web_sys::Blob {
+    fn from(blob: Blob) -> Self {
+        blob.0
+    }
+}
+
+impl From<Blob> for JsValue {
+    fn from(blob: Blob) -> Self {
+        blob.0.into()
+    }
+}
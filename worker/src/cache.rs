@@ -1,4 +1,7 @@
-use std::convert::TryInto;
+//! The [Cache API](https://developers.cloudflare.com/workers/runtime-apis/cache), and caching of
+//! values in the memory of the isolate.
+
+use std::{cell::RefCell, convert::TryInto, future::Future, rc::Rc, time::Duration};
 
 use serde::Serialize;
 use wasm_bindgen::JsCast;
@@ -7,7 +10,7 @@ use worker_sys::ext::CacheStorageExt;
 
 use crate::request::Request;
 use crate::response::Response;
-use crate::Result;
+use crate::{response_cache, single_flight::SingleFlight, Context, Date, Error, Result};
 
 /// Provides access to the [Cache API](https://developers.cloudflare.com/workers/runtime-apis/cache).
 /// Because `match` is a reserved keyword in Rust, the `match` method has been renamed to `get`.
@@ -189,6 +192,161 @@ impl<'a> From<&'a Request> for CacheKey<'a> {
     }
 }
 
+impl CacheKey<'_> {
+    /// A builder of the cache keys of requests, see [`CacheKeyBuilder`].
+    pub fn builder() -> CacheKeyBuilder {
+        CacheKeyBuilder::new()
+    }
+}
+
+/// Builds the cache key of a request as a URL, so that only what its response depends on is part
+/// of the key. Keys built from more than that fragment the cache, with a response cached for each
+/// tracking parameter; keys built from less poison it, with a response cached for one user served
+/// to another.
+///
+/// The URL is normalized, without its fragment and with its query parameters sorted. Every query
+/// parameter is kept unless an [allow-list](CacheKeyBuilder::query_params) is given. Headers and
+/// cookies are ignored, except for the ones [`header`](CacheKeyBuilder::header) and
+/// [`cookie`](CacheKeyBuilder::cookie) add to the key. Requests with an `Authorization` header or
+/// a [session cookie](CacheKeyBuilder::session_cookie) have no key by default, so they aren't
+/// cached at all.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(req: Request) -> Result<()> {
+/// let keys = CacheKey::builder()
+///     .query_params(["page", "q"])
+///     .header("Accept-Language")
+///     .session_cookie("session");
+/// if let Some(key) = keys.build(&req)? {
+///     let cached = Cache::default().get(key, false).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CacheKeyBuilder {
+    query_params: Option<Vec<String>>,
+    headers: Vec<String>,
+    cookies: Vec<String>,
+    session_cookies: Vec<String>,
+    authenticated: bool,
+}
+
+impl CacheKeyBuilder {
+    /// A builder keeping every query parameter, and skipping authenticated requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keep the query parameters in `names`, dropping the rest such as `utm_source`.
+    #[must_use]
+    pub fn query_params<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.query_params = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Drop every query parameter.
+    #[must_use]
+    pub fn ignore_query(mut self) -> Self {
+        self.query_params = Some(Vec::new());
+        self
+    }
+
+    /// Key on the value of the request header `name`, since the Cache API ignores the `Vary`
+    /// header of responses.
+    #[must_use]
+    pub fn header(mut self, name: &str) -> Self {
+        self.headers.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Key on the value of the cookie `name`, e.g. the variant of an A/B test. The other cookies
+    /// are never part of the key.
+    #[must_use]
+    pub fn cookie(mut self, name: impl Into<String>) -> Self {
+        self.cookies.push(name.into());
+        self
+    }
+
+    /// Treat the requests with the cookie `name` as authenticated.
+    #[must_use]
+    pub fn session_cookie(mut self, name: impl Into<String>) -> Self {
+        self.session_cookies.push(name.into());
+        self
+    }
+
+    /// Build keys for authenticated requests too, for responses which are the same for every
+    /// user. They're skipped by default.
+    #[must_use]
+    pub fn cache_authenticated(mut self, cache: bool) -> Self {
+        self.authenticated = cache;
+        self
+    }
+
+    /// The cache key of `req`, or `None` if it's authenticated and shouldn't be cached.
+    pub fn build(&self, req: &Request) -> Result<Option<String>> {
+        let headers = req.headers();
+        let mut values = Vec::new();
+        for name in ["authorization", "cookie"]
+            .iter()
+            .copied()
+            .chain(self.headers.iter().map(String::as_str))
+        {
+            values.push((name, headers.get(name)?));
+        }
+        Ok(self.key(req.url()?, |name| {
+            values
+                .iter()
+                .find(|(header, _)| *header == name)
+                .and_then(|(_, value)| value.clone())
+        }))
+    }
+
+    fn key(&self, mut url: url::Url, header: impl Fn(&str) -> Option<String>) -> Option<String> {
+        let cookie = header("cookie").unwrap_or_default();
+        let cookies: Vec<(&str, &str)> = crate::cookie::parse(&cookie).collect();
+        let has_session = cookies
+            .iter()
+            .any(|(name, _)| self.session_cookies.iter().any(|session| session == name));
+        if !self.authenticated && (has_session || header("authorization").is_some()) {
+            return None;
+        }
+
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| match &self.query_params {
+                Some(allowed) => allowed.iter().any(|allowed| allowed == name),
+                None => true,
+            })
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        query.sort();
+        for name in &self.headers {
+            let value = header(name).unwrap_or_default();
+            query.push((format!("__vary_{name}"), value.trim().to_string()));
+        }
+        for name in &self.cookies {
+            let value = cookies
+                .iter()
+                .find(|(cookie, _)| cookie == name)
+                .map_or("", |(_, value)| value);
+            query.push((format!("__cookie_{name}"), value.to_string()));
+        }
+
+        url.set_fragment(None);
+        url.set_query(None);
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Some(url.into())
+    }
+}
+
 /// Successful outcomes when attempting to delete a `Response` from the cache
 #[derive(Serialize)]
 pub enum CacheDeletionOutcome {
@@ -197,3 +355,220 @@ pub enum CacheDeletionOutcome {
     /// The response was not in the cache at the time of deletion.
     ResponseNotFound,
 }
+
+/// Serves the response cached under the URL `key` in the [default cache](Cache::default) with
+/// stale-while-revalidate semantics. A response is fresh for `ttl`, after which it's served stale
+/// for `stale_ttl` while `refresh` produces a new one in the background, through
+/// [`Context::wait_until`], by one call of the isolate at a time. Without a cached response, or
+/// when it's too old, `refresh` is awaited and its response is returned and cached, if it's a
+/// `200` without `Set-Cookie` and without a `Cache-Control` header forbidding it.
+///
+/// Like with [`ResponseCache`](crate::ResponseCache), the responses have an `Age` header and an
+/// `X-Cache-Status` header of `HIT`, `STALE` or `MISS`.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use std::time::Duration;
+/// # async fn example(ctx: Context) -> Result<Response> {
+/// cache::swr(
+///     &ctx,
+///     "https://cache.internal/leaderboard",
+///     Duration::from_secs(30),
+///     Duration::from_secs(300),
+///     || async { Fetch::Url("https://api.example.com/leaderboard".parse()?).send().await },
+/// )
+/// .await
+/// # }
+/// ```
+pub async fn swr<F, Fut>(
+    ctx: &Context,
+    key: &str,
+    ttl: Duration,
+    stale_ttl: Duration,
+    refresh: F,
+) -> Result<Response>
+where
+    F: FnOnce() -> Fut + 'static,
+    Fut: Future<Output = Result<Response>> + 'static,
+{
+    let max_age = ttl + stale_ttl;
+    if let Some(cached) = Cache::default().get(key, false).await? {
+        match response_cache::age(&cached)? {
+            Some(age) if age < ttl => return response_cache::serve(cached, age, "HIT"),
+            Some(age) if age < max_age => {
+                if let Some(revalidating) = response_cache::Revalidating::start(key) {
+                    let key = key.to_string();
+                    ctx.wait_until(async move {
+                        let revalidation = async {
+                            let mut resp = refresh().await?;
+                            if response_cache::is_cacheable(&resp)? {
+                                let stored = response_cache::to_store(&mut resp, max_age)?;
+                                Cache::default().put(key, stored).await?;
+                            }
+                            Ok::<_, Error>(())
+                        };
+                        if let Err(e) = revalidation.await {
+                            worker_sys::console_error!(
+                                "revalidating a cached response failed: {e}"
+                            );
+                        }
+                        drop(revalidating);
+                    });
+                }
+                return response_cache::serve(cached, age, "STALE");
+            }
+            _ => {}
+        }
+    }
+
+    let mut resp = refresh().await?;
+    if response_cache::is_cacheable(&resp)? {
+        let stored = response_cache::to_store(&mut resp, max_age)?;
+        let key = key.to_string();
+        ctx.wait_until(async move {
+            let _ = Cache::default().put(key, stored).await;
+        });
+    }
+    resp.headers_mut()
+        .set(response_cache::STATUS_HEADER, "MISS")?;
+    Ok(resp)
+}
+
+/// Caches the result of an expensive async computation, such as fetching a JWKS or a config from
+/// KV, in the memory of the isolate for a TTL. Concurrent calls to [`get`](Memo::get) while the
+/// value is being computed wait for that computation instead of starting their own.
+///
+/// A `Memo` is cheap to clone, and clones share the cached value, so it's usually kept in a
+/// `thread_local!` to be shared by all the requests handled by the isolate:
+///
+/// ```no_run
+/// # use worker::*;
+/// use std::time::Duration;
+/// use worker::cache::Memo;
+///
+/// thread_local! {
+///     static JWKS: Memo<jwt::JwkSet> = Memo::new(Duration::from_secs(600));
+/// }
+///
+/// # async fn example() -> Result<jwt::JwkSet> {
+/// let jwks = JWKS.with(Memo::clone);
+/// jwks.get(|| async {
+///     let mut resp = Fetch::Url("https://example.com/.well-known/jwks.json".parse()?)
+///         .send()
+///         .await?;
+///     resp.json().await
+/// })
+/// .await
+/// # }
+/// ```
+pub struct Memo<T> {
+    ttl: Duration,
+    /// The cached value and when it expires, in milliseconds since the UNIX epoch.
+    value: Rc<RefCell<Option<(T, u64)>>>,
+    computing: Rc<SingleFlight<T>>,
+}
+
+impl<T> Clone for Memo<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ttl: self.ttl,
+            value: self.value.clone(),
+            computing: self.computing.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Memo<T> {
+    /// Cache values for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            value: Rc::new(RefCell::new(None)),
+            computing: Rc::new(SingleFlight::new()),
+        }
+    }
+
+    /// The cached value, if it hasn't expired.
+    pub fn peek(&self) -> Option<T> {
+        match &*self.value.borrow() {
+            Some((value, expires)) if Date::now().as_millis() < *expires => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Forget the cached value, so that the next call to [`get`](Memo::get) computes it again.
+    pub fn invalidate(&self) {
+        *self.value.borrow_mut() = None;
+    }
+
+    /// The cached value, or the result of `compute` if it expired. Errors aren't cached, and are
+    /// passed to the concurrent calls as [`Error::RustError`] with the same message.
+    pub async fn get<F, Fut>(&self, compute: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Some(value) = self.peek() {
+            return Ok(value);
+        }
+        self.computing
+            .run("", "memoized computation was cancelled", || async {
+                let value = compute().await?;
+                let expires = Date::now().as_millis() + self.ttl.as_millis() as u64;
+                *self.value.borrow_mut() = Some((value.clone(), expires));
+                Ok(value)
+            })
+            .await
+    }
+}
+
+#[test]
+fn cache_key_builder_works() {
+    let headers = |cookie: &'static str| {
+        move |name: &str| match name {
+            "cookie" => Some(cookie.to_string()),
+            "accept-language" => Some(" fr ".to_string()),
+            _ => None,
+        }
+    };
+    let url: url::Url = "https://example.com/posts?utm_source=x&page=2&q=rust#top"
+        .parse()
+        .unwrap();
+
+    let all = CacheKeyBuilder::new();
+    assert_eq!(
+        all.key(url.clone(), headers("")).unwrap(),
+        "https://example.com/posts?page=2&q=rust&utm_source=x"
+    );
+
+    let keys = CacheKeyBuilder::new()
+        .query_params(["page", "q"])
+        .header("Accept-Language")
+        .cookie("variant")
+        .session_cookie("session");
+    assert_eq!(
+        keys.key(url.clone(), headers("variant=b; theme=dark"))
+            .unwrap(),
+        "https://example.com/posts?page=2&q=rust&__vary_accept-language=fr&__cookie_variant=b"
+    );
+    assert_eq!(keys.key(url.clone(), headers("session=abc")), None);
+    assert!(keys
+        .clone()
+        .cache_authenticated(true)
+        .key(url.clone(), headers("session=abc"))
+        .is_some());
+    assert_eq!(
+        CacheKeyBuilder::new()
+            .ignore_query()
+            .key(url.clone(), |name| (name == "authorization")
+                .then(|| "Bearer x".into())),
+        None
+    );
+    assert_eq!(
+        CacheKeyBuilder::new()
+            .ignore_query()
+            .key(url, headers(""))
+            .unwrap(),
+        "https://example.com/posts"
+    );
+}
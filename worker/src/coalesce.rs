@@ -0,0 +1,176 @@
+use std::future::Future;
+
+use crate::{single_flight::SingleFlight, Headers, Request, Response, Result};
+
+/// Runs identical concurrent work once, sharing its result with every caller who asked for it
+/// while it was running, e.g. so that a burst of identical requests to a Durable Object makes a
+/// single expensive storage read.
+///
+/// Work is identified by a key. A call arriving while the work of its key is running waits for
+/// that work rather than starting its own; once the work is done the key is forgotten, so later
+/// calls run it again. Errors are shared as [`Error::RustError`](crate::Error::RustError)s with
+/// the same message. If the call running the work is dropped before it's done, the calls waiting
+/// for it fail.
+///
+/// ```no_run
+/// # use worker::*;
+/// pub struct Catalog {
+///     state: State,
+///     reads: Coalescer<Vec<String>>,
+/// }
+///
+/// impl Catalog {
+///     async fn products(&self, category: &str) -> Result<Vec<String>> {
+///         let storage = self.state.storage();
+///         self.reads
+///             .run(category, || async move {
+///                 Ok(storage.get(category).await.unwrap_or_default())
+///             })
+///             .await
+///     }
+/// }
+/// ```
+pub struct Coalescer<T> {
+    flight: SingleFlight<T>,
+}
+
+impl<T: Clone> Coalescer<T> {
+    pub fn new() -> Self {
+        Self {
+            flight: SingleFlight::new(),
+        }
+    }
+
+    /// Run the work `f` returns for `key`, or wait for the result of the work of `key` which is
+    /// already running.
+    pub async fn run<F, Fut>(&self, key: impl Into<String>, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let key = key.into();
+        let cancelled = format!("the coalesced work of {key:?} was cancelled");
+        self.flight.run(&key, &cancelled, f).await
+    }
+
+    /// The number of keys whose work is running.
+    pub fn in_flight(&self) -> usize {
+        self.flight.running()
+    }
+}
+
+impl<T: Clone> Default for Coalescer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The key of a request for a [`Coalescer`], from its method, URL and whole body, so requests
+/// only share a key if their bodies are the same. Headers aren't part of the key, so requests
+/// whose responses depend on them should be given a key of their own.
+pub async fn request_key(req: &Request) -> Result<String> {
+    let body = req.clone()?.bytes().await?;
+    Ok(format!(
+        "{} {} {}",
+        req.method().as_ref(),
+        req.url()?,
+        body.escape_ascii()
+    ))
+}
+
+#[derive(Clone)]
+struct BufferedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// A [`Coalescer`] of the responses of a Durable Object's `fetch` handler, keyed by
+/// [`request_key`]. Every coalesced request gets a copy of the response, which is read into
+/// memory, so it's not suitable for streaming or WebSocket responses.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(coalescer: &RequestCoalescer, storage: Storage, req: Request) -> Result<Response> {
+/// coalescer
+///     .fetch(req, |req| async move {
+///         let report: Option<String> = storage.get(&req.path()).await.ok();
+///         Response::from_json(&report)
+///     })
+///     .await
+/// # }
+/// ```
+#[derive(Default)]
+pub struct RequestCoalescer {
+    responses: Coalescer<BufferedResponse>,
+}
+
+impl RequestCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Respond to `req` with `handler`, or with a copy of the response to an identical request
+    /// which is being handled.
+    pub async fn fetch<F, Fut>(&self, req: Request, handler: F) -> Result<Response>
+    where
+        F: FnOnce(Request) -> Fut,
+        Fut: Future<Output = Result<Response>>,
+    {
+        let key = request_key(&req).await?;
+        let buffered = self
+            .responses
+            .run(key, || async move {
+                let mut resp = handler(req).await?;
+                Ok(BufferedResponse {
+                    status: resp.status_code(),
+                    headers: resp.headers().entries().collect(),
+                    body: resp.bytes().await?,
+                })
+            })
+            .await?;
+        let headers: Headers = buffered.headers.iter().collect();
+        Ok(Response::from_bytes(buffered.body)?
+            .with_status(buffered.status)
+            .with_headers(headers))
+    }
+
+    /// The number of distinct requests being handled.
+    pub fn in_flight(&self) -> usize {
+        self.responses.in_flight()
+    }
+}
+
+#[test]
+fn coalescer_works() {
+    use std::{cell::Cell, pin::pin, task::Context};
+
+    let coalescer = Coalescer::new();
+    let calls = Cell::new(0);
+    let (tx, rx) = futures_channel::oneshot::channel::<()>();
+    let first = coalescer.run("key", || async {
+        calls.set(calls.get() + 1);
+        let _ = rx.await;
+        Ok(1)
+    });
+    let second = coalescer.run("key", || async {
+        calls.set(calls.get() + 1);
+        Ok(2)
+    });
+    let mut both = pin!(futures_util::future::join(first, second));
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(both.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(coalescer.in_flight(), 1);
+    tx.send(()).unwrap();
+    match both.as_mut().poll(&mut cx) {
+        std::task::Poll::Ready((first, second)) => {
+            assert_eq!(first.unwrap(), 1);
+            assert_eq!(second.unwrap(), 1);
+        }
+        std::task::Poll::Pending => panic!("the coalesced work should be done"),
+    }
+    assert_eq!(calls.get(), 1);
+    assert_eq!(coalescer.in_flight(), 0);
+}
@@ -2,7 +2,7 @@ use std::future::Future;
 
 use crate::worker_sys::Context as JsContext;
 
-use wasm_bindgen::JsValue;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::future_to_promise;
 
 /// A context bound to a `fetch` event.
@@ -47,6 +47,14 @@ impl Context {
     }
 }
 
+impl Clone for Context {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone().unchecked_into(),
+        }
+    }
+}
+
 impl AsRef<JsContext> for Context {
     fn as_ref(&self) -> &JsContext {
         &self.inner
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "durable-primitives")]
+use crate::primitives::LeaseClient;
+use crate::{kv::KvStore, Date, Env, Result, ScheduleContext};
+
+/// The KV namespace [`cron_lock`] stores its locks in.
+pub const CRON_LOCK_BINDING: &str = "CRON_LOCKS";
+
+const PREFIX: &str = "cron-lock:";
+
+#[derive(Serialize, Deserialize)]
+struct Held {
+    holder: String,
+    expires_at: u64,
+}
+
+#[derive(Clone)]
+enum Backend {
+    Kv(KvStore),
+    #[cfg(feature = "durable-primitives")]
+    Lease(std::rc::Rc<LeaseClient>),
+}
+
+/// Take the best-effort lock of the cron job `name` for `ttl` from the [`CRON_LOCK_BINDING`] KV
+/// namespace, or `None` if another run of the job has it, see [`CronLock`].
+pub async fn cron_lock(env: &Env, name: &str, ttl: Duration) -> Result<Option<CronLock>> {
+    CronLock::kv(env.kv(CRON_LOCK_BINDING)?, name, ttl).await
+}
+
+/// A lock preventing overlapping runs of a cron job, e.g. when a run takes longer than the
+/// interval of its schedule, or the same schedule fires in more than one location.
+///
+/// The lock is held until it's released, or its TTL passes so that a run which crashed doesn't
+/// keep it forever. The TTL should be longer than a run can take. Dropping the lock releases it
+/// in the background, but the Worker may be stopped before that's done, so it's better to
+/// release it with [`release`](CronLock::release) or [`release_on`](CronLock::release_on).
+///
+/// A lock stored in KV is best-effort: KV is eventually consistent, so runs starting in
+/// different locations within a minute of each other can both take it, as each may read back its
+/// own write. It only stops overlapping runs reliably when they start far enough apart for KV to
+/// have propagated the lock, such as a long run overlapping with the next one. Use
+/// [`CronLock::lease`] for a lock backed by a [`DurableLease`](crate::primitives::DurableLease),
+/// which only one run can hold at a time.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use std::time::Duration;
+/// # async fn example(event: ScheduledEvent, env: Env, ctx: ScheduleContext) -> Result<()> {
+/// let Some(lock) = cron_lock(&env, "daily-report", Duration::from_secs(15 * 60)).await? else {
+///     console_log!("the previous run of {} is still going", event.cron());
+///     return Ok(());
+/// };
+/// // ... the job ...
+/// lock.release_on(&ctx);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CronLock {
+    backend: Backend,
+    key: String,
+    holder: String,
+    released: bool,
+}
+
+impl CronLock {
+    /// Take the best-effort lock of the cron job `name` for `ttl` from `kv`, or `None` if it's held.
+    /// KV can't expire keys sooner than 60 seconds, so a lock held for less is still taken until
+    /// then.
+    pub async fn kv(kv: KvStore, name: &str, ttl: Duration) -> Result<Option<Self>> {
+        let key = format!("{PREFIX}{name}");
+        let now = Date::now().as_millis();
+        if let Some(held) = kv.get(&key).skip_consistency_check().json::<Held>().await? {
+            if held.expires_at > now {
+                return Ok(None);
+            }
+        }
+
+        let held = Held {
+            holder: holder(),
+            expires_at: now + ttl.as_millis() as u64,
+        };
+        let expiration_ttl = ttl.as_secs().max(crate::kv::MIN_EXPIRATION_TTL);
+        kv.put(&key, &held)?
+            .expiration_ttl(expiration_ttl)
+            .execute()
+            .await?;
+        // Another run in this location which read the key at the same time may have overwritten
+        // it since. Runs in other locations may not be visible yet, which is why the lock is only
+        // best-effort.
+        match kv.get(&key).skip_consistency_check().json::<Held>().await? {
+            Some(current) if current.holder == held.holder => Ok(Some(Self {
+                backend: Backend::Kv(kv),
+                key,
+                holder: held.holder,
+                released: false,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Take the lock of the cron job `name` for `ttl` from a
+    /// [`DurableLease`](crate::primitives::DurableLease) in `namespace`, or `None` if it's held.
+    ///
+    /// **Requires** `durable-primitives` feature.
+    #[cfg(feature = "durable-primitives")]
+    pub async fn lease(
+        namespace: &crate::ObjectNamespace,
+        name: &str,
+        ttl: Duration,
+    ) -> Result<Option<Self>> {
+        let client = LeaseClient::new(namespace, &format!("{PREFIX}{name}"))?;
+        let holder = holder();
+        if client.acquire(&holder, ttl).await?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            backend: Backend::Lease(std::rc::Rc::new(client)),
+            key: name.into(),
+            holder,
+            released: false,
+        }))
+    }
+
+    /// Release the lock, unless it expired and was taken by another run since.
+    pub async fn release(mut self) -> Result<()> {
+        self.released = true;
+        release(self.backend.clone(), self.key.clone(), self.holder.clone()).await
+    }
+
+    /// Release the lock once `ctx` is done waiting for the rest of the job.
+    pub fn release_on(self, ctx: &ScheduleContext) {
+        ctx.wait_until(async move {
+            let _ = self.release().await;
+        });
+    }
+}
+
+impl Drop for CronLock {
+    fn drop(&mut self) {
+        if !self.released {
+            let release = release(self.backend.clone(), self.key.clone(), self.holder.clone());
+            crate::futures::spawn_local(async move {
+                let _ = release.await;
+            });
+        }
+    }
+}
+
+async fn release(backend: Backend, key: String, holder: String) -> Result<()> {
+    match backend {
+        Backend::Kv(kv) => {
+            let held = kv.get(&key).skip_consistency_check().json::<Held>().await?;
+            if matches!(held, Some(held) if held.holder == holder) {
+                kv.delete(&key).await?;
+            }
+        }
+        #[cfg(feature = "durable-primitives")]
+        Backend::Lease(client) => {
+            client.release(&holder).await?;
+        }
+    }
+    Ok(())
+}
+
+/// A random identifier of this run.
+fn holder() -> String {
+    let random = (js_sys::Math::random() * (1u64 << 53) as f64) as u64;
+    format!("{:x}-{random:x}", Date::now().as_millis())
+}
@@ -0,0 +1,101 @@
+//! Helpers for the runtime's [Web Crypto](https://developers.cloudflare.com/workers/runtime-apis/web-crypto/)
+//! API, exposed as `crypto.subtle`.
+
+use js_sys::{Array, Object, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CryptoKey, SubtleCrypto};
+
+use crate::{Error, Result};
+
+/// Get the global [`SubtleCrypto`] instance.
+pub fn subtle() -> Result<SubtleCrypto> {
+    let global: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
+    Ok(global.crypto()?.subtle())
+}
+
+/// Generate `len` cryptographically secure random bytes with `crypto.getRandomValues`.
+pub fn random_bytes(len: usize) -> Result<Vec<u8>> {
+    let global: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
+    let mut bytes = vec![0; len];
+    global
+        .crypto()?
+        .get_random_values_with_u8_array(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Build a WebCrypto algorithm identifier of the form `{ name, hash }`, e.g. `("HMAC", "SHA-256")`.
+pub fn algorithm(name: &str, hash: Option<&str>) -> Object {
+    let obj = Object::new();
+    set(&obj, "name", &name.into());
+    if let Some(hash) = hash {
+        set(&obj, "hash", &hash.into());
+    }
+    obj
+}
+
+/// Imports a key from a [JSON Web Key](https://datatracker.ietf.org/doc/html/rfc7517) for the
+/// given algorithm and usages (e.g. `["verify"]`).
+pub async fn import_jwk(jwk: &JsValue, algorithm: &Object, usages: &[&str]) -> Result<CryptoKey> {
+    let key_data: &Object = jwk.dyn_ref().ok_or(Error::BadEncoding)?;
+    let promise = subtle()?.import_key_with_object(
+        "jwk",
+        key_data,
+        algorithm,
+        false,
+        &usages.iter().map(|u| JsValue::from(*u)).collect::<Array>(),
+    )?;
+    Ok(JsFuture::from(promise).await?.unchecked_into())
+}
+
+/// Imports raw key material (e.g. an HMAC secret) for the given algorithm and usages.
+pub async fn import_raw_key(key: &[u8], algorithm: &Object, usages: &[&str]) -> Result<CryptoKey> {
+    let promise = subtle()?.import_key_with_object(
+        "raw",
+        &Uint8Array::from(key),
+        algorithm,
+        false,
+        &usages.iter().map(|u| JsValue::from(*u)).collect::<Array>(),
+    )?;
+    Ok(JsFuture::from(promise).await?.unchecked_into())
+}
+
+/// Signs `data` using `key`, returning the raw signature bytes.
+pub async fn sign(algorithm: &Object, key: &CryptoKey, data: &[u8]) -> Result<Vec<u8>> {
+    let promise =
+        subtle()?.sign_with_object_and_buffer_source(algorithm, key, &Uint8Array::from(data))?;
+    let signature = JsFuture::from(promise).await?;
+    Ok(Uint8Array::new(&signature).to_vec())
+}
+
+/// Hashes `data` with the digest algorithm `name`, e.g. `SHA-256`, returning the raw hash bytes.
+pub async fn digest(name: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let promise = subtle()?.digest_with_str_and_buffer_source(name, &Uint8Array::from(data))?;
+    let hash = JsFuture::from(promise).await?;
+    Ok(Uint8Array::new(&hash).to_vec())
+}
+
+/// Verifies `signature` over `data` using `key`, returning whether the signature is valid.
+pub async fn verify(
+    algorithm: &Object,
+    key: &CryptoKey,
+    signature: &[u8],
+    data: &[u8],
+) -> Result<bool> {
+    let promise = subtle()?.verify_with_object_and_buffer_source_and_buffer_source(
+        algorithm,
+        key,
+        &Uint8Array::from(signature),
+        &Uint8Array::from(data),
+    )?;
+    Ok(JsFuture::from(promise).await?.as_bool().unwrap_or(false))
+}
+
+fn set(obj: &Object, key: &str, value: &JsValue) {
+    let r = js_sys::Reflect::set(obj, &JsValue::from(key), value);
+    debug_assert!(
+        r.is_ok(),
+        "setting properties should never fail on our dictionary objects"
+    );
+    let _ = r;
+}
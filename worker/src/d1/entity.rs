@@ -0,0 +1,87 @@
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+use super::{D1Database, D1PreparedStatement};
+use crate::Result;
+
+/// A struct mapped to the rows of a D1 table, usually implemented with
+/// [`#[derive(D1Entity)]`](derive@crate::d1::D1Entity), which gives the statements to insert, find,
+/// update and delete a row by its primary key.
+///
+/// ```ignore
+/// use serde::{Deserialize, Serialize};
+/// use worker::d1::D1Entity;
+///
+/// #[derive(Serialize, Deserialize, D1Entity)]
+/// #[d1(table = "users")]
+/// struct User {
+///     id: Option<u32>,
+///     name: String,
+///     email: String,
+/// }
+///
+/// # async fn example(db: worker::D1Database) -> worker::Result<()> {
+/// let user = User { id: None, name: "Ferris".into(), email: "ferris@example.com".into() };
+/// user.insert(&db)?.run().await?;
+/// if let Some(mut user) = User::find_by_id(&db, &1)?.first::<User>(None).await? {
+///     user.name = "Corro".into();
+///     user.update(&db)?.run().await?;
+/// }
+/// User::delete(&db, &1)?.run().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Fields are bound to the statements with `serde`, `None` as `NULL`, so an `INTEGER PRIMARY KEY`
+/// of `None` is assigned by SQLite when the row is inserted. The columns are the fields of the
+/// struct, which should deserialize from the rows under the same names.
+pub trait D1Entity {
+    const TABLE: &'static str;
+    /// Every column, in the order of the struct's fields.
+    const COLUMNS: &'static [&'static str];
+    const PRIMARY_KEY: &'static str;
+    /// Inserts every column, in the order of [`COLUMNS`](D1Entity::COLUMNS).
+    const INSERT: &'static str;
+    /// Selects every column of the row with the primary key `?1`.
+    const SELECT_BY_ID: &'static str;
+    /// Sets every column but the primary key, which is the last parameter.
+    const UPDATE: &'static str;
+    /// Deletes the row with the primary key `?1`.
+    const DELETE: &'static str;
+
+    /// The parameters of [`INSERT`](D1Entity::INSERT).
+    fn insert_values(&self) -> Result<Vec<JsValue>>;
+    /// The parameters of [`UPDATE`](D1Entity::UPDATE).
+    fn update_values(&self) -> Result<Vec<JsValue>>;
+    /// The value of the primary key.
+    fn id_value(&self) -> Result<JsValue>;
+
+    /// A statement inserting this row.
+    fn insert(&self, db: &D1Database) -> Result<D1PreparedStatement> {
+        db.prepare(Self::INSERT).bind(&self.insert_values()?)
+    }
+
+    /// A statement selecting the row with the primary key `id`, to be read with
+    /// [`first`](D1PreparedStatement::first).
+    fn find_by_id<T: Serialize + ?Sized>(db: &D1Database, id: &T) -> Result<D1PreparedStatement> {
+        db.prepare(Self::SELECT_BY_ID).bind(&[to_d1_value(id)?])
+    }
+
+    /// A statement updating the row with this primary key.
+    fn update(&self, db: &D1Database) -> Result<D1PreparedStatement> {
+        db.prepare(Self::UPDATE).bind(&self.update_values()?)
+    }
+
+    /// A statement deleting the row with the primary key `id`.
+    fn delete<T: Serialize + ?Sized>(db: &D1Database, id: &T) -> Result<D1PreparedStatement> {
+        db.prepare(Self::DELETE).bind(&[to_d1_value(id)?])
+    }
+}
+
+/// The parameter D1 binds for `value`, like [`query!`](crate::query) does.
+#[doc(hidden)]
+pub fn to_d1_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue> {
+    // D1 doesn't support taking in undefined values, so we translate these missing values to NULL.
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_missing_as_null(true);
+    Ok(value.serialize(&serializer)?)
+}
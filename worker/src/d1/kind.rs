@@ -0,0 +1,125 @@
+/// The cause of a [`D1Error`](crate::d1::D1Error), parsed from its message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum D1ErrorKind {
+    /// A constraint of the schema was violated, e.g. by inserting a duplicate value into a
+    /// `UNIQUE` column.
+    ConstraintViolation {
+        constraint: ConstraintKind,
+        /// The name of the violated constraint, or the columns it applies to (e.g.
+        /// `users.email`), if SQLite reported it.
+        name: Option<String>,
+    },
+    /// The query couldn't be parsed.
+    SyntaxError {
+        /// The token the error was found at.
+        near: Option<String>,
+        /// The offset of the error in the query.
+        offset: Option<usize>,
+    },
+    /// The query returned or touched more rows than D1 allows.
+    TooManyRows,
+    /// The database was locked or overloaded, the query may succeed when it's retried later.
+    Busy,
+    /// Any other error.
+    Other,
+}
+
+/// The kind of constraint in a [`D1ErrorKind::ConstraintViolation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    Unique,
+    PrimaryKey,
+    NotNull,
+    ForeignKey,
+    Check,
+    Other,
+}
+
+impl D1ErrorKind {
+    /// Parse the kind of error from the message of a D1 error, e.g.
+    /// `D1_ERROR: UNIQUE constraint failed: users.email: SQLITE_CONSTRAINT`.
+    pub fn from_message(message: &str) -> Self {
+        let lower = message.to_ascii_lowercase();
+
+        if let Some(index) = lower.find(" constraint failed") {
+            let constraint = match lower[..index].rsplit(": ").next().unwrap_or("").trim() {
+                s if s.ends_with("unique") => ConstraintKind::Unique,
+                s if s.ends_with("primary key") => ConstraintKind::PrimaryKey,
+                s if s.ends_with("not null") => ConstraintKind::NotNull,
+                s if s.ends_with("foreign key") => ConstraintKind::ForeignKey,
+                s if s.ends_with("check") => ConstraintKind::Check,
+                _ => ConstraintKind::Other,
+            };
+            let name = message[index + " constraint failed".len()..]
+                .strip_prefix(": ")
+                .map(|rest| rest.split(": SQLITE_").next().unwrap_or(rest).trim())
+                .filter(|name| !name.is_empty() && !name.starts_with("SQLITE_"))
+                .map(String::from);
+            return Self::ConstraintViolation { constraint, name };
+        }
+
+        if lower.contains("syntax error") || lower.contains("incomplete input") {
+            let near = message.find("near \"").and_then(|start| {
+                let rest = &message[start + "near \"".len()..];
+                rest.find('"').map(|end| rest[..end].to_string())
+            });
+            let offset = lower.find("at offset ").and_then(|start| {
+                lower[start + "at offset ".len()..]
+                    .split(|c: char| !c.is_ascii_digit())
+                    .next()
+                    .and_then(|offset| offset.parse().ok())
+            });
+            return Self::SyntaxError { near, offset };
+        }
+
+        if lower.contains("too many rows") || lower.contains("result set too large") {
+            return Self::TooManyRows;
+        }
+
+        if lower.contains("sqlite_busy")
+            || lower.contains("database is locked")
+            || lower.contains("overloaded")
+        {
+            return Self::Busy;
+        }
+
+        Self::Other
+    }
+}
+
+#[test]
+fn from_message_works() {
+    assert_eq!(
+        D1ErrorKind::from_message(
+            "D1_ERROR: UNIQUE constraint failed: users.email: SQLITE_CONSTRAINT"
+        ),
+        D1ErrorKind::ConstraintViolation {
+            constraint: ConstraintKind::Unique,
+            name: Some("users.email".into())
+        }
+    );
+    assert_eq!(
+        D1ErrorKind::from_message("D1_ERROR: FOREIGN KEY constraint failed: SQLITE_CONSTRAINT"),
+        D1ErrorKind::ConstraintViolation {
+            constraint: ConstraintKind::ForeignKey,
+            name: None
+        }
+    );
+    assert_eq!(
+        D1ErrorKind::from_message(
+            "D1_ERROR: near \"SELEC\": syntax error at offset 0: SQLITE_ERROR"
+        ),
+        D1ErrorKind::SyntaxError {
+            near: Some("SELEC".into()),
+            offset: Some(0)
+        }
+    );
+    assert_eq!(
+        D1ErrorKind::from_message("D1_ERROR: database is locked: SQLITE_BUSY"),
+        D1ErrorKind::Busy
+    );
+    assert_eq!(
+        D1ErrorKind::from_message("D1_ERROR: no such table: users: SQLITE_ERROR"),
+        D1ErrorKind::Other
+    );
+}
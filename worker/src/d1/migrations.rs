@@ -0,0 +1,278 @@
+//! Schema migrations applied by the Worker itself, e.g. from an admin route or a Cron Trigger,
+//! instead of with `wrangler d1 migrations apply`.
+//!
+//! Migrations are SQL scripts embedded in the Worker, applied in order. The names of the applied
+//! migrations are stored in a table of the database, `_migrations` by default, so every migration
+//! is applied once. Each migration is applied in a single [`batch`](D1Database::batch) with the
+//! insertion of its name, which D1 runs as a transaction: a migration which fails leaves no trace,
+//! and a migration applied concurrently by another request fails to record its name.
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::d1::migrations::{Migration, Migrator};
+//!
+//! const MIGRATIONS: &[Migration] = &[
+//!     Migration::new("0001_users", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);"),
+//!     Migration::new(
+//!         "0002_emails",
+//!         // Usually `include_str!("../migrations/0002_emails.sql")`.
+//!         "ALTER TABLE users ADD COLUMN email TEXT; CREATE INDEX users_email ON users (email);",
+//!     ),
+//! ];
+//!
+//! #[event(scheduled)]
+//! async fn scheduled(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+//!     let db = env.d1("DB").unwrap();
+//!     let applied = Migrator::new(&db, MIGRATIONS).run().await.unwrap();
+//!     console_log!("applied migrations: {applied:?}");
+//! }
+//! ```
+
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+
+use super::D1Database;
+use crate::Result;
+
+/// A named SQL script of one or more statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    name: &'static str,
+    sql: &'static str,
+}
+
+impl Migration {
+    pub const fn new(name: &'static str, sql: &'static str) -> Self {
+        Self { name, sql }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn sql(&self) -> &'static str {
+        self.sql
+    }
+
+    /// The statements of the script, which D1 prepares one at a time.
+    pub fn statements(&self) -> Vec<&'static str> {
+        split_statements(self.sql)
+    }
+}
+
+/// Applies [`Migration`]s to a database.
+pub struct Migrator<'a> {
+    db: &'a D1Database,
+    migrations: &'a [Migration],
+    table: String,
+}
+
+#[derive(Deserialize)]
+struct AppliedMigration {
+    name: String,
+}
+
+impl<'a> Migrator<'a> {
+    /// A migrator applying `migrations` to `db`, in order.
+    pub fn new(db: &'a D1Database, migrations: &'a [Migration]) -> Self {
+        Self {
+            db,
+            migrations,
+            table: "_migrations".into(),
+        }
+    }
+
+    /// The table storing the names of applied migrations.
+    #[must_use]
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    fn quoted_table(&self) -> String {
+        format!("\"{}\"", self.table.replace('"', "\"\""))
+    }
+
+    /// The names of the applied migrations, in the order they were applied.
+    pub async fn applied(&self) -> Result<Vec<String>> {
+        self.db
+            .exec(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (name TEXT PRIMARY KEY, \
+                 applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP)",
+                self.quoted_table()
+            ))
+            .await?;
+        let applied = self
+            .db
+            .prepare(format!(
+                "SELECT name FROM {} ORDER BY rowid",
+                self.quoted_table()
+            ))
+            .all()
+            .await?
+            .results::<AppliedMigration>()?;
+        Ok(applied.into_iter().map(|applied| applied.name).collect())
+    }
+
+    /// The migrations which haven't been applied yet.
+    pub async fn pending(&self) -> Result<Vec<Migration>> {
+        let applied = self.applied().await?;
+        Ok(self
+            .migrations
+            .iter()
+            .filter(|migration| !applied.iter().any(|name| name == migration.name))
+            .copied()
+            .collect())
+    }
+
+    /// Apply the pending migrations, returning their names. Migrations are applied until one
+    /// fails, whose error is returned.
+    pub async fn run(&self) -> Result<Vec<&'static str>> {
+        let mut applied = Vec::new();
+        for migration in self.pending().await? {
+            let mut statements: Vec<_> = migration
+                .statements()
+                .into_iter()
+                .map(|statement| self.db.prepare(statement))
+                .collect();
+            statements.push(
+                self.db
+                    .prepare(format!(
+                        "INSERT INTO {} (name) VALUES (?)",
+                        self.quoted_table()
+                    ))
+                    .bind(&[JsValue::from_str(migration.name)])?,
+            );
+            self.db.batch(statements).await?;
+            applied.push(migration.name);
+        }
+        Ok(applied)
+    }
+}
+
+/// Split `sql` into statements at the `;` which aren't in a string, an identifier, a comment, or
+/// the `BEGIN ... END` body of a trigger. A `BEGIN` outside of `CREATE TRIGGER` starts a
+/// transaction, which is a statement of its own.
+fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    // Whether the current statement has anything but whitespace and comments.
+    let mut has_code = false;
+    // The first word of the current statement, and whether it creates a trigger, whose `BEGIN`
+    // starts its body rather than a transaction.
+    let mut first_word = None;
+    let mut trigger = false;
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b';' if depth == 0 => {
+                if has_code {
+                    statements.push(sql[start..i].trim());
+                }
+                start = i + 1;
+                has_code = false;
+                first_word = None;
+                trigger = false;
+            }
+            byte if byte.is_ascii_whitespace() => {}
+            quote @ (b'\'' | b'"' | b'`') => {
+                has_code = true;
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'[' => {
+                has_code = true;
+                while i < bytes.len() && bytes[i] != b']' {
+                    i += 1;
+                }
+            }
+            byte if byte.is_ascii_alphabetic() || byte == b'_' => {
+                has_code = true;
+                let word_start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                let word = &sql[word_start..i];
+                let creates = first_word
+                    .get_or_insert(word)
+                    .eq_ignore_ascii_case("create");
+                if creates && word.eq_ignore_ascii_case("trigger") {
+                    trigger = true;
+                }
+                if (word.eq_ignore_ascii_case("begin") && trigger)
+                    || word.eq_ignore_ascii_case("case")
+                {
+                    depth += 1;
+                } else if word.eq_ignore_ascii_case("end") {
+                    depth = depth.saturating_sub(1);
+                }
+                continue;
+            }
+            _ => has_code = true,
+        }
+        i += 1;
+    }
+    if has_code {
+        statements.push(sql[start..].trim());
+    }
+    statements
+}
+
+#[test]
+fn split_statements_works() {
+    let sql = "
+        -- The users; and their posts.
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT DEFAULT 'a;b');
+        /* ; */ CREATE TABLE \"posts;\" (id INTEGER PRIMARY KEY, user_id INTEGER);
+        CREATE TRIGGER delete_posts AFTER DELETE ON users BEGIN
+            DELETE FROM \"posts;\" WHERE user_id = old.id;
+            SELECT CASE WHEN 1 THEN 1 END;
+        END;
+        INSERT INTO users (name) VALUES ('Ferris');
+        -- Done.
+    ";
+    let statements = split_statements(sql);
+    assert_eq!(statements.len(), 4);
+    assert!(statements[0].ends_with("DEFAULT 'a;b')"));
+    assert!(statements[1].starts_with("/* ; */ CREATE TABLE"));
+    assert!(statements[2].starts_with("CREATE TRIGGER") && statements[2].ends_with("END"));
+    assert_eq!(statements[3], "INSERT INTO users (name) VALUES ('Ferris')");
+}
+
+#[test]
+fn split_statements_handles_transactions() {
+    let sql = "
+        BEGIN TRANSACTION;
+        CREATE TABLE tags (id INTEGER PRIMARY KEY);
+        CREATE TRIGGER delete_tags AFTER DELETE ON tags BEGIN DELETE FROM tags; END;
+        COMMIT;
+        INSERT INTO tags (id) VALUES (1);
+    ";
+    let statements = split_statements(sql);
+    assert_eq!(
+        statements,
+        [
+            "BEGIN TRANSACTION",
+            "CREATE TABLE tags (id INTEGER PRIMARY KEY)",
+            "CREATE TRIGGER delete_tags AFTER DELETE ON tags BEGIN DELETE FROM tags; END",
+            "COMMIT",
+            "INSERT INTO tags (id) VALUES (1)",
+        ]
+    );
+}
@@ -17,12 +17,20 @@ use worker_sys::types::D1PreparedStatement as D1PreparedStatementSys;
 use worker_sys::types::D1Result as D1ResultSys;
 
 use crate::env::EnvBinding;
+use crate::request_metrics::{record, Call};
 use crate::Error;
 use crate::Result;
 
 pub use serde_wasm_bindgen;
 
+mod entity;
+mod kind;
 pub mod macros;
+pub mod migrations;
+
+pub use entity::*;
+pub use kind::*;
+pub use worker_macros::D1Entity;
 
 // A D1 Database.
 pub struct D1Database(D1DatabaseSys);
@@ -38,6 +46,7 @@ impl D1Database {
 
     /// Dump the data in the database to a `Vec`.
     pub async fn dump(&self) -> Result<Vec<u8>> {
+        record(Call::D1);
         let result = JsFuture::from(self.0.dump()).await;
         let array_buffer = cast_to_d1_error(result)?;
         let array_buffer = array_buffer.dyn_into::<ArrayBuffer>()?;
@@ -50,6 +59,7 @@ impl D1Database {
     /// Returns the results in the same order as the provided statements.
     pub async fn batch(&self, statements: Vec<D1PreparedStatement>) -> Result<Vec<D1Result>> {
         let statements = statements.into_iter().map(|s| s.0).collect::<Array>();
+        record(Call::D1);
         let results = JsFuture::from(self.0.batch(statements)).await;
         let results = cast_to_d1_error(results)?;
         let results = results.dyn_into::<Array>()?;
@@ -74,6 +84,7 @@ impl D1Database {
     /// If an error occurs, an exception is thrown with the query and error
     /// messages, execution stops and further statements are not executed.
     pub async fn exec(&self, query: &str) -> Result<D1ExecResult> {
+        record(Call::D1);
         let result = JsFuture::from(self.0.exec(query)).await;
         let result = cast_to_d1_error(result)?;
         Ok(result.into())
@@ -282,6 +293,7 @@ impl D1PreparedStatement {
     where
         T: for<'a> Deserialize<'a>,
     {
+        record(Call::D1);
         let result = JsFuture::from(self.0.first(col_name)).await;
         let js_value = cast_to_d1_error(result)?;
         let value = serde_wasm_bindgen::from_value(js_value)?;
@@ -290,6 +302,7 @@ impl D1PreparedStatement {
 
     /// Executes a query against the database but only return metadata.
     pub async fn run(&self) -> Result<D1Result> {
+        record(Call::D1);
         let result = JsFuture::from(self.0.run()).await;
         let result = cast_to_d1_error(result)?;
         Ok(D1Result(result.into()))
@@ -297,6 +310,7 @@ impl D1PreparedStatement {
 
     /// Executes a query against the database and returns all rows and metadata.
     pub async fn all(&self) -> Result<D1Result> {
+        record(Call::D1);
         let result = JsFuture::from(self.0.all()).await?;
         Ok(D1Result(result.into()))
     }
@@ -306,6 +320,7 @@ impl D1PreparedStatement {
     where
         T: for<'a> Deserialize<'a>,
     {
+        record(Call::D1);
         let result = JsFuture::from(self.0.raw()).await;
         let result = cast_to_d1_error(result)?;
         let result = result.dyn_into::<Array>()?;
@@ -372,6 +387,15 @@ impl D1Error {
             "unknown error".into()
         }
     }
+
+    /// The cause of the error, e.g. to handle the violation of a `UNIQUE` constraint.
+    pub fn kind(&self) -> D1ErrorKind {
+        let message = match self.inner.cause().dyn_into::<js_sys::Error>() {
+            Ok(cause) => String::from(cause.message()),
+            Err(_) => String::from(self.inner.message()),
+        };
+        D1ErrorKind::from_message(&message)
+    }
 }
 
 impl std::fmt::Debug for D1Error {
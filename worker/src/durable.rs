@@ -10,20 +10,25 @@
 //! [Learn more](https://developers.cloudflare.com/workers/learning/using-durable-objects) about
 //! using Durable Objects.
 
-use std::{ops::Deref, time::Duration};
+use std::{collections::VecDeque, ops::Deref, time::Duration};
 
+#[cfg(feature = "websocket")]
+use crate::WebSocket;
 use crate::{
     date::Date,
     env::{Env, EnvBinding},
     error::Error,
+    http::Method,
     request::Request,
+    request_metrics::{record, Call},
     response::Response,
-    Result, WebSocket,
+    serializer::{value_size, SerializerOptions},
+    Result,
 };
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use futures_util::Future;
+use futures_util::{Future, Stream, StreamExt};
 use js_sys::{Map, Number, Object};
 use serde::{de::DeserializeOwned, Serialize};
 use wasm_bindgen::{prelude::*, JsCast};
@@ -43,6 +48,7 @@ pub struct Stub {
 impl Stub {
     /// Send an internal Request to the Durable Object to which the stub points.
     pub async fn fetch_with_request(&self, req: Request) -> Result<Response> {
+        record(Call::DurableObject);
         let promise = self.inner.fetch_with_request(req.inner());
         let response = JsFuture::from(promise).await?;
         Ok(response.dyn_into::<web_sys::Response>()?.into())
@@ -50,10 +56,47 @@ impl Stub {
 
     /// Construct a Request from a URL to the Durable Object to which the stub points.
     pub async fn fetch_with_str(&self, url: &str) -> Result<Response> {
+        record(Call::DurableObject);
         let promise = self.inner.fetch_with_str(url);
         let response = JsFuture::from(promise).await?;
         Ok(response.dyn_into::<web_sys::Response>()?.into())
     }
+
+    /// Send a request with the given method to `path` of the Durable Object, e.g. `/count`. The
+    /// request gets a placeholder host, as the URL only needs to be meaningful to the object.
+    pub async fn request(&self, method: Method, path: &str) -> Result<Response> {
+        let req = Request::new(&internal_url(path), method)?;
+        self.fetch_with_request(req).await
+    }
+
+    /// Send a `GET` request to `path` of the Durable Object and deserialize the JSON response,
+    /// failing if the object responds with an error status.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let resp = self.request(Method::Get, path).await?;
+        json_response(resp, path).await
+    }
+}
+
+/// The JSON body of the response to `GET path`, or an error if its status isn't successful.
+pub(crate) async fn json_response<T: DeserializeOwned>(
+    mut resp: Response,
+    path: &str,
+) -> Result<T> {
+    let status = resp.status_code();
+    if !(200..300).contains(&status) {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(Error::RustError(format!(
+            "Durable Object responded to GET {path} with {status}: {body}"
+        )));
+    }
+    resp.json().await
+}
+
+/// The origin used for the URLs of requests sent by [`Stub::request`].
+const STUB_ORIGIN: &str = "https://durable-object.internal";
+
+pub(crate) fn internal_url(path: &str) -> String {
+    format!("{STUB_ORIGIN}/{}", path.trim_start_matches('/'))
 }
 
 /// Use an ObjectNamespace to get access to Stubs for communication with a Durable Object instance.
@@ -195,16 +238,19 @@ impl State {
         self.inner
     }
 
+    #[cfg(feature = "websocket")]
     pub fn accept_web_socket(&self, ws: &WebSocket) {
         self.inner.accept_websocket(ws.as_ref())
     }
 
+    #[cfg(feature = "websocket")]
     pub fn accept_websocket_with_tags(&self, ws: &WebSocket, tags: &[&str]) {
         let tags = tags.iter().map(|it| (*it).into()).collect();
 
         self.inner.accept_websocket_with_tags(ws.as_ref(), tags);
     }
 
+    #[cfg(feature = "websocket")]
     pub fn get_websockets(&self) -> Vec<WebSocket> {
         self.inner
             .get_websockets()
@@ -213,6 +259,7 @@ impl State {
             .collect()
     }
 
+    #[cfg(feature = "websocket")]
     pub fn get_websockets_with_tag(&self, tag: &str) -> Vec<WebSocket> {
         self.inner
             .get_websockets_with_tag(tag)
@@ -222,6 +269,7 @@ impl State {
     }
 
     /// Retrieve tags from a hibernatable websocket
+    #[cfg(feature = "websocket")]
     pub fn get_tags(&self, websocket: &WebSocket) -> Vec<String> {
         self.inner.get_tags(websocket.as_ref())
     }
@@ -236,6 +284,7 @@ impl From<DurableObjectState> for State {
 /// Access a Durable Object's Storage API. Each method is implicitly wrapped inside a transaction,
 /// such that its results are atomic and isolated from all other storage operations, even when
 /// accessing multiple key-value pairs.
+#[derive(Clone)]
 pub struct Storage {
     inner: DurableObjectStorage,
 }
@@ -246,7 +295,20 @@ impl Storage {
     ///
     /// Returns [Err] if the key does not exist.
     pub async fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<T> {
-        JsFuture::from(self.inner.get(key)?)
+        self.get_with_options(key, StorageGetOptions::default())
+            .await
+    }
+
+    /// Retrieves the value associated with the given key, see [`StorageGetOptions`].
+    ///
+    /// Returns [Err] if the key does not exist.
+    pub async fn get_with_options<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+        options: StorageGetOptions,
+    ) -> Result<T> {
+        let options = serde_wasm_bindgen::to_value(&options)?.into();
+        JsFuture::from(self.inner.get_with_options(key, options)?)
             .await
             .and_then(|val| {
                 if val.is_undefined() {
@@ -260,10 +322,21 @@ impl Storage {
 
     /// Retrieves the values associated with each of the provided keys.
     pub async fn get_multiple(&self, keys: Vec<impl Deref<Target = str>>) -> Result<Map> {
-        let keys = self.inner.get_multiple(
+        self.get_multiple_with_options(keys, StorageGetOptions::default())
+            .await
+    }
+
+    /// Retrieves the values associated with each of the provided keys, see [`StorageGetOptions`].
+    pub async fn get_multiple_with_options(
+        &self,
+        keys: Vec<impl Deref<Target = str>>,
+        options: StorageGetOptions,
+    ) -> Result<Map> {
+        let keys = self.inner.get_multiple_with_options(
             keys.into_iter()
                 .map(|key| JsValue::from(key.deref()))
                 .collect(),
+            serde_wasm_bindgen::to_value(&options)?.into(),
         )?;
         let keys = JsFuture::from(keys).await?;
         keys.dyn_into::<Map>().map_err(Error::from)
@@ -271,12 +344,45 @@ impl Storage {
 
     /// Stores the value and associates it with the given key.
     pub async fn put<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
-        self.put_raw(key, serde_wasm_bindgen::to_value(&value)?)
+        self.put_raw(key, crate::serializer::to_value(&value)?)
+            .await
+    }
+
+    /// Stores the value and associates it with the given key, converting it into JS with
+    /// `serializer` rather than the [options of the Worker](crate::set_serializer_options).
+    pub async fn put_with_serializer<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        serializer: &SerializerOptions,
+    ) -> Result<()> {
+        self.put_raw(key, serializer.to_value(&value)?).await
+    }
+
+    /// Stores the value and associates it with the given key, see [`StoragePutOptions`].
+    pub async fn put_with_options<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        options: StoragePutOptions,
+    ) -> Result<()> {
+        self.put_raw_with_options(key, crate::serializer::to_value(&value)?, options)
             .await
     }
 
     pub async fn put_raw(&mut self, key: &str, value: impl Into<JsValue>) -> Result<()> {
-        JsFuture::from(self.inner.put(key, value.into())?)
+        self.put_raw_with_options(key, value, StoragePutOptions::default())
+            .await
+    }
+
+    pub async fn put_raw_with_options(
+        &mut self,
+        key: &str,
+        value: impl Into<JsValue>,
+        options: StoragePutOptions,
+    ) -> Result<()> {
+        let options = serde_wasm_bindgen::to_value(&options)?.into();
+        JsFuture::from(self.inner.put_with_options(key, value.into(), options)?)
             .await
             .map_err(Error::from)
             .map(|_| ())
@@ -284,11 +390,39 @@ impl Storage {
 
     /// Takes a serializable struct and stores each of its keys and values to storage.
     pub async fn put_multiple<T: Serialize>(&mut self, values: T) -> Result<()> {
-        let values = serde_wasm_bindgen::to_value(&values)?;
+        self.put_multiple_with_options(values, StoragePutOptions::default())
+            .await
+    }
+
+    /// Takes a serializable struct and stores each of its keys and values to storage, see
+    /// [`StoragePutOptions`].
+    pub async fn put_multiple_with_options<T: Serialize>(
+        &mut self,
+        values: T,
+        options: StoragePutOptions,
+    ) -> Result<()> {
+        let values = crate::serializer::to_value(&values)?;
         if !values.is_object() {
             return Err("Must pass in a struct type".to_string().into());
         }
-        self.put_multiple_raw(values.dyn_into().unwrap()).await
+        self.put_multiple_raw_with_options(values.dyn_into().unwrap(), options)
+            .await
+    }
+
+    /// Takes a serializable struct and stores each of its keys and values to storage, converting
+    /// them into JS with `serializer` rather than the
+    /// [options of the Worker](crate::set_serializer_options).
+    pub async fn put_multiple_with_serializer<T: Serialize>(
+        &mut self,
+        values: T,
+        serializer: &SerializerOptions,
+    ) -> Result<()> {
+        let values = serializer.to_value(&values)?;
+        if !values.is_object() {
+            return Err("Must pass in a struct type".to_string().into());
+        }
+        self.put_multiple_raw_with_options(values.dyn_into().unwrap(), StoragePutOptions::default())
+            .await
     }
 
     /// Takes an object and stores each of its keys and values to storage.
@@ -305,15 +439,42 @@ impl Storage {
     /// storage.put_multiple_raw(obj);
     /// ```
     pub async fn put_multiple_raw(&mut self, values: Object) -> Result<()> {
-        JsFuture::from(self.inner.put_multiple(values.into())?)
+        self.put_multiple_raw_with_options(values, StoragePutOptions::default())
             .await
-            .map_err(Error::from)
-            .map(|_| ())
+    }
+
+    /// Takes an object and stores each of its keys and values to storage, see
+    /// [`StoragePutOptions`].
+    pub async fn put_multiple_raw_with_options(
+        &mut self,
+        values: Object,
+        options: StoragePutOptions,
+    ) -> Result<()> {
+        let options = serde_wasm_bindgen::to_value(&options)?.into();
+        JsFuture::from(
+            self.inner
+                .put_multiple_with_options(values.into(), options)?,
+        )
+        .await
+        .map_err(Error::from)
+        .map(|_| ())
     }
 
     /// Deletes the key and associated value. Returns true if the key existed or false if it didn't.
     pub async fn delete(&mut self, key: &str) -> Result<bool> {
-        let fut: JsFuture = self.inner.delete(key)?.into();
+        self.delete_with_options(key, StoragePutOptions::default())
+            .await
+    }
+
+    /// Deletes the key and associated value, see [`StoragePutOptions`]. Returns true if the key
+    /// existed or false if it didn't.
+    pub async fn delete_with_options(
+        &mut self,
+        key: &str,
+        options: StoragePutOptions,
+    ) -> Result<bool> {
+        let options = serde_wasm_bindgen::to_value(&options)?.into();
+        let fut: JsFuture = self.inner.delete_with_options(key, options)?.into();
         fut.await
             .and_then(|jsv| {
                 jsv.as_bool()
@@ -325,12 +486,24 @@ impl Storage {
     /// Deletes the provided keys and their associated values. Returns a count of the number of
     /// key-value pairs deleted.
     pub async fn delete_multiple(&mut self, keys: Vec<impl Deref<Target = str>>) -> Result<usize> {
+        self.delete_multiple_with_options(keys, StoragePutOptions::default())
+            .await
+    }
+
+    /// Deletes the provided keys and their associated values, see [`StoragePutOptions`]. Returns a
+    /// count of the number of key-value pairs deleted.
+    pub async fn delete_multiple_with_options(
+        &mut self,
+        keys: Vec<impl Deref<Target = str>>,
+        options: StoragePutOptions,
+    ) -> Result<usize> {
         let fut: JsFuture = self
             .inner
-            .delete_multiple(
+            .delete_multiple_with_options(
                 keys.into_iter()
                     .map(|key| JsValue::from(key.deref()))
                     .collect(),
+                serde_wasm_bindgen::to_value(&options)?.into(),
             )?
             .into();
         fut.await
@@ -376,6 +549,101 @@ impl Storage {
             .map_err(Error::from)
     }
 
+    /// Iterates over all keys starting with `prefix` and their values in ascending lexicographic
+    /// order, fetching them from storage in pages instead of loading them all into memory at once
+    /// like `list()` does.
+    ///
+    /// ```no_run
+    /// # use futures_util::TryStreamExt;
+    /// # async fn example(storage: worker::Storage) -> worker::Result<()> {
+    /// let users = storage.scan::<String>("user:");
+    /// futures_util::pin_mut!(users);
+    /// while let Some((key, name)) = users.try_next().await? {
+    ///     worker::console_log!("{key}: {name}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn scan<T: DeserializeOwned>(
+        &self,
+        prefix: &str,
+    ) -> impl Stream<Item = Result<(String, T)>> + '_ {
+        self.scan_with_page_size(prefix, DEFAULT_SCAN_PAGE_SIZE)
+    }
+
+    /// Like [`Storage::scan`], fetching `page_size` keys from storage at a time.
+    pub fn scan_with_page_size<T: DeserializeOwned>(
+        &self,
+        prefix: &str,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<(String, T)>> + '_ {
+        let state = ScanState {
+            pages: Pages::new(prefix, page_size),
+            page: VecDeque::new(),
+        };
+
+        futures_util::stream::unfold(state, move |mut state| async move {
+            if state.page.is_empty() {
+                match state.pages.next(self).await {
+                    Ok(Some(page)) => state.page.extend(page),
+                    Ok(None) => return None,
+                    Err(e) => return Some((Err(e), state)),
+                }
+            }
+
+            let (key, value) = state.page.pop_front()?;
+            let item = serde_wasm_bindgen::from_value(value)
+                .map(|value| (key, value))
+                .map_err(Error::from);
+            Some((item, state))
+        })
+    }
+
+    /// Counts the keys stored by the Durable Object, listing them in pages. The values are read
+    /// along with the keys, so this costs as much as reading the whole storage and should be done
+    /// sparingly, e.g. in an alarm.
+    pub async fn key_count(&self) -> Result<usize> {
+        Ok(self.usage("").await?.keys)
+    }
+
+    /// Measures the keys starting with `prefix` and their values, listing them in pages, e.g. to
+    /// evict old entries before reaching the
+    /// [storage limits](https://developers.cloudflare.com/durable-objects/platform/limits/).
+    /// Like [`key_count`](Storage::key_count), this reads all the matching values.
+    pub async fn usage(&self, prefix: &str) -> Result<StorageUsage> {
+        let mut usage = StorageUsage::default();
+        let mut pages = Pages::new(prefix, DEFAULT_SCAN_PAGE_SIZE);
+        while let Some(page) = pages.next(self).await? {
+            for (key, value) in page {
+                usage.keys += 1;
+                usage.key_bytes += key.len();
+                usage.value_bytes += value_size(&value);
+            }
+        }
+        Ok(usage)
+    }
+
+    /// A view of the keys starting with `prefix`, whose methods prefix the keys they're given
+    /// and strip the prefix from the keys they return, so several collections can share the
+    /// storage of a Durable Object without colliding.
+    ///
+    /// ```no_run
+    /// # async fn example(storage: worker::Storage) -> worker::Result<()> {
+    /// let mut users = storage.scoped("user:");
+    /// let mut sessions = storage.scoped("session:");
+    /// users.put("alice", "Alice").await?; // stored as `user:alice`
+    /// sessions.put("alice", 3).await?; // stored as `session:alice`
+    /// let name: String = users.get("alice").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn scoped(&self, prefix: impl Into<String>) -> ScopedStorage {
+        ScopedStorage {
+            storage: self.clone(),
+            prefix: prefix.into(),
+        }
+    }
+
     /// Retrieves the current alarm time (if set) as integer milliseconds since epoch.
     /// The alarm is considered to be set if it has not started, or if it has failed
     /// and any retry has not begun. If no alarm is set, `get_alarm()` returns `None`.
@@ -493,7 +761,7 @@ impl Transaction {
     }
 
     pub async fn put<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
-        JsFuture::from(self.inner.put(key, serde_wasm_bindgen::to_value(&value)?)?)
+        JsFuture::from(self.inner.put(key, crate::serializer::to_value(&value)?)?)
             .await
             .map_err(Error::from)
             .map(|_| ())
@@ -501,7 +769,7 @@ impl Transaction {
 
     // Each key-value pair in the serialized object will be added to the storage
     pub async fn put_multiple<T: Serialize>(&mut self, values: T) -> Result<()> {
-        let values = serde_wasm_bindgen::to_value(&values)?;
+        let values = crate::serializer::to_value(&values)?;
         if !values.is_object() {
             return Err("Must pass in a struct type".to_string().into());
         }
@@ -566,6 +834,186 @@ impl Transaction {
     }
 }
 
+pub(crate) const DEFAULT_SCAN_PAGE_SIZE: usize = 128;
+
+/// The number and size of the keys and values stored by a Durable Object, see
+/// [`Storage::usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    pub keys: usize,
+    /// The total length of the keys, in bytes.
+    pub key_bytes: usize,
+    /// The approximate total size of the values, in bytes: strings and binary data are measured
+    /// exactly, other values by the length of their JSON.
+    pub value_bytes: usize,
+}
+
+impl StorageUsage {
+    /// The approximate total size of the keys and values, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.key_bytes + self.value_bytes
+    }
+}
+
+/// The keys of a [`Storage`] starting with a prefix, see [`Storage::scoped`].
+#[derive(Clone)]
+pub struct ScopedStorage {
+    storage: Storage,
+    prefix: String,
+}
+
+impl ScopedStorage {
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The key under which `key` is stored, with the prefix of the scope.
+    pub fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// A nested scope, whose prefix is `prefix` appended to the prefix of this scope.
+    pub fn scoped(&self, prefix: &str) -> ScopedStorage {
+        ScopedStorage {
+            storage: self.storage.clone(),
+            prefix: self.key(prefix),
+        }
+    }
+
+    /// See [`Storage::get`].
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        self.storage.get(&self.key(key)).await
+    }
+
+    /// See [`Storage::put`].
+    pub async fn put<T: Serialize>(&mut self, key: &str, value: T) -> Result<()> {
+        self.storage.put(&self.key(key), value).await
+    }
+
+    /// See [`Storage::delete`].
+    pub async fn delete(&mut self, key: &str) -> Result<bool> {
+        self.storage.delete(&self.key(key)).await
+    }
+
+    /// Returns the keys of the scope, without the prefix, and their values. Like
+    /// [`Storage::list`], this loads all of them into memory; [`scan`](ScopedStorage::scan)
+    /// doesn't.
+    pub async fn list(&self) -> Result<Map> {
+        let page = self
+            .storage
+            .list_with_options(ListOptions::new().prefix(&self.prefix))
+            .await?;
+        let map = Map::new();
+        page.for_each(&mut |value, key| {
+            map.set(&self.strip(&key), &value);
+        });
+        Ok(map)
+    }
+
+    /// Iterates over the keys of the scope, without the prefix, and their values, see
+    /// [`Storage::scan`].
+    pub fn scan<T: DeserializeOwned>(&self) -> impl Stream<Item = Result<(String, T)>> + '_ {
+        self.storage
+            .scan(&self.prefix)
+            .map(move |item| item.map(|(key, value)| (key[self.prefix.len()..].to_string(), value)))
+    }
+
+    /// Deletes every key of the scope, in pages, leaving the rest of the storage untouched.
+    pub async fn delete_all(&mut self) -> Result<()> {
+        loop {
+            let page = self
+                .storage
+                .list_with_options(
+                    ListOptions::new()
+                        .prefix(&self.prefix)
+                        .limit(DEFAULT_SCAN_PAGE_SIZE),
+                )
+                .await?;
+            let keys: Vec<String> = page
+                .keys()
+                .into_iter()
+                .filter_map(|key| key.ok()?.as_string())
+                .collect();
+            if keys.is_empty() {
+                return Ok(());
+            }
+            self.storage.delete_multiple(keys).await?;
+        }
+    }
+
+    /// See [`Storage::usage`].
+    pub async fn usage(&self) -> Result<StorageUsage> {
+        self.storage.usage(&self.prefix).await
+    }
+
+    fn strip(&self, key: &JsValue) -> JsValue {
+        match key.as_string() {
+            Some(key) => key[self.prefix.len().min(key.len())..].into(),
+            None => key.clone(),
+        }
+    }
+}
+
+/// Lists the keys starting with a prefix and their values a page at a time, for the methods
+/// which go through all of them without loading them into memory at once.
+pub(crate) struct Pages {
+    prefix: String,
+    page_size: usize,
+    start: Option<String>,
+    done: bool,
+}
+
+impl Pages {
+    pub(crate) fn new(prefix: &str, page_size: usize) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            page_size: page_size.max(1),
+            start: None,
+            done: false,
+        }
+    }
+
+    /// The next page of keys and values, or `None` once they've all been listed or listing them
+    /// failed.
+    pub(crate) async fn next(
+        &mut self,
+        storage: &Storage,
+    ) -> Result<Option<Vec<(String, JsValue)>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut opts = ListOptions::new()
+            .prefix(&self.prefix)
+            .limit(self.page_size);
+        if let Some(start) = &self.start {
+            opts = opts.start(start);
+        }
+        // Stop at the first error rather than list the same page again.
+        self.done = true;
+        let page = storage.list_with_options(opts).await?;
+
+        let mut entries = Vec::new();
+        page.for_each(&mut |value, key| entries.push((key, value)));
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| match key.as_string() {
+                Some(key) => Ok((key, value)),
+                None => Err(Error::RustError("storage key is not a string".into())),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.done = entries.len() < self.page_size;
+        // `start` is inclusive, so continue right after the last key of this page.
+        self.start = entries.last().map(|(key, _)| format!("{key}\0"));
+        Ok(Some(entries))
+    }
+}
+
+struct ScanState {
+    pages: Pages,
+    page: VecDeque<(String, JsValue)>,
+}
+
 #[derive(Default, Serialize)]
 pub struct ListOptions<'a> {
     /// Key at which the list results should start, inclusive.
@@ -686,13 +1134,47 @@ impl From<Duration> for ScheduledTime {
     }
 }
 
+/// Options of the storage reads, which trade the guarantees of the input and output gates for
+/// latency.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageGetOptions {
+    /// Let other events be delivered to the object while the read is in progress, which gives up
+    /// on the guarantee that no event runs between the read and the code after it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_concurrency: Option<bool>,
+    /// Don't keep the value in the in-memory cache of the storage, e.g. for values which are read
+    /// once. Reads of the value go to disk, without changing its consistency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_cache: Option<bool>,
+}
+
+/// Options of the storage writes and deletions, which trade the guarantees of the input and
+/// output gates for latency.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoragePutOptions {
+    /// Let other events be delivered to the object while the write is in progress.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_concurrency: Option<bool>,
+    /// Send the responses and messages of the object without waiting for the write to be
+    /// confirmed, which may expose a write which is lost if the object fails.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_unconfirmed: Option<bool>,
+    /// Don't keep the value in the in-memory cache of the storage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_cache: Option<bool>,
+}
+
 #[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GetAlarmOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_concurrency: Option<bool>,
 }
 
 #[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SetAlarmOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_concurrency: Option<bool>,
@@ -730,6 +1212,7 @@ impl AsRef<JsValue> for ObjectNamespace {
     }
 }
 
+#[cfg(feature = "websocket")]
 pub enum WebSocketIncomingMessage {
     String(String),
     Binary(Vec<u8>),
@@ -769,6 +1252,37 @@ impl DurableObject for Chatroom {
     }
 }
 ```
+
+Instead of `Env`, `new` can receive a struct of bindings which implements [`Bindings`](crate::Bindings),
+usually through `#[derive(Bindings)]`. The bindings are loaded when the object is constructed, which
+fails with an error listing the missing bindings:
+
+```ignore
+use worker::*;
+
+#[derive(Bindings)]
+struct CounterBindings {
+    counts: kv::KvStore,
+    max_count: Var,
+}
+
+#[durable_object]
+pub struct Counter {
+    state: State,
+    bindings: CounterBindings,
+}
+
+#[durable_object]
+impl DurableObject for Counter {
+    fn new(state: State, bindings: CounterBindings) -> Self {
+        Self { state, bindings }
+    }
+
+    async fn fetch(&mut self, _req: Request) -> Result<Response> {
+        Response::ok(self.bindings.max_count.to_string())
+    }
+}
+```
 */
 
 #[async_trait(?Send)]
@@ -782,6 +1296,7 @@ pub trait DurableObject {
         unimplemented!("alarm() handler not implemented")
     }
 
+    #[cfg(feature = "websocket")]
     #[allow(unused_variables, clippy::diverging_sub_expression)]
     async fn websocket_message(
         &mut self,
@@ -791,6 +1306,7 @@ pub trait DurableObject {
         unimplemented!("websocket_message() handler not implemented")
     }
 
+    #[cfg(feature = "websocket")]
     #[allow(unused_variables, clippy::diverging_sub_expression)]
     async fn websocket_close(
         &mut self,
@@ -802,6 +1318,7 @@ pub trait DurableObject {
         unimplemented!("websocket_close() handler not implemented")
     }
 
+    #[cfg(feature = "websocket")]
     #[allow(unused_variables, clippy::diverging_sub_expression)]
     async fn websocket_error(&mut self, ws: WebSocket, error: Error) -> Result<()> {
         unimplemented!("websocket_error() handler not implemented")
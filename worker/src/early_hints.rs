@@ -0,0 +1,141 @@
+use std::fmt::{self, Display};
+
+use crate::{Headers, Result};
+
+/// The `Link` headers of resources a page needs, which Cloudflare sends to the client in a
+/// `103 Early Hints` response while the Worker is still working on the page, when Early Hints
+/// are enabled for the zone.
+///
+/// Workers can't send informational responses themselves: the runtime caches the `preload` and
+/// `preconnect` links of `200` HTML responses and replays them as early hints on later requests
+/// for the same URL. The links also work as regular `Link` headers for clients which don't
+/// support early hints.
+///
+/// The Workers runtime doesn't support sending HTTP trailers, so there's no equivalent API for
+/// them. Values only known once the body has been streamed, such as timings, have to go in the
+/// body instead.
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example() -> Result<Response> {
+/// let hints = EarlyHints::new()
+///     .link(Link::preload("/css/main.css", "style"))
+///     .link(
+///         Link::preload("/fonts/inter.woff2", "font")
+///             .mime_type("font/woff2")
+///             .crossorigin(),
+///     )
+///     .link(Link::preconnect("https://images.example.com"));
+/// Response::from_html("<!doctype html>...")?.with_early_hints(&hints)
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EarlyHints {
+    links: Vec<Link>,
+}
+
+impl EarlyHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn link(mut self, link: Link) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Append the links to the `Link` header of `headers`.
+    pub fn apply_headers(&self, headers: &mut Headers) -> Result<()> {
+        for link in &self.links {
+            headers.append("Link", &link.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// A resource hint of [`EarlyHints`], formatted as the value of a `Link` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    url: String,
+    rel: &'static str,
+    destination: Option<String>,
+    mime_type: Option<String>,
+    crossorigin: bool,
+}
+
+impl Link {
+    /// Fetch `url` before the page asks for it. `destination` is the kind of resource, such as
+    /// `style`, `script`, `font`, `image` or `fetch`.
+    pub fn preload(url: impl Into<String>, destination: impl Into<String>) -> Self {
+        Self::new(url, "preload", Some(destination.into()))
+    }
+
+    /// Connect to the origin of `url` before the page needs it.
+    pub fn preconnect(url: impl Into<String>) -> Self {
+        Self::new(url, "preconnect", None)
+    }
+
+    fn new(url: impl Into<String>, rel: &'static str, destination: Option<String>) -> Self {
+        Self {
+            url: url.into(),
+            rel,
+            destination,
+            mime_type: None,
+            crossorigin: false,
+        }
+    }
+
+    /// The MIME type of the resource, so that clients which don't support it can skip it.
+    #[must_use]
+    pub fn mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Fetch the resource with CORS, which fonts and `fetch` resources require to be used.
+    #[must_use]
+    pub fn crossorigin(mut self) -> Self {
+        self.crossorigin = true;
+        self
+    }
+}
+
+impl Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>; rel={}", self.url, self.rel)?;
+        if let Some(destination) = &self.destination {
+            write!(f, "; as={destination}")?;
+        }
+        if let Some(mime_type) = &self.mime_type {
+            write!(f, "; type=\"{mime_type}\"")?;
+        }
+        if self.crossorigin {
+            write!(f, "; crossorigin")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn link_display_works() {
+    assert_eq!(
+        Link::preload("/main.css", "style").to_string(),
+        "</main.css>; rel=preload; as=style"
+    );
+    assert_eq!(
+        Link::preload("/inter.woff2", "font")
+            .mime_type("font/woff2")
+            .crossorigin()
+            .to_string(),
+        "</inter.woff2>; rel=preload; as=font; type=\"font/woff2\"; crossorigin"
+    );
+    assert_eq!(
+        Link::preconnect("https://cdn.example.com").to_string(),
+        "<https://cdn.example.com>; rel=preconnect"
+    );
+}
@@ -1,11 +1,14 @@
 #[cfg(feature = "d1")]
 use crate::d1::D1Database;
 use crate::error::Error;
+#[cfg(feature = "r2")]
+use crate::Bucket;
 #[cfg(feature = "queue")]
 use crate::Queue;
-use crate::{durable::ObjectNamespace, Bucket, DynamicDispatcher, Fetcher, Result};
+use crate::{durable::ObjectNamespace, DynamicDispatcher, Fetcher, Result};
 
 use js_sys::Object;
+use serde::{Serialize, Serializer};
 use wasm_bindgen::{prelude::*, JsCast, JsValue};
 use worker_kv::KvStore;
 
@@ -74,6 +77,7 @@ impl Env {
     }
 
     /// Access an R2 Bucket by the binding name configured in your wrangler.toml file.
+    #[cfg(feature = "r2")]
     pub fn bucket(&self, binding: &str) -> Result<Bucket> {
         self.get_binding(binding)
     }
@@ -83,6 +87,102 @@ impl Env {
     pub fn d1(&self, binding: &str) -> Result<D1Database> {
         self.get_binding(binding)
     }
+
+    /// List the bindings of the Worker by name, with the kind of each binding detected from its
+    /// value. Useful to log the configuration of a Worker at startup, or for frameworks which
+    /// discover their bindings.
+    pub fn bindings(&self) -> Result<Vec<BindingInfo>> {
+        let mut bindings = Object::keys(self.unchecked_ref())
+            .iter()
+            .map(|name| {
+                let value = js_sys::Reflect::get(self, &name)?;
+                Ok(BindingInfo {
+                    name: name.as_string().unwrap_or_default(),
+                    kind: BindingKind::detect(&value),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        bindings.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(bindings)
+    }
+}
+
+/// A binding of the [`Env`], as listed by [`Env::bindings`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BindingInfo {
+    pub name: String,
+    pub kind: BindingKind,
+}
+
+/// The kind of a binding, detected from the constructor of its value. Serialized as a string,
+/// e.g. `"kv"`, or the name of the constructor for [`BindingKind::Other`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BindingKind {
+    /// A plaintext variable or a secret, which can't be told apart.
+    Text,
+    /// A JSON variable.
+    Json,
+    Kv,
+    DurableObject,
+    R2,
+    D1,
+    Queue,
+    /// A [Service Binding](https://developers.cloudflare.com/workers/runtime-apis/service-bindings/).
+    Service,
+    DynamicDispatcher,
+    /// A binding without a wrapper in workers-rs, with the name of its constructor.
+    Other(String),
+}
+
+impl BindingKind {
+    fn detect(value: &JsValue) -> Self {
+        if value.is_string() {
+            return BindingKind::Text;
+        }
+        if !value.is_object() {
+            return BindingKind::Json;
+        }
+        match Object::from(value.clone()).constructor().name().as_string() {
+            Some(name) => BindingKind::from_constructor(name),
+            None => BindingKind::Json,
+        }
+    }
+
+    fn from_constructor(name: String) -> Self {
+        match name.as_str() {
+            "Object" | "Array" => BindingKind::Json,
+            "KvNamespace" => BindingKind::Kv,
+            "DurableObjectNamespace" => BindingKind::DurableObject,
+            "R2Bucket" => BindingKind::R2,
+            "D1Database" => BindingKind::D1,
+            "WorkerQueue" => BindingKind::Queue,
+            "Fetcher" => BindingKind::Service,
+            "DynamicDispatcher" => BindingKind::DynamicDispatcher,
+            _ => BindingKind::Other(name),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            BindingKind::Text => "text",
+            BindingKind::Json => "json",
+            BindingKind::Kv => "kv",
+            BindingKind::DurableObject => "durable_object",
+            BindingKind::R2 => "r2",
+            BindingKind::D1 => "d1",
+            BindingKind::Queue => "queue",
+            BindingKind::Service => "service",
+            BindingKind::DynamicDispatcher => "dynamic_dispatcher",
+            BindingKind::Other(name) => name,
+        }
+    }
+}
+
+impl Serialize for BindingKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 pub trait EnvBinding: Sized + JsCast {
@@ -103,6 +203,67 @@ pub trait EnvBinding: Sized + JsCast {
     }
 }
 
+/// A type which can be read from a single binding of the [`Env`]. Used by
+/// [`#[derive(Bindings)]`](worker_macros::Bindings) to populate each field of the struct.
+pub trait FromEnv: Sized {
+    fn from_env(env: &Env, binding: &str) -> Result<Self>;
+}
+
+impl<T: EnvBinding> FromEnv for T {
+    fn from_env(env: &Env, binding: &str) -> Result<Self> {
+        env.get_binding(binding)
+    }
+}
+
+impl FromEnv for KvStore {
+    fn from_env(env: &Env, binding: &str) -> Result<Self> {
+        env.kv(binding)
+    }
+}
+
+impl FromEnv for String {
+    fn from_env(env: &Env, binding: &str) -> Result<Self> {
+        env.var(binding).map(|var| var.to_string())
+    }
+}
+
+/// Optional bindings are `None` when they aren't configured, rather than failing.
+impl<T: FromEnv> FromEnv for Option<T> {
+    fn from_env(env: &Env, binding: &str) -> Result<Self> {
+        let value = js_sys::Reflect::get(env, &JsValue::from(binding))?;
+        if value.is_undefined() {
+            Ok(None)
+        } else {
+            T::from_env(env, binding).map(Some)
+        }
+    }
+}
+
+/// A struct of typed bindings loaded from the [`Env`] in one go, usually implemented with
+/// [`#[derive(Bindings)]`](worker_macros::Bindings):
+///
+/// ```no_run
+/// # use worker::*;
+/// #[derive(Bindings)]
+/// struct AppBindings {
+///     // Read from the `UPLOADS_BUCKET` binding.
+///     uploads_bucket: Bucket,
+///     #[binding(name = "USERS")]
+///     users_kv: kv::KvStore,
+///     api_token: Option<Secret>,
+/// }
+///
+/// # fn example(env: Env) -> Result<()> {
+/// let bindings = AppBindings::load(&env)?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Bindings: Sized {
+    /// Load every binding, failing with an error listing all bindings which are missing or
+    /// have the wrong type.
+    fn load(env: &Env) -> Result<Self>;
+}
+
 pub struct StringBinding(JsValue);
 
 impl EnvBinding for StringBinding {
@@ -152,3 +313,23 @@ impl ToString for StringBinding {
 pub use StringBinding as Secret;
 /// A string value representing a binding to an environment variable in a Worker.
 pub type Var = StringBinding;
+
+#[test]
+fn binding_kind_works() {
+    assert_eq!(
+        BindingKind::from_constructor("KvNamespace".into()),
+        BindingKind::Kv
+    );
+    assert_eq!(
+        BindingKind::from_constructor("Object".into()),
+        BindingKind::Json
+    );
+    assert_eq!(
+        serde_json::to_string(&BindingInfo {
+            name: "AI".into(),
+            kind: BindingKind::from_constructor("Ai".into()),
+        })
+        .unwrap(),
+        r#"{"name":"AI","kind":"Ai"}"#
+    );
+}
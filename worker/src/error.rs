@@ -1,13 +1,27 @@
+use std::error::Error as StdError;
+
 use wasm_bindgen::{JsCast, JsValue};
 
 /// All possible Error variants that might be encountered while working with a Worker.
+///
+/// Formatting an `Error` with `{:#}` includes the messages of all of its sources, which is useful
+/// when logging errors that had [`context`](Error::context) attached to them.
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
     BadEncoding,
+    /// A body was larger than the limit it was read with, in bytes.
+    BodyTooLarge(u64),
     BodyUsed,
+    /// An error with an additional message describing what was being done when it occurred.
+    Context {
+        context: String,
+        source: Box<Error>,
+    },
     Json((String, u16)),
     JsError(String),
+    /// A JavaScript `Error` object thrown by the runtime, including its stack trace.
+    JsException(JsException),
     #[cfg(feature = "http")]
     Http(http::Error),
     Infallible,
@@ -25,6 +39,9 @@ pub enum Error {
     #[cfg(feature = "d1")]
     D1(crate::d1::D1Error),
     Utf8Error(std::str::Utf8Error),
+    /// The input of a handler didn't meet its constraints, see [`Validate`](crate::Validate). The
+    /// [`Router`](crate::Router) responds with [`Violations::to_response`](crate::Violations::to_response).
+    Validation(crate::Violations),
 }
 
 unsafe impl Sync for Error {}
@@ -107,13 +124,16 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::BadEncoding => write!(f, "content-type mismatch"),
+            Error::BodyTooLarge(limit) => write!(f, "body is larger than {limit} bytes"),
             Error::BodyUsed => write!(f, "body has already been read"),
+            Error::Context { context, .. } => write!(f, "{context}"),
             Error::Json((msg, status)) => write!(f, "{msg} (status: {status})"),
             Error::JsError(s) | Error::RustError(s) => {
                 write!(f, "{s}")
             }
             #[cfg(feature = "http")]
             Error::Http(e) => write!(f, "http::Error: {e}"),
+            Error::JsException(e) => write!(f, "{e}"),
             Error::Infallible => write!(f, "infallible"),
             Error::Internal(_) => write!(f, "unrecognized JavaScript object"),
             Error::Io(e) => write!(f, "IO Error: {e}"),
@@ -128,29 +148,188 @@ impl std::fmt::Display for Error {
             #[cfg(feature = "d1")]
             Error::D1(e) => write!(f, "D1: {e:#?}"),
             Error::Utf8Error(e) => write!(f, "{e}"),
+            Error::Validation(violations) => write!(f, "invalid input: {violations}"),
+        }?;
+
+        if f.alternate() {
+            // The messages of the other variants wrapping an error already include it, so only
+            // its sources are added.
+            let mut source = match self {
+                Error::Context { .. } | Error::JsException(_) => self.source(),
+                _ => self.source().and_then(StdError::source),
+            };
+            while let Some(e) = source {
+                write!(f, ": {e}")?;
+                source = e.source();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Context { source, .. } => Some(source.as_ref()),
+            Error::JsException(e) => e.source(),
+            #[cfg(feature = "http")]
+            Error::Http(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::RouteInsertError(e) => Some(e),
+            Error::SerdeJsonError(e) => Some(e),
+            #[cfg(feature = "queue")]
+            Error::SerdeWasmBindgenError(e) => Some(e),
+            #[cfg(feature = "http")]
+            Error::StatusCode(e) => Some(e),
+            Error::Utf8Error(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// Wrap this error with a message describing what was being done when it occurred, similar to
+    /// `anyhow::Context`.
+    ///
+    /// ```
+    /// # use worker::Error;
+    /// let err = Error::from("binding not found").context("failed to load config");
+    /// assert_eq!(err.to_string(), "failed to load config");
+    /// assert_eq!(format!("{err:#}"), "failed to load config: binding not found");
+    /// ```
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Error::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The JavaScript stack trace of the underlying [`JsException`], if there is one.
+    pub fn stack(&self) -> Option<&str> {
+        match self {
+            Error::Context { source, .. } => source.stack(),
+            Error::JsException(e) => e.stack(),
+            _ => None,
+        }
+    }
+
+    /// The original JavaScript value this error was created from, if any.
+    pub fn js_value(&self) -> Option<&JsValue> {
+        match self {
+            Error::Context { source, .. } => source.js_value(),
+            Error::JsException(e) => Some(e.as_ref()),
+            Error::Internal(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Extension methods for attaching [`context`](Error::context) to the error of a `Result`.
+pub trait ResultExt<T> {
+    /// Wrap the error with the given context message.
+    fn context(self, context: impl Into<String>) -> Result<T, Error>;
+
+    /// Wrap the error with a context message which is only built if an error occurred.
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, Error> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, f: impl FnOnce() -> C) -> Result<T, Error> {
+        self.map_err(|e| e.into().context(f()))
+    }
+}
+
+/// A JavaScript [`Error`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Error)
+/// object, keeping the original value along with its stack trace and cause.
+#[derive(Debug)]
+pub struct JsException {
+    inner: js_sys::Error,
+    name: String,
+    message: String,
+    stack: Option<String>,
+    cause: Option<Box<Error>>,
+}
+
+impl JsException {
+    /// The error's name, e.g. `TypeError`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The stack trace captured by the runtime when the error was created.
+    pub fn stack(&self) -> Option<&str> {
+        self.stack.as_deref()
+    }
+
+    /// The error's `cause`, if it was set.
+    pub fn cause(&self) -> Option<&Error> {
+        self.cause.as_deref()
+    }
+}
+
+impl std::fmt::Display for JsException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+impl StdError for JsException {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.cause.as_deref().map(|e| e as _)
+    }
+}
+
+impl From<js_sys::Error> for JsException {
+    fn from(inner: js_sys::Error) -> Self {
+        let stack = js_sys::Reflect::get(&inner, &JsValue::from_str("stack"))
+            .ok()
+            .and_then(|stack| stack.as_string());
+        let cause = inner.cause();
+        let cause = if cause.is_undefined() || cause.is_null() {
+            None
+        } else {
+            Some(Box::new(Error::from(cause)))
+        };
+
+        Self {
+            name: inner.name().into(),
+            message: inner.message().into(),
+            stack,
+            cause,
+            inner,
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl AsRef<js_sys::Error> for JsException {
+    fn as_ref(&self) -> &js_sys::Error {
+        &self.inner
+    }
+}
+
+impl AsRef<JsValue> for JsException {
+    fn as_ref(&self) -> &JsValue {
+        &self.inner
+    }
+}
 
-// Not sure if the changes I've made here are good or bad...
 impl From<JsValue> for Error {
     fn from(v: JsValue) -> Self {
-        match v.as_string().or_else(|| {
-            v.dyn_ref::<js_sys::Error>().map(|e| {
-                format!(
-                    "Error: {} - Cause: {}",
-                    e.to_string(),
-                    e.cause()
-                        .as_string()
-                        .or_else(|| { Some(e.to_string().into()) })
-                        .unwrap_or(String::from("N/A"))
-                )
-            })
-        }) {
-            Some(s) => Self::JsError(s),
-            None => Self::Internal(v),
+        if let Some(s) = v.as_string() {
+            return Self::JsError(s);
+        }
+
+        match v.dyn_into::<js_sys::Error>() {
+            Ok(e) => Self::JsException(e.into()),
+            Err(v) => Self::Internal(v),
         }
     }
 }
@@ -163,7 +342,11 @@ impl From<std::io::Error> for Error {
 
 impl From<Error> for JsValue {
     fn from(e: Error) -> Self {
-        JsValue::from_str(&e.to_string())
+        match e {
+            // Rethrow the original exception so the runtime can still report its stack trace.
+            Error::JsException(e) => e.inner.into(),
+            e => JsValue::from_str(&format!("{e:#}")),
+        }
     }
 }
 
@@ -190,3 +373,23 @@ impl From<serde_json::Error> for Error {
         Error::SerdeJsonError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternate_display_does_not_repeat_inner_errors() {
+        let err = Error::from(std::io::Error::new(std::io::ErrorKind::Other, "disk full"));
+        assert_eq!(format!("{err:#}"), "IO Error: disk full");
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+
+        let json = serde_json::from_str::<u32>("x").unwrap_err();
+        let message = json.to_string();
+        let err = Error::from(json).context("failed to parse config");
+        assert_eq!(
+            format!("{err:#}"),
+            format!("failed to parse config: Serde Error: {message}")
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{env::EnvBinding, RequestInit, Result};
+use crate::{
+    env::EnvBinding,
+    outbound::{self, Transport},
+    request_metrics::{record, Call},
+    trace, Method, RequestInit, Result,
+};
 #[cfg(feature = "http")]
 use std::convert::TryInto;
 use wasm_bindgen::{JsCast, JsValue};
@@ -32,6 +37,19 @@ impl Fetcher {
         init: Option<RequestInit>,
     ) -> Result<FetchResponseType> {
         let path = url.into();
+        if let Some(next) = outbound::chain(Transport::Service(self.clone())) {
+            let req = match init {
+                Some(ref init) => Request::new_with_init(&path, init)?,
+                None => Request::new(&path, Method::Get)?,
+            };
+            let response = next.run(req).await?;
+            #[cfg(feature = "http")]
+            let result = response.try_into();
+            #[cfg(not(feature = "http"))]
+            let result = Ok(response);
+            return result;
+        }
+        record(Call::Service);
         let promise = match init {
             Some(ref init) => self.0.fetch_with_str_and_init(&path, &init.into()),
             None => self.0.fetch_with_str(&path),
@@ -57,9 +75,10 @@ impl Fetcher {
         let req = TryInto::<Request>::try_into(request)?;
         #[cfg(not(feature = "http"))]
         let req = request;
-        let promise = self.0.fetch(req.inner());
-        let resp_sys: web_sys::Response = JsFuture::from(promise).await?.dyn_into()?;
-        let response = Response::from(resp_sys);
+        let response = match outbound::chain(Transport::Service(self.clone())) {
+            Some(next) => next.run(req).await?,
+            None => self.send(&req).await?,
+        };
         #[cfg(feature = "http")]
         let result = response.try_into();
         #[cfg(not(feature = "http"))]
@@ -68,6 +87,17 @@ impl Fetcher {
     }
 }
 
+impl Fetcher {
+    /// Send `req` through the binding, without the [interceptors](crate::outbound).
+    pub(crate) async fn send(&self, req: &Request) -> Result<Response> {
+        record(Call::Service);
+        let traced = trace::propagate(req);
+        let promise = self.0.fetch(traced.as_ref().unwrap_or_else(|| req.inner()));
+        let resp_sys: web_sys::Response = JsFuture::from(promise).await?.dyn_into()?;
+        Ok(Response::from(resp_sys))
+    }
+}
+
 impl EnvBinding for Fetcher {
     const TYPE_NAME: &'static str = "Fetcher";
 }
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use crate::error::Error;
+use crate::Blob;
+use crate::ByteStream;
 use crate::Date;
 use crate::DateInit;
 use crate::Result;
@@ -105,6 +107,26 @@ impl FormData {
         self.0.set_with_str(name, value).map_err(Error::from)
     }
 
+    /// Appends a file onto an existing key inside a `FormData` object, or adds the key if it does
+    /// not already exist.
+    pub fn append_file(&mut self, name: &str, file: &File) -> Result<()> {
+        self.0
+            .append_with_blob_and_filename(name, &file.0, &file.name())
+            .map_err(Error::from)
+    }
+
+    /// Appends a blob onto an existing key inside a `FormData` object, or adds the key if it does
+    /// not already exist. The blob is sent as a file named `filename`, or `blob` by default.
+    pub fn append_blob(&mut self, name: &str, blob: &Blob, filename: Option<&str>) -> Result<()> {
+        let result = match filename {
+            Some(filename) => self
+                .0
+                .append_with_blob_and_filename(name, blob.inner(), filename),
+            None => self.0.append_with_blob(name, blob.inner()),
+        };
+        result.map_err(Error::from)
+    }
+
     /// Deletes a key/value pair from a `FormData` object.
     pub fn delete(&mut self, name: &str) {
         self.0.delete(name)
@@ -117,6 +139,12 @@ impl From<JsValue> for FormData {
     }
 }
 
+impl From<FormData> for JsValue {
+    fn from(val: FormData) -> Self {
+        val.0.into()
+    }
+}
+
 impl From<HashMap<&dyn AsRef<&str>, &dyn AsRef<&str>>> for FormData {
     fn from(m: HashMap<&dyn AsRef<&str>, &dyn AsRef<&str>>) -> Self {
         let mut formdata = FormData::new();
@@ -130,6 +158,7 @@ impl From<HashMap<&dyn AsRef<&str>, &dyn AsRef<&str>>> for FormData {
 
 /// A [File](https://developer.mozilla.org/en-US/docs/Web/API/File) representation used with
 /// `FormData`.
+#[derive(Debug, Clone)]
 pub struct File(web_sys::File);
 
 impl File {
@@ -147,6 +176,23 @@ impl File {
         Self(file)
     }
 
+    /// Construct a new named file from a buffer, with the media type `type_`.
+    pub fn new_with_type(data: impl AsRef<[u8]>, name: &str, type_: &str) -> Result<Self> {
+        let data = data.as_ref();
+        let arr = Uint8Array::new_with_length(data.len() as u32);
+        arr.copy_from(data);
+
+        let mut options = web_sys::FilePropertyBag::new();
+        options.type_(type_);
+        let file = web_sys::File::new_with_u8_array_sequence_and_options(
+            &Array::of1(&arr.buffer()),
+            name,
+            &options,
+        )?;
+
+        Ok(Self(file))
+    }
+
     /// Get the file name.
     pub fn name(&self) -> String {
         self.0.name()
@@ -175,10 +221,43 @@ impl File {
             })
     }
 
+    /// Read the file as UTF-8 text.
+    pub async fn text(&self) -> Result<String> {
+        self.to_blob().text().await
+    }
+
+    /// Get a stream of the bytes of the file, without reading it in memory first.
+    pub fn stream(&self) -> ByteStream {
+        self.to_blob().stream()
+    }
+
+    /// A blob with the bytes from `start` up to `end`, excluding `end`, of the file.
+    pub fn slice(&self, start: usize, end: usize) -> Result<Blob> {
+        self.to_blob().slice(start, end)
+    }
+
     /// Get the last_modified metadata property of the file.
     pub fn last_modified(&self) -> Date {
         DateInit::Millis(self.0.last_modified() as u64).into()
     }
+
+    /// The file as a blob, without its name and modification date.
+    pub fn to_blob(&self) -> Blob {
+        let blob: &web_sys::Blob = &self.0;
+        Blob::from(blob.clone())
+    }
+}
+
+impl From<File> for Blob {
+    fn from(file: File) -> Self {
+        file.to_blob()
+    }
+}
+
+impl From<File> for JsValue {
+    fn from(file: File) -> Self {
+        file.0.into()
+    }
 }
 
 impl From<web_sys::File> for File {
@@ -186,3 +265,154 @@ impl From<web_sys::File> for File {
         Self(file)
     }
 }
+
+/// A file uploaded with a form, as read by [`#[derive(FromFormData)]`](worker_macros::FromFormData).
+#[derive(Debug, Clone)]
+pub struct FilePart(File);
+
+impl FilePart {
+    /// The name of the file on the client.
+    pub fn file_name(&self) -> String {
+        self.0.name()
+    }
+
+    /// The media type of the file, or an empty string if the client didn't send one.
+    pub fn content_type(&self) -> String {
+        self.0.type_()
+    }
+
+    /// The size of the file in bytes.
+    pub fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    pub async fn bytes(&self) -> Result<Vec<u8>> {
+        self.0.bytes().await
+    }
+
+    pub fn stream(&self) -> ByteStream {
+        self.0.stream()
+    }
+
+    pub fn into_file(self) -> File {
+        self.0
+    }
+}
+
+/// A struct read from the fields of a [`FormData`], usually implemented with
+/// [`#[derive(FromFormData)]`](worker_macros::FromFormData), and read from a request with
+/// [`Request::form`](crate::Request::form):
+///
+/// ```no_run
+/// # use worker::*;
+/// #[derive(FromFormData)]
+/// struct Signup {
+///     email: String,
+///     age: u8,
+///     // Checkboxes are `false` when they're not checked.
+///     newsletter: bool,
+///     referrer: Option<String>,
+///     #[form(name = "profile-picture")]
+///     picture: Option<FilePart>,
+/// }
+///
+/// # async fn example(mut req: Request) -> Result<Response> {
+/// let signup: Signup = req.form().await?;
+/// # Response::ok("")
+/// # }
+/// ```
+pub trait FromFormData: Sized {
+    /// Read every field, failing with an error listing all the fields which are missing or
+    /// invalid.
+    fn from_form_data(form: &FormData) -> Result<Self>;
+}
+
+/// A type which can be read from the field `name` of a [`FormData`]. Used by
+/// [`#[derive(FromFormData)]`](worker_macros::FromFormData) to read each field of the struct,
+/// failing with a description of the problem.
+pub trait FromFormField: Sized {
+    fn from_form_field(form: &FormData, name: &str) -> std::result::Result<Self, String>;
+}
+
+impl FromFormField for String {
+    fn from_form_field(form: &FormData, name: &str) -> std::result::Result<Self, String> {
+        match form.get(name) {
+            Some(FormEntry::Field(value)) => Ok(value),
+            Some(FormEntry::File(_)) => Err("expected a value, got a file".into()),
+            None => Err("missing field".into()),
+        }
+    }
+}
+
+macro_rules! impl_from_form_field_parse {
+    ($($ty:ty => $expected:literal),* $(,)?) => {
+        $(
+            impl FromFormField for $ty {
+                fn from_form_field(
+                    form: &FormData,
+                    name: &str,
+                ) -> std::result::Result<Self, String> {
+                    let value = String::from_form_field(form, name)?;
+                    value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("expected {}, got {:?}", $expected, value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_form_field_parse!(
+    i8 => "an integer",
+    i16 => "an integer",
+    i32 => "an integer",
+    i64 => "an integer",
+    isize => "an integer",
+    u8 => "a positive integer",
+    u16 => "a positive integer",
+    u32 => "a positive integer",
+    u64 => "a positive integer",
+    usize => "a positive integer",
+    f32 => "a number",
+    f64 => "a number",
+);
+
+/// Checkboxes are only sent when they're checked, with the value `on` unless they set another
+/// one, so a missing field is `false`.
+impl FromFormField for bool {
+    fn from_form_field(form: &FormData, name: &str) -> std::result::Result<Self, String> {
+        if !form.has(name) {
+            return Ok(false);
+        }
+        let value = String::from_form_field(form, name)?;
+        match value.to_ascii_lowercase().as_str() {
+            "on" | "true" | "1" | "yes" => Ok(true),
+            "off" | "false" | "0" | "no" | "" => Ok(false),
+            _ => Err(format!("expected a boolean, got {value:?}")),
+        }
+    }
+}
+
+impl FromFormField for FilePart {
+    fn from_form_field(form: &FormData, name: &str) -> std::result::Result<Self, String> {
+        match form.get(name) {
+            Some(FormEntry::File(file)) => Ok(FilePart(file)),
+            Some(FormEntry::Field(_)) => Err("expected a file".into()),
+            None => Err("missing file".into()),
+        }
+    }
+}
+
+/// Optional fields are `None` when they're missing, or empty as inputs are when left blank: a
+/// blank text input sends an empty value, and a blank file input an empty file with no name.
+impl<T: FromFormField> FromFormField for Option<T> {
+    fn from_form_field(form: &FormData, name: &str) -> std::result::Result<Self, String> {
+        match form.get(name) {
+            None => Ok(None),
+            Some(FormEntry::Field(value)) if value.is_empty() => Ok(None),
+            Some(FormEntry::File(file)) if file.name().is_empty() && file.size() == 0 => Ok(None),
+            Some(_) => T::from_form_field(form, name).map(Some),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Combinators for running futures concurrently which don't require them to be `Send`.
+//!
+//! Futures in a Worker are usually `!Send`, as almost all of them await a `JsFuture` somewhere,
+//! and everything runs on a single thread. The functions in this module accept such futures and
+//! spell out that no `Send` or `Unpin` bounds are needed.
+//!
+//! ```no_run
+//! # use worker::*;
+//! # async fn example(env: Env) -> Result<()> {
+//! let kv = env.kv("CACHE")?;
+//! let keys = ["a", "b", "c"];
+//! let values = futures::try_join_all(keys.iter().map(|key| async {
+//!     kv.get(key).text().await.map_err(Error::from)
+//! }))
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use futures_channel::oneshot;
+pub use futures_util::future::Either;
+use futures_util::{
+    future::{self, LocalBoxFuture},
+    stream, StreamExt,
+};
+
+/// Wait for all futures to complete, returning their outputs in the same order.
+pub async fn join_all<I>(futures: I) -> Vec<<I::Item as Future>::Output>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    future::join_all(futures).await
+}
+
+/// Wait for all futures to complete, running at most `limit` of them at a time, and return their
+/// outputs in the same order. Futures are started in order as the running ones complete, e.g. to
+/// fan out subrequests without exceeding the
+/// [simultaneous open connections limit](https://developers.cloudflare.com/workers/platform/limits/#simultaneous-open-connections).
+pub async fn join_all_limited<I>(futures: I, limit: usize) -> Vec<<I::Item as Future>::Output>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    stream::iter(futures).buffered(limit.max(1)).collect().await
+}
+
+/// Wait for all futures to complete successfully, returning their outputs in the same order, or
+/// the first error as soon as any of them fails.
+pub async fn try_join_all<I, T, E>(futures: I) -> Result<Vec<T>, E>
+where
+    I: IntoIterator,
+    I::Item: Future<Output = Result<T, E>>,
+{
+    future::try_join_all(futures).await
+}
+
+/// Wait for both futures to complete successfully, or return the first error.
+pub async fn try_join<A, B, T, U, E>(a: A, b: B) -> Result<(T, U), E>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    future::try_join(a, b).await
+}
+
+/// Wait for both futures to complete.
+pub async fn join<A: Future, B: Future>(a: A, b: B) -> (A::Output, B::Output) {
+    future::join(a, b).await
+}
+
+/// Return the output of the first future to complete successfully. The remaining futures are
+/// dropped, cancelling them. If all futures fail, the error of the last one is returned.
+///
+/// # Panics
+///
+/// Panics if `futures` is empty.
+pub async fn select_ok<'a, I, T, E>(futures: I) -> Result<T, E>
+where
+    I: IntoIterator,
+    I::Item: Future<Output = Result<T, E>> + 'a,
+{
+    let futures: Vec<LocalBoxFuture<'a, Result<T, E>>> = futures
+        .into_iter()
+        .map(|f| Box::pin(f) as LocalBoxFuture<'a, Result<T, E>>)
+        .collect();
+    future::select_ok(futures).await.map(|(output, _)| output)
+}
+
+/// Wait for the first of two futures to complete, dropping the other one.
+pub async fn select<A: Future, B: Future>(a: A, b: B) -> Either<A::Output, B::Output> {
+    let (a, b) = (Box::pin(a), Box::pin(b));
+    match future::select(a, b).await {
+        Either::Left((output, _)) => Either::Left(output),
+        Either::Right((output, _)) => Either::Right(output),
+    }
+}
+
+/// Run a future in the background on the current thread, returning a [`JoinHandle`] to wait for
+/// its output or cancel it. Dropping the handle lets the future run to completion.
+///
+/// The runtime may stop the Worker at any point once the response has been returned, use
+/// [`Context::wait_until`](crate::Context::wait_until) to keep it alive until the future is done.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(env: Env) -> Result<()> {
+/// let kv = env.kv("CONFIG")?;
+/// let config = futures::spawn_local(async move { kv.get("config").text().await });
+/// let resp = Fetch::Url("https://example.com".parse()?).send().await?;
+/// let config = config.await??;
+/// # Ok(())
+/// # }
+/// ```
+pub fn spawn_local<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+{
+    let (output_tx, output_rx) = oneshot::channel();
+    let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+    let finished = Rc::new(Cell::new(false));
+    let done = finished.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        // Dropping the handle drops the sender without cancelling the future.
+        let cancelled = async move {
+            if cancel_rx.await.is_err() {
+                future::pending::<()>().await
+            }
+        };
+        if let Either::Left(output) = select(future, cancelled).await {
+            let _ = output_tx.send(output);
+        }
+        done.set(true);
+    });
+    JoinHandle {
+        output: output_rx,
+        cancel: RefCell::new(Some(cancel_tx)),
+        finished,
+    }
+}
+
+/// A future spawned with [`spawn_local`], which resolves to its output, or to an error if it was
+/// [cancelled](JoinHandle::cancel).
+#[derive(Debug)]
+pub struct JoinHandle<T> {
+    output: oneshot::Receiver<T>,
+    cancel: RefCell<Option<oneshot::Sender<()>>>,
+    finished: Rc<Cell<bool>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Drop the future the next time the executor runs, unless it already completed.
+    pub fn cancel(&self) {
+        if let Some(cancel) = self.cancel.borrow_mut().take() {
+            let _ = cancel.send(());
+        }
+    }
+
+    /// Whether the future completed or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = crate::Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.output)
+            .poll(cx)
+            .map_err(|_| crate::Error::RustError("the spawned future was cancelled".into()))
+    }
+}
+
+/// Spawns futures on the current thread, keeping track of how many of them are still running.
+#[derive(Debug, Clone, Default)]
+pub struct LocalSpawner {
+    running: Rc<Cell<usize>>,
+}
+
+impl LocalSpawner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `future` in the background, see [`spawn_local`].
+    pub fn spawn<F: Future<Output = ()> + 'static>(&self, future: F) {
+        let running = self.running.clone();
+        running.set(running.get() + 1);
+        wasm_bindgen_futures::spawn_local(async move {
+            let _guard = RunningGuard(running);
+            future.await;
+        });
+    }
+
+    /// The number of spawned futures which haven't completed yet.
+    pub fn running(&self) -> usize {
+        self.running.get()
+    }
+}
+
+struct RunningGuard(Rc<Cell<usize>>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
@@ -0,0 +1,328 @@
+//! Decisions based on where requests come from, using their [`Cf`] properties: restricting
+//! access by country or continent, detecting visitors from the EU, and picking the nearest of
+//! several regions to send a request to.
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::geo::{GeoPolicy, Location, Region, RegionPicker};
+//!
+//! const ORIGINS: &[Region] = &[
+//!     Region::new("https://us.example.com", 39.04, -77.49),
+//!     Region::new("https://eu.example.com", 50.11, 8.68),
+//!     Region::new("https://ap.example.com", 1.35, 103.82),
+//! ];
+//!
+//! # fn example() -> Router<'static, ()> {
+//! Router::new()
+//!     .middleware(GeoPolicy::new().deny_countries(["KP"]))
+//!     .get("/", |_, ctx| {
+//!         let location = ctx.extensions().get::<Location>().unwrap();
+//!         let origin = RegionPicker::new(ORIGINS).pick(location);
+//!         let banner = if location.is_eu() { "cookie consent" } else { "" };
+//!         Response::ok(format!("{} {banner}", origin.name()))
+//!     })
+//! # }
+//! ```
+
+use futures_util::future::LocalBoxFuture;
+
+use crate::{Cf, Middleware, Next, Request, Response, Result, RouteContext};
+
+/// Where a request comes from, a snapshot of the location properties of its [`Cf`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    country: Option<String>,
+    continent: Option<String>,
+    coordinates: Option<(f32, f32)>,
+    is_eu: bool,
+}
+
+impl Location {
+    /// The location of the request, unknown for requests without `cf` properties, such as those
+    /// constructed by the Worker itself.
+    pub fn from_request(req: &Request) -> Self {
+        req.cf().map(Self::from_cf).unwrap_or_default()
+    }
+
+    pub fn from_cf(cf: &Cf) -> Self {
+        Self {
+            country: cf.country(),
+            continent: cf.continent(),
+            coordinates: cf.coordinates(),
+            is_eu: cf.is_eu_country(),
+        }
+    }
+
+    /// The ISO 3166-1 alpha-2 code of the country, e.g. `"DE"`.
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+
+    /// The code of the continent, e.g. `"EU"`.
+    pub fn continent(&self) -> Option<&str> {
+        self.continent.as_deref()
+    }
+
+    /// Latitude and longitude.
+    pub fn coordinates(&self) -> Option<(f32, f32)> {
+        self.coordinates
+    }
+
+    /// Whether the request comes from a country of the European Union, e.g. to apply the GDPR.
+    pub fn is_eu(&self) -> bool {
+        self.is_eu
+    }
+}
+
+/// A [`Middleware`] allowing or denying requests by their country and continent, which also adds
+/// the [`Location`] of every request to its [extensions](RouteContext::extensions).
+///
+/// Requests are denied if their country or continent is denied, or if there are allowed
+/// countries or continents and the request matches neither. Requests from an unknown location
+/// are allowed unless [`deny_unknown`](GeoPolicy::deny_unknown) is set. Denied requests get a
+/// `403 Forbidden` response by default.
+#[derive(Debug, Clone)]
+pub struct GeoPolicy {
+    allowed_countries: Vec<String>,
+    denied_countries: Vec<String>,
+    allowed_continents: Vec<String>,
+    denied_continents: Vec<String>,
+    deny_unknown: bool,
+    status: u16,
+    message: String,
+}
+
+impl GeoPolicy {
+    /// A policy allowing every request.
+    pub fn new() -> Self {
+        Self {
+            allowed_countries: Vec::new(),
+            denied_countries: Vec::new(),
+            allowed_continents: Vec::new(),
+            denied_continents: Vec::new(),
+            deny_unknown: false,
+            status: 403,
+            message: "Forbidden".into(),
+        }
+    }
+
+    /// Only allow requests from these countries, or from the [allowed
+    /// continents](GeoPolicy::allow_continents).
+    #[must_use]
+    pub fn allow_countries<T: Into<String>>(
+        mut self,
+        countries: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.allowed_countries.extend(codes(countries));
+        self
+    }
+
+    #[must_use]
+    pub fn deny_countries<T: Into<String>>(
+        mut self,
+        countries: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.denied_countries.extend(codes(countries));
+        self
+    }
+
+    /// Only allow requests from these continents, or from the [allowed
+    /// countries](GeoPolicy::allow_countries).
+    #[must_use]
+    pub fn allow_continents<T: Into<String>>(
+        mut self,
+        continents: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.allowed_continents.extend(codes(continents));
+        self
+    }
+
+    #[must_use]
+    pub fn deny_continents<T: Into<String>>(
+        mut self,
+        continents: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.denied_continents.extend(codes(continents));
+        self
+    }
+
+    /// Deny requests whose country and continent are unknown.
+    #[must_use]
+    pub fn deny_unknown(mut self, deny: bool) -> Self {
+        self.deny_unknown = deny;
+        self
+    }
+
+    /// The status and body of the response to denied requests, e.g.
+    /// `451 Unavailable For Legal Reasons`.
+    #[must_use]
+    pub fn denied_response(mut self, status: u16, message: impl Into<String>) -> Self {
+        self.status = status;
+        self.message = message.into();
+        self
+    }
+
+    /// Whether requests from `location` are allowed.
+    pub fn allows(&self, location: &Location) -> bool {
+        let country = location.country().map(str::to_ascii_uppercase);
+        let continent = location.continent().map(str::to_ascii_uppercase);
+        let contains = |codes: &[String], code: &Option<String>| {
+            code.as_ref().map_or(false, |code| codes.contains(code))
+        };
+
+        if country.is_none() && continent.is_none() {
+            return !self.deny_unknown;
+        }
+        if contains(&self.denied_countries, &country)
+            || contains(&self.denied_continents, &continent)
+        {
+            return false;
+        }
+        if self.allowed_countries.is_empty() && self.allowed_continents.is_empty() {
+            return true;
+        }
+        contains(&self.allowed_countries, &country)
+            || contains(&self.allowed_continents, &continent)
+    }
+}
+
+impl Default for GeoPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn codes<T: Into<String>>(codes: impl IntoIterator<Item = T>) -> impl Iterator<Item = String> {
+    codes
+        .into_iter()
+        .map(|code| code.into().to_ascii_uppercase())
+}
+
+impl<'a, D: 'a> Middleware<'a, D> for GeoPolicy {
+    fn handle(
+        &self,
+        req: Request,
+        mut ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> LocalBoxFuture<'a, Result<Response>> {
+        let location = Location::from_request(&req);
+        if !self.allows(&location) {
+            let resp = Response::error(self.message.clone(), self.status);
+            return Box::pin(async move { resp });
+        }
+        ctx.extensions_mut().insert(location);
+        Box::pin(next.run(req, ctx))
+    }
+}
+
+/// A location requests can be sent to, such as an origin or a database replica.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    name: &'static str,
+    latitude: f32,
+    longitude: f32,
+}
+
+impl Region {
+    pub const fn new(name: &'static str, latitude: f32, longitude: f32) -> Self {
+        Self {
+            name,
+            latitude,
+            longitude,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The great-circle distance to the given coordinates, in kilometers.
+    pub fn distance_to(&self, (latitude, longitude): (f32, f32)) -> f64 {
+        const EARTH_RADIUS: f64 = 6371.0;
+        let (lat1, lon1) = (
+            f64::from(self.latitude).to_radians(),
+            f64::from(self.longitude).to_radians(),
+        );
+        let (lat2, lon2) = (
+            f64::from(latitude).to_radians(),
+            f64::from(longitude).to_radians(),
+        );
+        let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+}
+
+/// Picks the [`Region`] nearest to a request.
+#[derive(Debug, Clone, Copy)]
+pub struct RegionPicker<'r> {
+    regions: &'r [Region],
+}
+
+impl<'r> RegionPicker<'r> {
+    /// A picker of `regions`, the first of which is used when the location of a request is
+    /// unknown.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `regions` is empty.
+    pub fn new(regions: &'r [Region]) -> Self {
+        assert!(
+            !regions.is_empty(),
+            "RegionPicker needs at least one region"
+        );
+        Self { regions }
+    }
+
+    /// The region nearest to `location`.
+    pub fn pick(&self, location: &Location) -> &'r Region {
+        match location.coordinates() {
+            Some(coordinates) => self.nearest(coordinates),
+            None => &self.regions[0],
+        }
+    }
+
+    /// The region nearest to the given latitude and longitude.
+    pub fn nearest(&self, coordinates: (f32, f32)) -> &'r Region {
+        self.regions
+            .iter()
+            .min_by(|a, b| {
+                a.distance_to(coordinates)
+                    .total_cmp(&b.distance_to(coordinates))
+            })
+            .unwrap_or(&self.regions[0])
+    }
+}
+
+#[test]
+fn geo_policy_works() {
+    let location = |country: &str, continent: &str| Location {
+        country: Some(country.into()),
+        continent: Some(continent.into()),
+        ..Location::default()
+    };
+    let policy = GeoPolicy::new()
+        .allow_continents(["eu"])
+        .allow_countries(["US"])
+        .deny_countries(["BY"]);
+    assert!(policy.allows(&location("DE", "EU")));
+    assert!(policy.allows(&location("US", "NA")));
+    assert!(!policy.allows(&location("CA", "NA")));
+    assert!(!policy.allows(&location("BY", "EU")));
+    assert!(policy.allows(&Location::default()));
+    assert!(!policy.deny_unknown(true).allows(&Location::default()));
+}
+
+#[test]
+fn region_picker_works() {
+    let regions = [
+        Region::new("iad", 38.94, -77.46),
+        Region::new("fra", 50.03, 8.57),
+        Region::new("sin", 1.36, 103.99),
+    ];
+    let picker = RegionPicker::new(&regions);
+    assert_eq!(picker.nearest((48.86, 2.35)).name(), "fra");
+    assert_eq!(picker.nearest((35.68, 139.69)).name(), "sin");
+    assert_eq!(picker.nearest((40.71, -74.01)).name(), "iad");
+    assert_eq!(picker.pick(&Location::default()).name(), "iad");
+}
@@ -1,12 +1,93 @@
-use std::ops::Deref;
+//! State which lives as long as the isolate, shared by every event it handles, and the global
+//! [`Fetch`].
+//!
+//! Expensive state, like compiled regular expressions or parsed configuration, can be built once
+//! per isolate with [`init`]. Building it in the [`#[event(start)]`](macro@crate::event) handler
+//! guarantees it's ready before any other handler runs; state which needs the [`Env`](crate::Env)
+//! can be built by the first handler which needs it instead, as the start handler has no `Env`.
+//!
+//! ```no_run
+//! use worker::*;
+//!
+//! struct Blocklist(Vec<String>);
+//!
+//! const BLOCKLIST: &str = "/wp-admin\n/.env\n";
+//!
+//! #[event(start)]
+//! fn start() {
+//!     global::init(|| Blocklist(BLOCKLIST.lines().map(String::from).collect()));
+//! }
+//!
+//! #[event(fetch)]
+//! async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+//!     let blocklist = global::get::<Blocklist>().expect("initialized on start");
+//!     if blocklist.0.iter().any(|path| req.path() == *path) {
+//!         return Response::error("Forbidden", 403);
+//!     }
+//!     Response::ok("Hello")
+//! }
+//! ```
+
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    ops::Deref,
+    rc::Rc,
+};
 
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 
 use crate::{
-    request::Request as WorkerRequest, response::Response as WorkerResponse, AbortSignal, Result,
+    outbound::{self, Transport},
+    request::Request as WorkerRequest,
+    request_metrics::{self, Call},
+    response::Response as WorkerResponse,
+    trace, AbortSignal, Method, Result,
 };
 
+thread_local! {
+    static STATE: RefCell<HashMap<TypeId, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// The isolate's `T`, built by `f` if it wasn't yet. `f` runs at most once per isolate, unless it
+/// panics.
+pub fn init<T: 'static>(f: impl FnOnce() -> T) -> Rc<T> {
+    match try_init(|| Ok::<_, std::convert::Infallible>(f())) {
+        Ok(value) => value,
+        Err(infallible) => match infallible {},
+    }
+}
+
+/// Like [`init`], for state which may fail to build, in which case it's built again by the next
+/// call.
+pub fn try_init<T: 'static, E>(
+    f: impl FnOnce() -> std::result::Result<T, E>,
+) -> std::result::Result<Rc<T>, E> {
+    if let Some(value) = get() {
+        return Ok(value);
+    }
+    // `f` may initialize other state, so it runs without borrowing the state.
+    let value = Rc::new(f()?);
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let value = state.entry(TypeId::of::<T>()).or_insert(value);
+        Ok(value
+            .clone()
+            .downcast()
+            .expect("the state of a type has that type"))
+    })
+}
+
+/// The isolate's `T`, if it was [initialized](init).
+pub fn get<T: 'static>() -> Option<Rc<T>> {
+    STATE.with(|state| {
+        let value = state.borrow().get(&TypeId::of::<T>())?.clone();
+        value.downcast().ok()
+    })
+}
+
 /// Construct a Fetch call from a URL string or a Request object. Call its `send` method to execute
 /// the request.
 pub enum Fetch {
@@ -17,25 +98,106 @@ pub enum Fetch {
 impl Fetch {
     /// Execute a Fetch call and receive a Response.
     pub async fn send(&self) -> Result<WorkerResponse> {
-        match self {
-            Fetch::Url(url) => fetch_with_str(url.as_ref(), None).await,
-            Fetch::Request(req) => fetch_with_request(req, None).await,
-        }
+        self.dispatch(None).await
     }
 
     /// Execute a Fetch call and receive a Response.
     pub async fn send_with_signal(&self, signal: &AbortSignal) -> Result<WorkerResponse> {
+        self.dispatch(Some(signal)).await
+    }
+
+    async fn dispatch(&self, signal: Option<&AbortSignal>) -> Result<WorkerResponse> {
+        if let Some(next) = outbound::chain(Transport::Fetch(signal.cloned())) {
+            let req = match self {
+                Fetch::Url(url) => WorkerRequest::new(url.as_str(), Method::Get)?,
+                Fetch::Request(req) => req.clone()?,
+            };
+            return next.run(req).await;
+        }
         match self {
-            Fetch::Url(url) => fetch_with_str(url.as_ref(), Some(signal)).await,
-            Fetch::Request(req) => fetch_with_request(req, Some(signal)).await,
+            Fetch::Url(url) => fetch_with_str(url.as_ref(), signal).await,
+            Fetch::Request(req) => fetch_with_request(req, signal).await,
         }
     }
 }
 
+/// Send `requests` concurrently, with at most `max_in_flight` of them in flight at a time, and
+/// collect the outcome of each, in the order of the requests, into a [`FetchReport`].
+///
+/// A request fails if it can't be sent, not because of the status of its response.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(ids: Vec<u32>) -> Result<Response> {
+/// let requests = ids
+///     .iter()
+///     .map(|id| Ok(Fetch::Url(format!("https://api.example/items/{id}").parse()?)))
+///     .collect::<Result<Vec<_>>>()?;
+/// let report = fetch_all_limited(requests, 6).await;
+/// for (i, err) in report.failures() {
+///     console_warn!("item {} failed: {}", ids[i], err);
+/// }
+/// let mut items = Vec::new();
+/// for mut resp in report.into_responses() {
+///     items.push(resp.text().await?);
+/// }
+/// Response::from_json(&items)
+/// # }
+/// ```
+pub async fn fetch_all_limited(
+    requests: impl IntoIterator<Item = Fetch>,
+    max_in_flight: usize,
+) -> FetchReport {
+    let requests: Vec<Fetch> = requests.into_iter().collect();
+    let results =
+        crate::futures::join_all_limited(requests.iter().map(Fetch::send), max_in_flight).await;
+    FetchReport { results }
+}
+
+/// The outcome of each request sent with [`fetch_all_limited`], in the order of the requests.
+#[derive(Debug)]
+pub struct FetchReport {
+    pub results: Vec<Result<WorkerResponse>>,
+}
+
+impl FetchReport {
+    /// Whether every request succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.results.iter().all(|result| result.is_ok())
+    }
+
+    /// The index of each request which failed, with its error.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &crate::Error)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, result)| result.as_ref().err().map(|err| (i, err)))
+    }
+
+    /// The responses of the requests which succeeded, in order, dropping the failures.
+    pub fn into_responses(self) -> Vec<WorkerResponse> {
+        self.results.into_iter().filter_map(Result::ok).collect()
+    }
+
+    /// The responses of every request, or the error of the first request which failed.
+    pub fn try_into_responses(self) -> Result<Vec<WorkerResponse>> {
+        self.results.into_iter().collect()
+    }
+}
+
+fn record_fetch(url: &str) {
+    let host = url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_default();
+    request_metrics::record(Call::Fetch(&host));
+}
+
 async fn fetch_with_str(url: &str, signal: Option<&AbortSignal>) -> Result<WorkerResponse> {
     let mut init = web_sys::RequestInit::new();
     init.signal(signal.map(|x| x.deref()));
 
+    record_fetch(url);
     let worker: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
     let promise = worker.fetch_with_str_and_init(url, &init);
     let resp = JsFuture::from(promise).await?;
@@ -43,7 +205,7 @@ async fn fetch_with_str(url: &str, signal: Option<&AbortSignal>) -> Result<Worke
     Ok(resp.into())
 }
 
-async fn fetch_with_request(
+pub(crate) async fn fetch_with_request(
     request: &WorkerRequest,
     signal: Option<&AbortSignal>,
 ) -> Result<WorkerResponse> {
@@ -51,9 +213,22 @@ async fn fetch_with_request(
     init.signal(signal.map(|x| x.deref()));
 
     let worker: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
-    let req = request.inner();
+    let traced = trace::propagate(request);
+    let req = traced.as_ref().unwrap_or_else(|| request.inner());
+    record_fetch(&req.url());
     let promise = worker.fetch_with_request_and_init(req, &init);
     let resp = JsFuture::from(promise).await?;
     let edge_response: web_sys::Response = resp.dyn_into()?;
     Ok(edge_response.into())
 }
+
+#[test]
+fn init_works() {
+    struct Config(u32);
+
+    assert!(get::<Config>().is_none());
+    assert!(try_init(|| Err::<Config, _>("unavailable")).is_err());
+    assert_eq!(init(|| Config(1)).0, 1);
+    assert_eq!(init(|| Config(2)).0, 1);
+    assert_eq!(get::<Config>().map(|config| config.0), Some(1));
+}
@@ -0,0 +1,84 @@
+use std::{cell::RefCell, rc::Rc};
+
+use async_trait::async_trait;
+
+#[cfg(feature = "queue")]
+use crate::MessageBatch;
+use crate::{Context, Env, Request, Response, Result, ScheduleContext, ScheduledEvent};
+
+/// The handlers of a Worker, which share the state of the implementing type. Implement it in an
+/// `impl` block annotated with [`#[worker]`](macro@crate::worker), which exports the handlers the
+/// block defines, and builds the state with [`Worker::new`] once per isolate.
+///
+/// ```no_run
+/// # use worker::*;
+/// struct App {
+///     greeting: String,
+/// }
+///
+/// #[worker]
+/// impl Worker for App {
+///     fn new(env: &Env) -> Result<Self> {
+///         Ok(Self {
+///             greeting: env.var("GREETING")?.to_string(),
+///         })
+///     }
+///
+///     async fn fetch(&self, _req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+///         Response::ok(&self.greeting)
+///     }
+///
+///     async fn scheduled(&self, event: ScheduledEvent, _env: Env, _ctx: ScheduleContext) {
+///         console_log!("{} at {}", self.greeting, event.cron());
+///     }
+/// }
+/// ```
+///
+/// Only the `fetch`, `scheduled` and `queue` handlers can be implemented this way. This crate has
+/// no bindings for `tail` and `email` events, and Durable Objects are classes of their own, still
+/// exported with [`#[durable_object]`](macro@crate::durable_object).
+#[async_trait(?Send)]
+pub trait Worker: Sized + 'static {
+    /// Build the state shared by the handlers. It's called by the first event of an isolate, and
+    /// again by the next event if it fails.
+    fn new(env: &Env) -> Result<Self>;
+
+    /// Handle an HTTP request, like [`#[event(fetch)]`](macro@crate::event).
+    async fn fetch(&self, _req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+        Response::error("Not Found", 404)
+    }
+
+    /// Handle a Cron Trigger, like [`#[event(scheduled)]`](macro@crate::event).
+    async fn scheduled(&self, _event: ScheduledEvent, _env: Env, _ctx: ScheduleContext) {}
+
+    /// Handle a batch of queue messages, like [`#[event(queue)]`](macro@crate::event).
+    #[cfg(feature = "queue")]
+    async fn queue(
+        &self,
+        _batch: MessageBatch<serde_json::Value>,
+        _env: Env,
+        _ctx: Context,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The state of a [`Worker`] in the current isolate, used by the code generated by
+/// [`#[worker]`](macro@crate::worker).
+#[doc(hidden)]
+pub struct WorkerInstance<W>(RefCell<Option<Rc<W>>>);
+
+impl<W: Worker> WorkerInstance<W> {
+    pub const fn new() -> Self {
+        Self(RefCell::new(None))
+    }
+
+    pub fn get(&self, env: &Env) -> Result<Rc<W>> {
+        if let Some(worker) = &*self.0.borrow() {
+            return Ok(worker.clone());
+        }
+        let worker = Rc::new(W::new(env)?);
+        *self.0.borrow_mut() = Some(worker.clone());
+        Ok(worker)
+    }
+}
@@ -0,0 +1,212 @@
+use std::{future::Future, pin::Pin, task::Poll, time::Duration};
+
+use futures_util::future::poll_fn;
+use url::Url;
+
+use crate::{AbortController, Delay, Error, Fetch, Request, RequestInit, Response, Result};
+
+/// Sends the same request to several candidate origins and returns the first successful response,
+/// aborting the other requests, to cut the tail latency of multi-origin setups.
+///
+/// Like Happy Eyeballs, each origin after the first is only tried once the previous ones have been
+/// pending for [`stagger`](Hedge::stagger), which avoids doubling the load of the origins when the
+/// first one is fast, or as soon as all of them failed. A response with a `5xx` status is a
+/// failure, which starts the next origin right away. If every origin fails, the last `5xx`
+/// response is returned, or the last error when no origin responded.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use std::time::Duration;
+/// # async fn example() -> Result<Response> {
+/// let hedge = Hedge::new([
+///     "https://eu.origin.example".parse()?,
+///     "https://us.origin.example".parse()?,
+/// ])
+/// .stagger(Duration::from_millis(200));
+/// let (origin, resp) = hedge.send("/api/items?page=2", &RequestInit::new()).await?;
+/// console_log!("served by {origin}");
+/// Ok(resp)
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Hedge {
+    origins: Vec<Url>,
+    stagger: Duration,
+}
+
+impl Hedge {
+    /// Hedge requests over `origins`, tried in order.
+    pub fn new(origins: impl IntoIterator<Item = Url>) -> Self {
+        Self {
+            origins: origins.into_iter().collect(),
+            stagger: Duration::from_millis(250),
+        }
+    }
+
+    /// How long to wait for the pending requests before trying the next origin, 250ms by default.
+    /// With zero, all the origins are tried at once.
+    #[must_use]
+    pub fn stagger(mut self, stagger: Duration) -> Self {
+        self.stagger = stagger;
+        self
+    }
+
+    /// Send a request for `path`, which may include a query, to the origins, returning the origin
+    /// which responded first and its response. The body of `init` is sent to every origin tried,
+    /// so it must be a string or bytes rather than a stream.
+    pub async fn send(&self, path: &str, init: &RequestInit) -> Result<(Url, Response)> {
+        if self.origins.is_empty() {
+            return Err(Error::RustError("no origin to send the request to".into()));
+        }
+        let controllers: Vec<AbortController> =
+            self.origins.iter().map(|_| Default::default()).collect();
+        let attempts = self
+            .origins
+            .iter()
+            .zip(&controllers)
+            .map(|(origin, controller)| {
+                let signal = controller.signal();
+                async move {
+                    let url = origin.join(path).map_err(|e| Failure::Error(e.into()))?;
+                    let req = Request::new_with_init(url.as_str(), init).map_err(Failure::Error)?;
+                    let resp = Fetch::Request(req)
+                        .send_with_signal(&signal)
+                        .await
+                        .map_err(Failure::Error)?;
+                    if resp.status_code() >= 500 {
+                        return Err(Failure::Status(resp));
+                    }
+                    Ok(resp)
+                }
+            });
+        let stagger = self.stagger;
+        let result = race(attempts, || async move {
+            if !stagger.is_zero() {
+                Delay::from(stagger).await;
+            }
+        })
+        .await;
+        let (winner, resp) = match result {
+            Ok(won) => won,
+            Err(mut failures) => {
+                let status = failures
+                    .iter()
+                    .rposition(|(_, failure)| matches!(failure, Failure::Status(_)));
+                match failures.swap_remove(status.unwrap_or(failures.len() - 1)) {
+                    (last, Failure::Status(resp)) => (last, resp),
+                    (_, Failure::Error(e)) => return Err(e),
+                }
+            }
+        };
+        for (i, controller) in controllers.into_iter().enumerate() {
+            // Aborting the winner would abort the body of its response.
+            if i != winner {
+                controller.abort();
+            }
+        }
+        Ok((self.origins[winner].clone(), resp))
+    }
+}
+
+/// Why an origin failed.
+enum Failure {
+    Status(Response),
+    Error(Error),
+}
+
+/// Run `attempts` in order until one succeeds, returning its index and output. Each attempt is
+/// started once the previous ones have been pending until the future `stagger` returns is ready,
+/// or right away when all of them failed. If every attempt fails, their failures are returned in
+/// the order they failed.
+///
+/// # Panics
+///
+/// Panics if `attempts` is empty.
+async fn race<T, E, A, S, D>(
+    attempts: impl IntoIterator<Item = A>,
+    mut stagger: S,
+) -> std::result::Result<(usize, T), Vec<(usize, E)>>
+where
+    A: Future<Output = std::result::Result<T, E>>,
+    S: FnMut() -> D,
+    D: Future<Output = ()>,
+{
+    let mut waiting = attempts.into_iter().enumerate();
+    let mut running: Vec<(usize, Pin<Box<A>>)> = Vec::new();
+    let mut timer: Option<Pin<Box<D>>> = None;
+    let mut failures = Vec::new();
+    poll_fn(|cx| loop {
+        let mut i = 0;
+        while i < running.len() {
+            match running[i].1.as_mut().poll(cx) {
+                Poll::Ready(Ok(output)) => return Poll::Ready(Ok((running[i].0, output))),
+                Poll::Ready(Err(e)) => failures.push((running.remove(i).0, e)),
+                Poll::Pending => i += 1,
+            }
+        }
+        let staggered = match &mut timer {
+            Some(timer) => timer.as_mut().poll(cx).is_ready(),
+            None => false,
+        };
+        if running.is_empty() || staggered {
+            match waiting.next() {
+                Some((i, attempt)) => {
+                    running.push((i, Box::pin(attempt)));
+                    timer = Some(Box::pin(stagger()));
+                    continue;
+                }
+                None if running.is_empty() => {
+                    assert!(!failures.is_empty(), "no attempt to race");
+                    return Poll::Ready(Err(std::mem::take(&mut failures)));
+                }
+                None => timer = None,
+            }
+        }
+        return Poll::Pending;
+    })
+    .await
+}
+
+#[test]
+fn failed_attempt_starts_the_next_one() {
+    use std::{cell::Cell, pin::pin, task::Context};
+
+    let started = Cell::new(0);
+    let (tx, rx) = futures_channel::oneshot::channel::<()>();
+    let mut rx = Some(rx);
+    let attempts = (0..3).map(|i| {
+        started.set(started.get().max(i + 1));
+        let rx = if i == 1 { rx.take() } else { None };
+        async move {
+            match rx {
+                Some(rx) => rx.await.map(|_| "second").map_err(|_| "cancelled"),
+                None => Err("failed"),
+            }
+        }
+    });
+    // The stagger never passes, so only failures start the next attempt.
+    let mut race = pin!(race(attempts, futures_util::future::pending::<()>));
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    assert!(race.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(started.get(), 2);
+    tx.send(()).unwrap();
+    assert_eq!(race.as_mut().poll(&mut cx), Poll::Ready(Ok((1, "second"))));
+    assert_eq!(started.get(), 2);
+}
+
+#[test]
+fn failures_are_returned_in_order() {
+    use std::{pin::pin, task::Context};
+
+    let attempts = ["first", "second"].map(|e| async move { std::result::Result::<(), _>::Err(e) });
+    let race = pin!(race(attempts, futures_util::future::pending::<()>));
+    let waker = futures_util::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    assert_eq!(
+        race.poll(&mut cx),
+        Poll::Ready(Err(vec![(0, "first"), (1, "second")]))
+    );
+}
@@ -8,6 +8,9 @@ mod redirect;
 pub mod request;
 #[cfg(feature = "http")]
 pub mod response;
+mod status;
+
+pub use status::StatusCode;
 
 /// A [`Method`](https://developer.mozilla.org/en-US/docs/Web/API/Request/method) representation
 /// used on Request objects.
@@ -84,3 +87,25 @@ impl ToString for Method {
         (*self).clone().into()
     }
 }
+
+impl From<Method> for http::Method {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::Head => http::Method::HEAD,
+            Method::Get => http::Method::GET,
+            Method::Post => http::Method::POST,
+            Method::Put => http::Method::PUT,
+            Method::Patch => http::Method::PATCH,
+            Method::Delete => http::Method::DELETE,
+            Method::Options => http::Method::OPTIONS,
+            Method::Connect => http::Method::CONNECT,
+            Method::Trace => http::Method::TRACE,
+        }
+    }
+}
+
+impl From<http::Method> for Method {
+    fn from(method: http::Method) -> Self {
+        Method::from(method.as_str().to_string())
+    }
+}
@@ -2,16 +2,19 @@ use super::header::{header_map_from_web_sys_headers, web_sys_headers_from_header
 use crate::http::body::Body;
 use crate::HttpResponse;
 use crate::Result;
+#[cfg(feature = "websocket")]
 use crate::WebSocket;
 use bytes::Bytes;
 
 use crate::http::body::BodyStream;
+#[cfg(feature = "websocket")]
 use worker_sys::ext::ResponseExt;
+#[cfg(feature = "websocket")]
 use worker_sys::ext::ResponseInitExt;
 
 /// **Requires** `http` feature. Convert generic [`http::Response<B>`](crate::HttpResponse)
 /// to [`web_sys::Resopnse`](web_sys::Response) where `B` can be any [`http_body::Body`](http_body::Body)
-pub fn to_wasm<B>(mut res: http::Response<B>) -> Result<web_sys::Response>
+pub fn to_wasm<B>(res: http::Response<B>) -> Result<web_sys::Response>
 where
     B: http_body::Body<Data = Bytes> + 'static,
 {
@@ -19,7 +22,8 @@ where
     init.status(res.status().as_u16());
     let headers = web_sys_headers_from_header_map(res.headers())?;
     init.headers(headers.as_ref());
-    if let Some(ws) = res.extensions_mut().remove::<WebSocket>() {
+    #[cfg(feature = "websocket")]
+    if let Some(ws) = res.extensions().get::<WebSocket>() {
         init.websocket(ws.as_ref());
     }
 
@@ -49,6 +53,7 @@ pub fn from_wasm(res: web_sys::Response) -> Result<HttpResponse> {
     if let Some(headers) = builder.headers_mut() {
         header_map_from_web_sys_headers(res.headers(), headers)?;
     }
+    #[cfg(feature = "websocket")]
     if let Some(ws) = res.websocket() {
         builder = builder.extension(WebSocket::from(ws));
     }
@@ -68,6 +73,7 @@ impl From<crate::Response> for http::Response<axum::body::Body> {
         if let Some(headers) = builder.headers_mut() {
             crate::http::header::header_map_from_web_sys_headers(res.headers(), headers).unwrap();
         }
+        #[cfg(feature = "websocket")]
         if let Some(ws) = res.websocket() {
             builder = builder.extension(WebSocket::from(ws));
         }
@@ -0,0 +1,195 @@
+use std::{convert::TryFrom, fmt};
+
+use crate::{Error, Result};
+
+macro_rules! status_codes {
+    ($($name:ident = $code:literal, $reason:literal;)*) => {
+        /// An HTTP [status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status), with a
+        /// variant for each code registered with IANA. Convert from a `u16` with
+        /// [`StatusCode::from_u16`], which only uses [`StatusCode::Other`] for unregistered codes.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum StatusCode {
+            $(
+                #[doc = concat!("`", $code, " ", $reason, "`")]
+                $name,
+            )*
+            /// A valid status code without a variant.
+            Other(u16),
+        }
+
+        impl StatusCode {
+            /// The status code for `code`, which must be in the 100-999 range.
+            pub fn from_u16(code: u16) -> Result<Self> {
+                http::StatusCode::from_u16(code)?;
+                Ok(match code {
+                    $($code => StatusCode::$name,)*
+                    code => StatusCode::Other(code),
+                })
+            }
+
+            pub fn as_u16(self) -> u16 {
+                match self {
+                    $(StatusCode::$name => $code,)*
+                    StatusCode::Other(code) => code,
+                }
+            }
+
+            /// The reason phrase of the status code, e.g. `Not Found`, if it's registered.
+            pub fn canonical_reason(self) -> Option<&'static str> {
+                match self {
+                    $(StatusCode::$name => Some($reason),)*
+                    StatusCode::Other(_) => None,
+                }
+            }
+        }
+    };
+}
+
+status_codes! {
+    Continue = 100, "Continue";
+    SwitchingProtocols = 101, "Switching Protocols";
+    Processing = 102, "Processing";
+    EarlyHints = 103, "Early Hints";
+    Ok = 200, "OK";
+    Created = 201, "Created";
+    Accepted = 202, "Accepted";
+    NonAuthoritativeInformation = 203, "Non-Authoritative Information";
+    NoContent = 204, "No Content";
+    ResetContent = 205, "Reset Content";
+    PartialContent = 206, "Partial Content";
+    MultiStatus = 207, "Multi-Status";
+    AlreadyReported = 208, "Already Reported";
+    ImUsed = 226, "IM Used";
+    MultipleChoices = 300, "Multiple Choices";
+    MovedPermanently = 301, "Moved Permanently";
+    Found = 302, "Found";
+    SeeOther = 303, "See Other";
+    NotModified = 304, "Not Modified";
+    UseProxy = 305, "Use Proxy";
+    TemporaryRedirect = 307, "Temporary Redirect";
+    PermanentRedirect = 308, "Permanent Redirect";
+    BadRequest = 400, "Bad Request";
+    Unauthorized = 401, "Unauthorized";
+    PaymentRequired = 402, "Payment Required";
+    Forbidden = 403, "Forbidden";
+    NotFound = 404, "Not Found";
+    MethodNotAllowed = 405, "Method Not Allowed";
+    NotAcceptable = 406, "Not Acceptable";
+    ProxyAuthenticationRequired = 407, "Proxy Authentication Required";
+    RequestTimeout = 408, "Request Timeout";
+    Conflict = 409, "Conflict";
+    Gone = 410, "Gone";
+    LengthRequired = 411, "Length Required";
+    PreconditionFailed = 412, "Precondition Failed";
+    ContentTooLarge = 413, "Content Too Large";
+    UriTooLong = 414, "URI Too Long";
+    UnsupportedMediaType = 415, "Unsupported Media Type";
+    RangeNotSatisfiable = 416, "Range Not Satisfiable";
+    ExpectationFailed = 417, "Expectation Failed";
+    ImATeapot = 418, "I'm a teapot";
+    MisdirectedRequest = 421, "Misdirected Request";
+    UnprocessableContent = 422, "Unprocessable Content";
+    Locked = 423, "Locked";
+    FailedDependency = 424, "Failed Dependency";
+    TooEarly = 425, "Too Early";
+    UpgradeRequired = 426, "Upgrade Required";
+    PreconditionRequired = 428, "Precondition Required";
+    TooManyRequests = 429, "Too Many Requests";
+    RequestHeaderFieldsTooLarge = 431, "Request Header Fields Too Large";
+    UnavailableForLegalReasons = 451, "Unavailable For Legal Reasons";
+    InternalServerError = 500, "Internal Server Error";
+    NotImplemented = 501, "Not Implemented";
+    BadGateway = 502, "Bad Gateway";
+    ServiceUnavailable = 503, "Service Unavailable";
+    GatewayTimeout = 504, "Gateway Timeout";
+    HttpVersionNotSupported = 505, "HTTP Version Not Supported";
+    VariantAlsoNegotiates = 506, "Variant Also Negotiates";
+    InsufficientStorage = 507, "Insufficient Storage";
+    LoopDetected = 508, "Loop Detected";
+    NotExtended = 510, "Not Extended";
+    NetworkAuthenticationRequired = 511, "Network Authentication Required";
+}
+
+impl StatusCode {
+    /// `1xx`
+    pub fn is_informational(self) -> bool {
+        (100..200).contains(&self.as_u16())
+    }
+
+    /// `2xx`
+    pub fn is_success(self) -> bool {
+        (200..300).contains(&self.as_u16())
+    }
+
+    /// `3xx`
+    pub fn is_redirection(self) -> bool {
+        (300..400).contains(&self.as_u16())
+    }
+
+    /// `4xx`
+    pub fn is_client_error(self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    /// `5xx`
+    pub fn is_server_error(self) -> bool {
+        (500..600).contains(&self.as_u16())
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.canonical_reason() {
+            Some(reason) => write!(f, "{} {}", self.as_u16(), reason),
+            None => write!(f, "{}", self.as_u16()),
+        }
+    }
+}
+
+impl From<StatusCode> for u16 {
+    fn from(status: StatusCode) -> Self {
+        status.as_u16()
+    }
+}
+
+impl TryFrom<u16> for StatusCode {
+    type Error = Error;
+
+    fn try_from(code: u16) -> Result<Self> {
+        Self::from_u16(code)
+    }
+}
+
+impl PartialEq<u16> for StatusCode {
+    fn eq(&self, other: &u16) -> bool {
+        self.as_u16() == *other
+    }
+}
+
+impl From<http::StatusCode> for StatusCode {
+    fn from(status: http::StatusCode) -> Self {
+        // `http::StatusCode` is always in the 100-999 range.
+        Self::from_u16(status.as_u16()).unwrap_or(StatusCode::Other(status.as_u16()))
+    }
+}
+
+impl From<StatusCode> for http::StatusCode {
+    fn from(status: StatusCode) -> Self {
+        http::StatusCode::from_u16(status.as_u16()).unwrap_or(http::StatusCode::OK)
+    }
+}
+
+#[test]
+fn status_code_works() {
+    let not_found = StatusCode::from_u16(404).unwrap();
+    assert_eq!(not_found, StatusCode::NotFound);
+    assert_eq!(not_found.to_string(), "404 Not Found");
+    assert!(not_found.is_client_error() && !not_found.is_success());
+    assert_eq!(StatusCode::from_u16(299).unwrap(), StatusCode::Other(299));
+    assert!(StatusCode::from_u16(1000).is_err());
+    assert_eq!(
+        http::StatusCode::from(StatusCode::UnprocessableContent),
+        http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+}
@@ -0,0 +1,274 @@
+use std::{cell::RefCell, collections::HashSet, future::Future, time::Duration};
+
+use async_trait::async_trait;
+use wasm_bindgen::JsValue;
+
+use crate::{kv::KvStore, Date, Error, Result, Storage};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+thread_local! {
+    static IN_PROGRESS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// Records which messages or requests were processed, so that work is done only once even though
+/// Queues deliver messages at least once.
+///
+/// Ids are only recorded once their work succeeded, so failed work runs again when the message
+/// is retried. Work for an id which is still running in the same isolate fails rather than runs
+/// twice, so that the message is retried once the first run is done. When backed by KV, which is eventually consistent, a message delivered twice in
+/// quick succession can still be processed twice; Durable Object storage doesn't have that
+/// problem if all work for an id goes through the same object.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(batch: MessageBatch<String>, env: Env) -> Result<()> {
+/// let processed = Idempotency::kv(env.kv("PROCESSED")?);
+/// for message in batch.messages()? {
+///     processed
+///         .run_once(&message.id(), async {
+///             console_log!("processing {}", message.body());
+///             Ok(())
+///         })
+///         .await?;
+///     message.ack();
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Idempotency {
+    backend: Backend,
+    prefix: String,
+    ttl: Duration,
+}
+
+#[derive(Clone)]
+enum Backend {
+    Kv(KvStore),
+    Storage(Storage),
+}
+
+impl Idempotency {
+    /// Record processed ids in a KV namespace.
+    pub fn kv(kv: KvStore) -> Self {
+        Self::new(Backend::Kv(kv))
+    }
+
+    /// Record processed ids in the storage of a Durable Object.
+    pub fn storage(storage: Storage) -> Self {
+        Self::new(Backend::Storage(storage))
+    }
+
+    fn new(backend: Backend) -> Self {
+        Self {
+            backend,
+            prefix: "idempotency:".into(),
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// How long processed ids are remembered, one day by default. This should be longer than
+    /// the time a message can spend being retried. KV requires at least 60 seconds.
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The prefix of the keys the ids are stored under, `idempotency:` by default.
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Run `f` unless `id` was already processed, recording `id` once `f` succeeds. Returns
+    /// `None` when `f` was skipped, and an error when `f` is already running for `id`.
+    pub async fn run_once<F, T>(&self, id: &str, f: F) -> Result<Option<T>>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        run_once(&self.backend, &self.key(id), self.ttl, f).await
+    }
+
+    /// Whether `id` was processed within the TTL.
+    pub async fn is_processed(&self, id: &str) -> Result<bool> {
+        self.backend.contains(&self.key(id)).await
+    }
+
+    /// Record `id` as processed.
+    pub async fn mark_processed(&self, id: &str) -> Result<()> {
+        self.backend.insert(&self.key(id), self.ttl).await
+    }
+
+    fn key(&self, id: &str) -> String {
+        key(&self.prefix, id)
+    }
+}
+
+fn key(prefix: &str, id: &str) -> String {
+    format!("{prefix}{id}")
+}
+
+/// Where the keys of processed ids are recorded.
+#[async_trait(?Send)]
+trait Records {
+    async fn contains(&self, key: &str) -> Result<bool>;
+
+    async fn insert(&self, key: &str, ttl: Duration) -> Result<()>;
+}
+
+async fn run_once<R, F, T>(records: &R, key: &str, ttl: Duration, f: F) -> Result<Option<T>>
+where
+    R: Records,
+    F: Future<Output = Result<T>>,
+{
+    let Some(_running) = Running::start(key) else {
+        return Err(Error::RustError(format!(
+            "{key} is already being processed"
+        )));
+    };
+    if records.contains(key).await? {
+        return Ok(None);
+    }
+    let output = f.await?;
+    records.insert(key, ttl).await?;
+    Ok(Some(output))
+}
+
+/// Marks the work of a key as running in this isolate until it's dropped.
+struct Running(String);
+
+impl Running {
+    fn start(key: &str) -> Option<Self> {
+        IN_PROGRESS
+            .with(|keys| keys.borrow_mut().insert(key.to_owned()))
+            .then(|| Self(key.to_owned()))
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        IN_PROGRESS.with(|keys| keys.borrow_mut().remove(&self.0));
+    }
+}
+
+#[async_trait(?Send)]
+impl Records for Backend {
+    async fn contains(&self, key: &str) -> Result<bool> {
+        match self {
+            Backend::Kv(kv) => Ok(kv.get(key).skip_consistency_check().text().await?.is_some()),
+            Backend::Storage(storage) => {
+                let values = storage.get_multiple(vec![key]).await?;
+                let expires = values.get(&JsValue::from_str(key)).as_f64();
+                let now = Date::now().as_millis() as f64;
+                Ok(matches!(expires, Some(expires) if expires > now))
+            }
+        }
+    }
+
+    async fn insert(&self, key: &str, ttl: Duration) -> Result<()> {
+        match self {
+            Backend::Kv(kv) => {
+                let ttl = ttl.as_secs().max(crate::kv::MIN_EXPIRATION_TTL);
+                kv.put(key, "1")?.expiration_ttl(ttl).execute().await?;
+            }
+            Backend::Storage(storage) => {
+                // Storage has no TTL, so the expiry is stored and checked when reading. Expired ids
+                // stay in storage until they're overwritten.
+                let expires = (Date::now().as_millis() + ttl.as_millis() as u64) as f64;
+                storage.clone().put(key, expires).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{pin::pin, task::Context};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Memory(RefCell<HashSet<String>>);
+
+    #[async_trait(?Send)]
+    impl Records for Memory {
+        async fn contains(&self, key: &str) -> Result<bool> {
+            Ok(self.0.borrow().contains(key))
+        }
+
+        async fn insert(&self, key: &str, _ttl: Duration) -> Result<()> {
+            self.0.borrow_mut().insert(key.to_owned());
+            Ok(())
+        }
+    }
+
+    fn ready<T>(fut: impl Future<Output = T>) -> T {
+        let waker = futures_util::task::noop_waker();
+        match pin!(fut).poll(&mut Context::from_waker(&waker)) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("the future should be ready"),
+        }
+    }
+
+    #[test]
+    fn key_works() {
+        assert_eq!(key("idempotency:", "msg-1"), "idempotency:msg-1");
+        assert_eq!(key("", "msg-1"), "msg-1");
+    }
+
+    #[test]
+    fn run_once_skips_processed_ids() {
+        let records = Memory::default();
+        let ttl = DEFAULT_TTL;
+        assert_eq!(
+            ready(run_once(&records, "a", ttl, async { Ok(1) })).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            ready(run_once(&records, "a", ttl, async { Ok(2) })).unwrap(),
+            None
+        );
+        assert_eq!(
+            ready(run_once(&records, "b", ttl, async { Ok(3) })).unwrap(),
+            Some(3)
+        );
+
+        // Failed work isn't recorded, so it runs again.
+        assert!(ready(run_once(&records, "c", ttl, async {
+            Err::<(), _>("oops".into())
+        }))
+        .is_err());
+        assert_eq!(
+            ready(run_once(&records, "c", ttl, async { Ok(4) })).unwrap(),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn run_once_rejects_ids_in_progress() {
+        let records = Memory::default();
+        let (tx, rx) = futures_channel::oneshot::channel::<()>();
+        let mut first = pin!(run_once(&records, "a", DEFAULT_TTL, async {
+            let _ = rx.await;
+            Ok(1)
+        }));
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+
+        assert!(ready(run_once(&records, "a", DEFAULT_TTL, async { Ok(2) })).is_err());
+        tx.send(()).unwrap();
+        match first.as_mut().poll(&mut cx) {
+            std::task::Poll::Ready(output) => assert_eq!(output.unwrap(), Some(1)),
+            std::task::Poll::Pending => panic!("the first run should be done"),
+        }
+        assert_eq!(
+            ready(run_once(&records, "a", DEFAULT_TTL, async { Ok(3) })).unwrap(),
+            None
+        );
+    }
+}
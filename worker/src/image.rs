@@ -0,0 +1,474 @@
+use serde::{Serialize, Serializer};
+
+use crate::{Error, Result};
+
+/// Options to resize and optimize an image while fetching it, sent as the `cf.image` property of
+/// an outbound request.
+///
+/// See the [documentation](https://developers.cloudflare.com/images/transform-images/transform-via-workers/)
+/// for the details of each option.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example() -> Result<Response> {
+/// let image = ImageOptions::new()
+///     .width(800)
+///     .fit(ImageFit::Cover)
+///     .gravity(ImageGravity::Auto)
+///     .format(ImageFormat::Webp)
+///     .quality(80);
+///
+/// let mut init = RequestInit::new();
+/// init.with_image(image)?;
+/// let req = Request::new_with_init("https://example.com/cat.jpg", &init)?;
+/// Fetch::Request(req).send().await
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImageOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fit: Option<ImageFit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gravity: Option<ImageGravity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<ImageFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dpr: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blur: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sharpen: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rotate: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<ImageMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anim: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    draw: Vec<ImageDraw>,
+}
+
+impl ImageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum width of the image in pixels.
+    #[must_use]
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// The maximum height of the image in pixels.
+    #[must_use]
+    pub fn height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// How the image is fitted into the given width and height.
+    #[must_use]
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = Some(fit);
+        self
+    }
+
+    /// Which part of the image is kept when it's cropped. Requires [`ImageFit::Cover`] or
+    /// [`ImageFit::Crop`].
+    #[must_use]
+    pub fn gravity(mut self, gravity: ImageGravity) -> Self {
+        self.gravity = Some(gravity);
+        self
+    }
+
+    /// The format of the resulting image.
+    #[must_use]
+    pub fn format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// The quality of lossy formats, between 1 and 100.
+    #[must_use]
+    pub fn quality(mut self, quality: u8) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// The device pixel ratio the width and height are multiplied with, between 1 and 3.
+    #[must_use]
+    pub fn dpr(mut self, dpr: f64) -> Self {
+        self.dpr = Some(dpr);
+        self
+    }
+
+    /// The color of the padding added by [`ImageFit::Pad`] and of transparent areas, as a CSS
+    /// color, e.g. `#RRGGBB`.
+    #[must_use]
+    pub fn background(mut self, background: impl Into<String>) -> Self {
+        self.background = Some(background.into());
+        self
+    }
+
+    /// The radius of a blur applied to the image, between 1 and 250.
+    #[must_use]
+    pub fn blur(mut self, blur: u8) -> Self {
+        self.blur = Some(blur);
+        self
+    }
+
+    /// The strength of sharpening applied to the image, between 0 and 10.
+    #[must_use]
+    pub fn sharpen(mut self, sharpen: f64) -> Self {
+        self.sharpen = Some(sharpen);
+        self
+    }
+
+    /// Rotate the image clockwise by 90, 180 or 270 degrees.
+    #[must_use]
+    pub fn rotate(mut self, degrees: u16) -> Self {
+        self.rotate = Some(degrees);
+        self
+    }
+
+    /// Which metadata of the original image is preserved.
+    #[must_use]
+    pub fn metadata(mut self, metadata: ImageMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Whether animations are preserved. When `false`, only the first frame is kept.
+    #[must_use]
+    pub fn anim(mut self, anim: bool) -> Self {
+        self.anim = Some(anim);
+        self
+    }
+
+    /// Draw an overlay, e.g. a watermark, on top of the image. Overlays are drawn in the order
+    /// they are added.
+    #[must_use]
+    pub fn draw(mut self, overlay: ImageDraw) -> Self {
+        self.draw.push(overlay);
+        self
+    }
+
+    /// Check that the options are within their allowed ranges and don't conflict with each other.
+    pub fn validate(&self) -> Result<()> {
+        if self.width == Some(0) || self.height == Some(0) {
+            return Err(invalid("width and height must be greater than 0"));
+        }
+        if let Some(quality) = self.quality {
+            if !(1..=100).contains(&quality) {
+                return Err(invalid("quality must be between 1 and 100"));
+            }
+            if matches!(self.format, Some(ImageFormat::Png | ImageFormat::Json)) {
+                return Err(invalid("quality can't be used with a lossless format"));
+            }
+        }
+        if let Some(dpr) = self.dpr {
+            if !(1.0..=3.0).contains(&dpr) {
+                return Err(invalid("dpr must be between 1 and 3"));
+            }
+        }
+        if let Some(blur) = self.blur {
+            if !(1..=250).contains(&blur) {
+                return Err(invalid("blur must be between 1 and 250"));
+            }
+        }
+        if let Some(sharpen) = self.sharpen {
+            if !(0.0..=10.0).contains(&sharpen) {
+                return Err(invalid("sharpen must be between 0 and 10"));
+            }
+        }
+        if let Some(rotate) = self.rotate {
+            if ![90, 180, 270].contains(&rotate) {
+                return Err(invalid("rotate must be 90, 180 or 270"));
+            }
+        }
+        if self.gravity.is_some() && !matches!(self.fit, Some(ImageFit::Cover | ImageFit::Crop)) {
+            return Err(invalid("gravity requires fit to be `cover` or `crop`"));
+        }
+        if self.fit.is_some() && self.width.is_none() && self.height.is_none() {
+            return Err(invalid("fit requires a width or height"));
+        }
+        if !self.draw.is_empty() && self.format == Some(ImageFormat::Json) {
+            return Err(invalid("overlays can't be drawn when the format is `json`"));
+        }
+        self.draw.iter().try_for_each(ImageDraw::validate)
+    }
+}
+
+/// An image drawn on top of another one, see [`ImageOptions::draw`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageDraw {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fit: Option<ImageFit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gravity: Option<ImageGravity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    opacity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeat: Option<ImageRepeat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bottom: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    left: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    right: Option<u32>,
+}
+
+impl ImageDraw {
+    /// Draw the image at `url`, which must be on the same zone as the Worker.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            width: None,
+            height: None,
+            fit: None,
+            gravity: None,
+            opacity: None,
+            repeat: None,
+            top: None,
+            bottom: None,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// The maximum width of the overlay in pixels.
+    #[must_use]
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// The maximum height of the overlay in pixels.
+    #[must_use]
+    pub fn height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// How the overlay is fitted into its width and height.
+    #[must_use]
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = Some(fit);
+        self
+    }
+
+    /// Which part of the overlay is kept when it's cropped.
+    #[must_use]
+    pub fn gravity(mut self, gravity: ImageGravity) -> Self {
+        self.gravity = Some(gravity);
+        self
+    }
+
+    /// The opacity of the overlay, between 0 (transparent) and 1 (opaque).
+    #[must_use]
+    pub fn opacity(mut self, opacity: f64) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Tile the overlay across the image.
+    #[must_use]
+    pub fn repeat(mut self, repeat: ImageRepeat) -> Self {
+        self.repeat = Some(repeat);
+        self
+    }
+
+    /// The distance to the top edge of the image in pixels.
+    #[must_use]
+    pub fn top(mut self, top: u32) -> Self {
+        self.top = Some(top);
+        self
+    }
+
+    /// The distance to the bottom edge of the image in pixels.
+    #[must_use]
+    pub fn bottom(mut self, bottom: u32) -> Self {
+        self.bottom = Some(bottom);
+        self
+    }
+
+    /// The distance to the left edge of the image in pixels.
+    #[must_use]
+    pub fn left(mut self, left: u32) -> Self {
+        self.left = Some(left);
+        self
+    }
+
+    /// The distance to the right edge of the image in pixels.
+    #[must_use]
+    pub fn right(mut self, right: u32) -> Self {
+        self.right = Some(right);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.url.is_empty() {
+            return Err(invalid("overlays require a url"));
+        }
+        if self.top.is_some() && self.bottom.is_some() {
+            return Err(invalid(
+                "overlays can't be positioned by both top and bottom",
+            ));
+        }
+        if self.left.is_some() && self.right.is_some() {
+            return Err(invalid(
+                "overlays can't be positioned by both left and right",
+            ));
+        }
+        if let Some(opacity) = self.opacity {
+            if !(0.0..=1.0).contains(&opacity) {
+                return Err(invalid("overlay opacity must be between 0 and 1"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::RustError(format!("invalid image options: {reason}"))
+}
+
+/// How an image is resized to fit the requested width and height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageFit {
+    /// Shrink the image to fit within the width and height, but never enlarge it.
+    ScaleDown,
+    /// Resize the image to fit within the width and height, preserving its aspect ratio.
+    Contain,
+    /// Resize the image to fill the width and height, cropping it if necessary.
+    Cover,
+    /// Like `Cover` for large images, and `ScaleDown` for smaller ones.
+    Crop,
+    /// Like `Contain`, padding the image with the background color to the exact size.
+    Pad,
+}
+
+/// The part of an image which is kept when it's cropped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageGravity {
+    /// Automatically picks the most interesting part of the image.
+    Auto,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// A point given as fractions of the width and height, where `(0.5, 0.5)` is the center.
+    Point {
+        x: f64,
+        y: f64,
+    },
+}
+
+impl Serialize for ImageGravity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+
+        match *self {
+            Self::Auto => serializer.serialize_str("auto"),
+            Self::Left => serializer.serialize_str("left"),
+            Self::Right => serializer.serialize_str("right"),
+            Self::Top => serializer.serialize_str("top"),
+            Self::Bottom => serializer.serialize_str("bottom"),
+            Self::Point { x, y } => Point { x, y }.serialize(serializer),
+        }
+    }
+}
+
+/// The format of the resized image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageFormat {
+    Avif,
+    Webp,
+    Jpeg,
+    /// A progressive JPEG.
+    BaselineJpeg,
+    Png,
+    /// Information about the image as JSON instead of the image itself.
+    Json,
+}
+
+/// Which metadata of the original image is preserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageMetadata {
+    Keep,
+    Copyright,
+    None,
+}
+
+/// How an overlay is tiled across an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageRepeat {
+    /// Repeat in both directions.
+    Both,
+    /// Repeat horizontally.
+    X,
+    /// Repeat vertically.
+    Y,
+}
+
+impl Serialize for ImageRepeat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::Both => serializer.serialize_bool(true),
+            Self::X => serializer.serialize_str("x"),
+            Self::Y => serializer.serialize_str("y"),
+        }
+    }
+}
+
+#[test]
+fn validate_works() {
+    assert!(ImageOptions::new()
+        .width(100)
+        .fit(ImageFit::Cover)
+        .gravity(ImageGravity::Point { x: 0.5, y: 0.2 })
+        .quality(80)
+        .draw(ImageDraw::new("/watermark.png").bottom(10).right(10))
+        .validate()
+        .is_ok());
+
+    assert!(ImageOptions::new().quality(0).validate().is_err());
+    assert!(ImageOptions::new()
+        .width(100)
+        .fit(ImageFit::Contain)
+        .gravity(ImageGravity::Auto)
+        .validate()
+        .is_err());
+    assert!(ImageOptions::new()
+        .format(ImageFormat::Png)
+        .quality(50)
+        .validate()
+        .is_err());
+    assert!(ImageOptions::new()
+        .draw(ImageDraw::new("/overlay.png").left(0).right(0))
+        .validate()
+        .is_err());
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize,
+};
+
+use crate::api::{self, ApiBody};
+use crate::{
+    Env, Fetch, File, FormData, ImageOptions, Method, Request, RequestInit, Response, Result,
+};
+
+/// A client for the [Cloudflare Images](https://developers.cloudflare.com/images/) API, to store
+/// images and let clients upload them directly.
+///
+/// The API token needs the `Cloudflare Images: Edit` permission, and is best stored as a secret:
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(env: Env) -> Result<Response> {
+/// let images = Images::from_env(&env, "ACCOUNT_ID", "IMAGES_API_TOKEN")?;
+/// let upload = images
+///     .direct_upload(&DirectUploadOptions::new().require_signed_urls(true))
+///     .await?;
+/// Response::from_json(&serde_json::json!({ "uploadURL": upload.upload_url }))
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Images {
+    account_id: String,
+    api_token: String,
+}
+
+/// Options for [`Images::direct_upload`].
+#[derive(Debug, Clone, Default)]
+pub struct DirectUploadOptions {
+    id: Option<String>,
+    require_signed_urls: Option<bool>,
+    metadata: Option<serde_json::Value>,
+    expiry: Option<String>,
+}
+
+impl DirectUploadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A custom id for the image instead of a generated one.
+    #[must_use]
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Only serve the image through signed URLs.
+    #[must_use]
+    pub fn require_signed_urls(mut self, require: bool) -> Self {
+        self.require_signed_urls = Some(require);
+        self
+    }
+
+    /// Metadata stored with the image, which isn't visible to end users.
+    #[must_use]
+    pub fn metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// When the upload URL expires, as an RFC 3339 timestamp. Defaults to 30 minutes from now.
+    #[must_use]
+    pub fn expiry(mut self, expiry: impl Into<String>) -> Self {
+        self.expiry = Some(expiry.into());
+        self
+    }
+
+    fn to_form(&self) -> Result<FormData> {
+        let mut form = FormData::new();
+        if let Some(id) = &self.id {
+            form.append("id", id)?;
+        }
+        if let Some(require) = self.require_signed_urls {
+            form.append("requireSignedURLs", &require.to_string())?;
+        }
+        if let Some(metadata) = &self.metadata {
+            form.append("metadata", &metadata.to_string())?;
+        }
+        if let Some(expiry) = &self.expiry {
+            form.append("expiry", expiry)?;
+        }
+        Ok(form)
+    }
+}
+
+/// A one-time upload URL created by [`Images::direct_upload`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct DirectUpload {
+    /// The id the image will have once it's uploaded.
+    pub id: String,
+    /// The URL a client can `POST` the image to, as multipart form data with a `file` field.
+    #[serde(rename = "uploadURL")]
+    pub upload_url: String,
+}
+
+/// An image stored in Cloudflare Images.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageDetails {
+    pub id: String,
+    pub filename: Option<String>,
+    /// When the image was uploaded, as an RFC 3339 timestamp.
+    pub uploaded: Option<String>,
+    #[serde(rename = "requireSignedURLs", default)]
+    pub require_signed_urls: bool,
+    /// The delivery URLs of each variant of the image.
+    #[serde(default)]
+    pub variants: Vec<String>,
+    #[serde(default)]
+    pub meta: HashMap<String, serde_json::Value>,
+    /// Whether the image hasn't been uploaded yet, only set for pending direct uploads.
+    #[serde(default)]
+    pub draft: bool,
+}
+
+impl Images {
+    /// Create a client for the Images of the given account.
+    pub fn new(account_id: impl Into<String>, api_token: impl Into<String>) -> Self {
+        Self {
+            account_id: account_id.into(),
+            api_token: api_token.into(),
+        }
+    }
+
+    /// Create a client with the account id from the `account_id` variable and the API token from
+    /// the `api_token` secret.
+    pub fn from_env(env: &Env, account_id: &str, api_token: &str) -> Result<Self> {
+        Ok(Self::new(
+            env.var(account_id)?.to_string(),
+            env.secret(api_token)?.to_string(),
+        ))
+    }
+
+    /// Create a URL which a client can upload an image to directly, without the image passing
+    /// through the Worker or exposing the API token.
+    pub async fn direct_upload(&self, options: &DirectUploadOptions) -> Result<DirectUpload> {
+        let form = options.to_form()?;
+        self.request(Method::Post, "v2/direct_upload", Some(form))
+            .await
+    }
+
+    /// Upload an image.
+    pub async fn upload(&self, file: &File, options: &DirectUploadOptions) -> Result<ImageDetails> {
+        let mut form = options.to_form()?;
+        form.append_file("file", file)?;
+        self.request(Method::Post, "v1", Some(form)).await
+    }
+
+    /// Upload the image at `url`, which is fetched by Cloudflare Images.
+    pub async fn upload_from_url(
+        &self,
+        url: &str,
+        options: &DirectUploadOptions,
+    ) -> Result<ImageDetails> {
+        let mut form = options.to_form()?;
+        form.append("url", url)?;
+        self.request(Method::Post, "v1", Some(form)).await
+    }
+
+    /// Get the details of an image.
+    pub async fn get(&self, id: &str) -> Result<ImageDetails> {
+        self.request(Method::Get, &format!("v1/{id}"), None).await
+    }
+
+    /// Delete an image.
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let _: IgnoredAny = self
+            .request(Method::Delete, &format!("v1/{id}"), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch the image at `url`, resizing and optimizing it on the way. Unlike the other methods,
+    /// this doesn't use the API but [image transformations](https://developers.cloudflare.com/images/transform-images/transform-via-workers/),
+    /// which must be enabled on the zone.
+    pub async fn transform(url: &str, options: ImageOptions) -> Result<Response> {
+        let mut init = RequestInit::new();
+        init.with_image(options)?;
+        Fetch::Request(Request::new_with_init(url, &init)?)
+            .send()
+            .await
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        form: Option<FormData>,
+    ) -> Result<T> {
+        let path = format!("accounts/{}/images/{path}", self.account_id);
+        api::request(
+            "Images",
+            &self.api_token,
+            method,
+            &path,
+            form.map(ApiBody::Form),
+        )
+        .await
+    }
+}
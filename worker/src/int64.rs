@@ -0,0 +1,187 @@
+//! Serde helpers for 64-bit integers, which can't all be represented by a JS number: an `f64`
+//! only has 53 bits of mantissa, so larger integers are silently rounded.
+//!
+//! [`Request::json`](crate::Request::json) and [`Response::json`](crate::Response::json) parse
+//! the JSON in Rust, so their integers are exact. The values which go through JS are not:
+//!
+//! - Queue messages and Durable Object storage can hold `BigInt`s, see
+//!   [`SerializerOptions::large_numbers_as_bigints`](crate::SerializerOptions::large_numbers_as_bigints),
+//!   but messages sent as JSON can't.
+//! - D1 returns `INTEGER` columns as numbers, so large ones have to be selected as text, e.g.
+//!   `SELECT CAST(id AS TEXT) AS id`.
+//!
+//! Fields using [`string`] or [`option_string`] are serialized as strings, which any of them
+//! can hold. They're deserialized from strings, `BigInt`s and numbers, but numbers which may have
+//! been rounded are errors rather than wrong values.
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Order {
+//!     #[serde(with = "worker::int64::string")]
+//!     id: u64,
+//!     #[serde(default, with = "worker::int64::option_string")]
+//!     parent: Option<i64>,
+//! }
+//! ```
+
+use std::{convert::TryFrom, fmt, marker::PhantomData, str::FromStr};
+
+use serde::de::{self, Unexpected, Visitor};
+
+/// The largest integer that an `f64` holds exactly, along with every integer below it.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// An integer type which [`string`] and [`option_string`] work with.
+pub trait Integer: fmt::Display + FromStr + TryFrom<i64> + TryFrom<u64> {}
+
+impl Integer for i64 {}
+impl Integer for u64 {}
+impl Integer for i128 {}
+impl Integer for u128 {}
+
+struct IntegerVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Integer> Visitor<'de> for IntegerVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer or a string of an integer")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        <T as TryFrom<i64>>::try_from(v).map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        <T as TryFrom<u64>>::try_from(v)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+        if v.fract() != 0.0 || v.abs() > MAX_SAFE_INTEGER {
+            return Err(E::custom(format!(
+                "{v} is not an exact integer, it may have lost precision as a JS number"
+            )));
+        }
+        self.visit_i64(v as i64)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+/// Serializes an integer as a string, see the [module documentation](self).
+pub mod string {
+    use std::{fmt::Display, marker::PhantomData};
+
+    use serde::{Deserializer, Serializer};
+
+    use super::{Integer, IntegerVisitor};
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T: Integer, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        deserializer.deserialize_any(IntegerVisitor(PhantomData))
+    }
+}
+
+/// Serializes an optional integer as a string, see the [module documentation](self).
+pub mod option_string {
+    use std::{fmt, marker::PhantomData};
+
+    use serde::{de::Visitor, Deserializer, Serialize, Serializer};
+
+    use super::Integer;
+
+    struct AsString<'a, T>(&'a T);
+
+    impl<T: fmt::Display> Serialize for AsString<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::string::serialize(self.0, serializer)
+        }
+    }
+
+    pub fn serialize<T: fmt::Display, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&AsString(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    struct OptionVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Integer> Visitor<'de> for OptionVisitor<T> {
+        type Value = Option<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an optional integer or string of an integer")
+        }
+
+        fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            super::string::deserialize(deserializer).map(Some)
+        }
+    }
+
+    pub fn deserialize<'de, T: Integer, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+}
+
+#[test]
+fn int64_works() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Order {
+        #[serde(with = "string")]
+        id: u64,
+        #[serde(default, with = "option_string")]
+        parent: Option<i64>,
+    }
+
+    let order = Order {
+        id: u64::MAX,
+        parent: Some(-1),
+    };
+    let json = serde_json::to_string(&order).unwrap();
+    assert_eq!(json, r#"{"id":"18446744073709551615","parent":"-1"}"#);
+    assert_eq!(serde_json::from_str::<Order>(&json).unwrap(), order);
+
+    let order: Order =
+        serde_json::from_str(r#"{"id":18446744073709551615,"parent":null}"#).unwrap();
+    assert_eq!(order.id, u64::MAX);
+    assert_eq!(order.parent, None);
+    assert_eq!(
+        serde_json::from_str::<Order>(r#"{"id":42.0}"#).unwrap().id,
+        42
+    );
+
+    assert!(serde_json::from_str::<Order>(r#"{"id":9007199254740993.0}"#).is_err());
+    assert!(serde_json::from_str::<Order>(r#"{"id":1.5}"#).is_err());
+    assert!(serde_json::from_str::<Order>(r#"{"id":-1}"#).is_err());
+    assert!(serde_json::from_str::<Order>(r#"{"id":"abc"}"#).is_err());
+}
@@ -0,0 +1,196 @@
+//! Decoding and signature verification of [JSON Web Tokens](https://datatracker.ietf.org/doc/html/rfc7519).
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{crypto, Date, Error, Result};
+
+/// The header of a JSON Web Token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtHeader {
+    /// The signing algorithm, e.g. `RS256`.
+    pub alg: String,
+    /// Identifier of the key used to sign the token.
+    pub kid: Option<String>,
+    /// The token type, usually `JWT`.
+    pub typ: Option<String>,
+}
+
+/// The registered claims from [RFC 7519](https://datatracker.ietf.org/doc/html/rfc7519#section-4.1)
+/// which are used to validate a token.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RegisteredClaims {
+    pub iss: Option<String>,
+    pub sub: Option<String>,
+    #[serde(default)]
+    pub aud: Audience,
+    pub exp: Option<u64>,
+    pub nbf: Option<u64>,
+    pub iat: Option<u64>,
+}
+
+/// The `aud` claim, which may either be a single string or an array of strings.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    /// Whether the audience contains `aud`.
+    pub fn contains(&self, aud: &str) -> bool {
+        match self {
+            Audience::None => false,
+            Audience::One(one) => one == aud,
+            Audience::Many(many) => many.iter().any(|a| a == aud),
+        }
+    }
+}
+
+/// A public key in [JSON Web Key](https://datatracker.ietf.org/doc/html/rfc7517) format, as
+/// published by identity providers in their JWKS endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kid: Option<String>,
+    pub kty: String,
+    pub alg: Option<String>,
+    /// The remaining key parameters, e.g. `n` and `e` for RSA keys.
+    #[serde(flatten)]
+    pub params: Map<String, Value>,
+}
+
+/// A JSON Web Key Set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Find the key with the given key id.
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|k| k.kid.as_deref() == Some(kid))
+    }
+}
+
+/// A decoded, but not yet verified, JSON Web Token.
+#[derive(Debug, Clone)]
+pub struct Jwt {
+    header: JwtHeader,
+    claims: Value,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+impl Jwt {
+    /// Decode a compact serialized token (`header.payload.signature`). This does **not** verify
+    /// the signature, use [`Jwt::verify`] for that.
+    pub fn decode(token: &str) -> Result<Self> {
+        let mut parts = token.split('.');
+        let (header, payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(h), Some(p), Some(s)) if parts.next().is_none() => (h, p, s),
+            _ => return Err(Error::RustError("malformed JWT".into())),
+        };
+
+        Ok(Self {
+            header: serde_json::from_slice(&decode_segment(header)?)?,
+            claims: serde_json::from_slice(&decode_segment(payload)?)?,
+            signing_input: format!("{header}.{payload}"),
+            signature: decode_segment(signature)?,
+        })
+    }
+
+    /// The token's header.
+    pub fn header(&self) -> &JwtHeader {
+        &self.header
+    }
+
+    /// Deserialize the token's claims into `T`.
+    pub fn claims<T: DeserializeOwned>(&self) -> Result<T> {
+        T::deserialize(&self.claims).map_err(Error::from)
+    }
+
+    /// The registered claims (`iss`, `aud`, `exp`, ...) of the token.
+    pub fn registered_claims(&self) -> Result<RegisteredClaims> {
+        self.claims()
+    }
+
+    /// Verify the token's signature against `jwk` using WebCrypto. Only `RS256` tokens are
+    /// currently supported.
+    pub async fn verify(&self, jwk: &Jwk) -> Result<bool> {
+        if self.header.alg != "RS256" {
+            return Err(Error::RustError(format!(
+                "unsupported JWT algorithm: {}",
+                self.header.alg
+            )));
+        }
+
+        let algorithm = crypto::algorithm("RSASSA-PKCS1-v1_5", Some("SHA-256"));
+        let jwk = js_sys::JSON::parse(&serde_json::to_string(jwk)?)?;
+        let key = crypto::import_jwk(&jwk, &algorithm, &["verify"]).await?;
+        crypto::verify(
+            &algorithm,
+            &key,
+            &self.signature,
+            self.signing_input.as_bytes(),
+        )
+        .await
+    }
+
+    /// Check the time based claims (`exp` and `nbf`) of the token against the current time.
+    /// Tokens without an `exp` claim never expire, so they're rejected.
+    pub fn validate_time(&self) -> Result<()> {
+        let claims = self.registered_claims()?;
+        let now = Date::now().as_millis() / 1000;
+        match claims.exp {
+            None => return Err(Error::RustError("JWT has no expiration time".into())),
+            Some(exp) if exp <= now => return Err(Error::RustError("JWT has expired".into())),
+            Some(_) => {}
+        }
+        if claims.nbf.map(|nbf| nbf > now).unwrap_or(false) {
+            return Err(Error::RustError("JWT is not yet valid".into()));
+        }
+        Ok(())
+    }
+}
+
+fn decode_segment(segment: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(segment.trim_end_matches('='))
+        .map_err(|e| Error::RustError(format!("invalid base64 in JWT: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &str) -> String {
+        URL_SAFE_NO_PAD.encode(value)
+    }
+
+    #[test]
+    fn decode_works() {
+        let token = format!(
+            "{}.{}.{}",
+            encode(r#"{"alg":"RS256","kid":"abc","typ":"JWT"}"#),
+            encode(r#"{"aud":["one","two"],"email":"a@b.c","exp":10}"#),
+            encode("sig"),
+        );
+        let jwt = Jwt::decode(&token).unwrap();
+        assert_eq!(jwt.header().kid.as_deref(), Some("abc"));
+
+        let claims = jwt.registered_claims().unwrap();
+        assert!(claims.aud.contains("two"));
+        assert!(!claims.aud.contains("three"));
+        assert_eq!(claims.exp, Some(10));
+    }
+
+    #[test]
+    fn decode_rejects_malformed() {
+        assert!(Jwt::decode("only.two").is_err());
+        assert!(Jwt::decode("a.b.c.d").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+use async_trait::async_trait;
+use js_sys::{Object, Reflect, Uint8Array};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{kv::KvStore, Cache, Date, Headers, Response, ResponseBody, Result, Storage};
+
+/// A store of bytes by key, implemented by [`KvStore`], Durable Object [`Storage`], the Cache API
+/// through [`CacheStore`], and [`MemoryStore`], so that code can be written once for any of them
+/// and tested without bindings.
+///
+/// ```no_run
+/// # use worker::*;
+/// async fn count_visit(store: &impl KeyValueStore, page: &str) -> Result<u64> {
+///     let visits = store.get_json::<u64>(page).await?.unwrap_or(0) + 1;
+///     store.put_json(page, &visits, None).await?;
+///     Ok(visits)
+/// }
+/// ```
+#[async_trait(?Send)]
+pub trait KeyValueStore {
+    /// The value of `key`, `None` if it doesn't exist or expired.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store `value` under `key`, replacing any previous value. The value expires after `ttl`, if
+    /// any, although stores may keep it a while longer, such as KV which keeps values for at least
+    /// 60 seconds.
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()>;
+
+    /// Delete the value of `key`, if any.
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// The value of `key`, as UTF-8 text.
+    async fn get_text(&self, key: &str) -> Result<Option<String>> {
+        match self.get(key).await? {
+            Some(value) => Ok(Some(String::from_utf8(value).map_err(|e| e.to_string())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The value of `key`, deserialized from JSON.
+    async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>
+    where
+        Self: Sized,
+    {
+        match self.get(key).await? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store `value` under `key`, serialized to JSON.
+    async fn put_json<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        self.put(key, serde_json::to_vec(value)?, ttl).await
+    }
+
+    /// The value of `key`, deserialized from [MessagePack](https://msgpack.org).
+    ///
+    /// **Requires** `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    async fn get_msgpack<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>
+    where
+        Self: Sized,
+    {
+        match self.get(key).await? {
+            Some(value) => Ok(Some(crate::msgpack::decode(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store `value` under `key`, serialized to [MessagePack](https://msgpack.org), which is
+    /// smaller than JSON and stored as bytes.
+    ///
+    /// **Requires** `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    async fn put_msgpack<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        self.put(key, crate::msgpack::encode(value)?, ttl).await
+    }
+}
+
+#[async_trait(?Send)]
+impl KeyValueStore for KvStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(KvStore::get(self, key).bytes().await?)
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let mut put = self.put_bytes(key, &value)?;
+        if let Some(ttl) = ttl {
+            put = put.expiration_ttl(ttl.as_secs().max(crate::kv::MIN_EXPIRATION_TTL));
+        }
+        Ok(put.execute().await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        Ok(KvStore::delete(self, key).await?)
+    }
+}
+
+/// Durable Object storage has no TTL of its own: values are stored with their expiration time,
+/// and expired values are deleted when they're read.
+#[async_trait(?Send)]
+impl KeyValueStore for Storage {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let values = self.get_multiple(vec![key]).await?;
+        let stored = values.get(&JsValue::from_str(key));
+        if stored.is_undefined() {
+            return Ok(None);
+        }
+        let expires = Reflect::get(&stored, &JsValue::from_str("expires"))?.as_f64();
+        if matches!(expires, Some(expires) if expires <= Date::now().as_millis() as f64) {
+            Storage::delete(&mut self.clone(), key).await?;
+            return Ok(None);
+        }
+        let value: Uint8Array = Reflect::get(&stored, &JsValue::from_str("value"))?.dyn_into()?;
+        Ok(Some(value.to_vec()))
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let stored = Object::new();
+        Reflect::set(
+            &stored,
+            &JsValue::from_str("value"),
+            &Uint8Array::from(value.as_slice()),
+        )?;
+        if let Some(ttl) = ttl {
+            let expires = (Date::now().as_millis() + ttl.as_millis() as u64) as f64;
+            Reflect::set(&stored, &JsValue::from_str("expires"), &expires.into())?;
+        }
+        self.clone().put_raw(key, stored).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        Storage::delete(&mut self.clone(), key).await.map(|_| ())
+    }
+}
+
+/// A [`KeyValueStore`] in a [`Cache`], which stores values as responses under URLs made of a
+/// prefix and the key.
+///
+/// The cache is local to the data center, and may evict values at any time: it's best suited to
+/// values which can be computed again, like the results of slow subrequests.
+pub struct CacheStore {
+    cache: Cache,
+    prefix: String,
+}
+
+impl CacheStore {
+    /// Values which don't have a TTL are cached for this long.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+    /// A store in `cache`, whose keys are appended to `prefix`, a URL such as
+    /// `https://kv.example.com/`. The host of the URL should belong to the zone of the Worker, or
+    /// values will be cached separately from other Workers using the same prefix.
+    pub fn new(cache: Cache, prefix: impl Into<String>) -> Self {
+        Self {
+            cache,
+            prefix: prefix.into(),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        let key: String = url::form_urlencoded::byte_serialize(key.as_bytes()).collect();
+        format!("{}{key}", self.prefix)
+    }
+}
+
+#[async_trait(?Send)]
+impl KeyValueStore for CacheStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.cache.get(self.url(key), false).await? {
+            Some(mut resp) => Ok(Some(resp.bytes().await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let ttl = ttl.unwrap_or(Self::DEFAULT_TTL);
+        let mut headers = Headers::new();
+        headers.set("Cache-Control", &format!("max-age={}", ttl.as_secs()))?;
+        let resp = Response::from_body(ResponseBody::Body(value))?.with_headers(headers);
+        self.cache.put(self.url(key), resp).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.cache.delete(self.url(key), false).await.map(|_| ())
+    }
+}
+
+/// A [`KeyValueStore`] in memory, e.g. to test code written against the trait. Clones share the
+/// same values.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    values: Rc<RefCell<HashMap<String, (Vec<u8>, Option<u64>)>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of values stored, including expired values which weren't read since.
+    pub fn len(&self) -> usize {
+        self.values.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait(?Send)]
+impl KeyValueStore for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut values = self.values.borrow_mut();
+        let expired = match values.get(key) {
+            Some((_, Some(expires))) => *expires <= Date::now().as_millis(),
+            Some((value, None)) => return Ok(Some(value.clone())),
+            None => return Ok(None),
+        };
+        if expired {
+            values.remove(key);
+            return Ok(None);
+        }
+        Ok(values.get(key).map(|(value, _)| value.clone()))
+    }
+
+    async fn put(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let expires = ttl.map(|ttl| Date::now().as_millis() + ttl.as_millis() as u64);
+        self.values
+            .borrow_mut()
+            .insert(key.to_owned(), (value, expires));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.values.borrow_mut().remove(key);
+        Ok(())
+    }
+}
+
+#[test]
+fn memory_store_works() {
+    use futures_util::FutureExt;
+
+    let store = MemoryStore::new();
+    let run = |future: futures_util::future::LocalBoxFuture<'_, Result<()>>| {
+        future.now_or_never().unwrap().unwrap()
+    };
+    run(Box::pin(async {
+        assert_eq!(store.get("missing").await?, None);
+        store.put_json("count", &41, None).await?;
+        let count: u64 = store.get_json("count").await?.unwrap();
+        store.put_json("count", &(count + 1), None).await?;
+        assert_eq!(store.get_text("count").await?.as_deref(), Some("42"));
+        store.clone().delete("count").await?;
+        assert!(store.is_empty());
+        Ok(())
+    }));
+}
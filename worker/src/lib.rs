@@ -1,9 +1,31 @@
 #![allow(clippy::new_without_default)]
 #![allow(clippy::or_fun_call)]
 //! # Features
+//!
+//! No features are enabled by default, so Workers only build and ship the integrations they
+//! enable.
+//!
+//! ## `websocket`
+//!
+//! Allows the use of [WebSockets](WebSocket), in responses and hibernatable Durable Objects, and
+//! the [upgrade](WebSocketUpgrade), [hub](Hub), [codec](WsCodec) and [RPC](RpcServer) helpers
+//! built on them.
+//!
+//! ## `r2`
+//!
+//! Allows the use of [R2 bindings](Bucket) with [`Env::bucket`].
+//!
+//! ## `crypto`
+//!
+//! Exposes the [Web Crypto](crate::crypto) API and everything built on it: [JSON Web
+//! Tokens](crate::jwt), [Cloudflare Access](CloudflareAccess) identities, [signed URLs](UrlSigner),
+//! [sessions](crate::sessions), [webhook verification](crate::webhook), the [AWS SigV4
+//! signer](crate::aws) and [`DigestingStream`].
+//!
 //! ## `d1`
 //!
-//! Allows the use of [D1 bindings](crate::d1) and [`query!`](crate::query) macro.
+//! Allows the use of [D1 bindings](crate::d1), the [`query!`](crate::query) macro and
+//! [`#[derive(D1Entity)]`](derive@crate::d1::D1Entity) for simple CRUD statements.
 //!
 //!
 //! ## `queue`
@@ -18,6 +40,47 @@
 //! }
 //! ```
 //!
+//! ## `queue-compression`
+//!
+//! Allows sending queue messages with their JSON bodies [compressed](Compression), which
+//! [`MessageBatch::iter`] decompresses. Compression is opt-in, through
+//! [`Queue::send_compressed`] and [`Queue::send_batch_compressed`]: `send` and `send_batch` don't
+//! compress.
+//!
+//! ## `openapi`
+//!
+//! Allows generating an [OpenAPI](crate::openapi) document from the routes of a [`Router`].
+//!
+//! ## `durable-primitives`
+//!
+//! Exports ready-made [Durable Objects](crate::primitives) for counters, leases, presence and rate
+//! limits. Enables `websocket`.
+//!
+//! ## `embed`
+//!
+//! Allows embedding the files of a directory into the binary with [`embed_dir!`] and [serving](crate::assets)
+//! them with `ETag`s, content types and immutable fingerprinted paths.
+//!
+//! ## `msgpack`
+//!
+//! Allows storing values as MessagePack rather than JSON in any [`KeyValueStore`], such as KV
+//! and Durable Object storage, and sending queue messages as MessagePack.
+//!
+//! ## `protobuf`
+//!
+//! Allows encoding and decoding [protobuf](crate::protobuf) bodies of requests, responses and
+//! queue messages with [`prost`].
+//!
+//! ## `templates`
+//!
+//! Allows rendering [askama](crate::template) templates into a [`Response`], and streaming pages
+//! in parts as their data loads.
+//!
+//! ## `structured-logs`
+//!
+//! Makes the `console_*` macros emit a [`LogEvent`] with the formatted message, so Workers Logs
+//! and Logpush can filter on its level.
+//!
 //! ## `http`
 //! `worker` `0.0.21` introduced an `http` feature flag which starts to replace custom types with widely used types from the [`http`](https://docs.rs/http/latest/http/) crate.
 //!
@@ -105,6 +168,10 @@
 #[doc(hidden)]
 use std::result::Result as StdResult;
 
+// Lets the code generated by the macros refer to `worker` from within this crate.
+#[cfg(feature = "durable-primitives")]
+extern crate self as worker;
+
 #[doc(hidden)]
 pub use async_trait;
 #[doc(hidden)]
@@ -117,45 +184,113 @@ pub use wasm_bindgen_futures;
 pub use worker_kv as kv;
 
 pub use cf::{Cf, TlsClientAuth};
-pub use worker_macros::{durable_object, event, send};
+#[cfg(feature = "embed")]
+pub use worker_macros::embed_dir;
+pub use worker_macros::{durable_object, event, routes, send, worker, Bindings, FromFormData};
 #[doc(hidden)]
 pub use worker_sys;
+#[cfg(not(feature = "structured-logs"))]
 pub use worker_sys::{console_debug, console_error, console_log, console_warn};
 
 pub use crate::abort::*;
-pub use crate::cache::{Cache, CacheDeletionOutcome, CacheKey};
+#[cfg(feature = "crypto")]
+pub use crate::access::{AccessIdentity, AccessIdentityData, CloudflareAccess};
+pub use crate::blob::Blob;
+pub use crate::cache::{Cache, CacheDeletionOutcome, CacheKey, CacheKeyBuilder};
+pub use crate::coalesce::{request_key, Coalescer, RequestCoalescer};
 pub use crate::context::Context;
+pub use crate::cookie::{Cookie, SameSite};
 pub use crate::cors::Cors;
+pub use crate::cron_lock::{cron_lock, CronLock, CRON_LOCK_BINDING};
 #[cfg(feature = "d1")]
 pub use crate::d1::*;
 pub use crate::date::{Date, DateInit};
 pub use crate::delay::Delay;
 pub use crate::durable::*;
 pub use crate::dynamic_dispatch::*;
-pub use crate::env::{Env, EnvBinding, Secret, Var};
-pub use crate::error::Error;
+pub use crate::early_hints::{EarlyHints, Link};
+pub use crate::env::{BindingInfo, BindingKind, Bindings, Env, EnvBinding, FromEnv, Secret, Var};
+pub use crate::error::{Error, JsException, ResultExt};
+pub use crate::extensions::Extensions;
 pub use crate::fetcher::Fetcher;
 pub use crate::formdata::*;
-pub use crate::global::Fetch;
+pub use crate::futures::{spawn_local, JoinHandle};
+pub use crate::global::{fetch_all_limited, Fetch, FetchReport};
+pub use crate::handler::{Worker, WorkerInstance};
 pub use crate::headers::Headers;
-pub use crate::http::Method;
+pub use crate::hedge::Hedge;
+pub use crate::http::{Method, StatusCode};
+pub use crate::idempotency::Idempotency;
+pub use crate::image::{
+    ImageDraw, ImageFit, ImageFormat, ImageGravity, ImageMetadata, ImageOptions, ImageRepeat,
+};
+pub use crate::images::{DirectUpload, DirectUploadOptions, ImageDetails, Images};
+pub use crate::key_value::{CacheStore, KeyValueStore, MemoryStore};
+pub use crate::log_event::{LogEvent, LogLevel};
+pub use crate::negotiate::{respond_to, Negotiator};
+#[cfg(feature = "queue")]
+pub use crate::pull_queue::*;
 #[cfg(feature = "queue")]
 pub use crate::queue::*;
+#[cfg(feature = "queue-compression")]
+pub use crate::queue_compression::Compression;
+#[cfg(feature = "queue")]
+pub use crate::queue_envelope::{Envelope, EnvelopeReader, Versioned};
+#[cfg(feature = "r2")]
 pub use crate::r2::*;
-pub use crate::request::Request;
+pub use crate::redirect::Redirect;
+pub use crate::request::{ForwardedNode, Request};
 pub use crate::request_init::*;
+pub use crate::request_metrics::{MetricsSnapshot, RequestMetrics};
 pub use crate::response::{Response, ResponseBody};
-pub use crate::router::{RouteContext, RouteParams, Router};
+pub use crate::response_cache::ResponseCache;
+pub use crate::response_writer::ResponseWriter;
+#[doc(hidden)]
+pub use crate::router::encode_route_param;
+pub use crate::router::{Middleware, Next, RouteContext, RouteParams, Router};
 pub use crate::schedule::*;
+pub use crate::secret_versions::SecretVersions;
+pub use crate::serializer::{serializer_options, set_serializer_options, SerializerOptions};
+#[cfg(feature = "crypto")]
+pub use crate::signed_url::UrlSigner;
 pub use crate::socket::*;
+pub use crate::storage_migration::{Migration, MigrationReport, VersionedValue};
 pub use crate::streams::*;
+pub use crate::stub_retry::{RetryPolicy, RetryingStub};
+pub use crate::trace::TraceContext;
+pub use crate::url_pattern::{UrlPattern, UrlPatternComponent, UrlPatternInit, UrlPatternMatch};
+pub use crate::validation::{Validate, Violation, Violations};
+#[cfg(feature = "websocket")]
 pub use crate::websocket::*;
+#[cfg(feature = "websocket")]
+pub use crate::websocket_codec::{Decoded, WsCodec};
+#[cfg(feature = "websocket")]
+pub use crate::websocket_hub::Hub;
+#[cfg(feature = "websocket")]
+pub use crate::websocket_rpc::{RpcClient, RpcRequest, RpcServer};
+#[cfg(feature = "websocket")]
+pub use crate::websocket_upgrade::{UpgradedWebSocket, WebSocketUpgrade};
 
 mod abort;
-mod cache;
+#[cfg(feature = "crypto")]
+mod access;
+mod api;
+#[cfg(feature = "embed")]
+pub mod assets;
+#[cfg(feature = "crypto")]
+/// **Requires** `crypto` feature.
+pub mod aws;
+mod blob;
+pub mod cache;
 mod cf;
+mod coalesce;
 mod context;
+mod cookie;
 mod cors;
+mod cron_lock;
+#[cfg(feature = "crypto")]
+/// **Requires** `crypto` feature.
+pub mod crypto;
 // Require pub module for macro export
 #[cfg(feature = "d1")]
 /// **Requires** `d1` feature.
@@ -164,25 +299,98 @@ mod date;
 mod delay;
 pub mod durable;
 mod dynamic_dispatch;
+mod early_hints;
 mod env;
 mod error;
+mod extensions;
 mod fetcher;
 mod formdata;
-mod global;
+pub mod futures;
+pub mod geo;
+pub mod global;
+mod handler;
 mod headers;
+mod hedge;
 mod http;
+mod idempotency;
+mod image;
+mod images;
+pub mod int64;
+#[cfg(feature = "crypto")]
+/// **Requires** `crypto` feature.
+pub mod jwt;
+mod key_value;
+mod log_event;
+mod macros;
+pub mod metrics;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod negotiate;
+#[cfg(feature = "openapi")]
+/// **Requires** `openapi` feature.
+pub mod openapi;
+pub mod outbound;
+pub mod panic;
+pub mod perf;
+#[cfg(feature = "durable-primitives")]
+pub mod primitives;
+#[cfg(feature = "protobuf")]
+/// **Requires** `protobuf` feature.
+pub mod protobuf;
+#[cfg(feature = "queue")]
+mod pull_queue;
 #[cfg(feature = "queue")]
 mod queue;
+#[cfg(feature = "queue-compression")]
+mod queue_compression;
+#[cfg(feature = "queue")]
+mod queue_envelope;
+#[cfg(feature = "r2")]
 mod r2;
+mod redirect;
 mod request;
 mod request_init;
+mod request_metrics;
 mod response;
+mod response_cache;
+mod response_writer;
 mod router;
+#[cfg(any(feature = "csv", feature = "ndjson"))]
+/// **Requires** `csv` or `ndjson` feature.
+pub mod rows;
 mod schedule;
+mod secret_versions;
 pub mod send;
+mod serializer;
+#[cfg(feature = "crypto")]
+/// **Requires** `crypto` feature.
+pub mod sessions;
+#[cfg(feature = "crypto")]
+mod signed_url;
+mod single_flight;
 mod socket;
+mod storage_migration;
 mod streams;
+mod stub_retry;
+#[cfg(feature = "templates")]
+/// **Requires** `templates` feature.
+pub mod template;
+mod trace;
+mod url_pattern;
+mod validation;
+#[cfg(feature = "crypto")]
+/// **Requires** `crypto` feature.
+pub mod webhook;
+#[cfg(feature = "websocket")]
 mod websocket;
+#[cfg(feature = "websocket")]
+mod websocket_codec;
+#[cfg(feature = "websocket")]
+mod websocket_hub;
+#[cfg(feature = "websocket")]
+mod websocket_rpc;
+#[cfg(feature = "websocket")]
+mod websocket_upgrade;
 
 pub type Result<T> = StdResult<T, error::Error>;
 
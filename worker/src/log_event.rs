@@ -0,0 +1,170 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use wasm_bindgen::JsValue;
+
+/// The severity of a [`LogEvent`], which also picks the `console` method it's written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Debug => "debug",
+            Self::Log => "log",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A structured log line, written to the console as a JSON object so that
+/// [Workers Logs](https://developers.cloudflare.com/workers/observability/logs/workers-logs/)
+/// and Logpush index its fields.
+///
+/// The object has a `level`, a `message`, an ISO 8601 `timestamp` and the added fields, which
+/// can't override the other three.
+///
+/// ```no_run
+/// # use worker::*;
+/// LogEvent::info("cache miss")
+///     .field("key", "users/1")
+///     .field("latency_ms", 12)
+///     .emit();
+/// ```
+///
+/// With the `structured-logs` feature the `console_*` macros emit a `LogEvent` with the formatted
+/// message, instead of writing plain text.
+#[derive(Debug, Clone)]
+pub struct LogEvent {
+    level: LogLevel,
+    message: String,
+    fields: BTreeMap<String, Value>,
+}
+
+impl LogEvent {
+    pub fn new(level: LogLevel, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            message: message.into(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn debug(message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Debug, message)
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Info, message)
+    }
+
+    pub fn warn(message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Warn, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(LogLevel::Error, message)
+    }
+
+    /// Add a field, which is `null` if `value` can't be serialized as JSON.
+    #[must_use]
+    pub fn field(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.fields.insert(key.into(), value);
+        self
+    }
+
+    /// The event as a JSON object, with the current time as its timestamp.
+    pub fn to_json(&self) -> Value {
+        let timestamp = String::from(js_sys::Date::new_0().to_iso_string());
+        self.to_json_with_timestamp(timestamp)
+    }
+
+    fn to_json_with_timestamp(&self, timestamp: String) -> Value {
+        let mut object: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        object.insert("level".into(), self.level.to_string().into());
+        object.insert("message".into(), self.message.as_str().into());
+        object.insert("timestamp".into(), timestamp.into());
+        Value::Object(object)
+    }
+
+    /// Write the event to the console.
+    pub fn emit(&self) {
+        let json = self.to_json().to_string();
+        // Logging the parsed object rather than its text lets the dashboard show it expanded.
+        let value = js_sys::JSON::parse(&json).unwrap_or_else(|_| JsValue::from_str(&json));
+        match self.level {
+            LogLevel::Debug => web_sys::console::debug_1(&value),
+            LogLevel::Log => web_sys::console::log_1(&value),
+            LogLevel::Info => web_sys::console::info_1(&value),
+            LogLevel::Warn => web_sys::console::warn_1(&value),
+            LogLevel::Error => web_sys::console::error_1(&value),
+        }
+    }
+}
+
+/// Emit a [`LogEvent`] with the formatted message at the `debug` level.
+#[cfg(feature = "structured-logs")]
+#[macro_export]
+macro_rules! console_debug {
+    ($($t:tt)*) => {
+        $crate::LogEvent::new($crate::LogLevel::Debug, format!($($t)*)).emit()
+    }
+}
+
+/// Emit a [`LogEvent`] with the formatted message at the `log` level.
+#[cfg(feature = "structured-logs")]
+#[macro_export]
+macro_rules! console_log {
+    ($($t:tt)*) => {
+        $crate::LogEvent::new($crate::LogLevel::Log, format!($($t)*)).emit()
+    }
+}
+
+/// Emit a [`LogEvent`] with the formatted message at the `warn` level.
+#[cfg(feature = "structured-logs")]
+#[macro_export]
+macro_rules! console_warn {
+    ($($t:tt)*) => {
+        $crate::LogEvent::new($crate::LogLevel::Warn, format!($($t)*)).emit()
+    }
+}
+
+/// Emit a [`LogEvent`] with the formatted message at the `error` level.
+#[cfg(feature = "structured-logs")]
+#[macro_export]
+macro_rules! console_error {
+    ($($t:tt)*) => {
+        $crate::LogEvent::new($crate::LogLevel::Error, format!($($t)*)).emit()
+    }
+}
+
+#[test]
+fn to_json_works() {
+    let event = LogEvent::warn("slow")
+        .field("ms", 120)
+        .field("level", "ignored");
+    assert_eq!(
+        event.to_json_with_timestamp("2024-01-01T00:00:00.000Z".into()),
+        serde_json::json!({
+            "level": "warn",
+            "message": "slow",
+            "timestamp": "2024-01-01T00:00:00.000Z",
+            "ms": 120
+        })
+    );
+}
@@ -0,0 +1,15 @@
+/// Build a `js_sys::Object` from `key => value` pairs, for the dictionaries passed to runtime
+/// APIs. `JsObject` and `JsString` must be in scope.
+macro_rules! js_object {
+    {$($key: expr => $value: expr),* $(,)?} => {{
+        let obj = JsObject::new();
+        $(
+            {
+                let res = ::js_sys::Reflect::set(&obj, &JsString::from($key), &JsValue::from($value));
+                debug_assert!(res.is_ok(), "setting properties should never fail on our dictionary objects");
+            }
+        )*
+        obj
+    }};
+}
+pub(crate) use js_object;
@@ -0,0 +1,378 @@
+//! A registry of counters and histograms, which can be rendered in the
+//! [OpenMetrics](https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md)
+//! text format to be scraped by Prometheus or pushed to a gateway.
+//!
+//! Metrics live in the memory of the isolate, so they are shared by all requests it handles and
+//! reset whenever the isolate is evicted. Each isolate reports its own values, which should be
+//! aggregated by the collector.
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::metrics::{Registry, DEFAULT_BUCKETS};
+//!
+//! # fn example() {
+//! Router::new()
+//!     .get("/", |_, _| {
+//!         let registry = Registry::global();
+//!         let requests = registry.counter("http_requests", "Handled requests.")?;
+//!         requests.with_labels(&[("path", "/")]).inc();
+//!         registry
+//!             .histogram("http_latency_seconds", "Request latency.", DEFAULT_BUCKETS)?
+//!             .observe(0.02);
+//!         Response::ok("hello")
+//!     })
+//!     .get("/metrics", |_, _| Registry::global().response());
+//! # }
+//! ```
+
+use std::{cell::RefCell, collections::BTreeMap, fmt::Write, rc::Rc};
+
+use crate::{Error, Fetch, Headers, Method, Request, RequestInit, Response, Result};
+
+/// The buckets used by Prometheus client libraries by default, suited to latencies in seconds.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The `Content-Type` of [`Registry::render`]'s output.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+thread_local! {
+    static GLOBAL: Registry = Registry::new();
+}
+
+/// A collection of metrics, which is cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    families: Rc<RefCell<BTreeMap<String, Family>>>,
+}
+
+#[derive(Debug)]
+struct Family {
+    help: String,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Counter(BTreeMap<String, f64>),
+    Histogram {
+        buckets: Vec<f64>,
+        series: BTreeMap<String, Series>,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+struct Series {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The registry shared by the whole isolate.
+    pub fn global() -> Self {
+        GLOBAL.with(Registry::clone)
+    }
+
+    /// Register a counter, or get the one which was registered with the same name. The `_total`
+    /// suffix is added to its samples.
+    pub fn counter(&self, name: &str, help: &str) -> Result<Counter> {
+        let name = name.strip_suffix("_total").unwrap_or(name);
+        self.register(name, help, Kind::Counter(BTreeMap::new()))?;
+        Ok(Counter(Metric::new(self, name)))
+    }
+
+    /// Register a histogram with the given upper bounds of its buckets, or get the one which was
+    /// registered with the same name. A `+Inf` bucket is always added.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> Result<Histogram> {
+        if buckets.iter().any(|bound| bound.is_nan()) {
+            return Err(Error::RustError(format!(
+                "the buckets of histogram {name} must not be NaN"
+            )));
+        }
+        let mut buckets = buckets.to_vec();
+        buckets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        buckets.dedup();
+        if buckets.last() != Some(&f64::INFINITY) {
+            buckets.push(f64::INFINITY);
+        }
+        let kind = Kind::Histogram {
+            buckets,
+            series: BTreeMap::new(),
+        };
+        self.register(name, help, kind)?;
+        Ok(Histogram(Metric::new(self, name)))
+    }
+
+    fn register(&self, name: &str, help: &str, kind: Kind) -> Result<()> {
+        if !is_valid_name(name) {
+            return Err(Error::RustError(format!("invalid metric name: {name}")));
+        }
+        let mut families = self.families.borrow_mut();
+        match families.get(name) {
+            Some(family) if kind_of(&family.kind) != kind_of(&kind) => {
+                Err(Error::RustError(format!(
+                    "metric {name} is already registered as a {}",
+                    kind_of(&family.kind)
+                )))
+            }
+            Some(_) => Ok(()),
+            None => {
+                let help = help.into();
+                families.insert(name.into(), Family { help, kind });
+                Ok(())
+            }
+        }
+    }
+
+    /// Render all metrics in the OpenMetrics text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in self.families.borrow().iter() {
+            let _ = writeln!(out, "# TYPE {name} {}", kind_of(&family.kind));
+            if !family.help.is_empty() {
+                let _ = writeln!(out, "# HELP {name} {}", escape_help(&family.help));
+            }
+            match &family.kind {
+                Kind::Counter(series) => {
+                    for (labels, value) in series {
+                        let _ = writeln!(out, "{name}_total{} {value}", braced(labels));
+                    }
+                }
+                Kind::Histogram { buckets, series } => {
+                    for (labels, series) in series {
+                        let mut cumulative = 0;
+                        for (bound, count) in buckets.iter().zip(&series.counts) {
+                            cumulative += count;
+                            let le = format!("le=\"{}\"", format_value(*bound));
+                            let labels = if labels.is_empty() {
+                                le
+                            } else {
+                                format!("{labels},{le}")
+                            };
+                            let _ = writeln!(out, "{name}_bucket{{{labels}}} {cumulative}");
+                        }
+                        let labels = braced(labels);
+                        let _ = writeln!(out, "{name}_sum{labels} {}", format_value(series.sum));
+                        let _ = writeln!(out, "{name}_count{labels} {}", series.count);
+                    }
+                }
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+
+    /// A response with the rendered metrics, to be served to a scraper.
+    pub fn response(&self) -> Result<Response> {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", OPENMETRICS_CONTENT_TYPE)?;
+        Ok(Response::ok(self.render())?.with_headers(headers))
+    }
+
+    /// Send the rendered metrics to a gateway, e.g. the
+    /// `/metrics/job/<job>` endpoint of a Prometheus Pushgateway.
+    pub async fn push(&self, url: &str, mut headers: Headers) -> Result<()> {
+        headers.set("Content-Type", OPENMETRICS_CONTENT_TYPE)?;
+        let mut init = RequestInit::new();
+        init.with_method(Method::Post)
+            .with_headers(headers)
+            .with_body(Some(self.render().into()));
+        let mut resp = Fetch::Request(Request::new_with_init(url, &init)?)
+            .send()
+            .await?;
+        let status = resp.status_code();
+        if !(200..300).contains(&status) {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(Error::RustError(format!(
+                "pushing metrics to {url} failed with {status}: {body}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn kind_of(kind: &Kind) -> &'static str {
+    match kind {
+        Kind::Counter(_) => "counter",
+        Kind::Histogram { .. } => "histogram",
+    }
+}
+
+/// A registered metric with a set of label values.
+#[derive(Debug, Clone)]
+struct Metric {
+    registry: Registry,
+    name: Rc<str>,
+    labels: Rc<str>,
+}
+
+impl Metric {
+    fn new(registry: &Registry, name: &str) -> Self {
+        Self {
+            registry: registry.clone(),
+            name: name.into(),
+            labels: "".into(),
+        }
+    }
+
+    fn with_labels(&self, labels: &[(&str, &str)]) -> Self {
+        let mut labels = labels.to_vec();
+        labels.sort_by_key(|(name, _)| *name);
+        let labels = labels
+            .iter()
+            .map(|(name, value)| format!("{name}=\"{}\"", escape_label(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        Self {
+            registry: self.registry.clone(),
+            name: self.name.clone(),
+            labels: labels.into(),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Kind, &str)) {
+        let mut families = self.registry.families.borrow_mut();
+        if let Some(family) = families.get_mut(&*self.name) {
+            f(&mut family.kind, &self.labels);
+        }
+    }
+}
+
+/// A value which only goes up, like the number of handled requests.
+#[derive(Debug, Clone)]
+pub struct Counter(Metric);
+
+impl Counter {
+    /// The counter with the given label values, e.g. `&[("method", "GET")]`.
+    pub fn with_labels(&self, labels: &[(&str, &str)]) -> Self {
+        Self(self.0.with_labels(labels))
+    }
+
+    pub fn inc(&self) {
+        self.inc_by(1.0);
+    }
+
+    /// Increase the counter by `value`, which is ignored if negative.
+    pub fn inc_by(&self, value: f64) {
+        if value.is_nan() || value < 0.0 {
+            return;
+        }
+        self.0.update(|kind, labels| {
+            if let Kind::Counter(series) = kind {
+                *series.entry(labels.into()).or_default() += value;
+            }
+        });
+    }
+
+    /// The current value of the counter.
+    pub fn get(&self) -> f64 {
+        let families = self.0.registry.families.borrow();
+        match families.get(&*self.0.name).map(|family| &family.kind) {
+            Some(Kind::Counter(series)) => series.get(&*self.0.labels).copied().unwrap_or(0.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Counts observed values, like latencies, in buckets.
+#[derive(Debug, Clone)]
+pub struct Histogram(Metric);
+
+impl Histogram {
+    /// The histogram with the given label values, e.g. `&[("method", "GET")]`.
+    pub fn with_labels(&self, labels: &[(&str, &str)]) -> Self {
+        Self(self.0.with_labels(labels))
+    }
+
+    pub fn observe(&self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.0.update(|kind, labels| {
+            if let Kind::Histogram { buckets, series } = kind {
+                let series = series.entry(labels.into()).or_insert_with(|| Series {
+                    counts: vec![0; buckets.len()],
+                    ..Series::default()
+                });
+                // The last bucket is `+Inf`, so a bucket is always found.
+                if let Some(index) = buckets.iter().position(|bound| value <= *bound) {
+                    series.counts[index] += 1;
+                }
+                series.sum += value;
+                series.count += 1;
+            }
+        });
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".into()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".into()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[test]
+fn render_works() {
+    let registry = Registry::new();
+    let requests = registry.counter("requests_total", "Requests.").unwrap();
+    requests.with_labels(&[("path", "/\"a\"")]).inc();
+    requests.with_labels(&[("path", "/\"a\"")]).inc_by(2.0);
+    let latency = registry
+        .histogram("latency_seconds", "", &[1.0, 0.1])
+        .unwrap();
+    latency.observe(0.0625);
+    latency.observe(0.5);
+    latency.observe(4.0);
+
+    assert!(registry.histogram("requests", "", &[]).is_err());
+    assert!(registry.counter("2xx", "").is_err());
+    assert_eq!(
+        registry.render(),
+        "# TYPE latency_seconds histogram\n\
+         latency_seconds_bucket{le=\"0.1\"} 1\n\
+         latency_seconds_bucket{le=\"1\"} 2\n\
+         latency_seconds_bucket{le=\"+Inf\"} 3\n\
+         latency_seconds_sum 4.5625\n\
+         latency_seconds_count 3\n\
+         # TYPE requests counter\n\
+         # HELP requests Requests.\n\
+         requests_total{path=\"/\\\"a\\\"\"} 3\n\
+         # EOF\n"
+    );
+}
@@ -0,0 +1,58 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Error, Result};
+#[cfg(feature = "queue")]
+use crate::{Message, MessageBatch, Queue};
+
+/// Serialize `value` as [MessagePack](https://msgpack.org), with structs as maps so that fields
+/// can be added and removed as with JSON.
+pub(crate) fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    rmp_serde::to_vec_named(value)
+        .map_err(|e| Error::RustError(format!("failed to encode MessagePack: {e}")))
+}
+
+pub(crate) fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    rmp_serde::from_slice(bytes)
+        .map_err(|e| Error::RustError(format!("failed to decode MessagePack: {e}")))
+}
+
+#[cfg(feature = "queue")]
+impl Queue {
+    /// Send `body` serialized as MessagePack with the `bytes` content type, which is smaller
+    /// than JSON and keeps the Rust types as they are, e.g. maps with non-string keys.
+    pub async fn send_msgpack<T: Serialize + ?Sized>(&self, body: &T) -> Result<()> {
+        self.send_encoded(encode(body)?).await
+    }
+}
+
+#[cfg(feature = "queue")]
+impl<T> MessageBatch<T> {
+    /// Iterator for messages whose body was sent with [`Queue::send_msgpack`]. Messages which
+    /// aren't `bytes` or can't be deserialized are returned as errors, so that they can be
+    /// retried or acknowledged individually.
+    pub fn iter_msgpack<U: DeserializeOwned>(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = Result<Message<U>>> + ExactSizeIterator {
+        self.iter_decoded(decode::<U>)
+    }
+}
+
+#[test]
+fn msgpack_works() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Scores {
+        name: String,
+        by_level: BTreeMap<u32, u64>,
+    }
+
+    let scores = Scores {
+        name: "alice".into(),
+        by_level: [(1, 100), (2, 250)].into_iter().collect(),
+    };
+    let bytes = encode(&scores).unwrap();
+    assert!(bytes.len() < serde_json::to_vec(&scores).unwrap().len());
+    assert_eq!(decode::<Scores>(&bytes).unwrap(), scores);
+    assert!(decode::<Scores>(b"\xc1").is_err());
+}
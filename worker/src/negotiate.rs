@@ -0,0 +1,145 @@
+use serde::Serialize;
+
+use crate::{Request, Response, Result};
+
+type Render<'a> = Box<dyn FnOnce() -> Result<Response> + 'a>;
+
+/// Start negotiating the representation of a response to `req`, based on its `Accept` header.
+///
+/// Each representation is only rendered if it's selected. When the request prefers none of them,
+/// a `406 Not Acceptable` response is returned, and when it has no `Accept` header the first
+/// representation is used.
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example(req: Request) -> Result<Response> {
+/// let user = serde_json::json!({ "name": "Alice" });
+/// respond_to(&req)
+///     .json(|| &user)
+///     .html(|| format!("<h1>{}</h1>", user["name"]))
+///     .text(|| user["name"].to_string())
+///     .respond()
+/// # }
+/// ```
+pub fn respond_to(req: &Request) -> Negotiator<'_> {
+    Negotiator {
+        accept: req.headers().get("Accept").ok().flatten(),
+        representations: Vec::new(),
+    }
+}
+
+/// The representations a response can be rendered as, created with [`respond_to`].
+#[must_use = "Negotiator does nothing until you call `respond`"]
+pub struct Negotiator<'a> {
+    accept: Option<String>,
+    representations: Vec<(&'static str, Render<'a>)>,
+}
+
+impl<'a> Negotiator<'a> {
+    /// Offer a JSON (`application/json`) representation.
+    pub fn json<T: Serialize>(self, f: impl FnOnce() -> T + 'a) -> Self {
+        self.with("application/json", move || Response::from_json(&f()))
+    }
+
+    /// Offer an HTML (`text/html`) representation.
+    pub fn html<S: AsRef<str>>(self, f: impl FnOnce() -> S + 'a) -> Self {
+        self.with("text/html", move || Response::from_html(f()))
+    }
+
+    /// Offer a plain text (`text/plain`) representation.
+    pub fn text<S: Into<String>>(self, f: impl FnOnce() -> S + 'a) -> Self {
+        self.with("text/plain", move || Response::ok(f()))
+    }
+
+    /// Offer a representation of any other media type, e.g. `application/xml`.
+    pub fn with(
+        mut self,
+        media_type: &'static str,
+        f: impl FnOnce() -> Result<Response> + 'a,
+    ) -> Self {
+        self.representations.push((media_type, Box::new(f)));
+        self
+    }
+
+    /// Render the representation preferred by the request.
+    pub fn respond(self) -> Result<Response> {
+        let offered: Vec<_> = self.representations.iter().map(|(t, _)| *t).collect();
+        let selected = match &self.accept {
+            Some(accept) => select(accept, &offered),
+            None if offered.is_empty() => None,
+            None => Some(0),
+        };
+
+        let mut resp = match selected {
+            Some(index) => {
+                let (_, render) = self.representations.into_iter().nth(index).unwrap();
+                render()?
+            }
+            None => Response::error("Not Acceptable", 406)?,
+        };
+        resp.headers_mut().append("Vary", "Accept")?;
+        Ok(resp)
+    }
+}
+
+/// The index of the media type in `offered` which `accept` prefers, if any are acceptable.
+/// Ties are resolved in favour of the type which was offered first.
+fn select(accept: &str, offered: &[&str]) -> Option<usize> {
+    let ranges: Vec<(&str, &str, f32)> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let (ty, subtype) = params.next()?.trim().split_once('/')?;
+            let q = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse().ok())
+                .unwrap_or(1.0);
+            Some((ty.trim(), subtype.trim(), q))
+        })
+        .collect();
+
+    let mut best: Option<(usize, f32)> = None;
+    for (index, media_type) in offered.iter().enumerate() {
+        let (ty, subtype) = media_type.split_once('/').unwrap_or((media_type, ""));
+        // The most specific matching range determines the quality of a media type.
+        let quality = ranges
+            .iter()
+            .filter_map(|&(r_ty, r_subtype, q)| {
+                let specificity = match (r_ty, r_subtype) {
+                    _ if r_ty.eq_ignore_ascii_case(ty)
+                        && r_subtype.eq_ignore_ascii_case(subtype) =>
+                    {
+                        2
+                    }
+                    _ if r_ty.eq_ignore_ascii_case(ty) && r_subtype == "*" => 1,
+                    ("*", "*") => 0,
+                    _ => return None,
+                };
+                Some((specificity, q))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, q)| q)
+            .unwrap_or(0.0);
+
+        if quality > 0.0 && best.map(|(_, q)| quality > q).unwrap_or(true) {
+            best = Some((index, quality));
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+#[test]
+fn select_works() {
+    let offered = ["application/json", "text/html", "text/plain"];
+    assert_eq!(
+        select("text/html,application/xhtml+xml,*/*;q=0.8", &offered),
+        Some(1)
+    );
+    assert_eq!(select("*/*", &offered), Some(0));
+    assert_eq!(
+        select("text/*;q=0.5, application/json;q=0.4", &offered),
+        Some(1)
+    );
+    assert_eq!(select("text/*, text/html;q=0", &offered), Some(2));
+    assert_eq!(select("image/png", &offered), None);
+}
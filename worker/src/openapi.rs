@@ -0,0 +1,359 @@
+//! Generate an [OpenAPI 3.1](https://spec.openapis.org/oas/v3.1.0) document from the routes of a
+//! [`Router`](crate::Router).
+//!
+//! Every route registered for a single method is included in the document with its path
+//! parameters. Routes can be described further with [`Router::document`](crate::Router::document),
+//! using types which implement [`Schema`] for request and response bodies, and the document is
+//! served with [`Router::openapi`](crate::Router::openapi):
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::openapi::{Info, Operation, Schema};
+//! use serde_json::{json, Value};
+//!
+//! struct User;
+//!
+//! impl Schema for User {
+//!     fn schema() -> Value {
+//!         json!({
+//!             "type": "object",
+//!             "properties": { "id": String::schema(), "name": String::schema() },
+//!             "required": ["id", "name"]
+//!         })
+//!     }
+//! }
+//!
+//! # fn example() {
+//! Router::new()
+//!     .get_async("/users/:id", |_, _| async { Response::ok("...") })
+//!     .document(
+//!         Method::Get,
+//!         "/users/:id",
+//!         Operation::new()
+//!             .summary("Get a user")
+//!             .response::<User>(200, "The user")
+//!             .response_without_body(404, "No such user"),
+//!     )
+//!     .openapi("/openapi.json", Info::new("Users API", "1.0.0"));
+//! # }
+//! ```
+//!
+//! **Requires** `openapi` feature.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Map, Value};
+
+use crate::Method;
+
+/// A type with a [JSON Schema](https://json-schema.org/) which describes its JSON representation.
+pub trait Schema {
+    fn schema() -> Value;
+}
+
+macro_rules! impl_schema {
+    ($ty:literal, $($t:ty),*) => {
+        $(
+            impl Schema for $t {
+                fn schema() -> Value {
+                    json!({ "type": $ty })
+                }
+            }
+        )*
+    };
+}
+
+impl_schema!("boolean", bool);
+impl_schema!("integer", i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_schema!("number", f32, f64);
+impl_schema!("string", String, str, char);
+
+impl<T: Schema + ?Sized> Schema for &T {
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for [T] {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for HashMap<String, T> {
+    fn schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for BTreeMap<String, T> {
+    fn schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::schema() })
+    }
+}
+
+impl Schema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+/// The metadata of an API, the `info` object of the document.
+#[derive(Debug, Clone)]
+pub struct Info {
+    title: String,
+    version: String,
+    description: Option<String>,
+}
+
+impl Info {
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+        }
+    }
+
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// The description of a route.
+#[derive(Debug, Clone, Default)]
+pub struct Operation {
+    operation_id: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    request_body: Option<Value>,
+    responses: BTreeMap<u16, (String, Option<Value>)>,
+}
+
+impl Operation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A unique identifier of the operation, which code generators use as the function name.
+    #[must_use]
+    pub fn operation_id(mut self, id: impl Into<String>) -> Self {
+        self.operation_id = Some(id.into());
+        self
+    }
+
+    #[must_use]
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Group the operation with others under `tag`.
+    #[must_use]
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// The operation expects a JSON body of type `T`.
+    #[must_use]
+    pub fn request_body<T: Schema + ?Sized>(mut self) -> Self {
+        self.request_body = Some(T::schema());
+        self
+    }
+
+    /// The operation responds with a JSON body of type `T` and the given status.
+    #[must_use]
+    pub fn response<T: Schema + ?Sized>(mut self, status: u16, description: &str) -> Self {
+        self.responses
+            .insert(status, (description.into(), Some(T::schema())));
+        self
+    }
+
+    /// The operation responds with the given status and no body.
+    #[must_use]
+    pub fn response_without_body(mut self, status: u16, description: &str) -> Self {
+        self.responses.insert(status, (description.into(), None));
+        self
+    }
+
+    fn to_json(&self, params: &[String]) -> Value {
+        let mut operation = Map::new();
+        if let Some(id) = &self.operation_id {
+            operation.insert("operationId".into(), id.as_str().into());
+        }
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".into(), summary.as_str().into());
+        }
+        if let Some(description) = &self.description {
+            operation.insert("description".into(), description.as_str().into());
+        }
+        if !self.tags.is_empty() {
+            operation.insert("tags".into(), self.tags.clone().into());
+        }
+        if !params.is_empty() {
+            let params = params
+                .iter()
+                .map(|name| {
+                    json!({
+                        "name": name,
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" }
+                    })
+                })
+                .collect();
+            operation.insert("parameters".into(), Value::Array(params));
+        }
+        if let Some(schema) = &self.request_body {
+            operation.insert(
+                "requestBody".into(),
+                json!({ "required": true, "content": json_content(schema) }),
+            );
+        }
+
+        let mut responses: Map<String, Value> = self
+            .responses
+            .iter()
+            .map(|(status, (description, schema))| {
+                let mut response = json!({ "description": description });
+                if let Some(schema) = schema {
+                    response["content"] = json_content(schema);
+                }
+                (status.to_string(), response)
+            })
+            .collect();
+        // Every operation must describe at least one response.
+        if responses.is_empty() {
+            responses.insert("default".into(), json!({ "description": "Response" }));
+        }
+        operation.insert("responses".into(), Value::Object(responses));
+
+        Value::Object(operation)
+    }
+}
+
+fn json_content(schema: &Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// The routes of a `Router`, collected while they are registered.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Spec {
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    method: Method,
+    pattern: String,
+    operation: Operation,
+}
+
+impl Spec {
+    /// Record a route. Routes for several methods, like those registered with `on`, aren't
+    /// documented unless they are described with `document`.
+    pub(crate) fn add_route(&mut self, methods: &[Method], pattern: &str) {
+        let method = match methods {
+            [method] => method,
+            _ => return,
+        };
+        if self.find(method, pattern).is_none() {
+            self.routes.push(Route {
+                method: method.clone(),
+                pattern: pattern.into(),
+                operation: Operation::default(),
+            });
+        }
+    }
+
+    pub(crate) fn document(&mut self, method: &Method, pattern: &str, operation: Operation) {
+        match self.find(method, pattern) {
+            Some(index) => self.routes[index].operation = operation,
+            None => self.routes.push(Route {
+                method: method.clone(),
+                pattern: pattern.into(),
+                operation,
+            }),
+        }
+    }
+
+    fn find(&self, method: &Method, pattern: &str) -> Option<usize> {
+        self.routes
+            .iter()
+            .position(|route| &route.method == method && route.pattern == pattern)
+    }
+
+    pub(crate) fn to_json(&self, info: &Info) -> Value {
+        let mut paths = BTreeMap::<String, Map<String, Value>>::new();
+        for route in &self.routes {
+            let (path, params) = openapi_path(&route.pattern);
+            paths.entry(path).or_default().insert(
+                route.method.as_ref().to_ascii_lowercase(),
+                route.operation.to_json(&params),
+            );
+        }
+
+        let mut info_json = json!({ "title": info.title, "version": info.version });
+        if let Some(description) = &info.description {
+            info_json["description"] = description.as_str().into();
+        }
+
+        json!({
+            "openapi": "3.1.0",
+            "info": info_json,
+            "paths": paths,
+        })
+    }
+}
+
+/// Convert a route pattern like `/users/:id/*rest` to an OpenAPI path like `/users/{id}/{rest}`,
+/// along with the names of its parameters.
+fn openapi_path(pattern: &str) -> (String, Vec<String>) {
+    let mut params = Vec::new();
+    let path = pattern
+        .split('/')
+        .map(|segment| match segment.strip_prefix([':', '*']) {
+            Some(name) => {
+                params.push(name.to_string());
+                format!("{{{name}}}")
+            }
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    (path, params)
+}
+
+#[test]
+fn openapi_path_works() {
+    assert_eq!(
+        openapi_path("/users/:id/files/*path"),
+        (
+            "/users/{id}/files/{path}".to_string(),
+            vec!["id".to_string(), "path".to_string()]
+        )
+    );
+    assert_eq!(openapi_path("/"), ("/".to_string(), vec![]));
+}
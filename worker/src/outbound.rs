@@ -0,0 +1,124 @@
+//! Interceptors which run around every outbound request: the requests sent with [`Fetch`] and
+//! through [service bindings](crate::Fetcher). They're registered once per isolate, e.g. in the
+//! [`#[event(start)]`](macro@crate::event) handler, to add headers, log or measure subrequests, or
+//! retry failed ones, without changing each call site.
+//!
+//! Interceptors run in the order they were registered, each calling [`Next::run`] to pass the
+//! request on to the next one, and finally to the network. `Next::run` can be called more than
+//! once, to retry a request.
+//!
+//! ```no_run
+//! use worker::*;
+//! use worker::outbound::{self, Next};
+//!
+//! #[event(start)]
+//! fn start() {
+//!     outbound::intercept(|mut req: Request, next: Next| async move {
+//!         req.headers_mut()?.set("User-Agent", "my-worker/1.0")?;
+//!         let started = Date::now().as_millis();
+//!         let resp = next.run(req.clone()?).await;
+//!         match &resp {
+//!             // Retry once on a server error.
+//!             Ok(resp) if resp.status_code() >= 500 => return next.run(req).await,
+//!             _ => {}
+//!         }
+//!         console_log!("subrequest took {}ms", Date::now().as_millis() - started);
+//!         resp
+//!     });
+//! }
+//! ```
+//!
+//! Sending a request through an interceptor clones it, so a request with a streamed body can only
+//! be retried if it was cloned before being sent.
+
+use std::{cell::RefCell, future::Future, rc::Rc};
+
+use futures_util::future::LocalBoxFuture;
+
+use crate::{AbortSignal, Fetcher, Request, Response, Result};
+
+thread_local! {
+    static INTERCEPTORS: RefCell<Rc<Vec<Rc<dyn Interceptor>>>> = RefCell::new(Rc::new(Vec::new()));
+}
+
+/// Logic which runs around every outbound request, see the [module documentation](self).
+///
+/// Any closure taking a [`Request`] and a [`Next`] and returning a `Future` implements this
+/// trait.
+pub trait Interceptor {
+    fn intercept(&self, req: Request, next: Next) -> LocalBoxFuture<'static, Result<Response>>;
+}
+
+impl<F, T> Interceptor for F
+where
+    F: Fn(Request, Next) -> T,
+    T: Future<Output = Result<Response>> + 'static,
+{
+    fn intercept(&self, req: Request, next: Next) -> LocalBoxFuture<'static, Result<Response>> {
+        Box::pin(self(req, next))
+    }
+}
+
+/// Register an interceptor for all the outbound requests of the isolate, after those already
+/// registered.
+pub fn intercept(interceptor: impl Interceptor + 'static) {
+    INTERCEPTORS.with(|interceptors| {
+        let mut interceptors = interceptors.borrow_mut();
+        let mut list = interceptors.as_ref().clone();
+        list.push(Rc::new(interceptor));
+        *interceptors = Rc::new(list);
+    });
+}
+
+/// Remove every interceptor registered with [`intercept`].
+pub fn clear() {
+    INTERCEPTORS.with(|interceptors| *interceptors.borrow_mut() = Rc::new(Vec::new()));
+}
+
+/// Where a request goes once it passed every interceptor.
+#[derive(Clone)]
+pub(crate) enum Transport {
+    Fetch(Option<AbortSignal>),
+    Service(Fetcher),
+}
+
+/// The chain of interceptors for a request sent with `transport`, or `None` if there are no
+/// interceptors, in which case the request is sent directly.
+pub(crate) fn chain(transport: Transport) -> Option<Next> {
+    let interceptors = INTERCEPTORS.with(|interceptors| interceptors.borrow().clone());
+    (!interceptors.is_empty()).then(|| Next {
+        interceptors,
+        index: 0,
+        transport: Rc::new(transport),
+    })
+}
+
+/// The rest of the interceptors of an outbound request, and then the network.
+#[derive(Clone)]
+pub struct Next {
+    interceptors: Rc<Vec<Rc<dyn Interceptor>>>,
+    index: usize,
+    transport: Rc<Transport>,
+}
+
+impl Next {
+    /// Pass `req` on to the next interceptor, or send it once every interceptor has run.
+    pub async fn run(&self, req: Request) -> Result<Response> {
+        match self.interceptors.get(self.index) {
+            Some(interceptor) => {
+                let next = Next {
+                    interceptors: self.interceptors.clone(),
+                    index: self.index + 1,
+                    transport: self.transport.clone(),
+                };
+                interceptor.intercept(req, next).await
+            }
+            None => match self.transport.as_ref() {
+                Transport::Fetch(signal) => {
+                    crate::global::fetch_with_request(&req, signal.as_ref()).await
+                }
+                Transport::Service(fetcher) => fetcher.send(&req).await,
+            },
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! An opt-in panic hook which reports panics as structured JSON, including the request that was
+//! being handled when the panic occurred.
+//!
+//! Reports are logged with `console.error`, so they show up in `wrangler tail`, Workers Logs and
+//! any attached [Tail Worker](https://developers.cloudflare.com/workers/observability/logs/tail-workers/)
+//! as a single JSON object instead of a text blob:
+//!
+//! ```no_run
+//! use worker::*;
+//!
+//! #[event(start)]
+//! fn start() {
+//!     panic::PanicHook::new().install();
+//! }
+//!
+//! #[event(fetch)]
+//! async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+//!     panic::set_request(&req);
+//!     Response::ok("Hello")
+//! }
+//! ```
+
+use std::{any::Any, cell::RefCell, collections::BTreeMap, panic::Location};
+
+use serde::Serialize;
+
+use crate::Request;
+
+thread_local! {
+    static CONTEXT: RefCell<PanicContext> = RefCell::new(PanicContext::default());
+}
+
+#[derive(Debug, Clone, Default)]
+struct PanicContext {
+    request: Option<RequestInfo>,
+    tags: BTreeMap<String, String>,
+}
+
+/// The request which was being handled when a panic occurred.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestInfo {
+    pub method: String,
+    pub url: String,
+}
+
+/// A structured description of a panic, passed to the reporters of a [`PanicHook`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PanicReport {
+    pub level: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<RequestInfo>,
+    /// Additional values set with [`set_tag`], e.g. the trace and span ids of the request.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl PanicReport {
+    fn new(payload: &(dyn Any + Send), location: Option<&Location<'_>>) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let context = CONTEXT
+            .try_with(|context| context.try_borrow().map(|c| c.clone()).unwrap_or_default())
+            .unwrap_or_default();
+
+        Self {
+            level: "error",
+            message,
+            location: location.map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            request: context.request,
+            tags: context.tags,
+        }
+    }
+
+    /// The report serialized as a JSON string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| format!("{:?}", self.message))
+    }
+}
+
+type Reporter = Box<dyn Fn(&PanicReport) + Send + Sync>;
+
+/// Builder for the panic hook, see the [module documentation](self).
+#[derive(Default)]
+pub struct PanicHook {
+    reporters: Vec<Reporter>,
+    quiet: bool,
+}
+
+impl PanicHook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Don't log reports with `console.error`, only invoke the registered reporters.
+    #[must_use]
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Invoke `reporter` with every panic report. The reporter runs while the Worker is
+    /// panicking, so it must not block on or spawn futures: those won't get to run.
+    #[must_use]
+    pub fn report_with(mut self, reporter: impl Fn(&PanicReport) + Send + Sync + 'static) -> Self {
+        self.reporters.push(Box::new(reporter));
+        self
+    }
+
+    /// Send every panic report to `queue` as a JSON message. The message is handed to the runtime
+    /// before the panic aborts the Worker, but delivery can't be awaited or confirmed.
+    #[cfg(feature = "queue")]
+    #[must_use]
+    pub fn forward_to_queue(self, queue: crate::Queue) -> Self {
+        self.report_with(move |report| {
+            if let Ok(message) = js_sys::JSON::parse(&report.to_json()) {
+                queue.send_detached(message);
+            }
+        })
+    }
+
+    /// Install the hook, replacing any previously installed panic hook.
+    pub fn install(self) {
+        std::panic::set_hook(Box::new(move |info| {
+            let report = PanicReport::new(info.payload(), info.location());
+            if !self.quiet {
+                worker_sys::console_error!("{}", report.to_json());
+            }
+            for reporter in &self.reporters {
+                reporter(&report);
+            }
+        }));
+    }
+}
+
+/// Record `req` as the request currently being handled, to be included in panic reports. Previous
+/// tags are cleared, since they belonged to the previous request.
+pub fn set_request(req: &Request) {
+    let request = RequestInfo {
+        method: req.method().to_string(),
+        url: req.inner().url(),
+    };
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.request = Some(request);
+        context.tags.clear();
+    });
+}
+
+/// Attach an additional value to panic reports for the current request.
+pub fn set_tag(key: impl Into<String>, value: impl Into<String>) {
+    CONTEXT.with(|context| {
+        context.borrow_mut().tags.insert(key.into(), value.into());
+    });
+}
+
+/// Forget the current request and tags.
+pub fn clear_context() {
+    CONTEXT.with(|context| *context.borrow_mut() = PanicContext::default());
+}
@@ -0,0 +1,179 @@
+//! Timing of the phases of a request with
+//! [`performance.now()`](https://developers.cloudflare.com/workers/runtime-apis/performance/),
+//! and reporting them in a
+//! [`Server-Timing`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Server-Timing)
+//! header.
+//!
+//! To mitigate timing side channels the clock of the runtime only advances on I/O, so only the
+//! time spent waiting for subrequests, storage and other I/O can be measured, not CPU time.
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::perf::ServerTiming;
+//!
+//! # async fn example(env: Env) -> Result<Response> {
+//! let mut timing = ServerTiming::new();
+//! let user = timing
+//!     .measure("kv", env.kv("USERS")?.get("alice").text())
+//!     .await?;
+//! let mut resp = timing
+//!     .measure("origin", Fetch::Url("https://example.com".parse()?).send())
+//!     .await?;
+//! timing.apply(&mut resp)?;
+//! Ok(resp)
+//! # }
+//! ```
+
+use std::{fmt, future::Future, time::Duration};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Response, Result};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+/// The milliseconds elapsed since the timeline of the request started, from
+/// `performance.now()`.
+pub fn now() -> f64 {
+    performance_now()
+}
+
+/// Measures the time elapsed since it was started.
+#[derive(Debug, Clone, Copy)]
+pub struct Stopwatch {
+    start: f64,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self { start: now() }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((now() - self.start).max(0.0) / 1000.0)
+    }
+
+    /// The time elapsed, restarting the stopwatch.
+    pub fn lap(&mut self) -> Duration {
+        let elapsed = self.elapsed();
+        self.start = now();
+        elapsed
+    }
+}
+
+/// A metric of a [`ServerTiming`] header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub name: String,
+    pub description: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(description) = &self.description {
+            let escaped = description.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, ";desc=\"{escaped}\"")?;
+        }
+        if let Some(duration) = self.duration {
+            let ms = (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0;
+            write!(f, ";dur={ms}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The metrics of a `Server-Timing` header, collected while handling a request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerTiming {
+    timings: Vec<Timing>,
+}
+
+impl ServerTiming {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `name`, a token such as `db`, took `duration`.
+    pub fn record(&mut self, name: impl Into<String>, duration: Duration) -> &mut Self {
+        self.timings.push(Timing {
+            name: name.into(),
+            description: None,
+            duration: Some(duration),
+        });
+        self
+    }
+
+    /// Like [`record`](ServerTiming::record), with a description shown by browsers.
+    pub fn record_with_description(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        duration: Duration,
+    ) -> &mut Self {
+        self.timings.push(Timing {
+            name: name.into(),
+            description: Some(description.into()),
+            duration: Some(duration),
+        });
+        self
+    }
+
+    /// Record a metric without a duration, e.g. `cache;desc="hit"`.
+    pub fn mark(&mut self, name: impl Into<String>, description: impl Into<String>) -> &mut Self {
+        self.timings.push(Timing {
+            name: name.into(),
+            description: Some(description.into()),
+            duration: None,
+        });
+        self
+    }
+
+    /// Await `future`, recording how long it took as `name`.
+    pub async fn measure<F: Future>(&mut self, name: impl Into<String>, future: F) -> F::Output {
+        let stopwatch = Stopwatch::start();
+        let output = future.await;
+        self.record(name, stopwatch.elapsed());
+        output
+    }
+
+    pub fn timings(&self) -> &[Timing] {
+        &self.timings
+    }
+
+    /// The value of the `Server-Timing` header.
+    pub fn header_value(&self) -> String {
+        self.timings
+            .iter()
+            .map(Timing::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Append the metrics to the `Server-Timing` header of `resp`, if there are any.
+    pub fn apply(&self, resp: &mut Response) -> Result<()> {
+        if !self.timings.is_empty() {
+            resp.headers_mut()
+                .append("Server-Timing", &self.header_value())?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn header_value_works() {
+    let mut timing = ServerTiming::new();
+    timing
+        .record("db", Duration::from_micros(12_500))
+        .record_with_description("origin", "GET \"/\"", Duration::from_millis(3))
+        .mark("cache", "miss");
+    assert_eq!(
+        timing.header_value(),
+        r#"db;dur=12.5, origin;desc="GET \"/\"";dur=3, cache;desc="miss""#
+    );
+}
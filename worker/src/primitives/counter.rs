@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    durable::{DurableObject, State, Stub},
+    durable_object, Env, ObjectNamespace, Request, Response, Result,
+};
+
+const KEY: &str = "value";
+
+#[derive(Serialize, Deserialize)]
+struct Increment {
+    by: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Value {
+    value: i64,
+}
+
+/// A counter stored in a Durable Object, which is consistent no matter where it's updated
+/// from.
+#[durable_object]
+pub struct DurableCounter {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for DurableCounter {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let mut storage = self.state.storage();
+        let value = super::load::<i64>(&storage, KEY).await?.unwrap_or(0);
+        let value = match req.path().as_str() {
+            "/get" => value,
+            "/increment" => {
+                let Increment { by } = req.json().await?;
+                let value = value.saturating_add(by);
+                storage.put(KEY, value).await?;
+                value
+            }
+            "/reset" => {
+                storage.delete(KEY).await?;
+                0
+            }
+            _ => return Response::error("Not Found", 404),
+        };
+        Response::from_json(&Value { value })
+    }
+}
+
+/// A client of a [`DurableCounter`].
+pub struct CounterClient {
+    stub: Stub,
+}
+
+impl CounterClient {
+    /// The client of the counter with the given name in `namespace`.
+    pub fn new(namespace: &ObjectNamespace, name: &str) -> Result<Self> {
+        Ok(Self {
+            stub: super::stub(namespace, name)?,
+        })
+    }
+
+    /// The current value of the counter, which starts at 0.
+    pub async fn get(&self) -> Result<i64> {
+        let Value { value } = super::call(&self.stub, "/get", &()).await?;
+        Ok(value)
+    }
+
+    /// Add `by`, which may be negative, to the counter and return its new value.
+    pub async fn increment(&self, by: i64) -> Result<i64> {
+        let Value { value } = super::call(&self.stub, "/increment", &Increment { by }).await?;
+        Ok(value)
+    }
+
+    /// Set the counter back to 0.
+    pub async fn reset(&self) -> Result<()> {
+        let _: Value = super::call(&self.stub, "/reset", &()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    durable::{DurableObject, State, Stub},
+    durable_object, Date, Env, ObjectNamespace, Request, Response, Result,
+};
+
+const KEY: &str = "lease";
+
+/// A lease held by a [`DurableLease`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lease {
+    /// The identifier of the holder.
+    pub holder: String,
+    /// When the lease expires, in milliseconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Acquire {
+    holder: String,
+    ttl_ms: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Release {
+    holder: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Acquired {
+    acquired: bool,
+    lease: Option<Lease>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Released {
+    released: bool,
+}
+
+/// A mutex held by one holder at a time, which is released automatically once its TTL passes so
+/// that a crashed holder can't keep it forever.
+#[durable_object]
+pub struct DurableLease {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for DurableLease {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        let mut storage = self.state.storage();
+        let now = Date::now().as_millis();
+        let current = super::load::<Lease>(&storage, KEY)
+            .await?
+            .filter(|lease| lease.expires_at > now);
+
+        match req.path().as_str() {
+            "/get" => Response::from_json(&current),
+            "/acquire" => {
+                let Acquire { holder, ttl_ms } = req.json().await?;
+                if matches!(&current, Some(lease) if lease.holder != holder) {
+                    return Response::from_json(&Acquired {
+                        acquired: false,
+                        lease: current,
+                    });
+                }
+                let lease = Lease {
+                    holder,
+                    expires_at: now.saturating_add(ttl_ms),
+                };
+                storage.put(KEY, &lease).await?;
+                Response::from_json(&Acquired {
+                    acquired: true,
+                    lease: Some(lease),
+                })
+            }
+            "/release" => {
+                let Release { holder } = req.json().await?;
+                let released = matches!(&current, Some(lease) if lease.holder == holder);
+                if released {
+                    storage.delete(KEY).await?;
+                }
+                Response::from_json(&Released { released })
+            }
+            _ => Response::error("Not Found", 404),
+        }
+    }
+}
+
+/// A client of a [`DurableLease`].
+///
+/// ```no_run
+/// # use worker::*;
+/// # use std::time::Duration;
+/// # async fn example(env: Env) -> Result<()> {
+/// let lease = primitives::LeaseClient::new(&env.durable_object("LEASE")?, "daily-report")?;
+/// if lease.acquire("worker-1", Duration::from_secs(60)).await?.is_some() {
+///     // Only one holder gets here until the lease is released or expires.
+///     lease.release("worker-1").await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LeaseClient {
+    stub: Stub,
+}
+
+impl LeaseClient {
+    /// The client of the lease with the given name in `namespace`.
+    pub fn new(namespace: &ObjectNamespace, name: &str) -> Result<Self> {
+        Ok(Self {
+            stub: super::stub(namespace, name)?,
+        })
+    }
+
+    /// Acquire the lease for `holder` for `ttl`, returning `None` if another holder has it.
+    /// Acquiring a lease which `holder` already has renews it.
+    pub async fn acquire(&self, holder: &str, ttl: Duration) -> Result<Option<Lease>> {
+        let body = Acquire {
+            holder: holder.into(),
+            ttl_ms: ttl.as_millis() as u64,
+        };
+        let acquired: Acquired = super::call(&self.stub, "/acquire", &body).await?;
+        Ok(acquired.lease.filter(|_| acquired.acquired))
+    }
+
+    /// Release the lease if `holder` has it, returning whether it did.
+    pub async fn release(&self, holder: &str) -> Result<bool> {
+        let body = Release {
+            holder: holder.into(),
+        };
+        let Released { released } = super::call(&self.stub, "/release", &body).await?;
+        Ok(released)
+    }
+
+    /// The current lease, if it's held.
+    pub async fn current(&self) -> Result<Option<Lease>> {
+        super::call(&self.stub, "/get", &()).await
+    }
+}
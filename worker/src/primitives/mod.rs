@@ -0,0 +1,86 @@
+//! Ready-made Durable Objects for common coordination patterns, each with a typed client:
+//!
+//! - [`DurableCounter`], a counter which can be incremented from anywhere, used through
+//!   [`CounterClient`].
+//! - [`DurableLease`], a mutex which is held for a limited time, used through [`LeaseClient`].
+//! - [`DurablePresence`], the members of a room with heartbeats, used through
+//!   [`PresenceClient`] or over WebSockets.
+//! - [`DurableRateLimiter`], a token bucket, used through [`RateLimiterClient`].
+//!
+//! The objects are exported from the Worker when the feature is enabled, so they only need to be
+//! bound in `wrangler.toml` with their class name:
+//!
+//! ```toml
+//! [durable_objects]
+//! bindings = [{ name = "RATE_LIMITER", class_name = "DurableRateLimiter" }]
+//!
+//! [[migrations]]
+//! tag = "v1"
+//! new_classes = ["DurableRateLimiter"]
+//! ```
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::primitives::RateLimiterClient;
+//!
+//! # async fn example(req: Request, env: Env) -> Result<Response> {
+//! let ip = req.headers().get("CF-Connecting-IP")?.unwrap_or_default();
+//! let limiter = RateLimiterClient::new(&env.durable_object("RATE_LIMITER")?, &ip, 10, 1.0)?;
+//! let decision = limiter.take(1).await?;
+//! if !decision.allowed {
+//!     return Response::error("Too Many Requests", 429);
+//! }
+//! # Response::ok("")
+//! # }
+//! ```
+//!
+//! **Requires** `durable-primitives` feature.
+
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    durable::{internal_url, Stub},
+    Error, Method, ObjectNamespace, Request, RequestInit, Result, Storage,
+};
+
+mod counter;
+mod lease;
+mod presence;
+mod rate_limiter;
+
+pub use counter::{CounterClient, DurableCounter};
+pub use lease::{DurableLease, Lease, LeaseClient};
+pub use presence::{DurablePresence, Member, PresenceClient, PresenceEvent};
+pub use rate_limiter::{DurableRateLimiter, RateLimitDecision, RateLimiterClient};
+
+fn stub(namespace: &ObjectNamespace, name: &str) -> Result<Stub> {
+    namespace.id_from_name(name)?.get_stub()
+}
+
+/// Send `body` as JSON to `path` of a primitive object and deserialize its JSON response.
+async fn call<B: Serialize, T: DeserializeOwned>(stub: &Stub, path: &str, body: &B) -> Result<T> {
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_body(Some(JsValue::from_str(&serde_json::to_string(body)?)));
+    let req = Request::new_with_init(&internal_url(path), &init)?;
+    let mut resp = stub.fetch_with_request(req).await?;
+    let status = resp.status_code();
+    if !(200..300).contains(&status) {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(Error::RustError(format!(
+            "Durable Object responded to {path} with {status}: {body}"
+        )));
+    }
+    resp.json().await
+}
+
+/// The value stored under `key`, or `None` if there is none.
+async fn load<T: DeserializeOwned>(storage: &Storage, key: &str) -> Result<Option<T>> {
+    let values = storage.get_multiple(vec![key]).await?;
+    let value = values.get(&JsValue::from_str(key));
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    Ok(Some(serde_wasm_bindgen::from_value(value)?))
+}
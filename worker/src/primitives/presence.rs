@@ -0,0 +1,319 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    durable::{internal_url, DurableObject, ScopedStorage, State, Stub},
+    durable_object, Date, Env, Headers, Hub, ObjectNamespace, Request, RequestInit, Response,
+    Result, WebSocket, WebSocketIncomingMessage, WebSocketPair,
+};
+
+const MEMBER_PREFIX: &str = "member:";
+
+/// A member of a [`DurablePresence`] room.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Member {
+    pub id: String,
+    /// The JSON metadata the member joined with, e.g. a display name.
+    pub meta: Value,
+    /// When the member leaves unless it sends a heartbeat, in milliseconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+/// A change to the members of a room, sent as JSON to the WebSockets connected to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresenceEvent {
+    Join { member: Member },
+    Leave { id: String },
+}
+
+/// A member as stored, as JSON since the metadata is arbitrary JSON.
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    member: Member,
+    ttl_ms: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Join {
+    id: String,
+    meta: Value,
+    ttl_ms: u64,
+}
+
+/// The query of a `/connect` request, with the metadata as JSON.
+#[derive(Deserialize)]
+struct Connect {
+    id: String,
+    meta: String,
+    ttl_ms: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Heartbeat {
+    id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Leave {
+    id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Present {
+    present: bool,
+}
+
+/// The members of a room, who join with a TTL and leave once they stop sending heartbeats, or
+/// when they leave explicitly.
+///
+/// Members can also join over a [hibernatable WebSocket](Hub) with
+/// [`PresenceClient::connect`]: any message on the socket is a heartbeat, closing it leaves the
+/// room, and the socket receives a [`PresenceEvent`] whenever a member joins or leaves. Expired
+/// members are removed by an alarm, so the object doesn't need to stay awake.
+#[durable_object]
+pub struct DurablePresence {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for DurablePresence {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        match req.path().as_str() {
+            "/list" => Response::from_json(&self.members().await?),
+            "/join" => {
+                let join: Join = req.json().await?;
+                Response::from_json(&self.join(join).await?)
+            }
+            "/heartbeat" => {
+                let Heartbeat { id } = req.json().await?;
+                let present = self.heartbeat(&id).await?;
+                Response::from_json(&Present { present })
+            }
+            "/leave" => {
+                let Leave { id } = req.json().await?;
+                let present = self.leave(&id).await?;
+                Response::from_json(&Present { present })
+            }
+            "/connect" => {
+                let Connect { id, meta, ttl_ms } = req.query()?;
+                let join = Join {
+                    id,
+                    meta: serde_json::from_str(&meta)?,
+                    ttl_ms,
+                };
+                let pair = WebSocketPair::new()?;
+                Hub::new(&self.state).accept(&pair.server, &[&join.id]);
+                self.join(join).await?;
+                Response::from_websocket(pair.client)
+            }
+            _ => Response::error("Not Found", 404),
+        }
+    }
+
+    async fn alarm(&mut self) -> Result<Response> {
+        self.members().await?;
+        Response::empty()
+    }
+
+    async fn websocket_message(
+        &mut self,
+        ws: WebSocket,
+        _message: WebSocketIncomingMessage,
+    ) -> Result<()> {
+        for id in Hub::new(&self.state).tags(&ws) {
+            self.heartbeat(&id).await?;
+        }
+        Ok(())
+    }
+
+    async fn websocket_close(
+        &mut self,
+        ws: WebSocket,
+        _code: usize,
+        _reason: String,
+        _was_clean: bool,
+    ) -> Result<()> {
+        for id in Hub::new(&self.state).tags(&ws) {
+            self.leave(&id).await?;
+        }
+        Ok(())
+    }
+}
+
+impl DurablePresence {
+    fn storage(&self) -> ScopedStorage {
+        self.state.storage().scoped(MEMBER_PREFIX)
+    }
+
+    async fn join(&self, Join { id, meta, ttl_ms }: Join) -> Result<Member> {
+        let member = Member {
+            id,
+            meta,
+            expires_at: Date::now().as_millis().saturating_add(ttl_ms),
+        };
+        let stored = Stored {
+            member: member.clone(),
+            ttl_ms,
+        };
+        self.storage()
+            .put(&member.id, serde_json::to_string(&stored)?)
+            .await?;
+        Hub::new(&self.state).broadcast(&PresenceEvent::Join {
+            member: member.clone(),
+        })?;
+        self.members().await?;
+        Ok(member)
+    }
+
+    /// Renew the TTL of `id`, returning whether it's a member.
+    async fn heartbeat(&self, id: &str) -> Result<bool> {
+        let mut storage = self.storage();
+        let now = Date::now().as_millis();
+        let stored = storage.get::<String>(id).await.ok();
+        let mut stored = match stored.and_then(|json| serde_json::from_str::<Stored>(&json).ok()) {
+            Some(stored) if stored.member.expires_at > now => stored,
+            _ => return Ok(false),
+        };
+        stored.member.expires_at = now.saturating_add(stored.ttl_ms);
+        storage.put(id, serde_json::to_string(&stored)?).await?;
+        self.members().await?;
+        Ok(true)
+    }
+
+    /// Remove `id`, returning whether it was a member.
+    async fn leave(&self, id: &str) -> Result<bool> {
+        let left = self.storage().delete(id).await?;
+        if left {
+            Hub::new(&self.state).broadcast(&PresenceEvent::Leave { id: id.into() })?;
+            self.members().await?;
+        }
+        Ok(left)
+    }
+
+    /// The current members, after removing the expired ones and scheduling the alarm for the
+    /// next one to expire.
+    async fn members(&self) -> Result<Vec<Member>> {
+        let mut storage = self.storage();
+        let now = Date::now().as_millis();
+        let mut members = Vec::new();
+        let mut expired = Vec::new();
+        let mut values = Vec::new();
+        storage
+            .list()
+            .await?
+            .for_each(&mut |value, _| values.push(value));
+        for value in values {
+            let json = value.as_string().unwrap_or_default();
+            let Stored { member, .. } = serde_json::from_str(&json)?;
+            if member.expires_at > now {
+                members.push(member);
+            } else {
+                expired.push(member.id);
+            }
+        }
+
+        let hub = Hub::new(&self.state);
+        for id in expired {
+            storage.delete(&id).await?;
+            hub.broadcast(&PresenceEvent::Leave { id })?;
+        }
+        let storage = self.state.storage();
+        match members.iter().map(|member| member.expires_at).min() {
+            Some(next) => {
+                storage
+                    .set_alarm(Duration::from_millis(next.saturating_sub(now)))
+                    .await?
+            }
+            None => storage.delete_alarm().await?,
+        }
+        Ok(members)
+    }
+}
+
+/// A client of a [`DurablePresence`] room.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use std::time::Duration;
+/// # async fn example(req: Request, env: Env) -> Result<Response> {
+/// let room = primitives::PresenceClient::new(&env.durable_object("PRESENCE")?, "lobby")?;
+/// if WebSocketUpgrade::is_upgrade(&req)? {
+///     // The socket receives an event whenever someone joins or leaves.
+///     return room
+///         .connect("alice", &serde_json::json!({ "name": "Alice" }), Duration::from_secs(30))
+///         .await;
+/// }
+/// Response::from_json(&room.list().await?)
+/// # }
+/// ```
+pub struct PresenceClient {
+    stub: Stub,
+}
+
+impl PresenceClient {
+    /// The client of the room with the given name in `namespace`.
+    pub fn new(namespace: &ObjectNamespace, room: &str) -> Result<Self> {
+        Ok(Self {
+            stub: super::stub(namespace, room)?,
+        })
+    }
+
+    /// Add `id` to the room with `meta` until `ttl` passes without a heartbeat. Joining again
+    /// replaces the metadata and renews the TTL.
+    pub async fn join<M: Serialize>(&self, id: &str, meta: &M, ttl: Duration) -> Result<Member> {
+        let body = Join {
+            id: id.into(),
+            meta: serde_json::to_value(meta)?,
+            ttl_ms: ttl.as_millis() as u64,
+        };
+        super::call(&self.stub, "/join", &body).await
+    }
+
+    /// Renew the TTL of `id`, returning whether it's still in the room.
+    pub async fn heartbeat(&self, id: &str) -> Result<bool> {
+        let body = Heartbeat { id: id.into() };
+        let Present { present } = super::call(&self.stub, "/heartbeat", &body).await?;
+        Ok(present)
+    }
+
+    /// Remove `id` from the room, returning whether it was in it.
+    pub async fn leave(&self, id: &str) -> Result<bool> {
+        let body = Leave { id: id.into() };
+        let Present { present } = super::call(&self.stub, "/leave", &body).await?;
+        Ok(present)
+    }
+
+    /// The members of the room.
+    pub async fn list(&self) -> Result<Vec<Member>> {
+        super::call(&self.stub, "/list", &()).await
+    }
+
+    /// Join the room over a WebSocket, returning the `101` response to hand to the client. The
+    /// member stays in the room while the socket sends a message at least every `ttl`.
+    pub async fn connect<M: Serialize>(
+        &self,
+        id: &str,
+        meta: &M,
+        ttl: Duration,
+    ) -> Result<Response> {
+        let mut url = Url::parse(&internal_url("/connect"))?;
+        url.query_pairs_mut()
+            .append_pair("id", id)
+            .append_pair("meta", &serde_json::to_string(meta)?)
+            .append_pair("ttl_ms", &ttl.as_millis().to_string());
+        let mut headers = Headers::new();
+        headers.set("Upgrade", "websocket")?;
+        let mut init = RequestInit::new();
+        init.with_headers(headers);
+        let req = Request::new_with_init(url.as_str(), &init)?;
+        self.stub.fetch_with_request(req).await
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    durable::{DurableObject, State, Stub},
+    durable_object, Date, Env, ObjectNamespace, Request, Response, Result,
+};
+
+const KEY: &str = "bucket";
+
+#[derive(Serialize, Deserialize)]
+struct Bucket {
+    tokens: f64,
+    updated_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Take {
+    capacity: u32,
+    refill_per_second: f64,
+    tokens: u32,
+}
+
+/// The outcome of [`RateLimiterClient::take`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitDecision {
+    /// Whether the tokens were taken, i.e. the request is allowed.
+    pub allowed: bool,
+    /// The tokens left in the bucket.
+    pub remaining: f64,
+    /// How long to wait until enough tokens are available, 0 if the request is allowed.
+    pub retry_after_ms: u64,
+}
+
+/// A [token bucket](https://en.wikipedia.org/wiki/Token_bucket) which refills continuously. The
+/// capacity and refill rate are sent with every request, so they can be changed without
+/// migrating the object.
+#[durable_object]
+pub struct DurableRateLimiter {
+    state: State,
+}
+
+#[durable_object]
+impl DurableObject for DurableRateLimiter {
+    fn new(state: State, _env: Env) -> Self {
+        Self { state }
+    }
+
+    async fn fetch(&mut self, mut req: Request) -> Result<Response> {
+        if req.path() != "/take" {
+            return Response::error("Not Found", 404);
+        }
+        let take: Take = req.json().await?;
+        let mut storage = self.state.storage();
+        let now = Date::now().as_millis();
+        let capacity = f64::from(take.capacity);
+        let bucket = super::load::<Bucket>(&storage, KEY).await?;
+        let mut tokens = match bucket {
+            Some(bucket) => {
+                let elapsed = now.saturating_sub(bucket.updated_at) as f64 / 1000.0;
+                (bucket.tokens + elapsed * take.refill_per_second).min(capacity)
+            }
+            None => capacity,
+        };
+
+        let cost = f64::from(take.tokens);
+        let allowed = tokens >= cost;
+        let retry_after_ms = if allowed {
+            tokens -= cost;
+            0
+        } else if take.refill_per_second > 0.0 {
+            ((cost - tokens) / take.refill_per_second * 1000.0).ceil() as u64
+        } else {
+            u64::MAX
+        };
+        storage
+            .put(
+                KEY,
+                Bucket {
+                    tokens,
+                    updated_at: now,
+                },
+            )
+            .await?;
+
+        Response::from_json(&RateLimitDecision {
+            allowed,
+            remaining: tokens,
+            retry_after_ms,
+        })
+    }
+}
+
+/// A client of a [`DurableRateLimiter`] with a fixed capacity and refill rate.
+pub struct RateLimiterClient {
+    stub: Stub,
+    capacity: u32,
+    refill_per_second: f64,
+}
+
+impl RateLimiterClient {
+    /// The client of the bucket for `key`, e.g. a client IP, in `namespace`. The bucket holds up
+    /// to `capacity` tokens and gains `refill_per_second` tokens every second.
+    pub fn new(
+        namespace: &ObjectNamespace,
+        key: &str,
+        capacity: u32,
+        refill_per_second: f64,
+    ) -> Result<Self> {
+        Ok(Self {
+            stub: super::stub(namespace, key)?,
+            capacity,
+            refill_per_second,
+        })
+    }
+
+    /// Take `tokens` from the bucket if it has enough of them.
+    pub async fn take(&self, tokens: u32) -> Result<RateLimitDecision> {
+        let body = Take {
+            capacity: self.capacity,
+            refill_per_second: self.refill_per_second,
+            tokens,
+        };
+        super::call(&self.stub, "/take", &body).await
+    }
+}
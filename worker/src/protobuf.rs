@@ -0,0 +1,100 @@
+//! [Protocol Buffers](https://protobuf.dev) bodies for requests, responses and, with the `queue`
+//! feature, queue messages, with the message types generated by [`prost`].
+//!
+//! ```no_run
+//! # use worker::*;
+//! #[derive(Clone, PartialEq, prost::Message)]
+//! struct Order {
+//!     #[prost(string, tag = "1")]
+//!     id: String,
+//!     #[prost(uint32, tag = "2")]
+//!     quantity: u32,
+//! }
+//!
+//! # async fn example(mut req: Request) -> Result<Response> {
+//! let order: Order = req.proto().await?;
+//! Response::from_proto(&order)
+//! # }
+//! ```
+
+use prost::Message as ProtoMessage;
+
+use crate::{Error, Headers, Request, Response, Result};
+#[cfg(feature = "queue")]
+use crate::{Message, MessageBatch, Queue};
+
+/// The media type of protobuf bodies.
+pub const CONTENT_TYPE: &str = "application/x-protobuf";
+
+fn decode<T: ProtoMessage + Default>(bytes: &[u8]) -> Result<T> {
+    T::decode(bytes).map_err(|e| Error::RustError(format!("failed to decode protobuf: {e}")))
+}
+
+impl Request {
+    /// Decode the body of the request as the protobuf message `T`.
+    pub async fn proto<T: ProtoMessage + Default>(&mut self) -> Result<T> {
+        decode(&self.bytes().await?)
+    }
+}
+
+impl Response {
+    /// Create a `Response` with the encoded `message` for the body, and the `Content-Type`
+    /// header set to `application/x-protobuf`.
+    pub fn from_proto<T: ProtoMessage>(message: &T) -> Result<Self> {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", CONTENT_TYPE)?;
+        Ok(Response::from_bytes(message.encode_to_vec())?.with_headers(headers))
+    }
+
+    /// Decode the body of the response as the protobuf message `T`.
+    pub async fn proto<T: ProtoMessage + Default>(&mut self) -> Result<T> {
+        decode(&self.bytes().await?)
+    }
+}
+
+#[cfg(feature = "queue")]
+impl Queue {
+    /// Send the encoded `message` with the `bytes` content type.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # #[derive(Clone, PartialEq, prost::Message)]
+    /// # struct Order {}
+    /// # async fn example(env: Env, order: Order) -> Result<()> {
+    /// env.queue("ORDERS")?.send_proto(&order).await
+    /// # }
+    /// ```
+    pub async fn send_proto<T: ProtoMessage>(&self, message: &T) -> Result<()> {
+        self.send_encoded(message.encode_to_vec()).await
+    }
+}
+
+#[cfg(feature = "queue")]
+impl<T> MessageBatch<T> {
+    /// Iterator for messages whose body is a protobuf message `U`, sent with
+    /// [`Queue::send_proto`]. Messages which aren't `bytes` or can't be decoded are returned as
+    /// errors, so that they can be retried or acknowledged individually.
+    pub fn iter_proto<U: ProtoMessage + Default>(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = Result<Message<U>>> + ExactSizeIterator {
+        self.iter_decoded(decode::<U>)
+    }
+}
+
+#[test]
+fn decode_works() {
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Order {
+        #[prost(string, tag = "1")]
+        id: String,
+        #[prost(uint32, tag = "2")]
+        quantity: u32,
+    }
+
+    let order = Order {
+        id: "42".into(),
+        quantity: 3,
+    };
+    assert_eq!(decode::<Order>(&order.encode_to_vec()).unwrap(), order);
+    assert!(decode::<Order>(&[0xff]).is_err());
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::api::{self, ApiBody};
+use crate::{Error, Method, Result};
+
+/// A client for the [pull consumer](https://developers.cloudflare.com/queues/configuration/pull-consumers/)
+/// HTTP API of a Queue, for consuming a Queue from contexts without a push consumer such as a
+/// scheduled event or a Durable Object alarm.
+///
+/// The API token needs the `queues_write` permission.
+#[derive(Debug, Clone)]
+pub struct PullQueue {
+    account_id: String,
+    queue_id: String,
+    api_token: String,
+}
+
+/// Options for [`PullQueue::pull`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PullOptions {
+    /// Maximum number of messages to pull, at most 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<u32>,
+    /// How long the messages are leased to this consumer before they become visible again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility_timeout_ms: Option<u32>,
+}
+
+/// A message pulled from a Queue. The message must be acknowledged (or retried) with its
+/// [`lease_id`](PulledMessage::lease_id) before the visibility timeout expires.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PulledMessage {
+    pub id: String,
+    pub lease_id: String,
+    /// The message body. Messages sent with the `json` content type contain the JSON encoded
+    /// value, `bytes` and `v8` messages are base64 encoded.
+    pub body: String,
+    pub timestamp_ms: u64,
+    pub attempts: u32,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl PulledMessage {
+    /// The content type the message was sent with (`text`, `json`, `bytes` or `v8`).
+    pub fn content_type(&self) -> Option<&str> {
+        self.metadata.get("CF-Content-Type").map(String::as_str)
+    }
+
+    /// Deserialize a message sent with the `json` content type.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_str(&self.body).map_err(Error::from)
+    }
+}
+
+/// A batch of acknowledgements and retries, sent with [`PullQueue::acknowledge`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AckBatch {
+    acks: Vec<LeaseAck>,
+    retries: Vec<LeaseRetry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LeaseAck {
+    lease_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LeaseRetry {
+    lease_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delay_seconds: Option<u32>,
+}
+
+impl AckBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Acknowledge the message with the given lease, removing it from the Queue.
+    #[must_use]
+    pub fn ack(mut self, lease_id: impl Into<String>) -> Self {
+        self.acks.push(LeaseAck {
+            lease_id: lease_id.into(),
+        });
+        self
+    }
+
+    /// Mark the message with the given lease to be retried, optionally after a delay.
+    #[must_use]
+    pub fn retry(mut self, lease_id: impl Into<String>, delay_seconds: Option<u32>) -> Self {
+        self.retries.push(LeaseRetry {
+            lease_id: lease_id.into(),
+            delay_seconds,
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.acks.is_empty() && self.retries.is_empty()
+    }
+}
+
+/// The outcome of [`PullQueue::acknowledge`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AckResult {
+    pub ack_count: u32,
+    pub retry_count: u32,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PullResult {
+    #[serde(default)]
+    messages: Vec<PulledMessage>,
+}
+
+impl PullQueue {
+    /// Create a client for the Queue with the given id.
+    pub fn new(
+        account_id: impl Into<String>,
+        queue_id: impl Into<String>,
+        api_token: impl Into<String>,
+    ) -> Self {
+        Self {
+            account_id: account_id.into(),
+            queue_id: queue_id.into(),
+            api_token: api_token.into(),
+        }
+    }
+
+    /// Pull a batch of messages, leasing them to this consumer.
+    pub async fn pull(&self, options: &PullOptions) -> Result<Vec<PulledMessage>> {
+        let result: PullResult = self.post("messages/pull", options).await?;
+        Ok(result.messages)
+    }
+
+    /// Acknowledge the given leases.
+    pub async fn ack<S: AsRef<str>>(&self, lease_ids: &[S]) -> Result<AckResult> {
+        let batch = lease_ids
+            .iter()
+            .fold(AckBatch::new(), |batch, id| batch.ack(id.as_ref()));
+        self.acknowledge(&batch).await
+    }
+
+    /// Retry the given leases, optionally after a delay.
+    pub async fn retry<S: AsRef<str>>(
+        &self,
+        lease_ids: &[S],
+        delay_seconds: Option<u32>,
+    ) -> Result<AckResult> {
+        let batch = lease_ids.iter().fold(AckBatch::new(), |batch, id| {
+            batch.retry(id.as_ref(), delay_seconds)
+        });
+        self.acknowledge(&batch).await
+    }
+
+    /// Send a batch of acknowledgements and retries in a single request.
+    pub async fn acknowledge(&self, batch: &AckBatch) -> Result<AckResult> {
+        if batch.is_empty() {
+            return Ok(AckResult::default());
+        }
+        self.post("messages/ack", batch).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        let path = format!(
+            "accounts/{}/queues/{}/{path}",
+            self.account_id, self.queue_id
+        );
+        let body = ApiBody::Json(serde_json::to_string(body)?);
+        api::request("Queues", &self.api_token, Method::Post, &path, Some(body)).await
+    }
+}
@@ -1,9 +1,15 @@
 use std::{
+    cell::RefCell,
+    collections::HashSet,
     convert::{TryFrom, TryInto},
+    future::Future,
     marker::PhantomData,
+    rc::Rc,
+    time::Duration,
 };
 
-use crate::{env::EnvBinding, Date, Error, Result};
+use crate::{env::EnvBinding, Date, Error, Result, SerializerOptions};
+use futures_util::{stream, StreamExt};
 use js_sys::Array;
 use serde::{de::DeserializeOwned, Serialize};
 use wasm_bindgen::{prelude::*, JsCast};
@@ -13,9 +19,26 @@ use worker_sys::{Message as MessageSys, MessageBatch as MessageBatchSys, Queue a
 /// A batch of messages that are sent to a consumer Worker.
 pub struct MessageBatch<T> {
     inner: MessageBatchSys,
+    received_at: u64,
+    deadline: Option<Duration>,
+    settled: Settled,
     phantom: PhantomData<T>,
 }
 
+/// The ids of the messages of a batch which were acknowledged or marked to be retried.
+#[derive(Debug, Clone, Default)]
+struct Settled(Rc<RefCell<HashSet<String>>>);
+
+impl Settled {
+    fn insert(&self, id: String) {
+        self.0.borrow_mut().insert(id);
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.0.borrow().contains(id)
+    }
+}
+
 impl<T> MessageBatch<T> {
     /// The name of the Queue that belongs to this batch.
     pub fn queue(&self) -> String {
@@ -24,11 +47,13 @@ impl<T> MessageBatch<T> {
 
     /// Marks every message to be retried in the next batch.
     pub fn retry_all(&self) {
+        self.settle_all();
         self.inner.retry_all(JsValue::null());
     }
 
     /// Marks every message to be retried in the next batch with options.
     pub fn retry_all_with_options(&self, queue_retry_options: &QueueRetryOptions) {
+        self.settle_all();
         self.inner
             // SAFETY: QueueRetryOptions is controlled by this module and all data in it is serializable to a js value.
             .retry_all(serde_wasm_bindgen::to_value(&queue_retry_options).unwrap());
@@ -36,17 +61,149 @@ impl<T> MessageBatch<T> {
 
     /// Marks every message acknowledged in the batch.
     pub fn ack_all(&self) {
+        self.settle_all();
         self.inner.ack_all();
     }
 
+    /// Marks every message which wasn't acknowledged or marked to be retried yet to be retried in
+    /// the next batch, returning how many there were. Used to stop processing a batch near the
+    /// [deadline](MessageBatch::with_deadline) without losing the rest of its messages.
+    pub fn retry_remaining(&self) -> usize {
+        self.retry_remaining_with(JsValue::null())
+    }
+
+    /// Like [`retry_remaining`](MessageBatch::retry_remaining), with options.
+    pub fn retry_remaining_with_options(&self, queue_retry_options: &QueueRetryOptions) -> usize {
+        // SAFETY: QueueRetryOptions is controlled by this module and all data in it is serializable to a js value.
+        self.retry_remaining_with(serde_wasm_bindgen::to_value(&queue_retry_options).unwrap())
+    }
+
+    fn retry_remaining_with(&self, options: JsValue) -> usize {
+        let mut retried = 0;
+        for raw in self.raw_iter() {
+            if !self.settled.contains(&String::from(raw.inner.id())) {
+                raw.inner.retry(options.clone());
+                retried += 1;
+            }
+        }
+        self.settle_all();
+        retried
+    }
+
+    fn settle_all(&self) {
+        for raw in self.raw_iter() {
+            self.settled.insert(raw.inner.id().into());
+        }
+    }
+
+    /// Give the consumer a soft deadline of `budget` from when the batch was received, after
+    /// which [`deadline_reached`](MessageBatch::deadline_reached) is `true`.
+    ///
+    /// The runtime doesn't tell the Worker how much of its CPU or wall-clock time is left, so the
+    /// budget should be comfortably below the consumer's limits. The clock of the Workers runtime
+    /// only advances on I/O, so the deadline is checked against the time of the last I/O.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # use std::time::Duration;
+    /// # async fn process(_: &Message<String>) -> Result<()> { Ok(()) }
+    /// #[event(queue)]
+    /// pub async fn main(batch: MessageBatch<String>, _env: Env, _ctx: Context) -> Result<()> {
+    ///     let batch = batch.with_deadline(Duration::from_secs(20));
+    ///     for message in batch.iter() {
+    ///         if batch.deadline_reached() {
+    ///             break;
+    ///         }
+    ///         let message = message?;
+    ///         process(&message).await?;
+    ///         message.ack();
+    ///     }
+    ///     batch.retry_remaining();
+    ///     Ok(())
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_deadline(mut self, budget: Duration) -> Self {
+        self.deadline = Some(budget);
+        self
+    }
+
+    /// The time since the batch was received.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(Date::now().as_millis().saturating_sub(self.received_at))
+    }
+
+    /// The time left until the [deadline](MessageBatch::with_deadline), if there is one.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_sub(self.elapsed()))
+    }
+
+    /// Whether the [deadline](MessageBatch::with_deadline) passed, `false` without one.
+    pub fn deadline_reached(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
     /// Iterator for raw messages in the message batch. Ordering of messages is not guaranteed.
     pub fn raw_iter(&self) -> RawMessageIter {
         let messages = self.inner.messages();
         RawMessageIter {
             range: 0..messages.length(),
             array: messages,
+            settled: self.settled.clone(),
         }
     }
+
+    /// The messages in the batch with their bodies as sent, without deserializing them, e.g. to
+    /// [forward](Queue::forward) them. Ordering of messages is not guaranteed.
+    pub fn raw_messages(&self) -> Vec<RawMessage> {
+        self.raw_iter().collect()
+    }
+
+    /// Iterator for messages which were sent with the `text` content type. Messages with any other
+    /// body are returned as errors, so that they can be retried or acknowledged individually.
+    pub fn iter_text(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = Result<Message<String>>> + ExactSizeIterator {
+        self.raw_iter().map(|raw| {
+            let body = raw.text().ok_or_else(|| unexpected_body(&raw, "text"))?;
+            Ok(raw.with_body(body))
+        })
+    }
+
+    /// Iterator for messages which were sent with the `bytes` content type. Messages with any
+    /// other body are returned as errors, so that they can be retried or acknowledged
+    /// individually.
+    pub fn iter_bytes(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = Result<Message<Vec<u8>>>> + ExactSizeIterator {
+        self.raw_iter().map(|raw| {
+            let body = raw.bytes().ok_or_else(|| unexpected_body(&raw, "bytes"))?;
+            Ok(raw.with_body(body))
+        })
+    }
+
+    /// Iterator for messages sent with the `bytes` content type whose bodies are decoded with
+    /// `decode`, for binary formats such as MessagePack or protobuf.
+    #[cfg(any(feature = "msgpack", feature = "protobuf"))]
+    pub(crate) fn iter_decoded<U>(
+        &self,
+        decode: fn(&[u8]) -> Result<U>,
+    ) -> impl DoubleEndedIterator<Item = Result<Message<U>>> + ExactSizeIterator {
+        self.raw_iter().map(move |raw| {
+            let bytes = raw.bytes().ok_or_else(|| unexpected_body(&raw, "bytes"))?;
+            let body = decode(&bytes)?;
+            Ok(raw.with_body(body))
+        })
+    }
+}
+
+fn unexpected_body(raw: &RawMessage, expected: &str) -> Error {
+    Error::RustError(format!(
+        "expected the body of message {} to be {expected}, but it is {}",
+        String::from(raw.inner.id()),
+        raw.body_kind()
+    ))
 }
 
 impl<T: DeserializeOwned> MessageBatch<T> {
@@ -61,15 +218,94 @@ impl<T: DeserializeOwned> MessageBatch<T> {
         MessageIter {
             range: 0..messages.length(),
             array: messages,
+            settled: self.settled.clone(),
             marker: PhantomData,
         }
     }
+
+    /// Run `handler` for the messages of the batch, at most `max` at a time, acknowledging the
+    /// messages it succeeds for and retrying those it fails for or whose body can't be
+    /// deserialized, each individually. Messages the handler acknowledged or retried itself are
+    /// left as they are. Once the [deadline](MessageBatch::with_deadline) is reached, the
+    /// messages which weren't started yet are retried without running the handler.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn process(_: &str) -> Result<()> { Ok(()) }
+    /// #[event(queue)]
+    /// pub async fn main(batch: MessageBatch<String>, _env: Env, _ctx: Context) -> Result<()> {
+    ///     let report = batch
+    ///         .process_concurrently(8, |message| async move { process(message.body()).await })
+    ///         .await;
+    ///     for (id, error) in &report.failures {
+    ///         console_error!("message {id} failed: {error}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn process_concurrently<F, Fut>(&self, max: usize, handler: F) -> BatchReport
+    where
+        F: Fn(Message<T>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let report = RefCell::new(BatchReport::default());
+        let (handler, report_ref) = (&handler, &report);
+        stream::iter(self.raw_iter())
+            .for_each_concurrent(max.max(1), |raw| async move {
+                if self.deadline_reached() {
+                    return;
+                }
+                let handle = RawMessage {
+                    inner: raw.inner.clone(),
+                    settled: raw.settled.clone(),
+                };
+                let result = match Message::try_from(raw) {
+                    Ok(message) => handler(message).await,
+                    Err(e) => Err(e),
+                };
+                let id = handle.id();
+                if handle.settled.contains(&id) {
+                    return;
+                }
+                let mut report = report_ref.borrow_mut();
+                match result {
+                    Ok(()) => {
+                        handle.ack();
+                        report.acked += 1;
+                    }
+                    Err(e) => {
+                        handle.retry();
+                        report.retried += 1;
+                        report.failures.push((id, e));
+                    }
+                }
+            })
+            .await;
+        let mut report = report.into_inner();
+        report.retried += self.retry_remaining();
+        report
+    }
+}
+
+/// What [`MessageBatch::process_concurrently`] did with the messages of a batch.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// The number of messages which were processed successfully and acknowledged.
+    pub acked: usize,
+    /// The number of messages which failed or weren't processed before the deadline, and were
+    /// marked to be retried.
+    pub retried: usize,
+    /// The ids of the messages which failed, with their errors.
+    pub failures: Vec<(String, Error)>,
 }
 
 impl<T> From<MessageBatchSys> for MessageBatch<T> {
     fn from(value: MessageBatchSys) -> Self {
         Self {
             inner: value,
+            received_at: Date::now().as_millis(),
+            deadline: None,
+            settled: Settled::default(),
             phantom: PhantomData,
         }
     }
@@ -79,6 +315,7 @@ impl<T> From<MessageBatchSys> for MessageBatch<T> {
 pub struct Message<T> {
     inner: MessageSys,
     body: T,
+    settled: Settled,
 }
 
 impl<T> Message<T> {
@@ -105,10 +342,30 @@ where
     type Error = Error;
 
     fn try_from(value: RawMessage) -> std::result::Result<Self, Self::Error> {
-        let body = serde_wasm_bindgen::from_value(value.body())?;
+        #[cfg(feature = "queue-compression")]
+        if let Some(json) = crate::queue_compression::decompress_body(&value.body()) {
+            return Ok(Self {
+                body: serde_json::from_slice(&json?)?,
+                inner: value.inner,
+                settled: value.settled,
+            });
+        }
+        let body = serde_wasm_bindgen::from_value(value.body()).map_err(|e| {
+            // Most likely the message wasn't sent as JSON, so point to the right iterator.
+            let hint = match value.body_kind() {
+                MessageBodyKind::Text => ", use `MessageBatch::iter_text` for `text` messages",
+                MessageBodyKind::Bytes => ", use `MessageBatch::iter_bytes` for `bytes` messages",
+                MessageBodyKind::Structured => return Error::from(e),
+            };
+            Error::RustError(format!(
+                "failed to deserialize the body of message {}: {e}{hint}",
+                String::from(value.inner.id())
+            ))
+        })?;
         Ok(Self {
             inner: value.inner,
             body,
+            settled: value.settled,
         })
     }
 }
@@ -116,6 +373,7 @@ where
 /// A message that is sent to a consumer Worker.
 pub struct RawMessage {
     inner: MessageSys,
+    settled: Settled,
 }
 
 impl RawMessage {
@@ -123,28 +381,125 @@ impl RawMessage {
     pub fn body(&self) -> JsValue {
         self.inner.body()
     }
+
+    /// The kind of value the body of the message is, which depends on the content type it was
+    /// sent with.
+    pub fn body_kind(&self) -> MessageBodyKind {
+        let body = self.body();
+        if body.is_string() {
+            MessageBodyKind::Text
+        } else if body.is_instance_of::<js_sys::ArrayBuffer>()
+            || js_sys::ArrayBuffer::is_view(&body)
+        {
+            MessageBodyKind::Bytes
+        } else {
+            MessageBodyKind::Structured
+        }
+    }
+
+    /// The body of the message if it's a string, e.g. when it was sent with the `text` content
+    /// type.
+    pub fn text(&self) -> Option<String> {
+        self.body().as_string()
+    }
+
+    /// The body of the message if it's binary, i.e. when it was sent with the `bytes` content type.
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        match self.body_kind() {
+            MessageBodyKind::Bytes => Some(js_sys::Uint8Array::new(&self.body()).to_vec()),
+            _ => None,
+        }
+    }
+
+    /// The body of the message as JSON, whatever its schema. Binary bodies are arrays of bytes,
+    /// and structured values which JSON can't represent, e.g. `Map`s, fail to convert.
+    pub fn json(&self) -> Result<serde_json::Value> {
+        match self.bytes() {
+            Some(bytes) => Ok(bytes.into()),
+            None => Ok(serde_wasm_bindgen::from_value(self.body())?),
+        }
+    }
+
+    /// The content type to send the body with to keep it as is: `text` for strings, `bytes` for
+    /// binary bodies and `v8` for structured values, which also covers values sent as `json`.
+    pub fn content_type(&self) -> QueueContentType {
+        match self.body_kind() {
+            MessageBodyKind::Text => QueueContentType::Text,
+            MessageBodyKind::Bytes => QueueContentType::Bytes,
+            MessageBodyKind::Structured => QueueContentType::V8,
+        }
+    }
+
+    /// A message to send the body of this message as is, e.g. to another queue.
+    pub fn to_send_message(&self) -> SendMessage<JsValue> {
+        RawMessageBuilder::new(self.body()).build_with_content_type(self.content_type())
+    }
+
+    /// The message with its body deserialized as `body`.
+    pub(crate) fn with_body<T>(self, body: T) -> Message<T> {
+        Message {
+            inner: self.inner,
+            body,
+            settled: self.settled,
+        }
+    }
+}
+
+/// The kind of value the body of a [`RawMessage`] is.
+///
+/// The content type a message was sent with isn't delivered to the consumer, so it can only be
+/// inferred from the body: `text` messages are strings, `bytes` messages are binary, and `json`
+/// and `v8` messages are structured values. A string or number sent as `json` is indistinguishable
+/// from `text` though.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageBodyKind {
+    Text,
+    Bytes,
+    Structured,
+}
+
+impl std::fmt::Display for MessageBodyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Bytes => write!(f, "bytes"),
+            Self::Structured => write!(f, "a structured value"),
+        }
+    }
 }
 
 impl From<MessageSys> for RawMessage {
     fn from(value: MessageSys) -> Self {
-        Self { inner: value }
+        Self {
+            inner: value,
+            settled: Settled::default(),
+        }
     }
 }
 
 trait MessageSysInner {
     fn inner(&self) -> &MessageSys;
+    fn settled(&self) -> &Settled;
 }
 
 impl MessageSysInner for RawMessage {
     fn inner(&self) -> &MessageSys {
         &self.inner
     }
+
+    fn settled(&self) -> &Settled {
+        &self.settled
+    }
 }
 
 impl<T> MessageSysInner for Message<T> {
     fn inner(&self) -> &MessageSys {
         &self.inner
     }
+
+    fn settled(&self) -> &Settled {
+        &self.settled
+    }
 }
 
 #[derive(Serialize)]
@@ -211,11 +566,13 @@ impl<T: MessageSysInner> MessageExt for T {
 
     /// Marks message to be retried.
     fn retry(&self) {
+        self.settled().insert(self.id());
         self.inner().retry(JsValue::null());
     }
 
     /// Marks message to be retried with options.
     fn retry_with_options(&self, queue_retry_options: &QueueRetryOptions) {
+        self.settled().insert(self.id());
         self.inner()
             // SAFETY: QueueRetryOptions is controlled by this module and all data in it is serializable to a js value.
             .retry(serde_wasm_bindgen::to_value(&queue_retry_options).unwrap());
@@ -223,6 +580,7 @@ impl<T: MessageSysInner> MessageExt for T {
 
     /// Marks message acknowledged.
     fn ack(&self) {
+        self.settled().insert(self.id());
         self.inner().ack();
     }
 }
@@ -230,6 +588,7 @@ impl<T: MessageSysInner> MessageExt for T {
 pub struct MessageIter<T> {
     range: std::ops::Range<u32>,
     array: Array,
+    settled: Settled,
     marker: PhantomData<T>,
 }
 
@@ -242,7 +601,10 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         let index = self.range.next()?;
         let value = self.array.get(index);
-        let raw_message = RawMessage::from(MessageSys::from(value));
+        let raw_message = RawMessage {
+            inner: MessageSys::from(value),
+            settled: self.settled.clone(),
+        };
         Some(raw_message.try_into())
     }
 
@@ -259,7 +621,10 @@ where
     fn next_back(&mut self) -> Option<Self::Item> {
         let index = self.range.next_back()?;
         let value = self.array.get(index);
-        let raw_message = RawMessage::from(MessageSys::from(value));
+        let raw_message = RawMessage {
+            inner: MessageSys::from(value),
+            settled: self.settled.clone(),
+        };
         Some(raw_message.try_into())
     }
 }
@@ -271,6 +636,7 @@ impl<T> std::iter::ExactSizeIterator for MessageIter<T> where T: DeserializeOwne
 pub struct RawMessageIter {
     range: std::ops::Range<u32>,
     array: Array,
+    settled: Settled,
 }
 
 impl std::iter::Iterator for RawMessageIter {
@@ -279,7 +645,10 @@ impl std::iter::Iterator for RawMessageIter {
     fn next(&mut self) -> Option<Self::Item> {
         let index = self.range.next()?;
         let value = self.array.get(index);
-        Some(MessageSys::from(value).into())
+        Some(RawMessage {
+            inner: MessageSys::from(value),
+            settled: self.settled.clone(),
+        })
     }
 
     #[inline]
@@ -292,7 +661,10 @@ impl std::iter::DoubleEndedIterator for RawMessageIter {
     fn next_back(&mut self) -> Option<Self::Item> {
         let index = self.range.next_back()?;
         let value = self.array.get(index);
-        Some(MessageSys::from(value).into())
+        Some(RawMessage {
+            inner: MessageSys::from(value),
+            settled: self.settled.clone(),
+        })
     }
 }
 
@@ -336,7 +708,107 @@ impl AsRef<JsValue> for Queue {
     }
 }
 
+/// The maximum size of the body of a message, 128 KB.
+pub const MAX_QUEUE_MESSAGE_SIZE: usize = 128 * 1024;
+/// The maximum total size of the bodies of the messages of a batch, 256 KB.
+pub const MAX_QUEUE_BATCH_SIZE: usize = 256 * 1024;
+/// The maximum number of messages of a batch.
+pub const MAX_QUEUE_BATCH_MESSAGES: usize = 100;
+
+/// Describes a call to [`Queue::send`] or [`Queue::send_batch`] (and their raw variants), passed
+/// to the hook set with [`set_queue_send_hook`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueSendEvent {
+    /// The number of messages sent, 1 for [`Queue::send`].
+    pub messages: usize,
+    /// The estimated size of the bodies of the messages in bytes.
+    pub bytes: usize,
+    /// How long the runtime took to accept the messages.
+    pub latency: Duration,
+    /// Whether the runtime accepted the messages.
+    pub success: bool,
+}
+
+type SendHook = Rc<dyn Fn(&QueueSendEvent)>;
+
+thread_local! {
+    static SEND_HOOK: RefCell<Option<SendHook>> = RefCell::new(None);
+}
+
+/// Invoke `hook` after every send to any queue, e.g. to record the number and size of the messages
+/// produced by the Worker. Replaces the previously set hook.
+///
+/// Sends which are rejected before reaching the runtime, because a message is larger than
+/// [`MAX_QUEUE_MESSAGE_SIZE`] or a batch is too large, don't invoke the hook.
+///
+/// ```no_run
+/// # use worker::*;
+/// set_queue_send_hook(|event| {
+///     console_log!(
+///         "sent {} messages ({} bytes) in {:?}",
+///         event.messages,
+///         event.bytes,
+///         event.latency
+///     );
+/// });
+/// ```
+pub fn set_queue_send_hook(hook: impl Fn(&QueueSendEvent) + 'static) {
+    SEND_HOOK.with(|h| *h.borrow_mut() = Some(Rc::new(hook)));
+}
+
+/// Remove the hook set with [`set_queue_send_hook`].
+pub fn clear_queue_send_hook() {
+    SEND_HOOK.with(|h| *h.borrow_mut() = None);
+}
+
+/// Check the limits of the runtime before sending, so that oversized messages fail with a
+/// descriptive error rather than a JS exception. Returns the total size of the bodies.
+fn check_limits<'a>(bodies: impl ExactSizeIterator<Item = &'a JsValue>) -> Result<usize> {
+    let count = bodies.len();
+    if count > MAX_QUEUE_BATCH_MESSAGES {
+        return Err(Error::RustError(format!(
+            "batch of {count} messages exceeds the limit of {MAX_QUEUE_BATCH_MESSAGES} messages"
+        )));
+    }
+    let mut total = 0;
+    for (i, body) in bodies.enumerate() {
+        let size = crate::serializer::value_size(body);
+        if size > MAX_QUEUE_MESSAGE_SIZE {
+            return Err(Error::RustError(format!(
+                "message {i} is {size} bytes, which exceeds the limit of {MAX_QUEUE_MESSAGE_SIZE} bytes"
+            )));
+        }
+        total += size;
+    }
+    if count > 1 && total > MAX_QUEUE_BATCH_SIZE {
+        return Err(Error::RustError(format!(
+            "batch of {count} messages is {total} bytes, which exceeds the limit of {MAX_QUEUE_BATCH_SIZE} bytes"
+        )));
+    }
+    Ok(total)
+}
+
+/// Await `fut`, reporting the send to the hook.
+async fn send_with_hook(fut: JsFuture, messages: usize, bytes: usize) -> Result<()> {
+    let start = Date::now().as_millis();
+    let result = fut.await;
+    let hook = SEND_HOOK.with(|h| h.borrow().clone());
+    if let Some(hook) = hook {
+        hook(&QueueSendEvent {
+            messages,
+            bytes,
+            latency: Duration::from_millis(Date::now().as_millis().saturating_sub(start)),
+            success: result.is_ok(),
+        });
+    }
+    result.map_err(Error::from)?;
+    Ok(())
+}
+
+/// How the body of a message is encoded. More content types may be added, so matches need a
+/// wildcard arm.
 #[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
 pub enum QueueContentType {
     /// Send a JavaScript object that can be JSON-serialized. This content type can be previewed from the Cloudflare dashboard.
     Json,
@@ -344,6 +816,8 @@ pub enum QueueContentType {
     Text,
     /// Send a JavaScript object that cannot be JSON-serialized but is supported by structured clone (for example Date and Map). This content type cannot be previewed from the Cloudflare dashboard and will display as Base64-encoded.
     V8,
+    /// Send an `ArrayBuffer` or typed array as binary data.
+    Bytes,
 }
 
 impl Serialize for QueueContentType {
@@ -355,6 +829,7 @@ impl Serialize for QueueContentType {
             Self::Json => "json",
             Self::Text => "text",
             Self::V8 => "v8",
+            Self::Bytes => "bytes",
         })
     }
 }
@@ -460,12 +935,44 @@ pub struct SendMessage<T> {
 impl<T: Serialize> SendMessage<T> {
     fn into_raw_send_message(self) -> Result<SendMessage<JsValue>> {
         Ok(SendMessage {
-            message: serde_wasm_bindgen::to_value(&self.message)?,
+            message: crate::serializer::to_value(&self.message)?,
+            options: self.options,
+        })
+    }
+
+    fn into_raw_send_message_with(
+        self,
+        serializer: &SerializerOptions,
+    ) -> Result<SendMessage<JsValue>> {
+        Ok(SendMessage {
+            message: serializer.to_value(&self.message)?,
             options: self.options,
         })
     }
 }
 
+#[cfg(feature = "queue-compression")]
+impl<T: Serialize> SendMessage<T> {
+    /// The message with its body compressed as JSON, unless it's too small to be worth it.
+    fn into_compressed_send_message(
+        self,
+        compression: &crate::Compression,
+    ) -> Result<SendMessage<JsValue>> {
+        let json = serde_json::to_vec(&self.message)?;
+        let body = match compression.compress(&json)? {
+            Some(body) => body,
+            None => return self.into_raw_send_message(),
+        };
+        Ok(SendMessage {
+            message: js_sys::Uint8Array::from(body.as_slice()).into(),
+            options: Some(QueueSendOptions {
+                content_type: Some(QueueContentType::Bytes),
+                delay_seconds: self.options.and_then(|options| options.delay_seconds),
+            }),
+        })
+    }
+}
+
 impl<T: Serialize> From<T> for SendMessage<T> {
     fn from(message: T) -> Self {
         Self {
@@ -556,11 +1063,18 @@ where
 
 impl<T: Serialize> BatchSendMessage<T> {
     fn into_raw_batch_send_message(self) -> Result<BatchSendMessage<JsValue>> {
+        self.into_raw_batch_send_message_with(&crate::serializer_options())
+    }
+
+    fn into_raw_batch_send_message_with(
+        self,
+        serializer: &SerializerOptions,
+    ) -> Result<BatchSendMessage<JsValue>> {
         Ok(BatchSendMessage {
             body: self
                 .body
                 .into_iter()
-                .map(SendMessage::into_raw_send_message)
+                .map(|message| message.into_raw_send_message_with(serializer))
                 .collect::<Result<_>>()?,
             options: self.options,
         })
@@ -592,9 +1106,60 @@ impl Queue {
         self.send_raw(serialized_message).await
     }
 
+    /// Sends a message to the Queue, with its body converted into JS with `serializer` rather
+    /// than the [options of the Worker](crate::set_serializer_options).
+    pub async fn send_with_serializer<T, U: Into<SendMessage<T>>>(
+        &self,
+        message: U,
+        serializer: &SerializerOptions,
+    ) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let message: SendMessage<T> = message.into();
+        let serialized_message = message.into_raw_send_message_with(serializer)?;
+        self.send_raw(serialized_message).await
+    }
+
+    /// Sends a message to the Queue with its body compressed, see [`Compression`](crate::Compression).
+    /// [`send`](Queue::send) never compresses, compression is opt-in.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn example(queue: Queue, report: serde_json::Value) -> Result<()> {
+    /// queue.send_compressed(&report, Compression::gzip()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "queue-compression")]
+    pub async fn send_compressed<T, U: Into<SendMessage<T>>>(
+        &self,
+        message: U,
+        compression: crate::Compression,
+    ) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let message: SendMessage<T> = message.into();
+        let compressed = message.into_compressed_send_message(&compression)?;
+        self.send_raw(compressed).await
+    }
+
+    /// Sends the encoded `body` with the `bytes` content type, for binary formats such as
+    /// MessagePack or protobuf.
+    #[cfg(any(feature = "msgpack", feature = "protobuf"))]
+    pub(crate) async fn send_encoded(&self, body: Vec<u8>) -> Result<()> {
+        let body = js_sys::Uint8Array::from(body.as_slice());
+        let message =
+            RawMessageBuilder::new(body.into()).build_with_content_type(QueueContentType::Bytes);
+        self.send_raw(message).await
+    }
+
     /// Sends a raw `JsValue` to the Queue.
     ///
     /// Use the `RawMessageBuilder` to create the message.
+    ///
+    /// Fails without sending if the body is larger than [`MAX_QUEUE_MESSAGE_SIZE`].
     pub async fn send_raw<T: Into<SendMessage<JsValue>>>(&self, message: T) -> Result<()> {
         let message: SendMessage<JsValue> = message.into();
         let options = match message.options {
@@ -602,9 +1167,35 @@ impl Queue {
             None => JsValue::null(),
         };
 
+        let bytes = check_limits(std::iter::once(&message.message))?;
         let fut: JsFuture = self.0.send(message.message, options).into();
-        fut.await.map_err(Error::from)?;
-        Ok(())
+        send_with_hook(fut, 1, bytes).await
+    }
+
+    /// Sends the body of `message`, received from another queue, as is.
+    pub async fn forward(&self, message: &RawMessage) -> Result<()> {
+        self.send_raw(message.to_send_message()).await
+    }
+
+    /// Sends the bodies of `messages`, received from another queue, as is, e.g. to relay a
+    /// [batch](MessageBatch::raw_messages) without knowing the schema of its messages.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// #[event(queue)]
+    /// async fn main(batch: MessageBatch<serde_json::Value>, env: Env, _ctx: Context) -> Result<()> {
+    ///     env.queue("ARCHIVE")?.forward_batch(&batch.raw_messages()).await
+    /// }
+    /// ```
+    pub async fn forward_batch(&self, messages: &[RawMessage]) -> Result<()> {
+        let messages: Vec<_> = messages.iter().map(RawMessage::to_send_message).collect();
+        self.send_raw_batch(messages).await
+    }
+
+    /// Hands `message` to the runtime without waiting for it to be accepted, for contexts which
+    /// can't await the send such as a panic hook.
+    pub(crate) fn send_detached(&self, message: JsValue) {
+        let _ = self.0.send(message, JsValue::null());
     }
 
     /// Sends a batch of messages to the Queue.
@@ -631,17 +1222,56 @@ impl Queue {
         self.send_raw_batch(serialized_messages).await
     }
 
+    /// Sends a batch of messages to the Queue, with their bodies converted into JS with
+    /// `serializer` rather than the [options of the Worker](crate::set_serializer_options).
+    pub async fn send_batch_with_serializer<T: Serialize, U: Into<BatchSendMessage<T>>>(
+        &self,
+        messages: U,
+        serializer: &SerializerOptions,
+    ) -> Result<()> {
+        let messages: BatchSendMessage<T> = messages.into();
+        let serialized_messages = messages.into_raw_batch_send_message_with(serializer)?;
+        self.send_raw_batch(serialized_messages).await
+    }
+
+    /// Sends a batch of messages to the Queue with their bodies compressed, see
+    /// [`Compression`](crate::Compression). The limits of the batch apply to the compressed
+    /// bodies.
+    #[cfg(feature = "queue-compression")]
+    pub async fn send_batch_compressed<T: Serialize, U: Into<BatchSendMessage<T>>>(
+        &self,
+        messages: U,
+        compression: crate::Compression,
+    ) -> Result<()> {
+        let messages: BatchSendMessage<T> = messages.into();
+        let body = messages
+            .body
+            .into_iter()
+            .map(|message| message.into_compressed_send_message(&compression))
+            .collect::<Result<_>>()?;
+        self.send_raw_batch(BatchSendMessage {
+            body,
+            options: messages.options,
+        })
+        .await
+    }
+
     /// Sends a batch of raw messages to the Queue.
     ///
     /// Accepts an iterator that produces structs that are `serializable`.
     ///
     /// If message options are needed use the `BatchMessageBuilder` to create the batch.
+    ///
+    /// Fails without sending if a body is larger than [`MAX_QUEUE_MESSAGE_SIZE`], or the batch
+    /// exceeds [`MAX_QUEUE_BATCH_MESSAGES`] or [`MAX_QUEUE_BATCH_SIZE`].
     pub async fn send_raw_batch<T: Into<BatchSendMessage<JsValue>>>(
         &self,
         messages: T,
     ) -> Result<()> {
         let messages: BatchSendMessage<JsValue> = messages.into();
         let batch_send_options = serde_wasm_bindgen::to_value(&messages.options)?;
+        let count = messages.body.len();
+        let bytes = check_limits(messages.body.iter().map(|message| &message.message))?;
 
         let messages = messages
             .body
@@ -671,7 +1301,6 @@ impl Queue {
             .collect::<Result<js_sys::Array>>()?;
 
         let fut: JsFuture = self.0.send_batch(messages, batch_send_options).into();
-        fut.await.map_err(Error::from)?;
-        Ok(())
+        send_with_hook(fut, count, bytes).await
     }
 }
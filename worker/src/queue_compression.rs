@@ -0,0 +1,106 @@
+use std::io::{Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder};
+use js_sys::Uint8Array;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{Error, Result};
+
+/// The first byte of a compressed body, telling the codec it was compressed with.
+const GZIP: u8 = 0x01;
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Compression of the JSON bodies of queue messages, to stay under the
+/// [message size limit](crate::MAX_QUEUE_MESSAGE_SIZE) with large messages.
+///
+/// Compression is opt-in: [`Queue::send`](crate::Queue::send) and
+/// [`Queue::send_batch`](crate::Queue::send_batch) always send JSON, and only the messages sent
+/// with [`Queue::send_compressed`](crate::Queue::send_compressed) or
+/// [`Queue::send_batch_compressed`](crate::Queue::send_batch_compressed) are compressed.
+///
+/// A compressed body is sent with the `bytes` content type, as a header byte telling the codec
+/// followed by the compressed JSON. [`MessageBatch::iter`](crate::MessageBatch::iter) recognizes
+/// the header and decompresses the body before deserializing it, so consumers don't need to know
+/// which messages were compressed. Bodies smaller than the
+/// [minimum size](Compression::min_size) aren't worth compressing, and are sent as JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    min_size: usize,
+    level: u32,
+}
+
+impl Compression {
+    /// Gzip the bodies whose JSON is at least 1 KiB, at the default level.
+    pub fn gzip() -> Self {
+        Self {
+            min_size: 1024,
+            level: 6,
+        }
+    }
+
+    /// Only compress bodies whose JSON is at least `bytes` long.
+    #[must_use]
+    pub fn min_size(mut self, bytes: usize) -> Self {
+        self.min_size = bytes;
+        self
+    }
+
+    /// The compression level, from `0` for none to `9` for the smallest bodies.
+    #[must_use]
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = level.min(9);
+        self
+    }
+
+    /// The compressed body for `json` with its header, or `None` if it's too small to compress.
+    pub(crate) fn compress(&self, json: &[u8]) -> Result<Option<Vec<u8>>> {
+        if json.len() < self.min_size {
+            return Ok(None);
+        }
+        let mut encoder = GzEncoder::new(vec![GZIP], flate2::Compression::new(self.level));
+        encoder.write_all(json).map_err(compression_error)?;
+        Ok(Some(encoder.finish().map_err(compression_error)?))
+    }
+}
+
+/// The JSON of a message body which was compressed by [`Compression`], or `None` if it wasn't.
+pub(crate) fn decompress_body(body: &JsValue) -> Option<Result<Vec<u8>>> {
+    if !body.is_instance_of::<js_sys::ArrayBuffer>() && !js_sys::ArrayBuffer::is_view(body) {
+        return None;
+    }
+    decompress(&Uint8Array::new(body).to_vec())
+}
+
+fn decompress(bytes: &[u8]) -> Option<Result<Vec<u8>>> {
+    match bytes {
+        [GZIP, rest @ ..] if rest.starts_with(&GZIP_MAGIC) => {
+            let mut json = Vec::new();
+            let result = GzDecoder::new(rest)
+                .read_to_end(&mut json)
+                .map(|_| json)
+                .map_err(|e| {
+                    Error::RustError(format!("failed to decompress the message body: {e}"))
+                });
+            Some(result)
+        }
+        _ => None,
+    }
+}
+
+fn compression_error(e: std::io::Error) -> Error {
+    Error::RustError(format!("failed to compress the message body: {e}"))
+}
+
+#[test]
+fn compression_works() {
+    let json = serde_json::to_vec(&vec!["a large message"; 200]).unwrap();
+    let compressed = Compression::gzip().compress(&json).unwrap().unwrap();
+    assert_eq!(compressed[0], GZIP);
+    assert!(compressed.len() < json.len());
+    assert_eq!(decompress(&compressed).unwrap().unwrap(), json);
+
+    assert_eq!(Compression::gzip().compress(b"{}").unwrap(), None);
+    assert!(decompress(b"plain bytes").is_none());
+    assert!(decompress(&[GZIP, 0x1f, 0x8b, 0]).unwrap().is_err());
+}
@@ -0,0 +1,253 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, Message, MessageBatch, Queue, RawMessage, Result};
+
+/// A message payload whose schema is versioned, so that consumers can read messages which were
+/// sent with previous versions of it.
+///
+/// Bump [`VERSION`](Versioned::VERSION) whenever the schema changes in a way older consumers or
+/// newer messages can't deserialize, and register an upgrade from the previous version on the
+/// [`EnvelopeReader`].
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The name of the message type, which tells apart payloads of different types sent to the
+    /// same queue.
+    const TYPE: &'static str;
+    /// The current version of the schema.
+    const VERSION: u32;
+}
+
+/// A message payload tagged with its type and schema version.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct OrderPlaced {
+///     order_id: String,
+///     // Version 1 had a single `item` field.
+///     items: Vec<String>,
+/// }
+///
+/// impl Versioned for OrderPlaced {
+///     const TYPE: &'static str = "order_placed";
+///     const VERSION: u32 = 2;
+/// }
+///
+/// # async fn example(queue: Queue, batch: MessageBatch<serde_json::Value>) -> Result<()> {
+/// // Producer
+/// let order = OrderPlaced { order_id: "42".into(), items: vec!["book".into()] };
+/// queue.send_envelope(&order).await?;
+///
+/// // Consumer
+/// let reader = EnvelopeReader::<OrderPlaced>::new().upgrade(1, |mut payload| {
+///     let item = payload["item"].take();
+///     payload["items"] = serde_json::json!([item]);
+///     Ok(payload)
+/// });
+/// for message in batch.iter_envelopes(&reader) {
+///     let message = message?;
+///     console_log!("order {}", message.body().order_id);
+///     message.ack();
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub version: u32,
+    pub payload: T,
+}
+
+impl<T: Versioned> Envelope<T> {
+    /// An envelope of `payload` with the current version of its schema.
+    pub fn new(payload: T) -> Self {
+        Self {
+            kind: T::TYPE.into(),
+            version: T::VERSION,
+            payload,
+        }
+    }
+}
+
+type Upgrade = Box<dyn Fn(Value) -> Result<Value>>;
+
+/// Reads [`Envelope`]s of `T`, upgrading payloads of previous schema versions to the current one.
+///
+/// A payload of version `n` goes through the upgrades from `n`, `n + 1` and so on until it reaches
+/// [`T::VERSION`](Versioned::VERSION). Envelopes of another type, of a newer version, or of a
+/// version with a missing upgrade are errors.
+pub struct EnvelopeReader<T> {
+    upgrades: HashMap<u32, Upgrade>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Versioned> Default for EnvelopeReader<T> {
+    fn default() -> Self {
+        Self {
+            upgrades: HashMap::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Versioned> EnvelopeReader<T> {
+    /// A reader which only accepts the current version.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Upgrade the JSON payloads of version `from` to version `from + 1`.
+    #[must_use]
+    pub fn upgrade(
+        mut self,
+        from: u32,
+        upgrade: impl Fn(Value) -> Result<Value> + 'static,
+    ) -> Self {
+        self.upgrades.insert(from, Box::new(upgrade));
+        self
+    }
+
+    /// Upgrade the payloads of version `from`, deserialized as `Old`, to version `from + 1`.
+    #[must_use]
+    pub fn upgrade_from<Old, New>(self, from: u32, upgrade: impl Fn(Old) -> New + 'static) -> Self
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+    {
+        self.upgrade(from, move |payload| {
+            let old = serde_json::from_value(payload)?;
+            Ok(serde_json::to_value(upgrade(old))?)
+        })
+    }
+
+    /// The payload of `envelope`, upgraded to the current version.
+    pub fn read(&self, envelope: Envelope<Value>) -> Result<T> {
+        if envelope.kind != T::TYPE {
+            return Err(Error::RustError(format!(
+                "expected an envelope of type {}, but it is {}",
+                T::TYPE,
+                envelope.kind
+            )));
+        }
+        if envelope.version > T::VERSION {
+            return Err(Error::RustError(format!(
+                "{} version {} is newer than the supported version {}",
+                T::TYPE,
+                envelope.version,
+                T::VERSION
+            )));
+        }
+        let mut payload = envelope.payload;
+        for version in envelope.version..T::VERSION {
+            let upgrade = self.upgrades.get(&version).ok_or_else(|| {
+                Error::RustError(format!("no upgrade of {} from version {version}", T::TYPE))
+            })?;
+            payload = upgrade(payload)?;
+        }
+        Ok(serde_json::from_value(payload)?)
+    }
+
+    /// The payload of the envelope in the body of `message`.
+    pub fn read_message(&self, message: RawMessage) -> Result<Message<T>> {
+        let envelope = serde_wasm_bindgen::from_value(message.body())?;
+        let body = self.read(envelope)?;
+        Ok(message.with_body(body))
+    }
+}
+
+impl<T> MessageBatch<T> {
+    /// Iterator for messages whose body is an [`Envelope`], the payloads of which are upgraded by
+    /// `reader`. Messages which can't be read are returned as errors, so that they can be retried
+    /// or acknowledged individually.
+    pub fn iter_envelopes<'r, U: Versioned>(
+        &self,
+        reader: &'r EnvelopeReader<U>,
+    ) -> impl DoubleEndedIterator<Item = Result<Message<U>>> + ExactSizeIterator + 'r {
+        self.raw_iter().map(|raw| reader.read_message(raw))
+    }
+}
+
+impl Queue {
+    /// Send `payload` in an [`Envelope`] with the current version of its schema.
+    pub async fn send_envelope<T: Versioned>(&self, payload: &T) -> Result<()> {
+        self.send(Envelope {
+            kind: T::TYPE.into(),
+            version: T::VERSION,
+            payload,
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        items: Vec<String>,
+        express: bool,
+    }
+
+    impl Versioned for Order {
+        const TYPE: &'static str = "order";
+        const VERSION: u32 = 3;
+    }
+
+    #[derive(Deserialize)]
+    struct OrderV1 {
+        item: String,
+    }
+
+    #[derive(Serialize)]
+    struct OrderV2 {
+        items: Vec<String>,
+    }
+
+    fn reader() -> EnvelopeReader<Order> {
+        EnvelopeReader::new()
+            .upgrade_from(1, |old: OrderV1| OrderV2 {
+                items: vec![old.item],
+            })
+            .upgrade(2, |mut payload| {
+                payload["express"] = false.into();
+                Ok(payload)
+            })
+    }
+
+    fn envelope(kind: &str, version: u32, payload: Value) -> Envelope<Value> {
+        Envelope {
+            kind: kind.into(),
+            version,
+            payload,
+        }
+    }
+
+    #[test]
+    fn envelope_upgrades_work() {
+        let expected = Order {
+            items: vec!["book".into()],
+            express: false,
+        };
+        let v1 = envelope("order", 1, serde_json::json!({ "item": "book" }));
+        assert_eq!(reader().read(v1).unwrap(), expected);
+        let current = envelope(
+            "order",
+            3,
+            serde_json::json!({ "items": ["book"], "express": false }),
+        );
+        assert_eq!(reader().read(current).unwrap(), expected);
+
+        let v2 = envelope("order", 2, serde_json::json!({ "items": ["book"] }));
+        assert!(EnvelopeReader::<Order>::new().read(v2).is_err());
+        let newer = envelope("order", 4, serde_json::json!({}));
+        assert!(reader().read(newer).is_err());
+        let other = envelope("refund", 3, serde_json::json!({}));
+        assert!(reader().read(other).is_err());
+    }
+}
@@ -8,7 +8,11 @@ use worker_sys::{
     R2MultipartUpload as EdgeR2MultipartUpload, R2Object as EdgeR2Object, R2Range as R2RangeSys,
 };
 
-use crate::{Date, Error, MultipartUpload, ObjectInner, Objects, Result};
+use crate::{
+    macros::js_object,
+    request_metrics::{record, Call},
+    Date, Error, MultipartUpload, ObjectInner, Objects, Result,
+};
 
 use super::{Data, Object};
 
@@ -47,21 +51,27 @@ impl<'bucket> GetOptionsBuilder<'bucket> {
             .into(),
         );
 
+        record(Call::R2);
         let value = JsFuture::from(get_promise).await?;
 
-        if value.is_null() {
-            return Ok(None);
-        }
-
-        let res: EdgeR2Object = value.into();
-        let inner = if JsString::from("bodyUsed").js_in(&res) {
-            ObjectInner::Body(res.unchecked_into())
-        } else {
-            ObjectInner::NoBody(res)
-        };
+        Ok(object_from_get(value))
+    }
+}
 
-        Ok(Some(Object { inner }))
+/// The [Object] returned by a `get`, which has no body if a precondition failed.
+pub(super) fn object_from_get(value: JsValue) -> Option<Object> {
+    if value.is_null() {
+        return None;
     }
+
+    let res: EdgeR2Object = value.into();
+    let inner = if JsString::from("bodyUsed").js_in(&res) {
+        ObjectInner::Body(res.unchecked_into())
+    } else {
+        ObjectInner::NoBody(res)
+    };
+
+    Some(Object { inner })
 }
 
 /// You can pass an [Conditional] object to [GetOptionsBuilder]. If the condition check fails,
@@ -229,6 +239,7 @@ impl<'bucket> PutOptionsBuilder<'bucket> {
             }
             .into(),
         );
+        record(Call::R2);
         let res: EdgeR2Object = JsFuture::from(put_promise).await?.into();
         let inner = if JsString::from("bodyUsed").js_in(&res) {
             ObjectInner::Body(res.unchecked_into())
@@ -282,6 +293,7 @@ impl<'bucket> CreateMultipartUploadOptionsBuilder<'bucket> {
             }
             .into(),
         );
+        record(Call::R2);
         let inner: EdgeR2MultipartUpload = JsFuture::from(create_multipart_upload_promise)
             .await?
             .into();
@@ -416,6 +428,7 @@ impl<'bucket> ListOptionsBuilder<'bucket> {
             }
             .into(),
         );
+        record(Call::R2);
         let inner = JsFuture::from(list_promise).await?.into();
         Ok(Objects { inner })
     }
@@ -426,17 +439,3 @@ pub enum Include {
     HttpMetadata,
     CustomMetadata,
 }
-
-macro_rules! js_object {
-    {$($key: expr => $value: expr),* $(,)?} => {{
-        let obj = JsObject::new();
-        $(
-            {
-                let res = ::js_sys::Reflect::set(&obj, &JsString::from($key), &JsValue::from($value));
-                debug_assert!(res.is_ok(), "setting properties should never fail on our dictionary objects");
-            }
-        )*
-        obj
-    }};
-}
-pub(crate) use js_object;
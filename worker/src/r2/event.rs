@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Date, DateInit};
+
+/// An [R2 event notification](https://developers.cloudflare.com/r2/buckets/event-notifications/)
+/// delivered to a Queue consumer, e.g. as the messages of a `MessageBatch<R2Event>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct R2Event {
+    /// The id of the account the bucket belongs to.
+    pub account: String,
+    pub action: R2EventAction,
+    pub bucket: String,
+    pub object: R2EventObject,
+    /// When the event occurred, as an ISO 8601 timestamp.
+    pub event_time: String,
+    /// The source of the object for `CopyObject` events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_source: Option<R2EventCopySource>,
+}
+
+impl R2Event {
+    /// When the event occurred.
+    pub fn event_date(&self) -> Date {
+        DateInit::String(self.event_time.clone()).into()
+    }
+
+    /// The type of event, which is what notification rules are configured with.
+    pub fn event_type(&self) -> R2EventType {
+        self.action.event_type()
+    }
+}
+
+/// The object an [`R2Event`] refers to. `size` and `e_tag` are absent for deletions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct R2EventObject {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e_tag: Option<String>,
+}
+
+/// The object an object was copied from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct R2EventCopySource {
+    pub bucket: String,
+    pub object: String,
+}
+
+/// The action which triggered an [`R2Event`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum R2EventAction {
+    PutObject,
+    CopyObject,
+    CompleteMultipartUpload,
+    DeleteObject,
+    LifecycleDeletion,
+    /// An action which was added after this version of the crate.
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl R2EventAction {
+    pub fn event_type(&self) -> R2EventType {
+        match self {
+            Self::PutObject | Self::CopyObject | Self::CompleteMultipartUpload => {
+                R2EventType::ObjectCreate
+            }
+            Self::DeleteObject | Self::LifecycleDeletion => R2EventType::ObjectDelete,
+            Self::Other(_) => R2EventType::Unknown,
+        }
+    }
+}
+
+/// The event types notification rules can be configured for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum R2EventType {
+    /// `object-create`
+    ObjectCreate,
+    /// `object-delete`
+    ObjectDelete,
+    Unknown,
+}
+
+impl R2EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ObjectCreate => "object-create",
+            Self::ObjectDelete => "object-delete",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[test]
+fn r2_event_deserializes() {
+    let event: R2Event = serde_json::from_str(
+        r#"{
+            "account": "3f4b7e3dcab231cbfdaa90a6a28bd548",
+            "action": "CopyObject",
+            "bucket": "my-bucket",
+            "object": { "key": "my-new-object", "size": 65536, "eTag": "c846ff7a18f28c2e262116d6e8719ef0" },
+            "eventTime": "2024-05-24T19:36:44.379Z",
+            "copySource": { "bucket": "my-bucket", "object": "my-original-object" }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(event.event_type(), R2EventType::ObjectCreate);
+    assert_eq!(event.object.size, Some(65536));
+    assert_eq!(event.copy_source.unwrap().object, "my-original-object");
+
+    let event: R2Event = serde_json::from_str(
+        r#"{
+            "account": "3f4b7e3dcab231cbfdaa90a6a28bd548",
+            "action": "SomethingNew",
+            "bucket": "my-bucket",
+            "object": { "key": "my-object" },
+            "eventTime": "2024-05-24T19:36:44.379Z"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(event.action, R2EventAction::Other("SomethingNew".into()));
+}
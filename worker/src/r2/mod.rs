@@ -1,6 +1,8 @@
 use std::{collections::HashMap, convert::TryInto};
 
 pub use builder::*;
+pub use event::*;
+pub use serve::serve_r2_object;
 
 use js_sys::{JsString, Reflect, Uint8Array};
 use wasm_bindgen::{JsCast, JsValue};
@@ -13,10 +15,14 @@ use worker_sys::{
 };
 
 use crate::{
-    env::EnvBinding, ByteStream, Date, Error, FixedLengthStream, Headers, ResponseBody, Result,
+    env::EnvBinding,
+    request_metrics::{record, Call},
+    ByteStream, Date, Error, FixedLengthStream, Headers, ResponseBody, Result,
 };
 
 mod builder;
+mod event;
+mod serve;
 
 /// An instance of the R2 bucket binding.
 #[derive(Clone)]
@@ -27,6 +33,7 @@ pub struct Bucket {
 impl Bucket {
     /// Retrieves the [Object] for the given key containing only object metadata, if the key exists.
     pub async fn head(&self, key: impl Into<String>) -> Result<Option<Object>> {
+        record(Call::R2);
         let head_promise = self.inner.head(key.into());
         let value = JsFuture::from(head_promise).await?;
 
@@ -74,6 +81,7 @@ impl Bucket {
     /// R2 deletes are strongly consistent. Once the Promise resolves, all subsequent read
     /// operations will no longer see this key value pair globally.
     pub async fn delete(&self, key: impl Into<String>) -> Result<()> {
+        record(Call::R2);
         let delete_promise = self.inner.delete(key.into());
         JsFuture::from(delete_promise).await?;
         Ok(())
@@ -288,10 +296,7 @@ impl<'body> ObjectBody<'body> {
         }
 
         let stream = self.inner.body();
-        let stream = wasm_streams::ReadableStream::from_raw(stream.unchecked_into());
-        Ok(ByteStream {
-            inner: stream.into_stream(),
-        })
+        Ok(ByteStream::from_raw(stream.unchecked_into()))
     }
 
     /// Returns a [ResponseBody] containing the data in the [Object].
@@ -360,6 +365,7 @@ impl MultipartUpload {
         part_number: u16,
         value: impl Into<Data>,
     ) -> Result<UploadedPart> {
+        record(Call::R2);
         let uploaded_part =
             JsFuture::from(self.inner.upload_part(part_number, value.into().into())).await?;
         Ok(UploadedPart {
@@ -369,6 +375,7 @@ impl MultipartUpload {
 
     /// Aborts the multipart upload.
     pub async fn abort(&self) -> Result<()> {
+        record(Call::R2);
         JsFuture::from(self.inner.abort()).await?;
         Ok(())
     }
@@ -379,6 +386,7 @@ impl MultipartUpload {
         self,
         uploaded_parts: impl IntoIterator<Item = UploadedPart>,
     ) -> Result<Object> {
+        record(Call::R2);
         let object = JsFuture::from(
             self.inner.complete(
                 uploaded_parts
@@ -446,6 +454,7 @@ pub(crate) enum ObjectInner {
 
 pub enum Data {
     Stream(FixedLengthStream),
+    Blob(crate::Blob),
     Text(String),
     Bytes(Vec<u8>),
     Empty,
@@ -457,6 +466,12 @@ impl From<FixedLengthStream> for Data {
     }
 }
 
+impl From<crate::Blob> for Data {
+    fn from(blob: crate::Blob) -> Self {
+        Data::Blob(blob)
+    }
+}
+
 impl From<String> for Data {
     fn from(value: String) -> Self {
         Data::Text(value)
@@ -476,6 +491,7 @@ impl From<Data> for JsValue {
                 let stream_sys: EdgeFixedLengthStream = stream.into();
                 stream_sys.readable().into()
             }
+            Data::Blob(blob) => blob.into(),
             Data::Text(text) => JsString::from(text).into(),
             Data::Bytes(bytes) => {
                 let arr = Uint8Array::new_with_length(bytes.len() as u32);
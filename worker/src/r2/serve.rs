@@ -0,0 +1,165 @@
+use js_sys::{Date as JsDate, JsString, Object as JsObject};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{
+    request_metrics::{record, Call},
+    Headers, Method, Request, Response, Result,
+};
+
+use super::{
+    builder::{js_object, object_from_get},
+    Bucket, Range,
+};
+
+/// Respond to a `GET` or `HEAD` request with the object at `key` of `bucket`, the way a static
+/// file server would:
+///
+/// - `If-Match`, `If-None-Match`, `If-Modified-Since` and `If-Unmodified-Since` are evaluated by
+///   R2, responding with `304 Not Modified` or `412 Precondition Failed`.
+/// - A `Range` header is answered with `206 Partial Content` and a `Content-Range` header, or
+///   `416 Range Not Satisfiable` when R2 rejects it. Empty objects are served in full.
+/// - `Content-Type`, `Cache-Control`, `Content-Disposition`, `Content-Encoding`,
+///   `Content-Language` and `Expires` are set from the object's [`HttpMetadata`](super::HttpMetadata),
+///   along with its `ETag` and `Last-Modified`.
+/// - The body is streamed from R2 without passing through the Worker.
+///
+/// Missing objects get a `404 Not Found` response and other methods
+/// `405 Method Not Allowed`.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(req: Request, env: Env) -> Result<Response> {
+/// let bucket = env.bucket("FILES")?;
+/// let key = req.path().trim_start_matches('/').to_string();
+/// serve_r2_object(&req, &bucket, &key).await
+/// # }
+/// ```
+pub async fn serve_r2_object(req: &Request, bucket: &Bucket, key: &str) -> Result<Response> {
+    let method = req.method();
+    if method != Method::Get && method != Method::Head {
+        let mut headers = Headers::new();
+        headers.set("Allow", "GET, HEAD")?;
+        return Ok(Response::error("Method Not Allowed", 405)?.with_headers(headers));
+    }
+
+    let ranged = req.headers().has("Range")?;
+    // R2 parses conditional and range headers itself when they're passed as `Headers`.
+    let options = js_object! {
+        "onlyIf" => req.headers().0.clone(),
+        "range" => ranged.then(|| req.headers().0.clone()),
+    };
+    record(Call::R2);
+    let value = match JsFuture::from(bucket.inner.get(key.into(), options.into())).await {
+        Ok(value) => value,
+        Err(e) if ranged && is_invalid_range(&e) => return unsatisfiable(bucket, key).await,
+        Err(e) => return Err(e.into()),
+    };
+    let object = match object_from_get(value) {
+        Some(object) => object,
+        None => return Response::error("Not Found", 404),
+    };
+
+    let mut headers = Headers::new();
+    // `Headers::clone` copies the headers, the metadata must be written to the same object.
+    object.write_http_metadata(Headers(headers.0.clone()))?;
+    headers.set("ETag", &object.http_etag())?;
+    let uploaded = JsDate::from(object.uploaded()).to_utc_string();
+    headers.set("Last-Modified", &String::from(uploaded))?;
+    headers.set("Accept-Ranges", "bytes")?;
+
+    let body = match object.body() {
+        Some(body) => body,
+        None => {
+            // The object has no body when a precondition failed.
+            let status =
+                if req.headers().has("If-None-Match")? || req.headers().has("If-Modified-Since")? {
+                    304
+                } else {
+                    412
+                };
+            return Ok(Response::empty()?.with_status(status).with_headers(headers));
+        }
+    };
+
+    let size = object.size();
+    // An empty object has no bytes to select, so it's served in full.
+    let range = (ranged && size > 0).then(|| object.range().ok()).flatten();
+    let (status, length) = match range {
+        Some(range) => {
+            let (start, end) = byte_range(&range, size);
+            headers.set("Content-Range", &format!("bytes {start}-{end}/{size}"))?;
+            (206, end + 1 - start)
+        }
+        None => (200, size),
+    };
+    headers.set("Content-Length", &length.to_string())?;
+
+    let resp = if method == Method::Head {
+        Response::empty()?
+    } else {
+        Response::from_body(body.response_body()?)?
+    };
+    Ok(resp.with_status(status).with_headers(headers))
+}
+
+/// Whether `error` is R2 rejecting the `Range` of a request, with code `10039`.
+fn is_invalid_range(error: &JsValue) -> bool {
+    let message = match error.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => error.as_string().unwrap_or_default(),
+    };
+    message.contains("10039")
+        || message
+            .to_ascii_lowercase()
+            .contains("range not satisfiable")
+}
+
+/// The `416` response to a `Range` which R2 rejected.
+async fn unsatisfiable(bucket: &Bucket, key: &str) -> Result<Response> {
+    let object = match bucket.head(key).await? {
+        Some(object) => object,
+        None => return Response::error("Not Found", 404),
+    };
+    let mut headers = Headers::new();
+    headers.set("Content-Range", &format!("bytes */{}", object.size()))?;
+    Ok(Response::error("Range Not Satisfiable", 416)?.with_headers(headers))
+}
+
+/// The first and last byte of `range` in an object of `size` bytes.
+fn byte_range(range: &Range, size: u32) -> (u32, u32) {
+    let last = size.saturating_sub(1);
+    let (start, end) = match *range {
+        Range::OffsetWithLength { offset, length }
+        | Range::OffsetWithOptionalLength {
+            offset,
+            length: Some(length),
+        } => (offset, offset.saturating_add(length).saturating_sub(1)),
+        Range::OffsetWithOptionalLength {
+            offset,
+            length: None,
+        } => (offset, last),
+        Range::OptionalOffsetWithLength { offset, length } => {
+            let offset = offset.unwrap_or(0);
+            (offset, offset.saturating_add(length).saturating_sub(1))
+        }
+        Range::Suffix { suffix } => (size.saturating_sub(suffix), last),
+    };
+    (start, end.min(last))
+}
+
+#[test]
+fn byte_range_works() {
+    let range = Range::OffsetWithLength {
+        offset: 10,
+        length: 20,
+    };
+    assert_eq!(byte_range(&range, 100), (10, 29));
+    assert_eq!(byte_range(&range, 15), (10, 14));
+    assert_eq!(byte_range(&Range::Suffix { suffix: 10 }, 100), (90, 99));
+    let range = Range::OffsetWithOptionalLength {
+        offset: 50,
+        length: None,
+    };
+    assert_eq!(byte_range(&range, 100), (50, 99));
+}
@@ -0,0 +1,93 @@
+use url::Url;
+
+use crate::{Request, Response, Result};
+
+/// A redirect to a location which may be relative to the URL of the request, e.g. `/login` or
+/// `../`, which is resolved when the response is built with [`for_request`](Redirect::for_request).
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example(req: Request) -> Result<Response> {
+/// // `/old/page?ref=mail` redirects to `/new/page?ref=mail`.
+/// Redirect::permanent("/new/page")
+///     .preserve_query(true)
+///     .for_request(&req)
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    location: String,
+    status: u16,
+    preserve_query: bool,
+}
+
+impl Redirect {
+    /// Redirect with `status`, which must be in the 300-399 range.
+    pub fn with_status(location: impl Into<String>, status: u16) -> Self {
+        Self {
+            location: location.into(),
+            status,
+            preserve_query: false,
+        }
+    }
+
+    /// A `308 Permanent Redirect`, which keeps the method and body of the request.
+    pub fn permanent(location: impl Into<String>) -> Self {
+        Self::with_status(location, 308)
+    }
+
+    /// A `307 Temporary Redirect`, which keeps the method and body of the request.
+    pub fn temporary(location: impl Into<String>) -> Self {
+        Self::with_status(location, 307)
+    }
+
+    /// A `303 See Other`, which is followed with a `GET`, e.g. after a form was submitted.
+    pub fn see_other(location: impl Into<String>) -> Self {
+        Self::with_status(location, 303)
+    }
+
+    /// Whether to keep the query of the request when the location doesn't have one, false by
+    /// default.
+    #[must_use]
+    pub fn preserve_query(mut self, preserve_query: bool) -> Self {
+        self.preserve_query = preserve_query;
+        self
+    }
+
+    /// The location resolved against `base`, the URL of the request.
+    pub fn resolve(&self, base: &Url) -> Result<Url> {
+        let mut url = base.join(&self.location)?;
+        if self.preserve_query && url.query().is_none() {
+            url.set_query(base.query());
+        }
+        Ok(url)
+    }
+
+    /// The redirect response to `req`.
+    pub fn for_request(&self, req: &Request) -> Result<Response> {
+        let url = self.resolve(&req.url()?)?;
+        Response::redirect_with_status(url, self.status)
+    }
+}
+
+#[test]
+fn resolve_works() {
+    let base = Url::parse("https://example.com/docs/old?page=2").unwrap();
+    let resolve = |redirect: Redirect| redirect.resolve(&base).unwrap().to_string();
+    assert_eq!(
+        resolve(Redirect::permanent("new")),
+        "https://example.com/docs/new"
+    );
+    assert_eq!(
+        resolve(Redirect::permanent("/new").preserve_query(true)),
+        "https://example.com/new?page=2"
+    );
+    assert_eq!(
+        resolve(Redirect::see_other("../login?next=docs").preserve_query(true)),
+        "https://example.com/login?next=docs"
+    );
+    assert_eq!(
+        resolve(Redirect::temporary("https://other.example/")),
+        "https://other.example/"
+    );
+}
@@ -1,13 +1,14 @@
 use std::convert::TryFrom;
 
 use crate::{
-    cf::Cf, error::Error, headers::Headers, http::Method, ByteStream, FormData, RequestInit, Result,
+    cf::Cf, error::Error, extensions::Extensions, headers::Headers, http::Method, ByteStream,
+    FormData, FromFormData, RequestInit, Result, Validate,
 };
 
 use serde::de::DeserializeOwned;
 use std::borrow::Cow;
+use std::net::IpAddr;
 use url::{form_urlencoded::Parse, Url};
-use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use worker_sys::ext::RequestExt;
 
@@ -22,6 +23,7 @@ pub struct Request {
     edge_request: web_sys::Request,
     body_used: bool,
     immutable: bool,
+    extensions: Extensions,
 }
 
 unsafe impl Send for Request {}
@@ -62,6 +64,7 @@ impl From<web_sys::Request> for Request {
             edge_request: req,
             body_used: false,
             immutable: true,
+            extensions: Extensions::new(),
         }
     }
 }
@@ -117,21 +120,12 @@ impl Request {
     }
 
     /// Access this request's body encoded as JSON.
+    ///
+    /// The body is parsed in Rust rather than with `JSON.parse`, so that 64-bit integers keep
+    /// their precision rather than going through an `f64`.
     pub async fn json<B: DeserializeOwned>(&mut self) -> Result<B> {
-        if !self.body_used {
-            self.body_used = true;
-            return JsFuture::from(self.edge_request.json()?)
-                .await
-                .map_err(|e| {
-                    Error::JsError(
-                        e.as_string()
-                            .unwrap_or_else(|| "failed to get JSON for body value".into()),
-                    )
-                })
-                .and_then(|val| serde_wasm_bindgen::from_value(val).map_err(Error::from));
-        }
-
-        Err(Error::BodyUsed)
+        let text = self.text().await?;
+        serde_json::from_str(&text).map_err(Error::from)
     }
 
     /// Access this request's body as plaintext.
@@ -188,6 +182,22 @@ impl Request {
         Err(Error::BodyUsed)
     }
 
+    /// Read this request's form-encoded body into a struct, usually implemented with
+    /// [`#[derive(FromFormData)]`](worker_macros::FromFormData).
+    pub async fn form<T: FromFormData>(&mut self) -> Result<T> {
+        T::from_form_data(&self.form_data().await?)
+    }
+
+    /// Deserialize the JSON body of this request and [validate](Validate) it.
+    pub async fn json_validated<T: DeserializeOwned + Validate>(&mut self) -> Result<T> {
+        self.json::<T>().await?.validated()
+    }
+
+    /// Read the form-encoded body of this request and [validate](Validate) it.
+    pub async fn form_validated<T: FromFormData + Validate>(&mut self) -> Result<T> {
+        self.form::<T>().await?.validated()
+    }
+
     /// Access this request's body as a [`Stream`](futures::stream::Stream) of bytes.
     pub fn stream(&mut self) -> Result<ByteStream> {
         if self.body_used {
@@ -201,10 +211,7 @@ impl Request {
             .body()
             .ok_or_else(|| Error::RustError("no body for request".into()))?;
 
-        let stream = wasm_streams::ReadableStream::from_raw(stream.dyn_into().unwrap());
-        Ok(ByteStream {
-            inner: stream.into_stream(),
-        })
+        Ok(ByteStream::from_raw(stream))
     }
 
     /// Get the `Headers` for this request.
@@ -212,6 +219,61 @@ impl Request {
         &self.headers
     }
 
+    /// The value of the cookie called `name` in the `Cookie` header.
+    pub fn cookie(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.headers.get("Cookie")?.and_then(|header| {
+            crate::cookie::parse(&header)
+                .find(|(cookie, _)| *cookie == name)
+                .map(|(_, value)| value.to_string())
+        }))
+    }
+
+    /// The IP address of the client, from the `CF-Connecting-IP` header Cloudflare adds to
+    /// incoming requests.
+    pub fn client_ip(&self) -> Result<Option<IpAddr>> {
+        Ok(self
+            .headers
+            .get("CF-Connecting-IP")?
+            .and_then(|ip| ip.trim().parse().ok()))
+    }
+
+    /// The addresses the request was forwarded for, from the client to the last proxy, read from
+    /// the `Forwarded` header or else `X-Forwarded-For`. Addresses which can't be parsed, such as
+    /// obfuscated identifiers, are skipped.
+    ///
+    /// Clients can send these headers themselves, so only the addresses added by proxies in front
+    /// of the Worker can be trusted.
+    pub fn forwarded_for(&self) -> Result<Vec<ForwardedNode>> {
+        if let Some(forwarded) = self.headers.get("Forwarded")? {
+            let nodes: Vec<_> = forwarded
+                .split(',')
+                .flat_map(|element| element.split(';'))
+                .filter_map(|pair| {
+                    let (name, value) = pair.split_once('=')?;
+                    name.trim()
+                        .eq_ignore_ascii_case("for")
+                        .then(|| ForwardedNode::parse(value))?
+                })
+                .collect();
+            if !nodes.is_empty() {
+                return Ok(nodes);
+            }
+        }
+        Ok(self
+            .headers
+            .get("X-Forwarded-For")?
+            .map(|chain| chain.split(',').filter_map(ForwardedNode::parse).collect())
+            .unwrap_or_default())
+    }
+
+    /// The port the client connected to, from the `X-Forwarded-Port` header.
+    pub fn forwarded_port(&self) -> Result<Option<u16>> {
+        Ok(self
+            .headers
+            .get("X-Forwarded-Port")?
+            .and_then(|port| port.trim().parse().ok()))
+    }
+
     /// Get a mutable reference to this request's `Headers`.
     /// **Note:** they can only be modified if the request was created from scratch or cloned.
     pub fn headers_mut(&mut self) -> Result<&mut Headers> {
@@ -273,23 +335,76 @@ impl Request {
 
     #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Result<Self> {
-        self.edge_request
-            .clone()
-            .map(|req| req.into())
-            .map_err(Error::from)
+        let mut req: Request = self.edge_request.clone()?.into();
+        req.extensions = self.extensions.clone();
+        Ok(req)
     }
 
     pub fn clone_mut(&self) -> Result<Self> {
         let mut req: Request = web_sys::Request::new_with_request(&self.edge_request)?.into();
         req.immutable = false;
+        req.extensions = self.extensions.clone();
         Ok(req)
     }
 
+    /// Typed values attached to this `Request`, e.g. by [`Middleware`](crate::Middleware).
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Mutable access to the typed values attached to this `Request`.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     pub fn inner(&self) -> &web_sys::Request {
         &self.edge_request
     }
 }
 
+/// An address in the chain of proxies a request was [forwarded for](Request::forwarded_for).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForwardedNode {
+    ip: IpAddr,
+    port: Option<u16>,
+}
+
+impl ForwardedNode {
+    pub fn ip(&self) -> IpAddr {
+        self.ip
+    }
+
+    /// The client's port, if the proxy included it.
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// A node like `203.0.113.7`, `203.0.113.7:4711`, `2001:db8::1` or `"[2001:db8::1]:4711"`.
+    fn parse(node: &str) -> Option<Self> {
+        let node = node.trim().trim_matches('"');
+        if let Ok(ip) = node.parse() {
+            return Some(Self { ip, port: None });
+        }
+        let (ip, port) = match node.strip_prefix('[') {
+            Some(rest) => {
+                let (ip, rest) = rest.split_once(']')?;
+                (ip, rest.strip_prefix(':'))
+            }
+            None => {
+                let (ip, port) = node.rsplit_once(':')?;
+                (ip, Some(port))
+            }
+        };
+        Some(Self {
+            ip: ip.parse().ok()?,
+            port: match port {
+                Some(port) => Some(port.parse().ok()?),
+                None => None,
+            },
+        })
+    }
+}
+
 /// Used to add additional helper functions to url::Url
 pub trait UrlExt {
     /// Given a query parameter, returns the value of the first occurrence of that parameter if it
@@ -337,6 +452,27 @@ fn url_param_works() {
     assert_eq!(a_values.next(), None);
 }
 
+#[test]
+fn forwarded_node_parse_works() {
+    let parse = |node| ForwardedNode::parse(node).map(|node| (node.ip.to_string(), node.port));
+    assert_eq!(parse(" 203.0.113.7"), Some(("203.0.113.7".into(), None)));
+    assert_eq!(
+        parse("203.0.113.7:4711"),
+        Some(("203.0.113.7".into(), Some(4711)))
+    );
+    assert_eq!(parse("2001:db8::1"), Some(("2001:db8::1".into(), None)));
+    assert_eq!(
+        parse("\"[2001:db8::1]\""),
+        Some(("2001:db8::1".into(), None))
+    );
+    assert_eq!(
+        parse("\"[2001:db8::1]:4711\""),
+        Some(("2001:db8::1".into(), Some(4711)))
+    );
+    assert_eq!(parse("_hidden"), None);
+    assert_eq!(parse("unknown"), None);
+}
+
 #[test]
 fn clone_mut_works() {
     let req = Request::new(
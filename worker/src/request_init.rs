@@ -2,14 +2,18 @@ use std::collections::HashMap;
 
 use crate::headers::Headers;
 use crate::http::Method;
+use crate::image::ImageOptions;
+use crate::{Error, Result};
 
+use futures_util::TryStream;
 use js_sys::{self, Object};
 use serde::Serialize;
-use wasm_bindgen::{prelude::*, JsValue};
+use wasm_bindgen::{prelude::*, JsCast, JsValue};
 
 /// Optional options struct that contains settings to apply to the `Request`.
 pub struct RequestInit {
-    /// Currently requires a manual conversion from your data into a [`wasm_bindgen::JsValue`].
+    /// Currently requires a manual conversion from your data into a [`wasm_bindgen::JsValue`], or
+    /// use [`RequestInit::with_stream_body`] to stream the body.
     pub body: Option<JsValue>,
     /// Headers associated with the outbound `Request`.
     pub headers: Headers,
@@ -49,10 +53,39 @@ impl RequestInit {
         self
     }
 
+    /// Stream the body of the request from `stream`, without buffering it in memory first. This
+    /// allows proxying large uploads, e.g. by passing on the body of an incoming request.
+    pub fn with_stream_body<S>(&mut self, stream: S) -> &mut Self
+    where
+        S: TryStream + 'static,
+        S::Ok: Into<Vec<u8>>,
+        S::Error: Into<Error>,
+    {
+        self.body = Some(crate::streams::to_readable_stream(stream).into());
+        self
+    }
+
     pub fn with_cf_properties(&mut self, props: CfProperties) -> &mut Self {
         self.cf = props;
         self
     }
+
+    /// Resolve the hostname of the request as `host` instead of the host of its URL, keeping the
+    /// `Host` header of the URL, e.g. to send a request to a specific origin behind a load
+    /// balancer. Both hosts must be in the zone of the Worker, see
+    /// [`CfProperties::resolve_override`](crate::CfProperties::resolve_override).
+    pub fn with_resolve_override(&mut self, host: impl Into<String>) -> &mut Self {
+        self.cf.resolve_override = Some(host.into());
+        self
+    }
+
+    /// Resize the image being fetched, see [`ImageOptions`]. Returns an error if the options are
+    /// invalid.
+    pub fn with_image(&mut self, image: ImageOptions) -> Result<&mut Self> {
+        image.validate()?;
+        self.cf.image = Some(image);
+        Ok(self)
+    }
 }
 
 impl From<&RequestInit> for web_sys::RequestInit {
@@ -62,6 +95,17 @@ impl From<&RequestInit> for web_sys::RequestInit {
         inner.method(req.method.as_ref());
         inner.redirect(req.redirect.into());
         inner.body(req.body.as_ref());
+        if let Some(body) = &req.body {
+            // Fetch requires streaming bodies to be sent in half-duplex mode, which is the only
+            // mode that is supported.
+            if body.is_instance_of::<web_sys::ReadableStream>() {
+                set_prop(
+                    inner.as_ref(),
+                    &JsValue::from("duplex"),
+                    &JsValue::from("half"),
+                );
+            }
+        }
 
         // set the Cloudflare-specific `cf` property on FFI RequestInit
         let r = ::js_sys::Reflect::set(
@@ -92,6 +136,19 @@ impl Default for RequestInit {
 }
 
 /// <https://developers.cloudflare.com/workers/runtime-apis/request#requestinitcfproperties>
+///
+/// Fields are added as the runtime supports more options, so build it from the defaults rather
+/// than listing every field:
+///
+/// ```rust,ignore
+/// let cf = CfProperties {
+///     cache_ttl: Some(60),
+///     ..CfProperties::default()
+/// };
+/// ```
+///
+/// The `image` field is new; struct literals listing every field need to add it, usually as
+/// `image: None`.
 pub struct CfProperties {
     /// Whether Cloudflare Apps should be enabled for this request. Defaults to `true`.
     pub apps: Option<bool>,
@@ -141,6 +198,8 @@ pub struct CfProperties {
     /// Whether ScrapeShield should be enabled for this request, if otherwise configured for this
     /// zone. Defaults to `true`.
     pub scrape_shield: Option<bool>,
+    /// Resizes and optimizes the image being fetched, see [`ImageOptions`].
+    pub image: Option<ImageOptions>,
 }
 
 impl From<&CfProperties> for JsValue {
@@ -214,16 +273,18 @@ impl From<&CfProperties> for JsValue {
             .into();
         set_prop(&obj, &JsValue::from("polish"), &JsValue::from(polish_val));
 
-        set_prop(
-            &obj,
-            &JsValue::from("resolveOverride"),
-            &JsValue::from(
-                props
-                    .resolve_override
-                    .clone()
-                    .unwrap_or(defaults.resolve_override.unwrap_or_default()),
-            ),
-        );
+        // An empty override would resolve the request to nothing.
+        if let Some(host) = props
+            .resolve_override
+            .as_ref()
+            .or(defaults.resolve_override.as_ref())
+        {
+            set_prop(
+                &obj,
+                &JsValue::from("resolveOverride"),
+                &JsValue::from(host),
+            );
+        }
 
         set_prop(
             &obj,
@@ -235,6 +296,14 @@ impl From<&CfProperties> for JsValue {
             ),
         );
 
+        if let Some(image) = &props.image {
+            set_prop(
+                &obj,
+                &JsValue::from("image"),
+                &image.serialize(&serializer).unwrap_or_default(),
+            );
+        }
+
         obj.into()
     }
 }
@@ -267,6 +336,7 @@ impl Default for CfProperties {
             polish: None,
             resolve_override: None,
             scrape_shield: Some(true),
+            image: None,
         }
     }
 }
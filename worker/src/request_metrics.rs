@@ -0,0 +1,159 @@
+use std::{cell::RefCell, collections::BTreeMap, future::Future};
+
+use serde::Serialize;
+
+use crate::{Next, Request, Response, Result, RouteContext};
+
+thread_local! {
+    static COUNTERS: RefCell<Counters> = RefCell::new(Counters::default());
+}
+
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    fetch: u32,
+    service: u32,
+    durable_object: u32,
+    r2: u32,
+    d1: u32,
+    hosts: BTreeMap<String, u32>,
+}
+
+/// The kind of an outbound call made by the Worker.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Call<'a> {
+    /// A `fetch` to the given host.
+    Fetch(&'a str),
+    Service,
+    DurableObject,
+    #[cfg(feature = "r2")]
+    R2,
+    D1,
+}
+
+/// Count an outbound call.
+pub(crate) fn record(call: Call<'_>) {
+    let _ = COUNTERS.try_with(|counters| {
+        let mut counters = counters.borrow_mut();
+        match call {
+            Call::Fetch(host) => {
+                counters.fetch += 1;
+                *counters.hosts.entry(host.to_string()).or_default() += 1;
+            }
+            Call::Service => counters.service += 1,
+            Call::DurableObject => counters.durable_object += 1,
+            #[cfg(feature = "r2")]
+            Call::R2 => counters.r2 += 1,
+            Call::D1 => counters.d1 += 1,
+        }
+    });
+}
+
+fn counters() -> Counters {
+    COUNTERS.with(|counters| counters.borrow().clone())
+}
+
+/// Counts the outbound calls (subrequests, KV, D1 and R2 operations) made while handling a request,
+/// to help stay below the [subrequest limits](https://developers.cloudflare.com/workers/platform/limits/#subrequests).
+///
+/// The counters are shared by the whole isolate, so calls made by other requests which are
+/// handled concurrently by the same isolate are included in the counts as well.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(req: Request) -> Result<Response> {
+/// let metrics = RequestMetrics::start();
+/// let resp = Fetch::Url("https://example.com".parse()?).send().await?;
+/// metrics.log();
+/// # Ok(resp)
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    start: Counters,
+    kv_start: u64,
+}
+
+/// The number of calls counted by a [`RequestMetrics`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    /// Calls which count toward the subrequest limit: fetches, service bindings and Durable
+    /// Object stubs.
+    pub subrequests: u32,
+    pub fetch: u32,
+    pub service: u32,
+    pub durable_object: u32,
+    pub kv: u32,
+    pub r2: u32,
+    pub d1: u32,
+    /// The number of fetches made to each host, e.g. to spot requests which could share a
+    /// connection or be batched.
+    pub hosts: BTreeMap<String, u32>,
+}
+
+impl RequestMetrics {
+    /// Start counting calls from now on.
+    pub fn start() -> Self {
+        Self {
+            start: counters(),
+            kv_start: worker_kv::operation_count(),
+        }
+    }
+
+    /// The calls made since [`RequestMetrics::start`].
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let now = counters();
+        let fetch = now.fetch - self.start.fetch;
+        let service = now.service - self.start.service;
+        let durable_object = now.durable_object - self.start.durable_object;
+        let hosts = now
+            .hosts
+            .into_iter()
+            .filter_map(|(host, count)| {
+                let count = count - self.start.hosts.get(&host).copied().unwrap_or(0);
+                (count > 0).then_some((host, count))
+            })
+            .collect();
+
+        MetricsSnapshot {
+            subrequests: fetch + service + durable_object,
+            fetch,
+            service,
+            durable_object,
+            kv: (worker_kv::operation_count() - self.kv_start) as u32,
+            r2: now.r2 - self.start.r2,
+            d1: now.d1 - self.start.d1,
+            hosts,
+        }
+    }
+
+    /// Log the current snapshot as JSON with `console.log`.
+    pub fn log(&self) {
+        let snapshot = self.snapshot();
+        worker_sys::console_log!("{}", serde_json::to_string(&snapshot).unwrap_or_default());
+    }
+
+    /// Run `f` and return its output along with the calls it made.
+    pub async fn measure<F: Future>(f: F) -> (F::Output, MetricsSnapshot) {
+        let metrics = Self::start();
+        let output = f.await;
+        (output, metrics.snapshot())
+    }
+
+    /// A [`Middleware`](crate::Middleware) which logs the calls made while handling each request
+    /// and adds the number of subrequests as an `X-Subrequest-Count` header of the response.
+    pub async fn middleware<'a, D: 'a>(
+        req: Request,
+        ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> Result<Response> {
+        let metrics = Self::start();
+        let mut resp = next.run(req, ctx).await?;
+        let snapshot = metrics.snapshot();
+        worker_sys::console_log!("{}", serde_json::to_string(&snapshot).unwrap_or_default());
+        // Headers of responses from `fetch` are immutable, in which case the count is only logged.
+        let _ = resp
+            .headers_mut()
+            .set("X-Subrequest-Count", &snapshot.subrequests.to_string());
+        Ok(resp)
+    }
+}
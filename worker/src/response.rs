@@ -1,20 +1,24 @@
+use crate::cookie::Cookie;
 use crate::cors::Cors;
+use crate::early_hints::EarlyHints;
 use crate::error::Error;
+use crate::extensions::Extensions;
 use crate::headers::Headers;
+use crate::http::StatusCode;
 use crate::ByteStream;
 use crate::Result;
+#[cfg(feature = "websocket")]
 use crate::WebSocket;
 
 #[cfg(feature = "http")]
 use bytes::Bytes;
-use futures_util::{TryStream, TryStreamExt};
+use futures_util::{future, stream, Stream, StreamExt, TryStream, TryStreamExt};
 use js_sys::Uint8Array;
 use serde::{de::DeserializeOwned, Serialize};
 #[cfg(feature = "http")]
 use std::convert::TryFrom;
-use wasm_bindgen::JsCast;
-use wasm_bindgen::JsValue;
 use web_sys::ReadableStream;
+#[cfg(feature = "websocket")]
 use worker_sys::ext::{ResponseExt, ResponseInitExt};
 
 #[derive(Debug, Clone)]
@@ -33,7 +37,9 @@ pub struct Response {
     body: ResponseBody,
     headers: Headers,
     status_code: u16,
+    #[cfg(feature = "websocket")]
     websocket: Option<WebSocket>,
+    extensions: Extensions,
 }
 
 #[cfg(feature = "http")]
@@ -66,7 +72,9 @@ impl Response {
                 body: ResponseBody::Body(data.into_bytes()),
                 headers,
                 status_code: 200,
+                #[cfg(feature = "websocket")]
                 websocket: None,
+                extensions: Extensions::new(),
             });
         }
 
@@ -84,7 +92,9 @@ impl Response {
             body: ResponseBody::Body(data),
             headers,
             status_code: 200,
+            #[cfg(feature = "websocket")]
             websocket: None,
+            extensions: Extensions::new(),
         })
     }
 
@@ -98,7 +108,9 @@ impl Response {
             body: ResponseBody::Body(bytes),
             headers,
             status_code: 200,
+            #[cfg(feature = "websocket")]
             websocket: None,
+            extensions: Extensions::new(),
         })
     }
 
@@ -109,18 +121,22 @@ impl Response {
             body,
             headers: Headers::new(),
             status_code: 200,
+            #[cfg(feature = "websocket")]
             websocket: None,
+            extensions: Extensions::new(),
         })
     }
 
     /// Create a `Response` using a `WebSocket` client. Configures the browser to switch protocols
     /// (using status code 101) and returns the websocket.
+    #[cfg(feature = "websocket")]
     pub fn from_websocket(websocket: WebSocket) -> Result<Self> {
         Ok(Self {
             body: ResponseBody::Empty,
             headers: Headers::new(),
             status_code: 101,
             websocket: Some(websocket),
+            extensions: Extensions::new(),
         })
     }
 
@@ -133,24 +149,53 @@ impl Response {
         S::Ok: Into<Vec<u8>>,
         S::Error: Into<Error>,
     {
-        let js_stream = stream
-            .map_ok(|item| -> Vec<u8> { item.into() })
-            .map_ok(|chunk| {
-                let array = Uint8Array::new_with_length(chunk.len() as _);
-                array.copy_from(&chunk);
-
-                array.into()
-            })
-            .map_err(|err| -> crate::Error { err.into() })
-            .map_err(|e| JsValue::from(e.to_string()));
-
-        let stream = wasm_streams::ReadableStream::from_stream(js_stream);
-        let stream: ReadableStream = stream.into_raw().dyn_into().unwrap();
+        let stream = crate::streams::to_readable_stream(stream);
+        let edge_res = web_sys::Response::new_with_opt_readable_stream(Some(&stream))?;
+        Ok(Self::from(edge_res))
+    }
 
+    /// Like [`from_stream`](Response::from_stream), for a stream of `length` bytes which is sent
+    /// with a `Content-Length` header rather than with chunked encoding, see
+    /// [`FixedLengthStream`](crate::FixedLengthStream).
+    pub fn from_stream_with_length<S>(stream: S, length: u64) -> Result<Self>
+    where
+        S: TryStream + 'static,
+        S::Ok: Into<Vec<u8>>,
+        S::Error: Into<Error>,
+    {
+        let stream = stream
+            .map_ok(|chunk| -> Vec<u8> { chunk.into() })
+            .map_err(|err| -> Error { err.into() })
+            .into_stream();
+        let stream = crate::FixedLengthStream::wrap(stream, length).into_raw();
         let edge_res = web_sys::Response::new_with_opt_readable_stream(Some(&stream))?;
         Ok(Self::from(edge_res))
     }
 
+    /// Create a `Response` with a [`Blob`](crate::Blob) for the body, without copying it into
+    /// Wasm memory. The `Content-Type` header is set to the media type of the blob, if it has one.
+    pub fn from_blob(blob: &crate::Blob) -> Result<Self> {
+        let edge_res = web_sys::Response::new_with_opt_blob(Some(blob.inner()))?;
+        Ok(Self::from(edge_res))
+    }
+
+    /// Create a `Response` with a JSON array of the items of `stream`, serializing each item as
+    /// soon as it's ready instead of buffering the whole array, e.g. for large query results. Sets
+    /// the `Content-Type` header as `application/json`.
+    ///
+    /// An error of the stream, or an item that can't be serialized, aborts the body, which leaves
+    /// the client with an incomplete array.
+    pub fn from_json_stream<S>(stream: S) -> Result<Self>
+    where
+        S: TryStream + 'static,
+        S::Ok: Serialize,
+        S::Error: Into<Error>,
+    {
+        let mut headers = Headers::new();
+        headers.set(CONTENT_TYPE, "application/json")?;
+        Ok(Self::from_stream(json_array(stream))?.with_headers(headers))
+    }
+
     /// Create a `Response` using unprocessed text provided. Sets the associated `Content-Type`
     /// header for the `Response` as `text/plain; charset=utf-8`.
     pub fn ok(body: impl Into<String>) -> Result<Self> {
@@ -161,7 +206,9 @@ impl Response {
             body: ResponseBody::Body(body.into().into_bytes()),
             headers,
             status_code: 200,
+            #[cfg(feature = "websocket")]
             websocket: None,
+            extensions: Extensions::new(),
         })
     }
 
@@ -171,7 +218,9 @@ impl Response {
             body: ResponseBody::Empty,
             headers: Headers::new(),
             status_code: 200,
+            #[cfg(feature = "websocket")]
             websocket: None,
+            extensions: Extensions::new(),
         })
     }
 
@@ -188,7 +237,9 @@ impl Response {
             body: ResponseBody::Body(msg.into().into_bytes()),
             headers: Headers::new(),
             status_code: status,
+            #[cfg(feature = "websocket")]
             websocket: None,
+            extensions: Extensions::new(),
         })
     }
 
@@ -213,11 +264,46 @@ impl Response {
         }
     }
 
+    /// Create a `Response` which permanently redirects to the specified URL with status code 308,
+    /// which keeps the method and body of the request.
+    pub fn redirect_permanent(url: url::Url) -> Result<Self> {
+        Self::redirect_with_status(url, 308)
+    }
+
+    /// Create a `Response` which temporarily redirects to the specified URL with status code 307,
+    /// which keeps the method and body of the request.
+    pub fn redirect_temporary(url: url::Url) -> Result<Self> {
+        Self::redirect_with_status(url, 307)
+    }
+
+    /// Create a `Response` which redirects to the specified URL with status code 303, which is
+    /// followed with a `GET`, e.g. after a form was submitted.
+    pub fn redirect_see_other(url: url::Url) -> Result<Self> {
+        Self::redirect_with_status(url, 303)
+    }
+
+    /// Create a `Response` with `status`, whose body is the reason phrase of the status for client
+    /// and server errors, e.g. `Not Found`, and empty otherwise.
+    pub fn from_status(status: StatusCode) -> Result<Self> {
+        let resp = match status.canonical_reason() {
+            Some(reason) if status.is_client_error() || status.is_server_error() => {
+                Self::ok(reason)?
+            }
+            _ => Self::empty()?,
+        };
+        Ok(resp.with_status_code(status))
+    }
+
     /// Get the HTTP Status code of this `Response`.
     pub fn status_code(&self) -> u16 {
         self.status_code
     }
 
+    /// Get the HTTP status of this `Response` as a [`StatusCode`].
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::Other(self.status_code))
+    }
+
     /// Access this response's body
     pub fn body(&self) -> &ResponseBody {
         &self.body
@@ -262,6 +348,35 @@ impl Response {
         }
     }
 
+    /// Access this response's body as raw bytes, failing with [`Error::BodyTooLarge`] if it's
+    /// longer than `limit` bytes. The `Content-Length` header is checked before anything is read,
+    /// and a streamed body is only read until it exceeds the limit.
+    pub async fn bytes_with_limit(&mut self, limit: u64) -> Result<Vec<u8>> {
+        let content_length = self
+            .headers()
+            .get("Content-Length")?
+            .and_then(|length| length.parse::<u64>().ok());
+        if content_length.map_or(false, |length| length > limit) {
+            return Err(Error::BodyTooLarge(limit));
+        }
+        match &self.body {
+            ResponseBody::Body(bytes) if bytes.len() as u64 > limit => {
+                Err(Error::BodyTooLarge(limit))
+            }
+            ResponseBody::Body(bytes) => Ok(bytes.clone()),
+            ResponseBody::Empty => Ok(Vec::new()),
+            ResponseBody::Stream(_) => {
+                self.stream()?
+                    .limit(limit)
+                    .try_fold(Vec::new(), |mut bytes, mut chunk| async move {
+                        bytes.append(&mut chunk);
+                        Ok(bytes)
+                    })
+                    .await
+            }
+        }
+    }
+
     /// Access this response's body as a [`Stream`](futures::stream::Stream) of bytes.
     pub fn stream(&mut self) -> Result<ByteStream> {
         let stream = match &self.body {
@@ -269,14 +384,27 @@ impl Response {
             _ => return Err(Error::RustError("body is not streamable".into())),
         };
 
-        let stream = wasm_streams::ReadableStream::from_raw(stream.dyn_into().unwrap());
+        Ok(ByteStream::from_raw(stream))
+    }
 
-        Ok(ByteStream {
-            inner: stream.into_stream(),
-        })
+    /// Deserialize this response's body as [NDJSON](crate::rows), one row per line.
+    #[cfg(feature = "ndjson")]
+    pub fn ndjson_rows<T: DeserializeOwned>(
+        &mut self,
+    ) -> Result<impl Stream<Item = Result<T>> + 'static> {
+        Ok(crate::rows::ndjson(self.stream()?.text()))
+    }
+
+    /// Deserialize this response's body as [CSV](crate::rows), with a header row.
+    #[cfg(feature = "csv")]
+    pub fn csv_rows<T: DeserializeOwned>(
+        &mut self,
+    ) -> Result<crate::rows::CsvRows<crate::TextDecoderStream<ByteStream>, T>> {
+        Ok(crate::rows::CsvRows::new(self.stream()?.text()))
     }
 
     // Get the WebSocket returned by the the server.
+    #[cfg(feature = "websocket")]
     pub fn websocket(self) -> Option<WebSocket> {
         self.websocket
     }
@@ -287,6 +415,13 @@ impl Response {
         self
     }
 
+    /// Remove the body of this response, keeping its status and headers, e.g. to answer a HEAD
+    /// request.
+    pub(crate) fn without_body(mut self) -> Self {
+        self.body = ResponseBody::Empty;
+        self
+    }
+
     /// Set this response's status code.
     /// The Workers platform will reject HTTP status codes outside the range of 200..599 inclusive,
     /// and will throw a JavaScript `RangeError`, returning a response with an HTTP 500 status code.
@@ -295,6 +430,11 @@ impl Response {
         self
     }
 
+    /// Set this response's status, see [`with_status`](Response::with_status).
+    pub fn with_status_code(self, status: StatusCode) -> Self {
+        self.with_status(status.as_u16())
+    }
+
     /// Sets this response's cors headers from the `Cors` struct.
     /// Example usage:
     /// ```
@@ -311,8 +451,16 @@ impl Response {
         Ok(self.with_headers(headers))
     }
 
+    /// Adds the `Link` headers of `hints`, which Cloudflare uses to send `103 Early Hints`.
+    pub fn with_early_hints(self, hints: &EarlyHints) -> Result<Self> {
+        let mut headers = self.headers.clone();
+        hints.apply_headers(&mut headers)?;
+        Ok(self.with_headers(headers))
+    }
+
     /// Sets this response's `webSocket` option.
     /// This will require a status code 101 to work.
+    #[cfg(feature = "websocket")]
     pub fn with_websocket(mut self, websocket: Option<WebSocket>) -> Self {
         self.websocket = websocket;
         self
@@ -328,8 +476,14 @@ impl Response {
         &mut self.headers
     }
 
+    /// Add a `Set-Cookie` header for `cookie`.
+    pub fn add_cookie(&mut self, cookie: &Cookie) -> Result<()> {
+        self.headers.append("Set-Cookie", &cookie.to_string())
+    }
+
     /// Clones the response so it can be used multiple times.
     pub fn cloned(&mut self) -> Result<Self> {
+        #[cfg(feature = "websocket")]
         if self.websocket.is_some() {
             return Err(Error::RustError("WebSockets cannot be cloned".into()));
         }
@@ -344,10 +498,60 @@ impl Response {
             None => ResponseBody::Empty,
         };
 
-        Ok(cloned.into())
+        let mut cloned: Response = cloned.into();
+        cloned.extensions = self.extensions.clone();
+        Ok(cloned)
+    }
+
+    /// Typed values attached to this `Response`, e.g. by [`Middleware`](crate::Middleware).
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Mutable access to the typed values attached to this `Response`.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
     }
 }
 
+/// The chunks of a JSON array of the items of `stream`.
+fn json_array<S>(stream: S) -> impl Stream<Item = Result<Vec<u8>>>
+where
+    S: TryStream,
+    S::Ok: Serialize,
+    S::Error: Into<Error>,
+{
+    let mut first = true;
+    let items = stream.map_err(Into::into).and_then(move |item| {
+        let mut chunk = if std::mem::take(&mut first) {
+            Vec::new()
+        } else {
+            b",".to_vec()
+        };
+        let result = serde_json::to_writer(&mut chunk, &item).map(|_| chunk);
+        future::ready(result.map_err(Error::from))
+    });
+    let start = stream::once(future::ready(Ok(b"[".to_vec())));
+    let end = stream::once(future::ready(Ok(b"]".to_vec())));
+    start.chain(items).chain(end)
+}
+
+#[test]
+fn json_array_works() {
+    use futures_util::FutureExt;
+
+    let collect = |items: Vec<Result<u32>>| {
+        json_array(stream::iter(items))
+            .map(|chunk| String::from_utf8(chunk.unwrap()).unwrap())
+            .collect::<String>()
+            .now_or_never()
+            .unwrap()
+    };
+    assert_eq!(collect(vec![]), "[]");
+    assert_eq!(collect(vec![Ok(1)]), "[1]");
+    assert_eq!(collect(vec![Ok(1), Ok(2), Ok(3)]), "[1,2,3]");
+}
+
 #[test]
 fn no_using_invalid_error_status_code() {
     assert!(Response::error("OK", 200).is_err());
@@ -358,6 +562,7 @@ fn no_using_invalid_error_status_code() {
 pub struct ResponseInit {
     pub status: u16,
     pub headers: Headers,
+    #[cfg(feature = "websocket")]
     pub websocket: Option<WebSocket>,
 }
 
@@ -366,6 +571,7 @@ impl From<ResponseInit> for web_sys::ResponseInit {
         let mut edge_init = web_sys::ResponseInit::new();
         edge_init.status(init.status);
         edge_init.headers(&init.headers.0);
+        #[cfg(feature = "websocket")]
         if let Some(websocket) = &init.websocket {
             edge_init.websocket(websocket.as_ref());
         }
@@ -384,6 +590,7 @@ impl From<Response> for web_sys::Response {
                     &ResponseInit {
                         status: res.status_code,
                         headers: res.headers,
+                        #[cfg(feature = "websocket")]
                         websocket: res.websocket,
                     }
                     .into(),
@@ -396,6 +603,7 @@ impl From<Response> for web_sys::Response {
                     &ResponseInit {
                         status: res.status_code,
                         headers: res.headers,
+                        #[cfg(feature = "websocket")]
                         websocket: res.websocket,
                     }
                     .into(),
@@ -407,6 +615,7 @@ impl From<Response> for web_sys::Response {
                 &ResponseInit {
                     status: res.status_code,
                     headers: res.headers,
+                    #[cfg(feature = "websocket")]
                     websocket: res.websocket,
                 }
                 .into(),
@@ -427,6 +636,7 @@ impl From<&Response> for web_sys::Response {
                     &ResponseInit {
                         status: res.status_code,
                         headers: res.headers.clone(),
+                        #[cfg(feature = "websocket")]
                         websocket: res.websocket.clone(),
                     }
                     .into(),
@@ -439,6 +649,7 @@ impl From<&Response> for web_sys::Response {
                     &ResponseInit {
                         status: res.status_code,
                         headers: res.headers.clone(),
+                        #[cfg(feature = "websocket")]
                         websocket: res.websocket.clone(),
                     }
                     .into(),
@@ -450,6 +661,7 @@ impl From<&Response> for web_sys::Response {
                 &ResponseInit {
                     status: res.status_code,
                     headers: res.headers.clone(),
+                    #[cfg(feature = "websocket")]
                     websocket: res.websocket.clone(),
                 }
                 .into(),
@@ -464,7 +676,9 @@ impl From<web_sys::Response> for Response {
         Self {
             headers: Headers(res.headers()),
             status_code: res.status(),
+            #[cfg(feature = "websocket")]
             websocket: res.websocket().map(|ws| ws.into()),
+            extensions: Extensions::new(),
             body: match res.body() {
                 Some(stream) => ResponseBody::Stream(stream),
                 None => ResponseBody::Empty,
@@ -0,0 +1,257 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc, time::Duration};
+
+use futures_util::future::LocalBoxFuture;
+
+use crate::{
+    Cache, CacheKeyBuilder, Context, Date, Headers, Method, Middleware, Next, Request, Response,
+    Result, RouteContext,
+};
+
+/// When the response was stored, in milliseconds since the Unix epoch.
+const STORED_AT_HEADER: &str = "X-Response-Cache-Stored-At";
+/// The `Cache-Control` header of the handler's response, which is replaced while it's cached.
+const CACHE_CONTROL_HEADER: &str = "X-Response-Cache-Control";
+/// Whether a response was served from the cache: `HIT`, `STALE` or `MISS`.
+pub(crate) const STATUS_HEADER: &str = "X-Cache-Status";
+
+thread_local! {
+    /// The keys whose responses are being revalidated by this isolate.
+    static REVALIDATING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// A [`Middleware`] which caches the responses to `GET` requests in the
+/// [default cache](Cache::default) of the data center.
+///
+/// Responses are fresh for the TTL, after which they are served stale for the
+/// `stale-while-revalidate` period while the handler runs again in the background, through
+/// [`Context::wait_until`]. A response is only revalidated by one request of an isolate at a
+/// time, the others are served the stale response without running the handler. Only `200`
+/// responses without `Set-Cookie` and without a `Cache-Control` header forbidding it
+/// (`no-store`, `no-cache` or `private`) are cached.
+///
+/// Responses are keyed by their URL and the values of the [`vary`](ResponseCache::vary)
+/// headers, as the Cache API ignores the `Vary` header, or by the [`keys`](ResponseCache::keys)
+/// given. Requests which are authenticated by default [`CacheKeyBuilder`] rules go straight to
+/// the handler. Cached responses have an `Age` header and an `X-Cache-Status` header of `HIT` or
+/// `STALE`, fresh ones `X-Cache-Status: MISS`.
+///
+/// The background revalidation needs the handlers to outlive the request, so the middleware can
+/// only be used with a `Router<'static, D>`.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use std::time::Duration;
+/// # async fn example(req: Request, env: Env, ctx: Context) -> Result<Response> {
+/// Router::new()
+///     .middleware(
+///         ResponseCache::new(&ctx)
+///             .ttl(Duration::from_secs(60))
+///             .stale_while_revalidate(Duration::from_secs(600))
+///             .vary("Accept-Language"),
+///     )
+///     .get("/", |_, _| Response::ok("expensive"))
+///     .run(req, env)
+///     .await
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ResponseCache {
+    ctx: Rc<Context>,
+    ttl: Duration,
+    stale_while_revalidate: Duration,
+    keys: CacheKeyBuilder,
+}
+
+impl ResponseCache {
+    /// A cache which keeps responses fresh for 60 seconds and doesn't serve stale responses.
+    pub fn new(ctx: &Context) -> Self {
+        Self {
+            ctx: Rc::new(ctx.clone()),
+            ttl: Duration::from_secs(60),
+            stale_while_revalidate: Duration::ZERO,
+            keys: CacheKeyBuilder::new(),
+        }
+    }
+
+    /// How long responses are served without running the handler.
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// How long responses are served after their TTL while they're revalidated in the
+    /// background.
+    #[must_use]
+    pub fn stale_while_revalidate(mut self, period: Duration) -> Self {
+        self.stale_while_revalidate = period;
+        self
+    }
+
+    /// Cache a response for each value of the request header `name`.
+    #[must_use]
+    pub fn vary(mut self, name: &str) -> Self {
+        self.keys = self.keys.header(name);
+        self
+    }
+
+    /// Key the responses with `keys`, e.g. to drop tracking query parameters or to skip the
+    /// requests with a session cookie.
+    #[must_use]
+    pub fn keys(mut self, keys: CacheKeyBuilder) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Store `resp` in the cache in the background.
+    fn store(&self, key: String, resp: &mut Response) -> Result<()> {
+        let stored = to_store(resp, self.ttl + self.stale_while_revalidate)?;
+        self.ctx.wait_until(async move {
+            let _ = Cache::default().put(key, stored).await;
+        });
+        Ok(())
+    }
+
+    async fn revalidate<D: 'static>(
+        self,
+        key: String,
+        req: Request,
+        ctx: RouteContext<D>,
+        next: Next<'static, D>,
+    ) -> Result<()> {
+        let mut resp = next.run(req, ctx).await?;
+        if is_cacheable(&resp)? {
+            self.store(key, &mut resp)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `resp` can be cached: a `200` without `Set-Cookie` whose `Cache-Control` allows it.
+pub(crate) fn is_cacheable(resp: &Response) -> Result<bool> {
+    if resp.status_code() != 200 || resp.headers().has("Set-Cookie")? {
+        return Ok(false);
+    }
+    let cache_control = resp
+        .headers()
+        .get("Cache-Control")?
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    Ok(!["no-store", "no-cache", "private"]
+        .iter()
+        .any(|directive| cache_control.contains(directive)))
+}
+
+/// A copy of `resp` to put in the cache, kept for `max_age` and stamped with the time it's
+/// stored, with the handler's `Cache-Control` set aside.
+pub(crate) fn to_store(resp: &mut Response, max_age: Duration) -> Result<Response> {
+    let stored = resp.cloned()?;
+    let mut headers: Headers = stored.headers().entries().collect();
+    if let Some(cache_control) = headers.get("Cache-Control")? {
+        headers.set(CACHE_CONTROL_HEADER, &cache_control)?;
+    }
+    let max_age = max_age.as_secs().max(1);
+    headers.set("Cache-Control", &format!("public, max-age={max_age}"))?;
+    headers.set(STORED_AT_HEADER, &Date::now().as_millis().to_string())?;
+    Ok(stored.with_headers(headers))
+}
+
+/// How long ago a response returned by [`to_store`] was stored.
+pub(crate) fn age(cached: &Response) -> Result<Option<Duration>> {
+    Ok(cached
+        .headers()
+        .get(STORED_AT_HEADER)?
+        .and_then(|stored_at| stored_at.parse::<u64>().ok())
+        .map(|stored_at| Duration::from_millis(Date::now().as_millis().saturating_sub(stored_at))))
+}
+
+/// The cached response as it's served: with the handler's `Cache-Control`, its age and status.
+pub(crate) fn serve(cached: Response, age: Duration, status: &str) -> Result<Response> {
+    let mut headers: Headers = cached.headers().entries().collect();
+    headers.delete("Cache-Control")?;
+    if let Some(cache_control) = headers.get(CACHE_CONTROL_HEADER)? {
+        headers.set("Cache-Control", &cache_control)?;
+    }
+    headers.delete(CACHE_CONTROL_HEADER)?;
+    headers.delete(STORED_AT_HEADER)?;
+    headers.set("Age", &age.as_secs().to_string())?;
+    headers.set(STATUS_HEADER, status)?;
+    Ok(cached.with_headers(headers))
+}
+
+impl<D: 'static> Middleware<'static, D> for ResponseCache {
+    fn handle(
+        &self,
+        req: Request,
+        ctx: RouteContext<D>,
+        next: Next<'static, D>,
+    ) -> LocalBoxFuture<'static, Result<Response>> {
+        let cache = self.clone();
+        Box::pin(async move {
+            if req.method() != Method::Get {
+                return next.run(req, ctx).await;
+            }
+            let Some(key) = cache.keys.build(&req)? else {
+                return next.run(req, ctx).await;
+            };
+
+            if let Some(cached) = Cache::default().get(key.as_str(), false).await? {
+                if let Some(age) = age(&cached)? {
+                    if age < cache.ttl {
+                        return serve(cached, age, "HIT");
+                    }
+                    if age < cache.ttl + cache.stale_while_revalidate {
+                        if let Some(revalidating) = Revalidating::start(&key) {
+                            let revalidation = cache.clone().revalidate(key, req, ctx, next);
+                            cache.ctx.wait_until(async move {
+                                if let Err(e) = revalidation.await {
+                                    worker_sys::console_error!(
+                                        "revalidating a cached response failed: {e}"
+                                    );
+                                }
+                                drop(revalidating);
+                            });
+                        }
+                        return serve(cached, age, "STALE");
+                    }
+                }
+            }
+
+            let mut resp = next.run(req, ctx).await?;
+            if is_cacheable(&resp)? {
+                cache.store(key, &mut resp)?;
+            }
+            resp.headers_mut().set(STATUS_HEADER, "MISS")?;
+            Ok(resp)
+        })
+    }
+}
+
+/// Marks the response of a key as being revalidated until it's dropped, so that concurrent
+/// requests for a stale response don't all revalidate it.
+pub(crate) struct Revalidating(String);
+
+impl Revalidating {
+    /// Start revalidating the response of `key`, or `None` if it's already being revalidated.
+    pub(crate) fn start(key: &str) -> Option<Self> {
+        REVALIDATING
+            .with(|keys| keys.borrow_mut().insert(key.to_owned()))
+            .then(|| Self(key.to_owned()))
+    }
+}
+
+impl Drop for Revalidating {
+    fn drop(&mut self) {
+        REVALIDATING.with(|keys| keys.borrow_mut().remove(&self.0));
+    }
+}
+
+#[test]
+fn revalidates_once_at_a_time() {
+    let first = Revalidating::start("https://example.com/");
+    assert!(first.is_some());
+    assert!(Revalidating::start("https://example.com/").is_none());
+    assert!(Revalidating::start("https://example.com/other").is_some());
+    drop(first);
+    assert!(Revalidating::start("https://example.com/").is_some());
+}
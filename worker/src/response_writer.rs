@@ -0,0 +1,77 @@
+use futures_channel::mpsc::{self, Sender};
+use futures_util::future::poll_fn;
+
+use crate::{Error, Response, Result};
+
+/// The number of chunks buffered before [`ResponseWriter::write`] waits for the client.
+const BUFFER: usize = 16;
+
+/// A handle to write the body of a [`Response`] after it was returned from the handler, e.g. for
+/// long polling or to send a page progressively while its data is being loaded.
+///
+/// The response is sent as soon as it's returned, with its status and headers. The body ends
+/// when the writer is [closed](ResponseWriter::close) or dropped, and fails if it's
+/// [aborted](ResponseWriter::abort).
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(ctx: Context) -> Result<Response> {
+/// let (mut writer, resp) = ResponseWriter::new()?;
+/// ctx.wait_until(async move {
+///     for update in ["queued", "running", "done"] {
+///         if writer.write(format!("{update}\n")).await.is_err() {
+///             // The client went away.
+///             return;
+///         }
+///         Delay::from(std::time::Duration::from_secs(1)).await;
+///     }
+///     writer.close();
+/// });
+/// let mut headers = Headers::new();
+/// headers.set("Content-Type", "text/plain; charset=utf-8")?;
+/// Ok(resp.with_headers(headers))
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ResponseWriter {
+    sender: Sender<Result<Vec<u8>>>,
+}
+
+impl ResponseWriter {
+    /// A writer and the `200` response whose body it writes.
+    pub fn new() -> Result<(Self, Response)> {
+        let (sender, receiver) = mpsc::channel(BUFFER);
+        Ok((Self { sender }, Response::from_stream(receiver)?))
+    }
+
+    /// Write a chunk of the body, waiting if the client is reading slower than the body is
+    /// written. Fails once the client disconnected.
+    pub async fn write(&mut self, chunk: impl Into<Vec<u8>>) -> Result<()> {
+        poll_fn(|cx| self.sender.poll_ready(cx))
+            .await
+            .map_err(|_| closed())?;
+        self.sender
+            .start_send(Ok(chunk.into()))
+            .map_err(|_| closed())
+    }
+
+    /// Whether the client disconnected, or the writer was closed.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// End the body.
+    pub fn close(mut self) {
+        self.sender.close_channel();
+    }
+
+    /// End the body with an error, so that the client can tell it's incomplete.
+    pub fn abort(mut self, error: Error) {
+        let _ = self.sender.try_send(Err(error));
+        self.sender.close_channel();
+    }
+}
+
+fn closed() -> Error {
+    Error::RustError("the response body was closed".into())
+}
@@ -1,21 +1,24 @@
-use std::{collections::HashMap, future::Future, rc::Rc};
+use std::{cell::Cell, collections::HashMap, future::Future, rc::Rc, time::Duration};
 
-use futures_util::future::LocalBoxFuture;
+use futures_util::future::{self, Either, LocalBoxFuture};
 use matchit::{Match, Router as MatchItRouter};
 use worker_kv::KvStore;
 
+#[cfg(feature = "r2")]
+use crate::Bucket;
 use crate::{
     durable::ObjectNamespace,
     env::{Env, Secret, Var},
-    http::Method,
+    http::{Method, StatusCode},
     request::Request,
     response::Response,
-    Bucket, Fetcher, Result,
+    Cors, Delay, Error, Extensions, Fetcher, Result, UrlPattern, UrlPatternMatch,
 };
 
 type HandlerFn<D> = fn(Request, RouteContext<D>) -> Result<Response>;
 type AsyncHandlerFn<'a, D> =
     Rc<dyn 'a + Fn(Request, RouteContext<D>) -> LocalBoxFuture<'a, Result<Response>>>;
+type MiddlewareStack<'a, D> = Rc<Vec<Rc<dyn 'a + Middleware<'a, D>>>>;
 
 /// Represents the URL parameters parsed from the path, e.g. a route with "/user/:id" pattern would
 /// contain a single "id" key.
@@ -41,11 +44,88 @@ impl<D> Clone for Handler<'_, D> {
     }
 }
 
+/// Logic which runs around the route handler of every request processed by a [`Router`], such as
+/// authentication, logging or header manipulation. Middleware may short-circuit the request by
+/// returning a [`Response`] without calling [`Next::run`].
+///
+/// Any closure taking a [`Request`], a [`RouteContext`] and a [`Next`] and returning a `Future`
+/// implements this trait:
+///
+/// ```no_run
+/// # use worker::*;
+/// Router::new()
+///     .middleware(|req: Request, ctx, next: Next<'_, ()>| async move {
+///         console_log!("{} {}", req.method().to_string(), req.path());
+///         next.run(req, ctx).await
+///     })
+///     .get("/", |_, _| Response::ok("Hello"));
+/// ```
+pub trait Middleware<'a, D> {
+    fn handle(
+        &self,
+        req: Request,
+        ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> LocalBoxFuture<'a, Result<Response>>;
+}
+
+impl<'a, D, F, T> Middleware<'a, D> for F
+where
+    F: Fn(Request, RouteContext<D>, Next<'a, D>) -> T,
+    T: Future<Output = Result<Response>> + 'a,
+{
+    fn handle(
+        &self,
+        req: Request,
+        ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> LocalBoxFuture<'a, Result<Response>> {
+        Box::pin(self(req, ctx, next))
+    }
+}
+
+/// The remainder of the middleware chain for a request. Calling [`Next::run`] invokes the next
+/// registered middleware, or the matched route handler once all middleware has been visited.
+pub struct Next<'a, D> {
+    middleware: MiddlewareStack<'a, D>,
+    index: usize,
+    handler: Handler<'a, D>,
+}
+
+impl<'a, D: 'a> Next<'a, D> {
+    /// Pass the request on to the rest of the chain and return its `Response`.
+    pub async fn run(self, req: Request, ctx: RouteContext<D>) -> Result<Response> {
+        match self.middleware.get(self.index).cloned() {
+            Some(middleware) => {
+                let next = Next {
+                    middleware: self.middleware,
+                    index: self.index + 1,
+                    handler: self.handler,
+                };
+                middleware.handle(req, ctx, next).await
+            }
+            None => match self.handler {
+                Handler::Sync(func) => (func)(req, ctx),
+                Handler::Async(func) => (func)(req, ctx).await,
+            },
+        }
+    }
+}
+
 /// A path-based HTTP router supporting exact-match or wildcard placeholders and shared data.
 pub struct Router<'a, D> {
     handlers: HashMap<Method, MatchItRouter<Handler<'a, D>>>,
     or_else_any_method: MatchItRouter<Handler<'a, D>>,
+    patterns: Vec<PatternRoute<'a, D>>,
+    middleware: Vec<Rc<dyn 'a + Middleware<'a, D>>>,
     data: D,
+    state: Extensions,
+    fallbacks: Fallbacks,
+    last_limits: Option<Rc<Cell<RouteLimits>>>,
+    #[cfg(feature = "openapi")]
+    spec: crate::openapi::Spec,
+    #[cfg(feature = "openapi")]
+    openapi_route: Option<(String, crate::openapi::Info)>,
 }
 
 /// Container for a route's parsed parameters, data, and environment bindings from the Runtime (such
@@ -54,9 +134,43 @@ pub struct RouteContext<D> {
     pub data: D,
     pub env: Env,
     params: RouteParams,
+    extensions: Extensions,
+    state: Extensions,
 }
 
 impl<D> RouteContext<D> {
+    /// Get the state of type `T` registered with [`Router::with_state`].
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// #[derive(Clone)]
+    /// struct Config {
+    ///     greeting: String,
+    /// }
+    ///
+    /// Router::new()
+    ///     .with_state(Config { greeting: "Hello".into() })
+    ///     .get("/", |_, ctx| Response::ok(&ctx.state::<Config>()?.greeting));
+    /// ```
+    pub fn state<T: 'static>(&self) -> Result<&T> {
+        self.state.get::<T>().ok_or_else(|| {
+            Error::RustError(format!(
+                "no state of type `{}` was registered with the router",
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
+    /// Typed values attached to this request by [`Middleware`], such as an authenticated user.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Mutable access to the typed values attached to this request.
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
     /// Get a reference to the generic associated data provided to the `Router`.
     #[deprecated(since = "0.0.8", note = "please use the `data` field directly")]
     pub fn data(&self) -> &D {
@@ -102,6 +216,7 @@ impl<D> RouteContext<D> {
     }
 
     /// Get a R2 Bucket associated with this Worker, should one exist.
+    #[cfg(feature = "r2")]
     pub fn bucket(&self, binding: &str) -> Result<Bucket> {
         self.env.bucket(binding)
     }
@@ -127,10 +242,119 @@ impl<'a, D: 'a> Router<'a, D> {
         Self {
             handlers: HashMap::new(),
             or_else_any_method: MatchItRouter::new(),
+            patterns: Vec::new(),
+            middleware: Vec::new(),
             data,
+            state: Extensions::new(),
+            fallbacks: Fallbacks {
+                auto_head: true,
+                auto_options: true,
+                cors: None,
+            },
+            last_limits: None,
+            #[cfg(feature = "openapi")]
+            spec: Default::default(),
+            #[cfg(feature = "openapi")]
+            openapi_route: None,
         }
     }
 
+    /// Register a value which handlers and middleware can get by its type with
+    /// [`RouteContext::state`]. Unlike the single `data` value, any number of values of different
+    /// types can be registered, e.g. clients of several services. Registering a value of the same
+    /// type again replaces the previous one.
+    pub fn with_state<T: Clone + 'static>(mut self, value: T) -> Self {
+        self.state.insert(value);
+        self
+    }
+
+    /// Whether HEAD requests to routes without a HEAD handler are answered by their GET handler,
+    /// with the body of its response removed. Enabled by default.
+    pub fn auto_head(mut self, enabled: bool) -> Self {
+        self.fallbacks.auto_head = enabled;
+        self
+    }
+
+    /// Whether OPTIONS requests to routes without an OPTIONS handler are answered with a `204`
+    /// response listing the methods of the route in its `Allow` header, which makes CORS
+    /// preflight requests work without a handler per route. Enabled by default.
+    pub fn auto_options(mut self, enabled: bool) -> Self {
+        self.fallbacks.auto_options = enabled;
+        self
+    }
+
+    /// Apply `cors` to the automatic OPTIONS responses. Unless `cors` sets the allowed methods,
+    /// `Access-Control-Allow-Methods` lists the methods of the route.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// Router::new()
+    ///     .cors(Cors::new().with_origins(["https://app.example"]).with_max_age(3600))
+    ///     .post("/api/items", |_, _| Response::ok("created"));
+    /// ```
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.fallbacks.cors = Some(cors);
+        self
+    }
+
+    /// Reject the requests to the route registered last whose body is larger than `bytes` with
+    /// `413 Content Too Large`, before calling its handler. The size is checked against the
+    /// `Content-Length` header, so requests with a body but without the header are rejected with
+    /// `411 Length Required`.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # use std::time::Duration;
+    /// Router::new()
+    ///     .post_async("/upload", |mut req, _| async move {
+    ///         Response::ok(format!("{} bytes", req.bytes().await?.len()))
+    ///     })
+    ///     .limit_body(1 << 20)
+    ///     .timeout(Duration::from_secs(5));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if no route was registered yet.
+    pub fn limit_body(self, bytes: usize) -> Self {
+        let limits = self.last_limits("limit_body");
+        limits.set(RouteLimits {
+            max_body: Some(bytes),
+            ..limits.get()
+        });
+        self
+    }
+
+    /// Answer the requests to the route registered last with `504 Gateway Timeout` if its
+    /// asynchronous handler doesn't respond within `timeout`, dropping the handler's future and
+    /// with it the subrequests it's waiting for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no route was registered yet.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        let limits = self.last_limits("timeout");
+        limits.set(RouteLimits {
+            timeout: Some(timeout),
+            ..limits.get()
+        });
+        self
+    }
+
+    fn last_limits(&self, method: &str) -> &Cell<RouteLimits> {
+        self.last_limits
+            .as_deref()
+            .unwrap_or_else(|| panic!("Router::{method} must follow the registration of a route"))
+    }
+
+    /// Register a [`Middleware`] that will run around every request handled by this `Router`,
+    /// including requests which don't match any route. Middleware runs in the order it was
+    /// registered.
+    pub fn middleware(mut self, middleware: impl Middleware<'a, D> + 'a) -> Self {
+        self.middleware.push(Rc::new(middleware));
+        self
+    }
+
     /// Register an HTTP handler that will exclusively respond to HEAD requests.
     pub fn head(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
         self.add_handler(pattern, Handler::Sync(func), vec![Method::Head]);
@@ -179,12 +403,48 @@ impl<'a, D: 'a> Router<'a, D> {
         self
     }
 
+    /// Serve the files of `dir` to `GET` and `HEAD` requests matching `pattern`, which should end
+    /// with a catch-all parameter such as `/*path`, from their plain and fingerprinted paths, see
+    /// [`EmbeddedDir::serve`](crate::assets::EmbeddedDir::serve). Files are looked up by the
+    /// catch-all parameter, so `/static/*path` serves `/static/css/main.css` from the
+    /// `/css/main.css` file of `dir`.
+    ///
+    /// ```ignore
+    /// static ASSETS: EmbeddedDir = embed_dir!("public");
+    ///
+    /// Router::new()
+    ///     .get("/api/status", |_, _| Response::ok("ok"))
+    ///     .assets("/static/*path", &ASSETS)
+    /// ```
+    ///
+    /// **Requires** `embed` feature.
+    #[cfg(feature = "embed")]
+    pub fn assets(mut self, pattern: &str, dir: &'static crate::assets::EmbeddedDir) -> Self {
+        let catch_all: Option<Rc<str>> = pattern
+            .rsplit_once('*')
+            .map(|(_, name)| name.into())
+            .filter(|name: &Rc<str>| !name.is_empty() && !name.contains('/'));
+        self.add_handler(
+            pattern,
+            Handler::Async(Rc::new(move |req, ctx| {
+                let path = match &catch_all {
+                    Some(name) => ctx.param(name).cloned().unwrap_or_default(),
+                    None => req.path(),
+                };
+                Box::pin(async move { dir.serve_path(&req, &path) })
+            })),
+            vec![Method::Get, Method::Head],
+        );
+        self
+    }
+
     /// Register an HTTP handler that will respond to all methods that are not handled explicitly by
     /// other handlers.
     pub fn or_else_any_method(mut self, pattern: &str, func: HandlerFn<D>) -> Self {
-        self.or_else_any_method
-            .insert(pattern, Handler::Sync(func))
-            .unwrap_or_else(|e| panic!("failed to register route for {} pattern: {}", pattern, e));
+        self.register(
+            RouteTarget::OrElseAnyMethod(pattern.into()),
+            Handler::Sync(func),
+        );
         self
     }
 
@@ -314,75 +574,423 @@ impl<'a, D: 'a> Router<'a, D> {
     where
         T: Future<Output = Result<Response>> + 'a,
     {
-        self.or_else_any_method
-            .insert(
-                pattern,
-                Handler::Async(Rc::new(move |req, route| Box::pin(func(req, route)))),
-            )
-            .unwrap_or_else(|e| panic!("failed to register route for {} pattern: {}", pattern, e));
+        self.register(
+            RouteTarget::OrElseAnyMethod(pattern.into()),
+            Handler::Async(Rc::new(move |req, route| Box::pin(func(req, route)))),
+        );
+        self
+    }
+
+    /// Register a handler for requests with `method` whose URL matches `pattern`, which can match
+    /// on any part of the URL and constrain groups with regular expressions. The groups are the
+    /// route's parameters, and the [`UrlPatternMatch`] is in the [extensions](RouteContext::extensions)
+    /// of the request.
+    ///
+    /// Patterns are tried in the order they were registered, before the routes using paths.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # fn example() -> Result<()> {
+    /// Router::new().pattern(
+    ///     Method::Get,
+    ///     UrlPattern::new("https://:tenant.example.com/users/:id(\\d+)")?,
+    ///     |_, ctx| {
+    ///         let tenant = ctx.param("tenant").unwrap();
+    ///         Response::ok(format!("user {} of {tenant}", ctx.param("id").unwrap()))
+    ///     },
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pattern(mut self, method: Method, pattern: UrlPattern, func: HandlerFn<D>) -> Self {
+        self.register(RouteTarget::Pattern(method, pattern), Handler::Sync(func));
+        self
+    }
+
+    /// Register an asynchronous handler for requests with `method` whose URL matches `pattern`,
+    /// see [`Router::pattern`].
+    pub fn pattern_async<T>(
+        mut self,
+        method: Method,
+        pattern: UrlPattern,
+        func: fn(Request, RouteContext<D>) -> T,
+    ) -> Self
+    where
+        T: Future<Output = Result<Response>> + 'a,
+    {
+        self.register(
+            RouteTarget::Pattern(method, pattern),
+            Handler::Async(Rc::new(move |req, info| Box::pin(func(req, info)))),
+        );
+        self
+    }
+
+    /// Describe the route registered for `method` and `pattern` in the OpenAPI document served
+    /// by [`Router::openapi`].
+    ///
+    /// **Requires** `openapi` feature.
+    #[cfg(feature = "openapi")]
+    pub fn document(
+        mut self,
+        method: Method,
+        pattern: &str,
+        operation: crate::openapi::Operation,
+    ) -> Self {
+        self.spec.document(&method, pattern, operation);
+        self
+    }
+
+    /// Serve an OpenAPI document describing the routes of this `Router` at `path`, see
+    /// [`openapi`](crate::openapi).
+    ///
+    /// **Requires** `openapi` feature.
+    #[cfg(feature = "openapi")]
+    pub fn openapi(mut self, path: &str, info: crate::openapi::Info) -> Self {
+        self.openapi_route = Some((path.into(), info));
         self
     }
 
+    #[cfg(feature = "openapi")]
+    fn add_openapi_route(&mut self) {
+        if let Some((path, info)) = self.openapi_route.take() {
+            // The document is only generated when it's requested.
+            let spec = std::mem::take(&mut self.spec);
+            let handler = Handler::Async(Rc::new(move |_, _| {
+                let resp = Response::from_json(&spec.to_json(&info));
+                Box::pin(async move { resp })
+            }));
+            self.add_handler(&path, handler, vec![Method::Get]);
+        }
+    }
+
     fn add_handler(&mut self, pattern: &str, func: Handler<'a, D>, methods: Vec<Method>) {
-        for method in methods {
-            self.handlers
-                .entry(method.clone())
-                .or_default()
-                .insert(pattern, func.clone())
+        #[cfg(feature = "openapi")]
+        self.spec.add_route(&methods, pattern);
+        self.register(RouteTarget::Methods(pattern.into(), methods), func);
+    }
+
+    /// Add `handler` to the handlers, with limits which can be set until the next route is
+    /// registered.
+    fn register(&mut self, target: RouteTarget, handler: Handler<'a, D>) {
+        let limits = Rc::new(Cell::new(RouteLimits::default()));
+        let handler = RouteLimits::wrap(limits.clone(), handler);
+        self.last_limits = Some(limits);
+        match target {
+            RouteTarget::Methods(pattern, methods) => {
+                for method in methods {
+                    self.handlers
+                        .entry(method.clone())
+                        .or_default()
+                        .insert(&pattern, handler.clone())
+                        .unwrap_or_else(|e| {
+                            panic!(
+                                "failed to register {:?} route for {} pattern: {}",
+                                method, pattern, e
+                            )
+                        });
+                }
+            }
+            RouteTarget::OrElseAnyMethod(pattern) => self
+                .or_else_any_method
+                .insert(&pattern, handler)
                 .unwrap_or_else(|e| {
-                    panic!(
-                        "failed to register {:?} route for {} pattern: {}",
-                        method, pattern, e
-                    )
-                });
+                    panic!("failed to register route for {} pattern: {}", pattern, e)
+                }),
+            RouteTarget::Pattern(method, pattern) => self.patterns.push(PatternRoute {
+                method,
+                pattern,
+                handler,
+            }),
         }
     }
 
     /// Handle the request provided to the `Router` and return a `Future`.
-    pub async fn run(self, req: Request, env: Env) -> Result<Response> {
-        let (handlers, data, or_else_any_method_handler) = self.split();
-
-        if let Some(handlers) = handlers.get(&req.method()) {
-            if let Ok(Match { value, params }) = handlers.at(&req.path()) {
-                let route_info = RouteContext {
-                    data,
-                    env,
-                    params: params.into(),
-                };
-                return match value {
-                    Handler::Sync(func) => (func)(req, route_info),
-                    Handler::Async(func) => (func)(req, route_info).await,
+    pub async fn run(mut self, req: Request, env: Env) -> Result<Response> {
+        #[cfg(feature = "openapi")]
+        self.add_openapi_route();
+        let fallbacks = self.fallbacks.clone();
+        let (handlers, data, or_else_any_method_handler, patterns, middleware, state) =
+            self.split();
+        let mut extensions = Extensions::new();
+        let (handler, params) = match Self::resolve_pattern(&patterns, &req, &fallbacks) {
+            Some(PatternResolution::Matched(handler, matched)) => {
+                let params = RouteParams(matched.groups());
+                extensions.insert(matched);
+                (handler, params)
+            }
+            resolution => {
+                let pattern_allowed = match resolution {
+                    Some(PatternResolution::OtherMethod(allowed)) => allowed,
+                    _ => Vec::new(),
                 };
+                Self::resolve(
+                    &handlers,
+                    &or_else_any_method_handler,
+                    &req,
+                    &fallbacks,
+                    &pattern_allowed,
+                )
+                .unwrap_or_else(|| {
+                    let handler = if pattern_allowed.is_empty() {
+                        Handler::Sync(|_, _| Response::from_status(StatusCode::NotFound))
+                    } else {
+                        fallbacks.other_method(&req.method(), pattern_allowed)
+                    };
+                    (handler, RouteParams(HashMap::new()))
+                })
+            }
+        };
+
+        let route_info = RouteContext {
+            data,
+            env,
+            params,
+            extensions,
+            state,
+        };
+        let next = Next {
+            middleware: Rc::new(middleware),
+            index: 0,
+            handler,
+        };
+        match next.run(req, route_info).await {
+            Err(Error::Validation(violations)) => violations.to_response(),
+            result => result,
+        }
+    }
+
+    /// The handler for the request among the `matchit` routes. `pattern_allowed` are the methods
+    /// of the pattern routes which matched the request with another method, which the `Allow`
+    /// header lists along with the methods of the `matchit` routes matching the path.
+    fn resolve(
+        handlers: &HashMap<Method, NodeWithHandlers<'a, D>>,
+        or_else_any_method_handler: &NodeWithHandlers<'a, D>,
+        req: &Request,
+        fallbacks: &Fallbacks,
+        pattern_allowed: &[Method],
+    ) -> Option<(Handler<'a, D>, RouteParams)> {
+        let path = req.path();
+        let method = req.method();
+
+        if let Some(handlers) = handlers.get(&method) {
+            if let Ok(Match { value, params }) = handlers.at(&path) {
+                return Some((value.clone(), params.into()));
             }
         }
 
-        for method in Method::all() {
-            if method == Method::Head || method == Method::Options || method == Method::Trace {
-                continue;
+        if fallbacks.auto_head && method == Method::Head {
+            if let Some(Ok(Match { value, params })) = handlers
+                .get(&Method::Get)
+                .map(|handlers| handlers.at(&path))
+            {
+                return Some((head_from_get(value.clone()), params.into()));
             }
-            if let Some(handlers) = handlers.get(&method) {
-                if let Ok(Match { .. }) = handlers.at(&req.path()) {
-                    return Response::error("Method Not Allowed", 405);
+        }
+
+        let mut allowed: Vec<Method> = Method::all()
+            .into_iter()
+            .filter(|method| {
+                handlers
+                    .get(method)
+                    .map_or(false, |handlers| handlers.at(&path).is_ok())
+            })
+            .collect();
+        if !allowed.is_empty() {
+            allowed.extend(pattern_allowed.iter().cloned());
+        }
+        if allowed
+            .iter()
+            .any(|method| !matches!(method, Method::Head | Method::Options | Method::Trace))
+        {
+            return Some((
+                fallbacks.other_method(&method, allowed),
+                RouteParams(HashMap::new()),
+            ));
+        }
+
+        if let Ok(Match { value, params }) = or_else_any_method_handler.at(&path) {
+            return Some((value.clone(), params.into()));
+        }
+
+        None
+    }
+
+    /// The first pattern route matching the request, or the methods of the routes which matched
+    /// with another method.
+    fn resolve_pattern(
+        patterns: &[PatternRoute<'a, D>],
+        req: &Request,
+        fallbacks: &Fallbacks,
+    ) -> Option<PatternResolution<'a, D>> {
+        if patterns.is_empty() {
+            return None;
+        }
+        let url = req.url().ok()?;
+        let method = req.method();
+        let mut get_route = None;
+        let mut allowed = Vec::new();
+        for route in patterns {
+            if let Ok(Some(matched)) = route.pattern.exec(&url) {
+                if route.method == method {
+                    return Some(PatternResolution::Matched(route.handler.clone(), matched));
+                }
+                if fallbacks.auto_head && method == Method::Head && route.method == Method::Get {
+                    get_route.get_or_insert((route, matched));
                 }
+                allowed.push(route.method.clone());
             }
         }
+        if let Some((route, matched)) = get_route {
+            return Some(PatternResolution::Matched(
+                head_from_get(route.handler.clone()),
+                matched,
+            ));
+        }
+        (!allowed.is_empty()).then_some(PatternResolution::OtherMethod(allowed))
+    }
+}
 
-        if let Ok(Match { value, params }) = or_else_any_method_handler.at(&req.path()) {
-            let route_info = RouteContext {
-                data,
-                env,
-                params: params.into(),
-            };
-            return match value {
-                Handler::Sync(func) => (func)(req, route_info),
-                Handler::Async(func) => (func)(req, route_info).await,
-            };
+enum RouteTarget {
+    Methods(String, Vec<Method>),
+    OrElseAnyMethod(String),
+    Pattern(Method, UrlPattern),
+}
+
+/// The limits of a route, set with [`Router::limit_body`] and [`Router::timeout`].
+#[derive(Debug, Clone, Copy, Default)]
+struct RouteLimits {
+    max_body: Option<usize>,
+    timeout: Option<Duration>,
+}
+
+impl RouteLimits {
+    /// `handler`, checking the limits in `limits` when it's called, so that they can be set after
+    /// the route is added.
+    fn wrap<'a, D: 'a>(limits: Rc<Cell<RouteLimits>>, handler: Handler<'a, D>) -> Handler<'a, D> {
+        Handler::Async(Rc::new(move |req, ctx| {
+            let handler = handler.clone();
+            let limits = limits.get();
+            Box::pin(async move {
+                if let Some(max_body) = limits.max_body {
+                    let content_length = req.headers().get("Content-Length")?;
+                    let has_body = req.inner().body().is_some();
+                    if let Some(status) =
+                        body_rejection(content_length.as_deref(), has_body, max_body)
+                    {
+                        return Response::from_status(status);
+                    }
+                }
+                let resp = match handler {
+                    // A synchronous handler can't be interrupted.
+                    Handler::Sync(func) => return (func)(req, ctx),
+                    Handler::Async(func) => (func)(req, ctx),
+                };
+                match limits.timeout {
+                    Some(timeout) => match future::select(resp, Delay::from(timeout)).await {
+                        Either::Left((resp, _)) => resp,
+                        Either::Right(_) => Response::from_status(StatusCode::GatewayTimeout),
+                    },
+                    None => resp.await,
+                }
+            })
+        }))
+    }
+}
+
+/// The status to reject a request with, given its `Content-Length` and whether it has a body.
+fn body_rejection(
+    content_length: Option<&str>,
+    has_body: bool,
+    max_body: usize,
+) -> Option<StatusCode> {
+    match content_length.map(|len| len.trim().parse::<usize>()) {
+        Some(Ok(len)) if len > max_body => Some(StatusCode::ContentTooLarge),
+        Some(Ok(_)) => None,
+        Some(Err(_)) => Some(StatusCode::BadRequest),
+        None if has_body => Some(StatusCode::LengthRequired),
+        None => None,
+    }
+}
+
+struct PatternRoute<'a, D> {
+    method: Method,
+    pattern: UrlPattern,
+    handler: Handler<'a, D>,
+}
+
+enum PatternResolution<'a, D> {
+    Matched(Handler<'a, D>, UrlPatternMatch),
+    OtherMethod(Vec<Method>),
+}
+
+/// The value of the `Allow` header for a path with handlers for `methods`.
+fn allow_header(methods: &[Method], auto_head: bool) -> String {
+    let head = auto_head && methods.contains(&Method::Get);
+    Method::all()
+        .into_iter()
+        .filter(|method| methods.contains(method) || (head && *method == Method::Head))
+        .map(|method| method.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// How a router answers requests which don't match a route by their method.
+#[derive(Clone)]
+struct Fallbacks {
+    auto_head: bool,
+    auto_options: bool,
+    cors: Option<Cors>,
+}
+
+impl Fallbacks {
+    /// The handler for a request with `method` to a path which only has routes for `allowed`.
+    fn other_method<'a, D>(&self, method: &Method, mut allowed: Vec<Method>) -> Handler<'a, D> {
+        if !(self.auto_options && *method == Method::Options) {
+            let allow = allow_header(&allowed, self.auto_head);
+            return Handler::Async(Rc::new(move |_, _| {
+                let allow = allow.clone();
+                Box::pin(async move {
+                    let mut resp = Response::from_status(StatusCode::MethodNotAllowed)?;
+                    resp.headers_mut().set("Allow", &allow)?;
+                    Ok(resp)
+                })
+            }));
         }
 
-        Response::error("Not Found", 404)
+        allowed.push(Method::Options);
+        let allow = allow_header(&allowed, self.auto_head);
+        let cors = self.cors.clone();
+        Handler::Async(Rc::new(move |_, _| {
+            let allow = allow.clone();
+            let cors = cors.clone();
+            Box::pin(async move {
+                let mut resp = Response::from_status(StatusCode::NoContent)?;
+                let headers = resp.headers_mut();
+                headers.set("Allow", &allow)?;
+                if let Some(cors) = cors {
+                    headers.set("Access-Control-Allow-Methods", &allow)?;
+                    cors.apply_headers(headers)?;
+                }
+                Ok(resp)
+            })
+        }))
     }
 }
 
+/// Answer HEAD requests with the response of a GET handler, without its body.
+fn head_from_get<'a, D: 'a>(handler: Handler<'a, D>) -> Handler<'a, D> {
+    Handler::Async(Rc::new(move |req, ctx| {
+        let handler = handler.clone();
+        Box::pin(async move {
+            let resp = match handler {
+                Handler::Sync(func) => (func)(req, ctx)?,
+                Handler::Async(func) => (func)(req, ctx).await?,
+            };
+            Ok(resp.without_body())
+        })
+    }))
+}
+
 type NodeWithHandlers<'a, D> = MatchItRouter<Handler<'a, D>>;
 
 impl<'a, D: 'a> Router<'a, D> {
@@ -392,8 +1000,18 @@ impl<'a, D: 'a> Router<'a, D> {
         HashMap<Method, NodeWithHandlers<'a, D>>,
         D,
         NodeWithHandlers<'a, D>,
+        Vec<PatternRoute<'a, D>>,
+        Vec<Rc<dyn 'a + Middleware<'a, D>>>,
+        Extensions,
     ) {
-        (self.handlers, self.data, self.or_else_any_method)
+        (
+            self.handlers,
+            self.data,
+            self.or_else_any_method,
+            self.patterns,
+            self.middleware,
+            self.state,
+        )
     }
 }
 
@@ -407,3 +1025,60 @@ impl From<matchit::Params<'_, '_>> for RouteParams {
         route_params
     }
 }
+
+/// Percent-encodes `value` as the value of a route parameter, keeping the `/`s of a catch-all
+/// parameter. Used by the paths built by [`routes!`](macro@crate::routes).
+#[doc(hidden)]
+pub fn encode_route_param(value: &str, catch_all: bool) -> String {
+    uri_encode(value, !catch_all)
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986, and `/` unless
+/// `encode_slash`.
+pub(crate) fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[test]
+fn allow_header_works() {
+    let methods = [Method::Post, Method::Get];
+    assert_eq!(allow_header(&methods, true), "HEAD, GET, POST");
+    assert_eq!(allow_header(&methods, false), "GET, POST");
+    assert_eq!(allow_header(&[Method::Delete], true), "DELETE");
+}
+
+#[test]
+fn body_rejection_works() {
+    assert_eq!(body_rejection(Some("1024"), true, 1024), None);
+    assert_eq!(
+        body_rejection(Some("1025"), true, 1024),
+        Some(StatusCode::ContentTooLarge)
+    );
+    assert_eq!(
+        body_rejection(Some("lots"), true, 1024),
+        Some(StatusCode::BadRequest)
+    );
+    assert_eq!(
+        body_rejection(None, true, 1024),
+        Some(StatusCode::LengthRequired)
+    );
+    assert_eq!(body_rejection(None, false, 1024), None);
+}
+
+#[test]
+fn encode_route_param_works() {
+    assert_eq!(encode_route_param("42", false), "42");
+    assert_eq!(encode_route_param("a b/c?", false), "a%20b%2Fc%3F");
+    assert_eq!(encode_route_param("docs/a b.txt", true), "docs/a%20b.txt");
+    assert_eq!(encode_route_param("é", false), "%C3%A9");
+}
@@ -0,0 +1,235 @@
+//! Incremental parsers of [NDJSON](https://github.com/ndjson/ndjson-spec) and CSV bodies, which
+//! deserialize each row as soon as it's read instead of buffering the whole body, e.g. for Workers
+//! transforming large exports.
+//!
+//! **Requires** the `ndjson` or `csv` feature.
+//!
+//! ```no_run
+//! # use worker::*;
+//! # use futures_util::TryStreamExt;
+//! #[derive(serde::Deserialize)]
+//! struct Order {
+//!     id: u64,
+//!     total: f64,
+//! }
+//!
+//! # async fn example(url: &str) -> Result<()> {
+//! let mut export = Fetch::Url(url.parse()?).send().await?;
+//! let total = export
+//!     .csv_rows::<Order>()?
+//!     .try_fold(0.0, |total, order| async move { Ok(total + order.total) })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "csv")]
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_util::Stream;
+#[cfg(feature = "ndjson")]
+use futures_util::{future, StreamExt, TryStreamExt};
+#[cfg(feature = "csv")]
+use pin_project::pin_project;
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "csv")]
+use crate::Error;
+#[cfg(feature = "ndjson")]
+use crate::Lines;
+use crate::Result;
+
+/// Deserialize each non-empty line of `text` from JSON.
+#[cfg(feature = "ndjson")]
+pub fn ndjson<T, S>(text: S) -> impl Stream<Item = Result<T>>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<String>>,
+{
+    Lines::new(text)
+        .try_filter(|line| future::ready(!line.trim().is_empty()))
+        .map(|line| -> Result<T> { Ok(serde_json::from_str(&line?)?) })
+}
+
+/// A stream of the rows deserialized from a stream of CSV text. By default, the first row is read
+/// as the header of the others, whose fields are deserialized by name into structs.
+#[cfg(feature = "csv")]
+#[pin_project]
+pub struct CsvRows<S, T> {
+    #[pin]
+    inner: S,
+    delimiter: u8,
+    has_headers: bool,
+    headers: Option<csv::StringRecord>,
+    buffer: String,
+    /// How far `buffer` was scanned for the end of a record, and whether that's within quotes.
+    scanned: usize,
+    quoted: bool,
+    done: bool,
+    _row: PhantomData<T>,
+}
+
+#[cfg(feature = "csv")]
+impl<S, T> CsvRows<S, T> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            delimiter: b',',
+            has_headers: true,
+            headers: None,
+            buffer: String::new(),
+            scanned: 0,
+            quoted: false,
+            done: false,
+            _row: PhantomData,
+        }
+    }
+
+    /// The field delimiter, `,` by default.
+    #[must_use]
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether the first row is a header, `true` by default. Without headers, rows can only be
+    /// deserialized by position, e.g. into tuples.
+    #[must_use]
+    pub fn has_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+}
+
+#[cfg(feature = "csv")]
+fn csv_error(e: csv::Error) -> Error {
+    Error::RustError(format!("invalid CSV: {e}"))
+}
+
+#[cfg(feature = "csv")]
+impl<S, T> Stream for CsvRows<S, T>
+where
+    S: Stream<Item = Result<String>>,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            // A record ends at a newline which isn't within quotes: escaped quotes are doubled,
+            // which toggles the state twice.
+            let mut end = None;
+            for (i, byte) in this.buffer.bytes().enumerate().skip(*this.scanned) {
+                match byte {
+                    b'"' => *this.quoted = !*this.quoted,
+                    b'\n' if !*this.quoted => {
+                        end = Some(i + 1);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            let record: String = match end {
+                Some(end) => this.buffer.drain(..end).collect(),
+                None if *this.done => std::mem::take(this.buffer),
+                None => {
+                    *this.scanned = this.buffer.len();
+                    match futures_util::ready!(this.inner.as_mut().poll_next(cx)) {
+                        Some(Ok(text)) => this.buffer.push_str(&text),
+                        Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                        None => *this.done = true,
+                    }
+                    continue;
+                }
+            };
+            *this.scanned = 0;
+            *this.quoted = false;
+
+            if record.trim().is_empty() {
+                if *this.done && this.buffer.is_empty() {
+                    return Poll::Ready(None);
+                }
+                continue;
+            }
+            let record = csv::ReaderBuilder::new()
+                .delimiter(*this.delimiter)
+                .has_headers(false)
+                .from_reader(record.as_bytes())
+                .into_records()
+                .next()
+                .transpose()
+                .map_err(csv_error);
+            let record = match record {
+                Ok(Some(record)) => record,
+                Ok(None) => continue,
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            };
+            if *this.has_headers && this.headers.is_none() {
+                *this.headers = Some(record);
+                continue;
+            }
+            let row = record.deserialize(this.headers.as_ref()).map_err(csv_error);
+            return Poll::Ready(Some(row));
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ndjson"))]
+#[test]
+fn ndjson_works() {
+    use futures_util::FutureExt;
+
+    let chunks = ["{\"a\":1}\n\n{\"a\"", ":2}\r\n", "{\"a\":3}"];
+    let rows: Vec<serde_json::Value> = ndjson(futures_util::stream::iter(
+        chunks.map(|chunk| Ok(chunk.into())),
+    ))
+    .try_collect()
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2]["a"], 3);
+}
+
+#[cfg(all(test, feature = "csv"))]
+#[test]
+fn csv_rows_works() {
+    use futures_util::{FutureExt, TryStreamExt};
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Row {
+        name: String,
+        count: u32,
+    }
+
+    let chunks = [
+        "name,count\n\"Fer",
+        "ris, \"\"the\"\"\ncrab\",3\r\n",
+        "\nBob,4",
+    ];
+    let rows: Vec<Row> = CsvRows::new(futures_util::stream::iter(
+        chunks.map(|chunk| Ok(chunk.to_string())),
+    ))
+    .try_collect()
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        rows,
+        [
+            Row {
+                name: "Ferris, \"the\"\ncrab".into(),
+                count: 3
+            },
+            Row {
+                name: "Bob".into(),
+                count: 4
+            }
+        ]
+    );
+}
@@ -0,0 +1,117 @@
+use crate::{Env, Error, Result};
+
+/// The versions of a secret being rotated, newest first, see [`Env::secret_versions`].
+///
+/// During a rotation the secret holds both the new and the old values, so that what was signed
+/// or encrypted with the old one is still accepted while new work uses the new one. Once nothing
+/// depends on the old value anymore, it's removed from the secret.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretVersions {
+    versions: Vec<String>,
+}
+
+impl SecretVersions {
+    /// Parse the versions of a secret value, either a JSON array of strings or values separated
+    /// by commas, newest first.
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let versions: Vec<String> = if value.starts_with('[') {
+            serde_json::from_str(value)?
+        } else {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|version| !version.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        if versions.is_empty() {
+            return Err(Error::RustError("the secret has no versions".into()));
+        }
+        Ok(Self { versions })
+    }
+
+    /// The newest version, which new work should use.
+    pub fn current(&self) -> &str {
+        &self.versions[0]
+    }
+
+    /// Every version, newest first.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.versions.iter().map(String::as_str)
+    }
+
+    /// Sign `data` with the current version as an HMAC-SHA256 key.
+    #[cfg(feature = "crypto")]
+    pub async fn sign_hmac(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let algorithm = crate::crypto::algorithm("HMAC", Some("SHA-256"));
+        let key =
+            crate::crypto::import_raw_key(self.current().as_bytes(), &algorithm, &["sign"]).await?;
+        crate::crypto::sign(&algorithm, &key, data).await
+    }
+
+    /// Verify the HMAC-SHA256 `signature` of `data` with each version as the key in turn,
+    /// returning the index of the version which signed it, `0` being the current one, or `None`
+    /// if none did.
+    #[cfg(feature = "crypto")]
+    pub async fn verify_hmac(&self, signature: &[u8], data: &[u8]) -> Result<Option<usize>> {
+        let algorithm = crate::crypto::algorithm("HMAC", Some("SHA-256"));
+        for (index, version) in self.iter().enumerate() {
+            let key =
+                crate::crypto::import_raw_key(version.as_bytes(), &algorithm, &["verify"]).await?;
+            if crate::crypto::verify(&algorithm, &key, signature, data).await? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl std::fmt::Debug for SecretVersions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretVersions")
+            .field("len", &self.versions.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Env {
+    /// The versions of the secret `binding`, for secrets which are rotated without downtime.
+    /// The secret holds its versions newest first, separated by commas or as a JSON array:
+    ///
+    /// ```sh
+    /// echo '["new-key", "old-key"]' | wrangler secret put SIGNING_KEYS
+    /// ```
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn example(env: Env, signature: Vec<u8>, body: Vec<u8>) -> Result<()> {
+    /// let keys = env.secret_versions("SIGNING_KEYS")?;
+    /// match keys.verify_hmac(&signature, &body).await? {
+    ///     Some(0) => {}
+    ///     Some(_) => console_warn!("a request was signed with a key being rotated out"),
+    ///     None => return Err("invalid signature".into()),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn secret_versions(&self, binding: &str) -> Result<SecretVersions> {
+        SecretVersions::parse(&self.secret(binding)?.to_string())
+            .map_err(|e| Error::RustError(format!("invalid versions of secret {binding}: {e}")))
+    }
+}
+
+#[test]
+fn secret_versions_works() {
+    let versions = SecretVersions::parse(" new , old,").unwrap();
+    assert_eq!(versions.current(), "new");
+    assert_eq!(versions.iter().collect::<Vec<_>>(), ["new", "old"]);
+
+    let versions = SecretVersions::parse(r#"["a,b", "c"]"#).unwrap();
+    assert_eq!(versions.iter().collect::<Vec<_>>(), ["a,b", "c"]);
+    assert!(!format!("{versions:?}").contains("a,b"));
+
+    assert!(SecretVersions::parse("").is_err());
+    assert!(SecretVersions::parse("[]").is_err());
+    assert!(SecretVersions::parse("[1]").is_err());
+}
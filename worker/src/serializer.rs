@@ -0,0 +1,143 @@
+use std::cell::Cell;
+
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::Result;
+
+thread_local! {
+    static DEFAULT: Cell<SerializerOptions> = Cell::new(SerializerOptions::new());
+}
+
+/// How Rust values are converted into JS values by [`Queue::send`](crate::Queue::send),
+/// [`Storage::put`](crate::Storage::put) and the other methods taking a serializable value.
+///
+/// The defaults are those of [`serde_wasm_bindgen`]: maps become JS `Map`s, `u64` and `i64` values
+/// become numbers, which lose precision above 2^53, and bytes become `Uint8Array`s. They can be
+/// changed for the whole Worker with [`set_serializer_options`], or for a single call with the
+/// `_with_serializer` methods, e.g. [`Storage::put_with_serializer`](crate::Storage::put_with_serializer).
+/// KV values and metadata are stored as JSON, so they don't depend on these options.
+///
+/// ```no_run
+/// # use worker::*;
+/// // Large ids are stored as `BigInt`s, and maps as plain objects.
+/// set_serializer_options(
+///     SerializerOptions::new()
+///         .maps_as_objects(true)
+///         .large_numbers_as_bigints(true),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializerOptions {
+    missing_as_null: bool,
+    maps_as_objects: bool,
+    large_numbers_as_bigints: bool,
+    bytes_as_arrays: bool,
+}
+
+impl SerializerOptions {
+    /// The defaults of [`serde_wasm_bindgen`].
+    pub const fn new() -> Self {
+        Self {
+            missing_as_null: false,
+            maps_as_objects: false,
+            large_numbers_as_bigints: false,
+            bytes_as_arrays: false,
+        }
+    }
+
+    /// Values which are the same as their JSON, with `None` and `()` as `null`, maps as objects
+    /// and bytes as arrays of numbers.
+    pub const fn json_compatible() -> Self {
+        Self {
+            missing_as_null: true,
+            maps_as_objects: true,
+            large_numbers_as_bigints: false,
+            bytes_as_arrays: true,
+        }
+    }
+
+    /// Serialize `None` and `()` as `null` rather than `undefined`.
+    #[must_use]
+    pub fn missing_as_null(mut self, value: bool) -> Self {
+        self.missing_as_null = value;
+        self
+    }
+
+    /// Serialize maps as plain objects rather than `Map`s. Their keys must then be strings or
+    /// numbers.
+    #[must_use]
+    pub fn maps_as_objects(mut self, value: bool) -> Self {
+        self.maps_as_objects = value;
+        self
+    }
+
+    /// Serialize `u64`, `i64`, `u128` and `i128` values as `BigInt`s, so that they keep their
+    /// precision. They're read back into Rust either way.
+    #[must_use]
+    pub fn large_numbers_as_bigints(mut self, value: bool) -> Self {
+        self.large_numbers_as_bigints = value;
+        self
+    }
+
+    /// Serialize bytes as arrays of numbers rather than `Uint8Array`s.
+    #[must_use]
+    pub fn bytes_as_arrays(mut self, value: bool) -> Self {
+        self.bytes_as_arrays = value;
+        self
+    }
+
+    /// Convert `value` into a JS value with these options.
+    pub fn to_value<T: Serialize + ?Sized>(&self, value: &T) -> Result<JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::new()
+            .serialize_missing_as_null(self.missing_as_null)
+            .serialize_maps_as_objects(self.maps_as_objects)
+            .serialize_large_number_types_as_bigints(self.large_numbers_as_bigints)
+            .serialize_bytes_as_arrays(self.bytes_as_arrays);
+        Ok(value.serialize(&serializer)?)
+    }
+}
+
+impl Default for SerializerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Use `options` to serialize values for the rest of the Worker's lifetime, unless a call says
+/// otherwise.
+pub fn set_serializer_options(options: SerializerOptions) {
+    DEFAULT.with(|default| default.set(options));
+}
+
+/// The options set with [`set_serializer_options`].
+pub fn serializer_options() -> SerializerOptions {
+    DEFAULT.with(Cell::get)
+}
+
+/// Convert `value` into a JS value with the options of the Worker.
+pub(crate) fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue> {
+    serializer_options().to_value(value)
+}
+
+/// The approximate size of `value` once stored or sent: the length of strings and binary data,
+/// or of the JSON of other values, which approximates their structured clone.
+pub(crate) fn value_size(value: &JsValue) -> usize {
+    if let Some(text) = value.as_string() {
+        return text.len();
+    }
+    if let Some(buffer) = value.dyn_ref::<js_sys::ArrayBuffer>() {
+        return buffer.byte_length() as usize;
+    }
+    if js_sys::ArrayBuffer::is_view(value) {
+        return js_sys::Reflect::get(value, &"byteLength".into())
+            .ok()
+            .and_then(|len| len.as_f64())
+            .unwrap_or_default() as usize;
+    }
+    js_sys::JSON::stringify(value)
+        .ok()
+        .and_then(|json| json.as_string())
+        .map(|json| json.len())
+        .unwrap_or_default()
+}
@@ -0,0 +1,399 @@
+//! Server-side sessions, identified by a cookie and stored in KV or Durable Object storage.
+//!
+//! A [`SessionStore`] can be used directly with [`SessionStore::load`] and
+//! [`SessionStore::save`], or as a [`Middleware`] which loads the session of every request into
+//! its extensions and saves it once the response is ready:
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::sessions::{Session, SessionStore};
+//!
+//! # fn example(env: &Env) -> Result<()> {
+//! let store = SessionStore::kv(env.kv("SESSIONS")?);
+//! Router::new()
+//!     .middleware(store)
+//!     .get("/", |_, ctx| {
+//!         let session = ctx.extensions().get::<Session>().unwrap();
+//!         let visits = session.get::<u32>("visits")?.unwrap_or(0) + 1;
+//!         session.insert("visits", visits)?;
+//!         Response::ok(format!("{visits} visits"))
+//!     });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! New sessions are only stored, and their cookie set, once something is inserted into them.
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use futures_util::future::LocalBoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    crypto, kv::KvStore, Cookie, Date, Middleware, Next, Request, Response, Result, RouteContext,
+    SameSite, Storage,
+};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Converts the data of a session to and from the string it's stored as, e.g. to encrypt or
+/// compress it.
+pub trait SessionSerializer {
+    fn serialize(&self, data: &Map<String, Value>) -> Result<String>;
+    fn deserialize(&self, data: &str) -> Result<Map<String, Value>>;
+}
+
+/// Stores sessions as JSON, the default [`SessionSerializer`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSerializer;
+
+impl SessionSerializer for JsonSerializer {
+    fn serialize(&self, data: &Map<String, Value>) -> Result<String> {
+        Ok(serde_json::to_string(data)?)
+    }
+
+    fn deserialize(&self, data: &str) -> Result<Map<String, Value>> {
+        Ok(serde_json::from_str(data)?)
+    }
+}
+
+/// The data of one client, shared by every clone of the `Session`.
+#[derive(Debug, Clone)]
+pub struct Session(Rc<RefCell<SessionState>>);
+
+#[derive(Debug)]
+struct SessionState {
+    id: String,
+    data: Map<String, Value>,
+    /// When the stored session expires, in milliseconds, if it's known.
+    expires: Option<u64>,
+    is_new: bool,
+    changed: bool,
+    destroyed: bool,
+}
+
+impl Session {
+    fn new(id: String, data: Map<String, Value>, expires: Option<u64>, is_new: bool) -> Self {
+        Self(Rc::new(RefCell::new(SessionState {
+            id,
+            data,
+            expires,
+            is_new,
+            changed: false,
+            destroyed: false,
+        })))
+    }
+
+    pub fn id(&self) -> String {
+        self.0.borrow().id.clone()
+    }
+
+    /// Whether the session hasn't been stored yet.
+    pub fn is_new(&self) -> bool {
+        self.0.borrow().is_new
+    }
+
+    /// The value stored under `key`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.0.borrow().data.get(key) {
+            Some(value) => Ok(Some(T::deserialize(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn insert<T: Serialize>(&self, key: impl Into<String>, value: T) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.0.borrow_mut();
+        state.data.insert(key.into(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: &str) {
+        let mut state = self.0.borrow_mut();
+        if state.data.remove(key).is_some() {
+            state.changed = true;
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.0.borrow_mut();
+        state.changed |= !state.data.is_empty();
+        state.data.clear();
+    }
+
+    /// Delete the session when it's saved, e.g. when signing out.
+    pub fn destroy(&self) {
+        self.0.borrow_mut().destroyed = true;
+    }
+}
+
+/// Loads and saves [`Session`]s.
+#[derive(Clone)]
+pub struct SessionStore {
+    backend: Backend,
+    serializer: Rc<dyn SessionSerializer>,
+    prefix: String,
+    ttl: Duration,
+    rolling: bool,
+    cookie: Cookie,
+}
+
+#[derive(Clone)]
+enum Backend {
+    Kv(KvStore),
+    Storage(Storage),
+}
+
+/// A session in Durable Object storage, which has no TTL of its own.
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    data: String,
+    expires: f64,
+}
+
+/// The metadata of a session in KV.
+#[derive(Serialize, Deserialize)]
+struct SessionMetadata {
+    expires: u64,
+}
+
+impl SessionStore {
+    /// Store sessions in a KV namespace. Changes to a session may take a while to be visible in
+    /// other locations.
+    pub fn kv(kv: KvStore) -> Self {
+        Self::new(Backend::Kv(kv))
+    }
+
+    /// Store sessions in the storage of a Durable Object.
+    pub fn storage(storage: Storage) -> Self {
+        Self::new(Backend::Storage(storage))
+    }
+
+    fn new(backend: Backend) -> Self {
+        Self {
+            backend,
+            serializer: Rc::new(JsonSerializer),
+            prefix: "session:".into(),
+            ttl: DEFAULT_TTL,
+            rolling: true,
+            cookie: Cookie::new("session", "")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::Lax),
+        }
+    }
+
+    /// How long a session lasts, one day by default. KV requires at least 60 seconds.
+    #[must_use]
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Whether saving a session extends it by the TTL even if it wasn't changed, so that it only
+    /// expires once the client has been inactive for about the TTL. Enabled by default.
+    ///
+    /// Unchanged sessions are only rewritten once less than half of their TTL is left, so that
+    /// concurrent requests of a client don't exceed KV's limit of one write per second to a key.
+    #[must_use]
+    pub fn rolling(mut self, rolling: bool) -> Self {
+        self.rolling = rolling;
+        self
+    }
+
+    /// The prefix of the keys sessions are stored under, `session:` by default.
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// The name and attributes of the session cookie, by default a `Secure`, `HttpOnly` cookie
+    /// called `session` with `Path=/` and `SameSite=Lax`. Its value and `Max-Age` are set by the
+    /// store.
+    #[must_use]
+    pub fn cookie(mut self, cookie: Cookie) -> Self {
+        self.cookie = cookie;
+        self
+    }
+
+    #[must_use]
+    pub fn serializer(mut self, serializer: impl SessionSerializer + 'static) -> Self {
+        self.serializer = Rc::new(serializer);
+        self
+    }
+
+    /// A new, empty session.
+    pub fn create(&self) -> Result<Session> {
+        let id = crypto::random_bytes(16)?
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        Ok(Session::new(id, Map::new(), None, true))
+    }
+
+    /// The session of the request's cookie, or a new session if it has none or it expired.
+    pub async fn load(&self, req: &Request) -> Result<Session> {
+        if let Some(id) = req.cookie(self.cookie.name())? {
+            if let Some(session) = self.load_id(&id).await? {
+                return Ok(session);
+            }
+        }
+        self.create()
+    }
+
+    /// The session with the given id, if it exists.
+    pub async fn load_id(&self, id: &str) -> Result<Option<Session>> {
+        if id.len() != 32 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(None);
+        }
+        let key = self.key(id);
+        let (data, expires) = match &self.backend {
+            Backend::Kv(kv) => {
+                let (data, metadata) = kv
+                    .get(&key)
+                    .skip_consistency_check()
+                    .text_with_metadata::<SessionMetadata>()
+                    .await?;
+                (data, metadata.map(|metadata| metadata.expires))
+            }
+            Backend::Storage(storage) => {
+                let values = storage.get_multiple(vec![key.as_str()]).await?;
+                let value = values.get(&JsValue::from_str(&key));
+                if value.is_undefined() {
+                    (None, None)
+                } else {
+                    let stored: StoredSession = serde_wasm_bindgen::from_value(value)?;
+                    let now = Date::now().as_millis() as f64;
+                    let expires = stored.expires as u64;
+                    ((stored.expires > now).then_some(stored.data), Some(expires))
+                }
+            }
+        };
+        match data {
+            Some(data) => {
+                let data = self.serializer.deserialize(&data)?;
+                Ok(Some(Session::new(id.into(), data, expires, false)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Store the session if needed, returning the cookie to set on the response, if any.
+    pub async fn save(&self, session: &Session) -> Result<Option<Cookie>> {
+        let (id, data, expires, is_new, changed, destroyed) = {
+            let state = session.0.borrow();
+            (
+                state.id.clone(),
+                state.data.clone(),
+                state.expires,
+                state.is_new,
+                state.changed,
+                state.destroyed,
+            )
+        };
+
+        if destroyed {
+            if is_new {
+                return Ok(None);
+            }
+            self.destroy(&id).await?;
+            let cookie = self.cookie.clone().with_value("").max_age(Duration::ZERO);
+            return Ok(Some(cookie));
+        }
+        // Visitors who never store anything don't get a session.
+        if is_new && data.is_empty() {
+            return Ok(None);
+        }
+
+        let refresh = self.rolling && needs_refresh(expires, Date::now().as_millis(), self.ttl);
+        if !(changed || is_new || refresh) {
+            return Ok(None);
+        }
+        let expires = self.write(&id, &data).await?;
+        let mut state = session.0.borrow_mut();
+        state.expires = Some(expires);
+        state.is_new = false;
+        state.changed = false;
+        Ok((is_new || self.rolling).then(|| self.cookie.clone().with_value(id).max_age(self.ttl)))
+    }
+
+    /// Delete the session with the given id.
+    pub async fn destroy(&self, id: &str) -> Result<()> {
+        let key = self.key(id);
+        match &self.backend {
+            Backend::Kv(kv) => kv.delete(&key).await?,
+            Backend::Storage(storage) => {
+                storage.clone().delete(&key).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the session, returning when it expires.
+    async fn write(&self, id: &str, data: &Map<String, Value>) -> Result<u64> {
+        let key = self.key(id);
+        let data = self.serializer.serialize(data)?;
+        let expires = Date::now().as_millis() + self.ttl.as_millis() as u64;
+        match &self.backend {
+            Backend::Kv(kv) => {
+                let ttl = self.ttl.as_secs().max(crate::kv::MIN_EXPIRATION_TTL);
+                kv.put(&key, data)?
+                    .expiration_ttl(ttl)
+                    .metadata(SessionMetadata { expires })?
+                    .execute()
+                    .await?;
+            }
+            Backend::Storage(storage) => {
+                let stored = StoredSession {
+                    data,
+                    expires: expires as f64,
+                };
+                storage.clone().put(&key, stored).await?;
+            }
+        }
+        Ok(expires)
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}{id}", self.prefix)
+    }
+}
+
+/// Loads the session of each request into its [extensions](RouteContext::extensions) and saves it
+/// once the handler responded. Sessions aren't saved when the handler fails, and a failure to save
+/// one is logged rather than replacing the handler's response.
+impl<'a, D: 'a> Middleware<'a, D> for SessionStore {
+    fn handle(
+        &self,
+        req: Request,
+        mut ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> LocalBoxFuture<'a, Result<Response>> {
+        let store = self.clone();
+        Box::pin(async move {
+            let session = store.load(&req).await?;
+            ctx.extensions_mut().insert(session.clone());
+            let mut resp = next.run(req, ctx).await?;
+            match store.save(&session).await {
+                Ok(Some(cookie)) => resp.add_cookie(&cookie)?,
+                Ok(None) => {}
+                Err(e) => worker_sys::console_error!("saving the session failed: {e}"),
+            }
+            Ok(resp)
+        })
+    }
+}
+
+/// Whether a session expiring at `expires` should be rewritten at `now` to extend it, which is
+/// once less than half of the TTL is left.
+fn needs_refresh(expires: Option<u64>, now: u64, ttl: Duration) -> bool {
+    match expires {
+        Some(expires) => expires.saturating_sub(now) < ttl.as_millis() as u64 / 2,
+        None => true,
+    }
+}
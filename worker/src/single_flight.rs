@@ -0,0 +1,94 @@
+use std::{cell::RefCell, collections::HashMap, future::Future};
+
+use futures_channel::oneshot;
+
+use crate::{Error, Result};
+
+type Waiters<T> = Vec<oneshot::Sender<std::result::Result<T, String>>>;
+
+/// Runs the work of a key once at a time, sharing its result with the calls for the key which
+/// arrive while it's running. Errors are shared as [`Error::RustError`]s with the same message.
+///
+/// The key is forgotten once the work is done, or once the call running it is dropped, in which
+/// case the waiting calls fail rather than wait forever.
+pub(crate) struct SingleFlight<T> {
+    in_flight: RefCell<HashMap<String, Waiters<T>>>,
+}
+
+impl<T: Clone> SingleFlight<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            in_flight: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Run the work `f` returns for `key`, or wait for the work of `key` which is running. Calls
+    /// waiting for work which was cancelled fail with `cancelled`.
+    pub(crate) async fn run<F, Fut>(&self, key: &str, cancelled: &str, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let waiting = {
+            let mut in_flight = self.in_flight.borrow_mut();
+            match in_flight.get_mut(key) {
+                Some(waiters) => {
+                    let (tx, rx) = oneshot::channel();
+                    waiters.push(tx);
+                    Some(rx)
+                }
+                None => {
+                    in_flight.insert(key.to_owned(), Vec::new());
+                    None
+                }
+            }
+        };
+        if let Some(rx) = waiting {
+            return match rx.await {
+                Ok(result) => result.map_err(Error::RustError),
+                Err(_) => Err(Error::RustError(cancelled.into())),
+            };
+        }
+
+        let running = Running {
+            in_flight: &self.in_flight,
+            key,
+        };
+        let result = f().await;
+        for waiter in running.finish() {
+            let _ = waiter.send(match &result {
+                Ok(value) => Ok(value.clone()),
+                Err(e) => Err(e.to_string()),
+            });
+        }
+        result
+    }
+
+    /// The number of keys whose work is running.
+    pub(crate) fn running(&self) -> usize {
+        self.in_flight.borrow().len()
+    }
+}
+
+/// Forgets the key of the work when it's dropped before the work is done, dropping the senders
+/// of the waiting calls.
+struct Running<'a, T> {
+    in_flight: &'a RefCell<HashMap<String, Waiters<T>>>,
+    key: &'a str,
+}
+
+impl<T> Running<'_, T> {
+    fn finish(self) -> Waiters<T> {
+        let waiters = self.in_flight.borrow_mut().remove(self.key);
+        std::mem::forget(self);
+        waiters.unwrap_or_default()
+    }
+}
+
+impl<T> Drop for Running<'_, T> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.try_borrow_mut() {
+            in_flight.remove(self.key);
+        }
+    }
+}
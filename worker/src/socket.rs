@@ -4,7 +4,7 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::r2::js_object;
+use crate::macros::js_object;
 use crate::Result;
 use futures_util::FutureExt;
 use js_sys::{
@@ -88,6 +88,18 @@ impl Socket {
         Ok(())
     }
 
+    /// The writable side of the socket, e.g. to [pipe](crate::ByteStream::pipe_to) a body to
+    /// it. Writes to it must not be interleaved with writes through [`AsyncWrite`].
+    pub fn writable(&self) -> &WritableStream {
+        &self.writable
+    }
+
+    /// The readable side of the socket, e.g. to use as a body. Reads from it must not be
+    /// interleaved with reads through [`AsyncRead`].
+    pub fn readable(&self) -> &ReadableStream {
+        &self.readable
+    }
+
     /// Upgrades an insecure socket to a secure one that uses TLS,
     /// returning a new Socket. Note that in order to call this method,
     /// you must set [`secure_transport`](SocketOptions::secure_transport)
@@ -0,0 +1,305 @@
+use std::{collections::HashMap, convert::TryFrom};
+
+use js_sys::Object;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    durable::{ListOptions, Pages, DEFAULT_SCAN_PAGE_SIZE},
+    Error, Result, Storage,
+};
+
+/// A value stored along with the version of its schema, see [`Migration`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionedValue<T> {
+    pub version: u32,
+    pub value: T,
+}
+
+type Upgrade = Box<dyn Fn(Value) -> Result<Value>>;
+
+/// Upgrades the values of a Durable Object's storage when their schema changes, so a long-lived
+/// object can evolve its storage format without losing the data written by previous versions.
+///
+/// Values written with [`put`](Migration::put) are stored as a [`VersionedValue`]. Values which
+/// were stored without a version, with [`Storage::put`], are version `0`, so storage which
+/// predates the migration can be upgraded too. A value of version `n` goes through the upgrades
+/// from `n`, `n + 1` and so on until it reaches the current version. Values of a newer version,
+/// or of a version with a missing upgrade, are errors.
+///
+/// Values can be upgraded lazily as they're read with [`get`](Migration::get), or all at once
+/// with [`run`](Migration::run), e.g. from an alarm.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     // Version 1 had a single `name` field.
+///     first_name: String,
+///     last_name: String,
+/// }
+///
+/// # async fn example(mut storage: Storage) -> Result<()> {
+/// let users = Migration::new(2)
+///     // Version 0 was a plain string.
+///     .upgrade(0, |name| Ok(serde_json::json!({ "name": name })))
+///     .upgrade(1, |mut user| {
+///         let name = user["name"].take();
+///         let name = name.as_str().unwrap_or_default();
+///         let (first, last) = name.split_once(' ').unwrap_or((name, ""));
+///         Ok(serde_json::json!({ "first_name": first, "last_name": last }))
+///     });
+/// let report = users.run(&mut storage, "user:").await?;
+/// console_log!("upgraded {} of {} users", report.upgraded, report.scanned);
+/// let alice: Option<User> = users.get(&storage, "user:alice").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Migration {
+    version: u32,
+    upgrades: HashMap<u32, Upgrade>,
+}
+
+/// What [`Migration::run`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The number of keys read.
+    pub scanned: usize,
+    /// The number of values which were upgraded and written back.
+    pub upgraded: usize,
+}
+
+impl Migration {
+    /// A migration to `version`, the current version of the schema.
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            upgrades: HashMap::new(),
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Upgrade the JSON values of version `from` to version `from + 1`.
+    #[must_use]
+    pub fn upgrade(
+        mut self,
+        from: u32,
+        upgrade: impl Fn(Value) -> Result<Value> + 'static,
+    ) -> Self {
+        self.upgrades.insert(from, Box::new(upgrade));
+        self
+    }
+
+    /// Upgrade the values of version `from`, deserialized as `Old`, to version `from + 1`.
+    #[must_use]
+    pub fn upgrade_from<Old, New>(self, from: u32, upgrade: impl Fn(Old) -> New + 'static) -> Self
+    where
+        Old: DeserializeOwned,
+        New: Serialize,
+    {
+        self.upgrade(from, move |value| {
+            let old = serde_json::from_value(value)?;
+            Ok(serde_json::to_value(upgrade(old))?)
+        })
+    }
+
+    /// The value stored under `key`, upgraded to the current version, or `None` if there is no
+    /// such key. The upgraded value isn't written back.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        storage: &Storage,
+        key: &str,
+    ) -> Result<Option<T>> {
+        let stored = storage.get_multiple(vec![key]).await?.get(&key.into());
+        if stored.is_undefined() {
+            return Ok(None);
+        }
+        let value = self.upgrade_value(parse(stored)?)?;
+        Ok(Some(serde_json::from_value(value)?))
+    }
+
+    /// Store `value` under `key` with the current version.
+    pub async fn put<T: Serialize>(
+        &self,
+        storage: &mut Storage,
+        key: &str,
+        value: &T,
+    ) -> Result<()> {
+        let versioned = VersionedValue {
+            version: self.version,
+            value,
+        };
+        storage.put_raw(key, to_js(&versioned)?).await
+    }
+
+    /// Upgrade every value whose key starts with `prefix` to the current version, a page of keys
+    /// at a time. The values which are already current are left untouched, so a migration which
+    /// is interrupted can be run again.
+    pub async fn run(&self, storage: &mut Storage, prefix: &str) -> Result<MigrationReport> {
+        let mut report = MigrationReport::default();
+        let mut pages = Pages::new(prefix, DEFAULT_SCAN_PAGE_SIZE);
+        while let Some(page) = pages.next(storage).await? {
+            let upgraded = Object::new();
+            for (key, value) in page {
+                report.scanned += 1;
+                let stored = parse(value)?;
+                if stored.version != self.version {
+                    let versioned = VersionedValue {
+                        version: self.version,
+                        value: self.upgrade_value(stored)?,
+                    };
+                    js_sys::Reflect::set(&upgraded, &key.as_str().into(), &to_js(&versioned)?)?;
+                    report.upgraded += 1;
+                }
+            }
+            if Object::keys(&upgraded).length() > 0 {
+                storage.put_multiple_raw(upgraded).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// The value of `stored` upgraded to the current version.
+    fn upgrade_value(&self, stored: VersionedValue<Value>) -> Result<Value> {
+        let VersionedValue { version, mut value } = stored;
+        if version > self.version {
+            return Err(Error::RustError(format!(
+                "stored version {version} is newer than the supported version {}",
+                self.version
+            )));
+        }
+        for version in version..self.version {
+            let upgrade = self.upgrades.get(&version).ok_or_else(|| {
+                Error::RustError(format!(
+                    "no upgrade of stored values from version {version}"
+                ))
+            })?;
+            value = upgrade(value)?;
+        }
+        Ok(value)
+    }
+}
+
+fn parse(stored: JsValue) -> Result<VersionedValue<Value>> {
+    Ok(split_version(serde_wasm_bindgen::from_value(stored)?))
+}
+
+/// Splits a stored value into its version and value, values stored without a version being
+/// version `0`.
+fn split_version(stored: Value) -> VersionedValue<Value> {
+    let version = match &stored {
+        Value::Object(map) if map.len() == 2 && map.contains_key("value") => map
+            .get("version")
+            .and_then(Value::as_u64)
+            .and_then(|version| u32::try_from(version).ok()),
+        _ => None,
+    };
+    match (version, stored) {
+        (Some(version), Value::Object(mut map)) => VersionedValue {
+            version,
+            value: map.remove("value").unwrap_or_default(),
+        },
+        (_, value) => VersionedValue { version: 0, value },
+    }
+}
+
+/// Serializes `value` into plain JS objects rather than `Map`s, so that JSON objects are stored
+/// the same way whichever way they're written.
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
+
+impl Storage {
+    /// Move the value of `from` to `to`, replacing the value of `to` if there's one. Returns
+    /// whether `from` existed.
+    pub async fn rename(&mut self, from: &str, to: &str) -> Result<bool> {
+        let value = self.get_multiple(vec![from]).await?.get(&from.into());
+        if value.is_undefined() {
+            return Ok(false);
+        }
+        self.put_raw(to, value).await?;
+        self.delete(from).await?;
+        Ok(true)
+    }
+
+    /// Move every key starting with `from` to the same key starting with `to` instead, a page at
+    /// a time, e.g. to rename the collection of a [`ScopedStorage`](crate::ScopedStorage).
+    /// Returns the number of keys moved.
+    ///
+    /// Neither prefix can start with the other, since the keys would then be moved again.
+    pub async fn rename_prefix(&mut self, from: &str, to: &str) -> Result<usize> {
+        if from.starts_with(to) || to.starts_with(from) {
+            return Err(Error::RustError(format!(
+                "can't rename the keys starting with {from:?} to overlapping prefix {to:?}"
+            )));
+        }
+        let mut moved = 0;
+        loop {
+            let page = self
+                .list_with_options(
+                    ListOptions::new()
+                        .prefix(from)
+                        .limit(DEFAULT_SCAN_PAGE_SIZE),
+                )
+                .await?;
+            let values = Object::new();
+            let mut keys = Vec::new();
+            page.for_each(&mut |value, key| {
+                if let Some(key) = key.as_string() {
+                    let renamed = format!("{}{}", to, &key[from.len()..]);
+                    let _ = js_sys::Reflect::set(&values, &renamed.into(), &value);
+                    keys.push(key);
+                }
+            });
+            if keys.is_empty() {
+                return Ok(moved);
+            }
+            moved += keys.len();
+            self.put_multiple_raw(values).await?;
+            self.delete_multiple(keys).await?;
+        }
+    }
+}
+
+#[test]
+fn upgrade_value_works() {
+    let migration = Migration::new(2)
+        .upgrade(0, |name| Ok(serde_json::json!({ "name": name })))
+        .upgrade(1, |mut user| {
+            user["admin"] = false.into();
+            Ok(user)
+        });
+
+    let legacy = split_version(serde_json::json!("alice"));
+    assert_eq!(legacy.version, 0);
+    assert_eq!(
+        migration.upgrade_value(legacy).unwrap(),
+        serde_json::json!({ "name": "alice", "admin": false })
+    );
+
+    let current = split_version(serde_json::json!({ "version": 2, "value": { "name": "bob" } }));
+    assert_eq!(current.version, 2);
+    assert_eq!(
+        migration.upgrade_value(current).unwrap(),
+        serde_json::json!({ "name": "bob" })
+    );
+
+    let unversioned = split_version(serde_json::json!({ "value": 1, "other": 2 }));
+    assert_eq!(unversioned.version, 0);
+
+    let newer = VersionedValue {
+        version: 3,
+        value: Value::Null,
+    };
+    assert!(migration.upgrade_value(newer).is_err());
+    let missing = VersionedValue {
+        version: 0,
+        value: Value::Null,
+    };
+    assert!(Migration::new(1).upgrade_value(missing).is_err());
+}
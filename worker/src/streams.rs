@@ -1,31 +1,57 @@
 use std::{
+    cell::RefCell,
+    future::Future,
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
 };
 
-use futures_util::{Stream, TryStreamExt};
+use futures_util::{Sink, Stream, TryStream, TryStreamExt};
 use js_sys::{BigInt, Uint8Array};
 use pin_project::pin_project;
 use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
 use wasm_streams::readable::IntoStream;
-use web_sys::ReadableStream;
+#[cfg(feature = "crypto")]
+use web_sys::WritableStreamDefaultWriter;
+use web_sys::{ReadableStream, ReadableWritablePair, StreamPipeOptions, WritableStream};
+#[cfg(feature = "crypto")]
+use worker_sys::DigestStream as DigestStreamSys;
 use worker_sys::FixedLengthStream as FixedLengthStreamSys;
+use worker_sys::IdentityTransformStream as IdentityTransformStreamSys;
 
-use crate::{Error, Result};
+use crate::{AbortSignal, Error, Result};
 
 #[pin_project]
 #[derive(Debug)]
 pub struct ByteStream {
     #[pin]
-    pub(crate) inner: IntoStream<'static>,
+    state: ByteStreamState,
+}
+
+/// The JS stream is only locked to a reader once it's polled, so that until then it can be
+/// [piped](ByteStream::pipe_to) natively.
+#[pin_project(project = ByteStreamStateProj)]
+#[derive(Debug)]
+enum ByteStreamState {
+    Raw(ReadableStream),
+    Reading(#[pin] IntoStream<'static>),
 }
 
 impl Stream for ByteStream {
     type Item = Result<Vec<u8>>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let this = self.project();
-        let item = match futures_util::ready!(this.inner.poll_next(cx)) {
+        let mut state = self.project().state;
+        if let ByteStreamStateProj::Raw(raw) = state.as_mut().project() {
+            let stream = wasm_streams::ReadableStream::from_raw(raw.clone().unchecked_into());
+            state.set(ByteStreamState::Reading(stream.into_stream()));
+        }
+        let inner = match state.project() {
+            ByteStreamStateProj::Reading(inner) => inner,
+            ByteStreamStateProj::Raw(_) => unreachable!("the stream is being read"),
+        };
+        let item = match futures_util::ready!(inner.poll_next(cx)) {
             Some(res) => res.map(Uint8Array::from).map_err(Error::from),
             None => return Poll::Ready(None),
         };
@@ -38,6 +64,210 @@ impl Stream for ByteStream {
     }
 }
 
+impl ByteStream {
+    pub(crate) fn from_raw(stream: ReadableStream) -> Self {
+        Self {
+            state: ByteStreamState::Raw(stream),
+        }
+    }
+
+    /// The JS `ReadableStream` of the bytes. Unless the stream was already polled, this is the
+    /// stream it was created from, so the bytes aren't copied through Wasm memory.
+    pub fn into_raw(self) -> ReadableStream {
+        match self.state {
+            ByteStreamState::Raw(raw) => raw,
+            ByteStreamState::Reading(_) => to_readable_stream(self),
+        }
+    }
+
+    /// Write the stream to `writable`, completing once every chunk was written and `writable` was
+    /// closed. The runtime moves the bytes without going through Wasm, e.g. to proxy a body to a
+    /// [`Socket`](crate::Socket) or through a [`Transform`].
+    ///
+    /// Unless prevented by the `options`, an error of the stream aborts `writable`, an error of
+    /// `writable` cancels the stream, and aborting the [signal](PipeOptions::signal) does both.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// # async fn example(mut req: Request) -> Result<()> {
+    /// let socket = Socket::builder().connect("example.com", 8080)?;
+    /// req.stream()?
+    ///     .pipe_to(socket.writable(), PipeOptions::new().prevent_close(true))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn pipe_to(self, writable: &WritableStream, options: PipeOptions) -> Result<()> {
+        let promise = self
+            .into_raw()
+            .pipe_to_with_options(writable, &options.into());
+        JsFuture::from(promise).await?;
+        Ok(())
+    }
+
+    /// The stream of the chunks `transform` outputs for the chunks of this stream, which are
+    /// [piped](ByteStream::pipe_to) to its writable side with the default options.
+    pub fn pipe_through(self, transform: &Transform) -> ByteStream {
+        self.pipe_through_with_options(transform, PipeOptions::new())
+    }
+
+    /// Like [`pipe_through`](ByteStream::pipe_through), with `options` for piping to the writable
+    /// side of `transform`.
+    pub fn pipe_through_with_options(
+        self,
+        transform: &Transform,
+        options: PipeOptions,
+    ) -> ByteStream {
+        let pair = ReadableWritablePair::new(&transform.readable, &transform.writable);
+        ByteStream::from_raw(
+            self.into_raw()
+                .pipe_through_with_options(&pair, &options.into()),
+        )
+    }
+
+    /// The stream with its length, `length` bytes, known to the runtime, so that it's sent with
+    /// a `Content-Length` header, see [`FixedLengthStream`]. Unlike
+    /// [`FixedLengthStream::wrap`], the bytes aren't copied through Wasm memory.
+    pub fn fixed_length(self, length: u64) -> ByteStream {
+        self.pipe_through(&Transform::fixed_length(length))
+    }
+
+    /// Fail with [`Error::BodyTooLarge`] once the stream yielded more than `limit` bytes.
+    pub fn limit(self, limit: u64) -> LimitedStream<Self> {
+        LimitedStream::new(self, limit)
+    }
+
+    /// Decode the stream as UTF-8 text, see [`TextDecoderStream`].
+    pub fn text(self) -> TextDecoderStream<Self> {
+        TextDecoderStream::new(self)
+    }
+
+    /// Decode the stream as UTF-8 text and split it into lines, see [`Lines`].
+    pub fn lines(self) -> Lines<TextDecoderStream<Self>> {
+        Lines::new(self.text())
+    }
+
+    /// Hash the stream with `algorithm` as it's read, see [`DigestingStream`].
+    #[cfg(feature = "crypto")]
+    pub fn digest(self, algorithm: &str) -> Result<(DigestingStream<Self>, Digest)> {
+        DigestingStream::new(self, algorithm)
+    }
+}
+
+/// Options for [`ByteStream::pipe_to`].
+#[derive(Debug, Clone, Default)]
+pub struct PipeOptions {
+    prevent_close: bool,
+    prevent_abort: bool,
+    prevent_cancel: bool,
+    signal: Option<AbortSignal>,
+}
+
+impl PipeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave the writable stream open once the stream ended, e.g. to write more to it.
+    #[must_use]
+    pub fn prevent_close(mut self, prevent_close: bool) -> Self {
+        self.prevent_close = prevent_close;
+        self
+    }
+
+    /// Don't abort the writable stream if the stream fails.
+    #[must_use]
+    pub fn prevent_abort(mut self, prevent_abort: bool) -> Self {
+        self.prevent_abort = prevent_abort;
+        self
+    }
+
+    /// Don't cancel the stream if the writable stream fails.
+    #[must_use]
+    pub fn prevent_cancel(mut self, prevent_cancel: bool) -> Self {
+        self.prevent_cancel = prevent_cancel;
+        self
+    }
+
+    /// Stop piping once `signal` is aborted, e.g. when the client disconnected.
+    #[must_use]
+    pub fn signal(mut self, signal: AbortSignal) -> Self {
+        self.signal = Some(signal);
+        self
+    }
+}
+
+impl From<PipeOptions> for StreamPipeOptions {
+    fn from(options: PipeOptions) -> Self {
+        let mut raw = StreamPipeOptions::new();
+        raw.prevent_close(options.prevent_close);
+        raw.prevent_abort(options.prevent_abort);
+        raw.prevent_cancel(options.prevent_cancel);
+        if let Some(signal) = &options.signal {
+            raw.signal(signal);
+        }
+        raw
+    }
+}
+
+/// A stream of byte chunks which fails with [`Error::BodyTooLarge`] as soon as more than its
+/// limit was read, e.g. to stop proxying an oversized upstream body before it's buffered in
+/// full.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(url: &str) -> Result<Response> {
+/// let mut upstream = Fetch::Url(url.parse()?).send().await?;
+/// Response::from_stream(upstream.stream()?.limit(10 * 1024 * 1024))
+/// # }
+/// ```
+#[pin_project]
+#[derive(Debug)]
+pub struct LimitedStream<S> {
+    #[pin]
+    inner: S,
+    limit: u64,
+    read: u64,
+}
+
+impl<S> LimitedStream<S> {
+    pub fn new(inner: S, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            read: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream<Item = Result<Vec<u8>>>> Stream for LimitedStream<S> {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if *this.read > *this.limit {
+            return Poll::Ready(None);
+        }
+        let item = match futures_util::ready!(this.inner.poll_next(cx)) {
+            Some(Ok(chunk)) => chunk,
+            other => return Poll::Ready(other),
+        };
+        *this.read += item.len() as u64;
+        if *this.read > *this.limit {
+            return Poll::Ready(Some(Err(Error::BodyTooLarge(*this.limit))));
+        }
+        Poll::Ready(Some(Ok(item)))
+    }
+}
+
+/// A stream of byte chunks which must add up to a known length, so that the runtime can send it
+/// with a `Content-Length` header rather than with chunked encoding, e.g. as the body of a
+/// [`Response`](crate::Response::from_stream_with_length) or of an R2 object. The stream fails if
+/// there are more or fewer bytes.
 #[pin_project]
 pub struct FixedLengthStream {
     length: u64,
@@ -55,6 +285,16 @@ impl FixedLengthStream {
             inner: Box::pin(stream),
         }
     }
+
+    /// The number of bytes of the stream.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// The JS `ReadableStream` of the bytes, whose length is known to the runtime.
+    pub fn into_raw(self) -> ReadableStream {
+        FixedLengthStreamSys::from(self).readable()
+    }
 }
 
 impl Stream for FixedLengthStream {
@@ -93,13 +333,281 @@ impl Stream for FixedLengthStream {
     }
 }
 
+/// Convert a Rust stream of byte chunks into a JS `ReadableStream`, e.g. to use as a body.
+pub(crate) fn to_readable_stream<S>(stream: S) -> ReadableStream
+where
+    S: TryStream + 'static,
+    S::Ok: Into<Vec<u8>>,
+    S::Error: Into<Error>,
+{
+    let js_stream = stream
+        .map_ok(|item| -> Vec<u8> { item.into() })
+        .map_ok(|chunk| {
+            let array = Uint8Array::new_with_length(chunk.len() as _);
+            array.copy_from(&chunk);
+
+            array.into()
+        })
+        .map_err(|err| -> crate::Error { err.into() })
+        .map_err(|e| JsValue::from(e.to_string()));
+
+    let stream = wasm_streams::ReadableStream::from_stream(js_stream);
+    stream.into_raw().unchecked_into()
+}
+
+/// A JS `ReadableStream` of the byte chunks of `stream`, e.g. to use as the body of a
+/// [`Response`](crate::Response) or [`Request`](crate::Request), or to pipe to a
+/// [`Socket`](crate::Socket). An error fails the stream.
+pub fn readable_stream<S>(stream: S) -> ReadableStream
+where
+    S: TryStream + 'static,
+    S::Ok: Into<Vec<u8>>,
+    S::Error: Into<Error>,
+{
+    to_readable_stream(stream)
+}
+
+/// A JS `ReadableStream` whose chunks are produced by `pull`, which is called whenever the reader
+/// wants another chunk. Returning `None` closes the stream, and an error fails it.
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example() -> Result<Response> {
+/// let mut count = 0;
+/// let stream = pull_stream(move || {
+///     count += 1;
+///     let chunk = (count <= 3).then(|| format!("chunk {count}\n").into_bytes());
+///     async move { Ok(chunk) }
+/// });
+/// Response::from_body(ResponseBody::Stream(stream))
+/// # }
+/// ```
+pub fn pull_stream<F, Fut>(pull: F) -> ReadableStream
+where
+    F: FnMut() -> Fut + 'static,
+    Fut: Future<Output = Result<Option<Vec<u8>>>> + 'static,
+{
+    let stream = futures_util::stream::unfold(Some(pull), |pull| async move {
+        let mut pull = pull?;
+        match pull().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(pull))),
+            Ok(None) => None,
+            // Stop pulling once the stream failed.
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    to_readable_stream(stream)
+}
+
+/// A JS `WritableStream` which calls `write` with every chunk written to it, waiting for the
+/// returned future before accepting the next one. An error fails the stream, and the writer
+/// sees it.
+///
+/// Chunks of bytes and strings are accepted, strings being encoded as UTF-8.
+pub fn writable_stream<F, Fut>(write: F) -> WritableStream
+where
+    F: FnMut(Vec<u8>) -> Fut + 'static,
+    Fut: Future<Output = Result<()>> + 'static,
+{
+    writable_stream_with_close(write, || async { Ok(()) })
+}
+
+/// Like [`writable_stream`], calling `close` once the writer closed the stream, e.g. to flush a
+/// buffer.
+pub fn writable_stream_with_close<F, Fut, C, CFut>(mut write: F, close: C) -> WritableStream
+where
+    F: FnMut(Vec<u8>) -> Fut + 'static,
+    Fut: Future<Output = Result<()>> + 'static,
+    C: FnOnce() -> CFut + 'static,
+    CFut: Future<Output = Result<()>> + 'static,
+{
+    let sink = ClosureSink {
+        write: Box::new(move |chunk| Box::pin(write(chunk))),
+        close: Some(Box::new(move || Box::pin(close()))),
+        pending: None,
+    };
+    wasm_streams::WritableStream::from_sink(sink)
+        .into_raw()
+        .unchecked_into()
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = Result<()>>>>;
+
+/// A sink calling closures, see [`writable_stream_with_close`].
+struct ClosureSink {
+    write: Box<dyn FnMut(Vec<u8>) -> BoxFuture>,
+    close: Option<Box<dyn FnOnce() -> BoxFuture>>,
+    /// The future of the last write or of closing, which must complete before the next one.
+    pending: Option<BoxFuture>,
+}
+
+impl ClosureSink {
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), JsValue>> {
+        if let Some(pending) = &mut self.pending {
+            let result = futures_util::ready!(pending.as_mut().poll(cx));
+            self.pending = None;
+            result.map_err(JsValue::from)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Sink<JsValue> for ClosureSink {
+    type Error = JsValue;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), JsValue>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, chunk: JsValue) -> std::result::Result<(), JsValue> {
+        let this = self.get_mut();
+        let chunk = match chunk.as_string() {
+            Some(text) => text.into_bytes(),
+            None if chunk.is_instance_of::<js_sys::ArrayBuffer>()
+                || js_sys::ArrayBuffer::is_view(&chunk) =>
+            {
+                Uint8Array::new(&chunk).to_vec()
+            }
+            None => return Err(JsValue::from("chunks must be bytes or strings")),
+        };
+        this.pending = Some((this.write)(chunk));
+        Ok(())
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), JsValue>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::result::Result<(), JsValue>> {
+        let this = self.get_mut();
+        futures_util::ready!(this.poll_pending(cx))?;
+        if let Some(close) = this.close.take() {
+            this.pending = Some(close());
+            futures_util::ready!(this.poll_pending(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A pair of JS streams transforming the chunks written to [`writable`](Transform::writable) into
+/// those read from [`readable`](Transform::readable), like a `TransformStream`.
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example(mut req: Request) -> Result<Response> {
+/// let upper = Transform::new(|chunk| async move { Ok(chunk.to_ascii_uppercase()) });
+/// Response::from_stream(req.stream()?.pipe_through(&upper))
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Transform {
+    pub readable: ReadableStream,
+    pub writable: WritableStream,
+}
+
+impl Transform {
+    /// A transform passing the chunks through as they are, e.g. to get a writable stream whose
+    /// readable side is returned as a body before anything is written to it.
+    pub fn identity() -> Self {
+        Self::from(web_sys::TransformStream::from(
+            IdentityTransformStreamSys::new(),
+        ))
+    }
+
+    /// A transform passing through exactly `length` bytes, see [`ByteStream::fixed_length`].
+    pub fn fixed_length(length: u64) -> Self {
+        Self::from(web_sys::TransformStream::from(fixed_length_sys(length)))
+    }
+
+    /// Transform every chunk with `transform`, waiting for the reader to read the transformed
+    /// chunk before accepting the next one. Empty chunks aren't passed on, so a transform can
+    /// return one to hold back its output until a later chunk. If `transform` fails, both sides
+    /// of the transform fail with its error.
+    pub fn new<F, Fut>(mut transform: F) -> Self
+    where
+        F: FnMut(Vec<u8>) -> Fut + 'static,
+        Fut: Future<Output = Result<Vec<u8>>> + 'static,
+    {
+        let (sender, receiver) = futures_channel::mpsc::channel::<Result<Vec<u8>>>(0);
+        // Every clone of a sender gets a slot of its own, so the writes share one sender for
+        // `poll_ready` to wait until the reader took the previous chunk.
+        let sender = Rc::new(RefCell::new(sender));
+        let closing = sender.clone();
+        let writable = writable_stream_with_close(
+            move |chunk| {
+                let transformed = transform(chunk);
+                let sender = sender.clone();
+                async move {
+                    let chunk = transformed.await;
+                    if matches!(&chunk, Ok(chunk) if chunk.is_empty()) {
+                        return Ok(());
+                    }
+                    let ready =
+                        futures_util::future::poll_fn(|cx| sender.borrow_mut().poll_ready(cx))
+                            .await;
+                    match chunk {
+                        Ok(chunk) => {
+                            ready.map_err(|_| transform_closed())?;
+                            let result = sender.borrow_mut().start_send(Ok(chunk));
+                            result.map_err(|_| transform_closed())
+                        }
+                        // The readable side fails too, rather than looking like it ended.
+                        Err(e) => {
+                            if ready.is_ok() {
+                                let failed = Error::RustError(e.to_string());
+                                let _ = sender.borrow_mut().start_send(Err(failed));
+                            }
+                            sender.borrow_mut().close_channel();
+                            Err(e)
+                        }
+                    }
+                }
+            },
+            move || {
+                closing.borrow_mut().close_channel();
+                async { Ok(()) }
+            },
+        );
+        Self {
+            readable: to_readable_stream(receiver),
+            writable,
+        }
+    }
+}
+
+impl From<web_sys::TransformStream> for Transform {
+    fn from(stream: web_sys::TransformStream) -> Self {
+        Self {
+            readable: stream.readable(),
+            writable: stream.writable(),
+        }
+    }
+}
+
+fn transform_closed() -> Error {
+    Error::RustError("the readable side of the transform was cancelled".into())
+}
+
+fn fixed_length_sys(length: u64) -> FixedLengthStreamSys {
+    if length < u32::MAX as u64 {
+        FixedLengthStreamSys::new(length as u32)
+    } else {
+        FixedLengthStreamSys::new_big_int(BigInt::from(length))
+    }
+}
+
 impl From<FixedLengthStream> for FixedLengthStreamSys {
     fn from(stream: FixedLengthStream) -> Self {
-        let raw = if stream.length < u32::MAX as u64 {
-            FixedLengthStreamSys::new(stream.length as u32)
-        } else {
-            FixedLengthStreamSys::new_big_int(BigInt::from(stream.length))
-        };
+        let raw = fixed_length_sys(stream.length);
 
         let js_stream = stream
             .map_ok(|item| -> Vec<u8> { item })
@@ -120,3 +628,298 @@ impl From<FixedLengthStream> for FixedLengthStreamSys {
         raw
     }
 }
+
+/// A stream of the text decoded from a stream of byte chunks, with a
+/// [TextDecoder](https://developer.mozilla.org/en-US/docs/Web/API/TextDecoder). Characters split
+/// across chunks are decoded once their last byte is read, and invalid sequences are replaced with
+/// `U+FFFD`.
+#[pin_project]
+#[derive(Debug)]
+pub struct TextDecoderStream<S> {
+    #[pin]
+    inner: S,
+    decoder: web_sys::TextDecoder,
+    done: bool,
+}
+
+impl<S> TextDecoderStream<S> {
+    /// Decode `inner` as UTF-8.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            decoder: web_sys::TextDecoder::new().unwrap(),
+            done: false,
+        }
+    }
+
+    /// Decode `inner` with the encoding `label`, e.g. `utf-16le` or `windows-1252`, which fails if
+    /// the runtime doesn't support the encoding.
+    pub fn with_encoding(inner: S, label: &str) -> Result<Self> {
+        Ok(Self {
+            inner,
+            decoder: web_sys::TextDecoder::new_with_label(label)?,
+            done: false,
+        })
+    }
+
+    /// The name of the encoding, e.g. `utf-8`.
+    pub fn encoding(&self) -> String {
+        self.decoder.encoding()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream<Item = Result<Vec<u8>>>> Stream for TextDecoderStream<S> {
+    type Item = Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if *this.done {
+                return Poll::Ready(None);
+            }
+            let text = match futures_util::ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(mut chunk)) => {
+                    let mut options = web_sys::TextDecodeOptions::new();
+                    options.stream(true);
+                    this.decoder
+                        .decode_with_u8_array_and_options(&mut chunk, &options)
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    // Flush the bytes of an incomplete character at the end of the stream.
+                    *this.done = true;
+                    this.decoder.decode()
+                }
+            };
+            match text {
+                Ok(text) if text.is_empty() => continue,
+                Ok(text) => return Poll::Ready(Some(Ok(text))),
+                Err(e) => return Poll::Ready(Some(Err(e.into()))),
+            }
+        }
+    }
+}
+
+/// A stream of byte chunks encoded from a stream of text, as UTF-8 like a
+/// [TextEncoder](https://developer.mozilla.org/en-US/docs/Web/API/TextEncoder), e.g. to use as a
+/// body.
+#[pin_project]
+#[derive(Debug)]
+pub struct TextEncoderStream<S> {
+    #[pin]
+    inner: S,
+}
+
+impl<S> TextEncoderStream<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream<Item = Result<String>>> Stream for TextEncoderStream<S> {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        this.inner
+            .poll_next(cx)
+            .map(|text| text.map(|text| text.map(String::into_bytes)))
+    }
+}
+
+/// A stream of the lines of a stream of text, without their `\n` or `\r\n` terminator. The last
+/// line is yielded even if it isn't terminated, unless it's empty.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use futures_util::StreamExt;
+/// # async fn example(url: &str) -> Result<()> {
+/// let mut upstream = Fetch::Url(url.parse()?).send().await?;
+/// let mut lines = upstream.stream()?.lines();
+/// while let Some(line) = lines.next().await {
+///     let event: serde_json::Value = serde_json::from_str(&line?)?;
+///     console_log!("{event}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[pin_project]
+#[derive(Debug)]
+pub struct Lines<S> {
+    #[pin]
+    inner: S,
+    buffer: String,
+    done: bool,
+}
+
+impl<S> Lines<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            buffer: String::new(),
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream<Item = Result<String>>> Stream for Lines<S> {
+    type Item = Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(end) = this.buffer.find('\n') {
+                let mut line: String = this.buffer.drain(..=end).collect();
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+                return Poll::Ready(Some(Ok(line)));
+            }
+            if *this.done {
+                if this.buffer.is_empty() {
+                    return Poll::Ready(None);
+                }
+                return Poll::Ready(Some(Ok(std::mem::take(this.buffer))));
+            }
+            match futures_util::ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(text)) => this.buffer.push_str(&text),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => *this.done = true,
+            }
+        }
+    }
+}
+
+/// A stream of byte chunks which hashes the chunks as they're read, e.g. to check the integrity
+/// of an upload while it's forwarded to R2, or to validate the signature of a webhook, without
+/// buffering the body twice. The chunks are fed to the runtime's
+/// [`crypto.DigestStream`](https://developers.cloudflare.com/workers/runtime-apis/web-crypto/#constructors),
+/// which supports `SHA-1`, `SHA-256`, `SHA-384`, `SHA-512` and `MD5`.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(mut req: Request, bucket: Bucket) -> Result<Response> {
+/// let length = req
+///     .headers()
+///     .get("Content-Length")?
+///     .unwrap_or_default()
+///     .parse::<u64>()
+///     .map_err(|e| Error::RustError(e.to_string()))?;
+/// let (body, digest) = req.stream()?.digest("MD5")?;
+/// bucket
+///     .put("upload", FixedLengthStream::wrap(body, length))
+///     .execute()
+///     .await?;
+/// let md5 = digest.await?;
+/// # Response::ok("")
+/// # }
+/// ```
+///
+/// Dropping the stream before it ended fails the digest.
+///
+/// **Requires** `crypto` feature.
+#[cfg(feature = "crypto")]
+#[pin_project(PinnedDrop)]
+#[derive(Debug)]
+pub struct DigestingStream<S> {
+    #[pin]
+    inner: S,
+    writer: Option<WritableStreamDefaultWriter>,
+}
+
+#[cfg(feature = "crypto")]
+impl<S> DigestingStream<S> {
+    /// Hash the chunks of `inner` with `algorithm`, returning the stream and the digest, which
+    /// resolves once the stream was read to the end.
+    pub fn new(inner: S, algorithm: &str) -> Result<(Self, Digest)> {
+        let sink = DigestStreamSys::new(algorithm)?;
+        let digest = Digest(JsFuture::from(sink.digest()));
+        let writer = sink.get_writer()?;
+        let stream = Self {
+            inner,
+            writer: Some(writer),
+        };
+        Ok((stream, digest))
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl<S: Stream<Item = Result<Vec<u8>>>> Stream for DigestingStream<S> {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = futures_util::ready!(this.inner.poll_next(cx));
+        // The writes are queued by the writer, so they don't need to be awaited.
+        match (&item, this.writer.as_ref()) {
+            (Some(Ok(chunk)), Some(writer)) => {
+                let _ = writer.write_with_chunk(&Uint8Array::from(chunk.as_slice()));
+            }
+            (Some(Err(e)), Some(writer)) => {
+                let _ = writer.abort_with_reason(&JsValue::from_str(&e.to_string()));
+                *this.writer = None;
+            }
+            (None, Some(writer)) => {
+                let _ = writer.close();
+                *this.writer = None;
+            }
+            _ => {}
+        }
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(feature = "crypto")]
+#[pin_project::pinned_drop]
+impl<S> PinnedDrop for DigestingStream<S> {
+    fn drop(self: Pin<&mut Self>) {
+        if let Some(writer) = self.project().writer.take() {
+            let _ = writer.abort_with_reason(&JsValue::from_str("the stream was dropped"));
+        }
+    }
+}
+
+/// The digest of a [`DigestingStream`], which resolves to the raw hash bytes once the stream was
+/// read to the end, or fails if reading the stream failed or it was dropped.
+#[cfg(feature = "crypto")]
+#[derive(Debug)]
+pub struct Digest(JsFuture);
+
+#[cfg(feature = "crypto")]
+impl Future for Digest {
+    type Output = Result<Vec<u8>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let hash = futures_util::ready!(Pin::new(&mut self.0).poll(cx))?;
+        Poll::Ready(Ok(Uint8Array::new(&hash).to_vec()))
+    }
+}
+
+#[test]
+fn lines_works() {
+    use futures_util::{FutureExt, StreamExt};
+
+    let collect = |chunks: Vec<&str>| {
+        let chunks = chunks.into_iter().map(|chunk| Ok(chunk.to_string()));
+        Lines::new(futures_util::stream::iter(chunks))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .now_or_never()
+            .unwrap()
+    };
+    assert_eq!(collect(vec![]), Vec::<String>::new());
+    assert_eq!(collect(vec!["a\nb"]), ["a", "b"]);
+    assert_eq!(
+        collect(vec!["{\"a\":", "1}\r\n{}", "\n\n"]),
+        ["{\"a\":1}", "{}", ""]
+    );
+}
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use js_sys::Reflect;
+use serde::de::DeserializeOwned;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    durable::{internal_url, json_response},
+    Delay, Error, Method, Request, Response, Result, Stub,
+};
+
+/// When and how often a [`RetryingStub`] retries requests which failed with a transient Durable
+/// Object error, such as the object being reset after a code update.
+///
+/// Requests are retried with exponential backoff: the `n`th retry waits a random duration between
+/// half and all of `base_delay * 2^n`, capped at `max_delay`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    retry_overloaded: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+            retry_overloaded: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Up to 3 attempts, waiting around 100 and 200 milliseconds between them.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many times a request is sent at most, including the first attempt.
+    #[must_use]
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    #[must_use]
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Whether to also retry when the object is overloaded. Disabled by default, as retrying
+    /// adds to the load of an object which is already receiving more requests than it can handle.
+    #[must_use]
+    pub fn retry_overloaded(mut self, retry: bool) -> Self {
+        self.retry_overloaded = retry;
+        self
+    }
+
+    /// Whether `error` is worth retrying: the runtime marks transient errors as `retryable`.
+    fn should_retry(&self, error: &Error) -> bool {
+        let value = match error.js_value() {
+            Some(value) => value,
+            None => return false,
+        };
+        let flag = |name: &str| {
+            Reflect::get(value, &JsValue::from_str(name))
+                .map(|flag| flag.is_truthy())
+                .unwrap_or(false)
+        };
+        flag("retryable") && (self.retry_overloaded || !flag("overloaded"))
+    }
+
+    /// How long to wait before retry number `retry`, counted from 0, given a random number in
+    /// `[0, 1)`.
+    fn backoff(&self, retry: u32, random: f64) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let nanos = exponential.as_nanos() as f64 * (0.5 + random / 2.0);
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+/// A [`Stub`] which retries idempotent requests according to a [`RetryPolicy`], created with
+/// [`Stub::with_retry`].
+///
+/// Only requests with an idempotent method (`GET`, `HEAD`, `OPTIONS`, `PUT` and `DELETE`) are
+/// retried, as the object may have processed a request before failing. Other requests are sent
+/// once. Requests with a body which can't be replayed, such as a stream, are sent once too.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(env: Env) -> Result<()> {
+/// let stub = env
+///     .durable_object("COUNTER")?
+///     .id_from_name("visits")?
+///     .get_stub()?
+///     .with_retry(RetryPolicy::new().max_attempts(5));
+/// let count: u64 = stub.get_json("/count").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RetryingStub {
+    stub: Stub,
+    policy: RetryPolicy,
+}
+
+impl Stub {
+    /// Retry the requests sent through this stub which fail with transient errors.
+    pub fn with_retry(self, policy: RetryPolicy) -> RetryingStub {
+        RetryingStub { stub: self, policy }
+    }
+}
+
+impl RetryingStub {
+    pub fn stub(&self) -> &Stub {
+        &self.stub
+    }
+
+    pub fn into_inner(self) -> Stub {
+        self.stub
+    }
+
+    /// Send `req` to the Durable Object, retrying it if its method is idempotent.
+    pub async fn fetch_with_request(&self, req: Request) -> Result<Response> {
+        let idempotent = matches!(
+            req.method(),
+            Method::Get | Method::Head | Method::Options | Method::Put | Method::Delete
+        );
+        let mut retry = 0;
+        loop {
+            let last_attempt = !idempotent || retry + 1 >= self.policy.max_attempts;
+            // Every attempt but the last needs its own copy of the request, as sending it uses
+            // up its body.
+            let attempt = if last_attempt { None } else { req.clone().ok() };
+            let result = match attempt {
+                Some(attempt) => self.stub.fetch_with_request(attempt).await,
+                None => return self.stub.fetch_with_request(req).await,
+            };
+            match result {
+                Err(e) if self.policy.should_retry(&e) => {
+                    let delay = self.policy.backoff(retry, js_sys::Math::random());
+                    Delay::from(delay).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Send a `GET` request for `url` to the Durable Object.
+    pub async fn fetch_with_str(&self, url: &str) -> Result<Response> {
+        self.fetch_with_request(Request::new(url, Method::Get)?)
+            .await
+    }
+
+    /// Like [`Stub::request`].
+    pub async fn request(&self, method: Method, path: &str) -> Result<Response> {
+        let req = Request::new(&internal_url(path), method)?;
+        self.fetch_with_request(req).await
+    }
+
+    /// Like [`Stub::get_json`].
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let resp = self.request(Method::Get, path).await?;
+        json_response(resp, path).await
+    }
+}
+
+#[test]
+fn backoff_works() {
+    let policy = RetryPolicy::new()
+        .base_delay(Duration::from_millis(100))
+        .max_delay(Duration::from_millis(500));
+    assert_eq!(policy.backoff(0, 0.0), Duration::from_millis(50));
+    assert_eq!(policy.backoff(1, 0.0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(1, 0.5), Duration::from_millis(150));
+    assert_eq!(policy.backoff(5, 0.0), Duration::from_millis(250));
+    assert_eq!(policy.backoff(40, 0.0), Duration::from_millis(250));
+}
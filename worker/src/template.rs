@@ -0,0 +1,161 @@
+//! Server-side rendering of [askama](https://docs.rs/askama) templates, whose syntax is checked
+//! and compiled into the Worker at build time.
+//!
+//! A template is rendered synchronously, so on its own it's sent like any other body with
+//! [`Response::from_template`]. A page whose parts depend on slow data can be split into a
+//! [`TemplateStream`] instead: every part is sent as soon as it and the parts before it are
+//! rendered, so the browser can start loading the styles and scripts of the `<head>` while the
+//! rest of the page is still being fetched.
+//!
+//! ```no_run
+//! # use worker::*;
+//! use askama::Template;
+//! use worker::template::TemplateStream;
+//!
+//! #[derive(Template)]
+//! #[template(source = "<html><head><title>{{ title }}</title></head><body>", ext = "html")]
+//! struct Head<'a> {
+//!     title: &'a str,
+//! }
+//!
+//! #[derive(Template)]
+//! #[template(source = "<ul>{% for post in posts %}<li>{{ post }}</li>{% endfor %}</ul>", ext = "html")]
+//! struct Posts {
+//!     posts: Vec<String>,
+//! }
+//!
+//! # async fn example(env: Env) -> Result<Response> {
+//! let kv = env.kv("POSTS")?;
+//! TemplateStream::new()
+//!     .template(&Head { title: "Blog" })
+//!     .deferred(async move {
+//!         let posts = kv.get("latest").json().await?.unwrap_or_default();
+//!         Ok(Posts { posts })
+//!     })
+//!     .html("</body></html>")
+//!     .into_response()
+//! # }
+//! ```
+
+use std::future::Future;
+
+use askama::Template;
+use futures_util::{future::LocalBoxFuture, stream, FutureExt, StreamExt};
+
+use crate::{Error, Headers, Response, Result};
+
+fn render<T: Template>(template: &T) -> Result<String> {
+    template
+        .render()
+        .map_err(|e| Error::RustError(format!("failed to render template: {e}")))
+}
+
+fn content_type<T: Template>() -> String {
+    match T::MIME_TYPE {
+        mime if mime.starts_with("text/") => format!("{mime}; charset=utf-8"),
+        mime => mime.to_string(),
+    }
+}
+
+impl Response {
+    /// Create a `Response` with the rendered template for the body. The `Content-Type` header is
+    /// set from the extension of the template, e.g. `text/html; charset=utf-8` for `.html`
+    /// templates.
+    pub fn from_template<T: Template>(template: &T) -> Result<Self> {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", &content_type::<T>())?;
+        Ok(Response::from_bytes(render(template)?.into_bytes())?.with_headers(headers))
+    }
+}
+
+/// A page sent in parts, each as soon as it's rendered, see the [module documentation](self).
+///
+/// Deferred parts are all awaited concurrently, but are sent in order. If a part fails to render,
+/// the response body ends with an error, so the client can tell the page is incomplete.
+pub struct TemplateStream {
+    parts: Vec<LocalBoxFuture<'static, Result<String>>>,
+    content_type: String,
+}
+
+impl TemplateStream {
+    /// An empty HTML page.
+    pub fn new() -> Self {
+        Self {
+            parts: Vec::new(),
+            content_type: "text/html; charset=utf-8".into(),
+        }
+    }
+
+    /// Use `content_type` rather than `text/html; charset=utf-8`.
+    #[must_use]
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    /// Append `html` as it is, without escaping it.
+    #[must_use]
+    pub fn html(mut self, html: impl Into<String>) -> Self {
+        let html = html.into();
+        self.parts.push(async move { Ok(html) }.boxed_local());
+        self
+    }
+
+    /// Append `template`, rendered right away.
+    #[must_use]
+    pub fn template<T: Template>(mut self, template: &T) -> Self {
+        let rendered = render(template);
+        self.parts.push(async move { rendered }.boxed_local());
+        self
+    }
+
+    /// Append the template `future` resolves to, once it's rendered and every part before it was
+    /// sent.
+    #[must_use]
+    pub fn deferred<F, T>(mut self, future: F) -> Self
+    where
+        F: Future<Output = Result<T>> + 'static,
+        T: Template,
+    {
+        self.parts
+            .push(async move { render(&future.await?) }.boxed_local());
+        self
+    }
+
+    /// The `200` response streaming the parts.
+    pub fn into_response(self) -> Result<Response> {
+        let mut headers = Headers::new();
+        headers.set("Content-Type", &self.content_type)?;
+        let concurrency = self.parts.len().max(1);
+        let body = stream::iter(self.parts)
+            .buffered(concurrency)
+            .map(|part| part.map(String::into_bytes));
+        Ok(Response::from_stream(body)?.with_headers(headers))
+    }
+}
+
+impl Default for TemplateStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn content_type_works() {
+    #[derive(Template)]
+    #[template(source = "<p>{{ name }}</p>", ext = "html")]
+    struct Html<'a> {
+        name: &'a str,
+    }
+
+    #[derive(Template)]
+    #[template(source = "{{ name }}", ext = "txt")]
+    struct Text<'a> {
+        name: &'a str,
+    }
+
+    assert_eq!(content_type::<Html>(), "text/html; charset=utf-8");
+    assert_eq!(content_type::<Text>(), "text/plain; charset=utf-8");
+    assert_eq!(render(&Html { name: "<b>" }).unwrap(), "<p>&lt;b&gt;</p>");
+    assert_eq!(render(&Text { name: "<b>" }).unwrap(), "<b>");
+}
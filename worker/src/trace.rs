@@ -0,0 +1,199 @@
+use crate::{Headers, Next, Request, Response, Result, RouteContext};
+
+/// A [W3C Trace Context](https://www.w3.org/TR/trace-context/), identifying the trace a request
+/// belongs to and the span of this Worker within it.
+///
+/// [`TraceContext::middleware`] continues the trace of incoming requests with a `traceparent`
+/// header, or starts a new one, and stores it in the [extensions](RouteContext::extensions) of
+/// the request. The context travels with the request rather than being global, since an isolate
+/// handles many requests at once: outbound [`Fetch`](crate::Fetch) and
+/// [`Fetcher`](crate::Fetcher) requests carry it in their `traceparent` header when it's in their
+/// [extensions](Request::extensions_mut), unless they already have the header, so a trace can be
+/// followed across service bindings. [`inject`](TraceContext::inject) adds the header to requests
+/// built from a [`RequestInit`](crate::RequestInit).
+///
+/// ```no_run
+/// # use worker::*;
+/// Router::new()
+///     .middleware(TraceContext::middleware)
+///     .get_async("/", |_, ctx| async move {
+///         let trace = ctx.extensions().get::<TraceContext>().unwrap();
+///         let mut req = Request::new("https://api.example/users", Method::Get)?;
+///         req.extensions_mut().insert(trace.clone());
+///         Fetch::Request(req).send().await
+///     });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: String,
+    span_id: String,
+    parent_id: Option<String>,
+    sampled: bool,
+}
+
+impl TraceContext {
+    /// Start a new trace.
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_hex(16),
+            span_id: random_hex(8),
+            parent_id: None,
+            sampled: true,
+        }
+    }
+
+    /// Parse a `traceparent` header, returning the context of the remote parent span. Returns
+    /// `None` if the header is malformed.
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+        // Later versions may add fields, which are ignored.
+        if !is_hex(version, 2) || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if !is_id(trace_id, 32) || !is_id(parent_id, 16) || !is_hex(flags, 2) {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id: trace_id.into(),
+            span_id: parent_id.into(),
+            parent_id: None,
+            sampled: flags & 1 == 1,
+        })
+    }
+
+    /// Continue the trace of `req` from its `traceparent` header, or start a new trace if it has
+    /// none.
+    pub fn from_request(req: &Request) -> Self {
+        req.headers()
+            .get("traceparent")
+            .ok()
+            .flatten()
+            .and_then(|header| Self::parse(&header))
+            .map(|parent| parent.child())
+            .unwrap_or_else(Self::new_root)
+    }
+
+    /// A new span within the same trace, whose parent is this span.
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: random_hex(8),
+            parent_id: Some(self.span_id.clone()),
+            sampled: self.sampled,
+        }
+    }
+
+    /// The id of the whole trace, which also serves as an id of the request.
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// The id of this span.
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
+
+    /// The id of the span which made the request, if the trace was continued.
+    pub fn parent_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+
+    /// Whether the caller recorded the trace.
+    pub fn sampled(&self) -> bool {
+        self.sampled
+    }
+
+    /// The `traceparent` header for requests made from this span.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.trace_id, self.span_id, self.sampled as u8
+        )
+    }
+
+    /// Set the `traceparent` header of `headers` for an outbound request made from this span,
+    /// unless they already have one.
+    pub fn inject(&self, headers: &mut Headers) -> Result<()> {
+        if !headers.has("traceparent")? {
+            headers.set("traceparent", &self.traceparent())?;
+        }
+        Ok(())
+    }
+
+    /// A [`Middleware`](crate::Middleware) which continues or starts the trace of each request
+    /// and stores it in the [extensions](RouteContext::extensions).
+    pub async fn middleware<'a, D: 'a>(
+        req: Request,
+        mut ctx: RouteContext<D>,
+        next: Next<'a, D>,
+    ) -> Result<Response> {
+        ctx.extensions_mut().insert(Self::from_request(&req));
+        next.run(req, ctx).await
+    }
+}
+
+/// The request to send instead of `req`, with the `traceparent` header of the [`TraceContext`]
+/// in its extensions. The headers of `req` may be immutable, so it's copied when the header needs
+/// to be added.
+pub(crate) fn propagate(req: &Request) -> Option<web_sys::Request> {
+    let trace = req.extensions().get::<TraceContext>()?;
+    if req.inner().headers().has("traceparent").unwrap_or(true) {
+        return None;
+    }
+    let copy = web_sys::Request::new_with_request(req.inner()).ok()?;
+    copy.headers()
+        .set("traceparent", &trace.traceparent())
+        .ok()?;
+    Some(copy)
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Trace and span ids must not be all zeroes.
+fn is_id(s: &str, len: usize) -> bool {
+    is_hex(s, len) && s.bytes().any(|b| b != b'0')
+}
+
+fn random_hex(len: usize) -> String {
+    let weak_random = || {
+        (0..len)
+            .map(|_| (js_sys::Math::random() * 256.0) as u8)
+            .collect::<Vec<u8>>()
+    };
+    #[cfg(feature = "crypto")]
+    let bytes = crate::crypto::random_bytes(len).unwrap_or_else(|_| weak_random());
+    #[cfg(not(feature = "crypto"))]
+    let bytes = weak_random();
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn parse_works() {
+    let trace =
+        TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+    assert_eq!(trace.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(trace.span_id(), "00f067aa0ba902b7");
+    assert!(trace.sampled());
+    assert_eq!(
+        trace.traceparent(),
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+    );
+
+    assert!(
+        TraceContext::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+    );
+    assert!(
+        TraceContext::parse("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none()
+    );
+    assert!(
+        TraceContext::parse("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+    );
+    assert!(TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7").is_none());
+}
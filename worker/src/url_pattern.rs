@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use worker_sys::UrlPattern as UrlPatternSys;
+
+use crate::{Error, Result};
+
+/// The runtime's [`URLPattern`](https://developer.mozilla.org/en-US/docs/Web/API/URLPattern),
+/// which matches URLs against patterns for any of their components, e.g. to route on the
+/// hostname or to constrain a group with a regular expression.
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example() -> Result<()> {
+/// let pattern = UrlPattern::new("https://:tenant.example.com/users/:id(\\d+)")?;
+/// let url = Url::parse("https://acme.example.com/users/42")?;
+/// let matched = pattern.exec(&url)?.unwrap();
+/// assert_eq!(matched.group("tenant"), Some("acme"));
+/// assert_eq!(matched.pathname().group("id"), Some("42"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Patterns can also be [routed](crate::Router::pattern) on.
+#[derive(Debug, Clone)]
+pub struct UrlPattern(UrlPatternSys);
+
+impl UrlPattern {
+    /// A pattern for whole URLs, like `https://*.example.com/books/:id`.
+    pub fn new(pattern: &str) -> Result<Self> {
+        UrlPatternSys::new(&pattern.into())
+            .map(Self)
+            .map_err(Error::from)
+    }
+
+    /// A pattern which only matches the path of URLs, like `/books/:id`.
+    pub fn pathname(pattern: &str) -> Result<Self> {
+        Self::from_init(&UrlPatternInit::new().pathname(pattern))
+    }
+
+    /// A pattern of the given components, the others matching anything.
+    pub fn from_init(init: &UrlPatternInit) -> Result<Self> {
+        let init = serde_wasm_bindgen::to_value(init)?;
+        UrlPatternSys::new(&init).map(Self).map_err(Error::from)
+    }
+
+    /// A pattern which may be relative to `base_url`, like `/books/:id`.
+    pub fn with_base(pattern: &str, base_url: &str) -> Result<Self> {
+        UrlPatternSys::new_with_base(pattern, base_url)
+            .map(Self)
+            .map_err(Error::from)
+    }
+
+    /// Whether `url` matches the pattern.
+    pub fn test(&self, url: impl AsRef<str>) -> Result<bool> {
+        self.0.test(url.as_ref()).map_err(Error::from)
+    }
+
+    /// The groups matched in `url`, `None` if it doesn't match the pattern.
+    pub fn exec(&self, url: impl AsRef<str>) -> Result<Option<UrlPatternMatch>> {
+        let result = self.0.exec(url.as_ref())?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(serde_wasm_bindgen::from_value(result)?))
+    }
+
+    /// The normalized pattern of the protocol.
+    pub fn protocol_pattern(&self) -> String {
+        self.0.protocol()
+    }
+
+    /// The normalized pattern of the hostname.
+    pub fn hostname_pattern(&self) -> String {
+        self.0.hostname()
+    }
+
+    /// The normalized pattern of the port.
+    pub fn port_pattern(&self) -> String {
+        self.0.port()
+    }
+
+    /// The normalized pattern of the path.
+    pub fn pathname_pattern(&self) -> String {
+        self.0.pathname()
+    }
+
+    /// The normalized pattern of the query, without `?`.
+    pub fn search_pattern(&self) -> String {
+        self.0.search()
+    }
+
+    /// The normalized pattern of the fragment, without `#`.
+    pub fn hash_pattern(&self) -> String {
+        self.0.hash()
+    }
+}
+
+/// The components of a [`UrlPattern`]. Components which aren't set match anything.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlPatternInit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protocol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pathname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_url: Option<String>,
+}
+
+impl UrlPatternInit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn protocol(mut self, pattern: impl Into<String>) -> Self {
+        self.protocol = Some(pattern.into());
+        self
+    }
+
+    #[must_use]
+    pub fn username(mut self, pattern: impl Into<String>) -> Self {
+        self.username = Some(pattern.into());
+        self
+    }
+
+    #[must_use]
+    pub fn password(mut self, pattern: impl Into<String>) -> Self {
+        self.password = Some(pattern.into());
+        self
+    }
+
+    #[must_use]
+    pub fn hostname(mut self, pattern: impl Into<String>) -> Self {
+        self.hostname = Some(pattern.into());
+        self
+    }
+
+    #[must_use]
+    pub fn port(mut self, pattern: impl Into<String>) -> Self {
+        self.port = Some(pattern.into());
+        self
+    }
+
+    #[must_use]
+    pub fn pathname(mut self, pattern: impl Into<String>) -> Self {
+        self.pathname = Some(pattern.into());
+        self
+    }
+
+    /// The pattern of the query, without `?`.
+    #[must_use]
+    pub fn search(mut self, pattern: impl Into<String>) -> Self {
+        self.search = Some(pattern.into());
+        self
+    }
+
+    /// The pattern of the fragment, without `#`.
+    #[must_use]
+    pub fn hash(mut self, pattern: impl Into<String>) -> Self {
+        self.hash = Some(pattern.into());
+        self
+    }
+
+    /// The URL relative component patterns are resolved against.
+    #[must_use]
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+}
+
+/// The groups a [`UrlPattern`] matched in a URL.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrlPatternMatch {
+    protocol: UrlPatternComponent,
+    username: UrlPatternComponent,
+    password: UrlPatternComponent,
+    hostname: UrlPatternComponent,
+    port: UrlPatternComponent,
+    pathname: UrlPatternComponent,
+    search: UrlPatternComponent,
+    hash: UrlPatternComponent,
+}
+
+impl UrlPatternMatch {
+    pub fn protocol(&self) -> &UrlPatternComponent {
+        &self.protocol
+    }
+
+    pub fn username(&self) -> &UrlPatternComponent {
+        &self.username
+    }
+
+    pub fn password(&self) -> &UrlPatternComponent {
+        &self.password
+    }
+
+    pub fn hostname(&self) -> &UrlPatternComponent {
+        &self.hostname
+    }
+
+    pub fn port(&self) -> &UrlPatternComponent {
+        &self.port
+    }
+
+    pub fn pathname(&self) -> &UrlPatternComponent {
+        &self.pathname
+    }
+
+    pub fn search(&self) -> &UrlPatternComponent {
+        &self.search
+    }
+
+    pub fn hash(&self) -> &UrlPatternComponent {
+        &self.hash
+    }
+
+    fn components(&self) -> [&UrlPatternComponent; 8] {
+        [
+            &self.protocol,
+            &self.username,
+            &self.password,
+            &self.hostname,
+            &self.port,
+            &self.search,
+            &self.hash,
+            &self.pathname,
+        ]
+    }
+
+    /// The value of the group `name` in any component, preferring the path.
+    pub fn group(&self, name: &str) -> Option<&str> {
+        self.components()
+            .iter()
+            .rev()
+            .find_map(|component| component.group(name))
+    }
+
+    /// Every group which matched something, those of the path taking precedence over groups of
+    /// the same name in other components. Unnamed groups are numbered from `0` per component.
+    pub fn groups(&self) -> HashMap<String, String> {
+        self.components()
+            .iter()
+            .flat_map(|component| component.groups.iter())
+            .filter_map(|(name, value)| Some((name.clone(), value.clone()?)))
+            .collect()
+    }
+
+    /// Deserialize the [`groups`](UrlPatternMatch::groups) into `T`, parsing them like the
+    /// parameters of a query string.
+    ///
+    /// ```no_run
+    /// # use worker::*;
+    /// #[derive(serde::Deserialize)]
+    /// struct Book {
+    ///     author: String,
+    ///     id: u32,
+    /// }
+    ///
+    /// # fn example() -> Result<()> {
+    /// let pattern = UrlPattern::pathname("/:author/books/:id(\\d+)")?;
+    /// let book: Book = pattern
+    ///     .exec("https://example.com/asimov/books/7")?
+    ///     .unwrap()
+    ///     .params()?;
+    /// assert_eq!(book.id, 7);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn params<T: DeserializeOwned>(&self) -> Result<T> {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(self.groups())
+            .finish();
+        Ok(serde_urlencoded::from_str(&query)?)
+    }
+}
+
+/// What a component of a URL matched.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UrlPatternComponent {
+    input: String,
+    groups: HashMap<String, Option<String>>,
+}
+
+impl UrlPatternComponent {
+    /// The component of the URL which was matched.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The value of the group `name`, `None` if it's optional and didn't match.
+    pub fn group(&self, name: &str) -> Option<&str> {
+        self.groups.get(name)?.as_deref()
+    }
+
+    pub fn groups(&self) -> &HashMap<String, Option<String>> {
+        &self.groups
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    fmt::{self, Display},
+    ops::{Bound, RangeBounds},
+};
+
+use serde::Serialize;
+
+use crate::{Error, Response, Result};
+
+/// Constraints on the input of a handler, checked by [`Request::json_validated`] and
+/// [`Request::form_validated`]. Violations are returned as [`Error::Validation`], which the
+/// [`Router`](crate::Router) turns into a `422 Unprocessable Content` response listing them.
+///
+/// ```no_run
+/// # use worker::*;
+/// #[derive(serde::Deserialize)]
+/// struct NewUser {
+///     name: String,
+///     email: String,
+///     age: u8,
+/// }
+///
+/// impl Validate for NewUser {
+///     fn validate(&self, v: &mut Violations) {
+///         v.length("name", &self.name, 1..=64)
+///             .check("email", self.email.contains('@'), "must be an email address")
+///             .range("age", self.age, 13..);
+///     }
+/// }
+///
+/// # fn example() -> Router<'static, ()> {
+/// Router::new().post_async("/users", |mut req, _| async move {
+///     let user: NewUser = req.json_validated().await?;
+///     Response::ok(format!("Welcome, {}", user.name))
+/// })
+/// # }
+/// ```
+pub trait Validate {
+    /// Add a violation for every constraint that isn't met.
+    fn validate(&self, violations: &mut Violations);
+
+    /// Return `self` if it meets every constraint, or [`Error::Validation`] listing the violations.
+    fn validated(self) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut violations = Violations::new();
+        self.validate(&mut violations);
+        violations.into_result().map(|_| self)
+    }
+}
+
+/// A constraint which wasn't met by a field of the input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub field: String,
+    pub message: String,
+}
+
+/// The violations found while validating an input, see [`Validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Violations(Vec<Violation>);
+
+#[derive(Serialize)]
+struct ViolationsBody<'a> {
+    error: &'static str,
+    violations: &'a Violations,
+}
+
+impl Violations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `field` violates a constraint described by `message`.
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) -> &mut Self {
+        self.0.push(Violation {
+            field: field.into(),
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Record a violation of `field` with `message` unless `valid`.
+    pub fn check(&mut self, field: &str, valid: bool, message: &str) -> &mut Self {
+        if !valid {
+            self.add(field, message);
+        }
+        self
+    }
+
+    /// Check that `value` has a number of characters in `range`.
+    pub fn length(
+        &mut self,
+        field: &str,
+        value: &str,
+        range: impl RangeBounds<usize>,
+    ) -> &mut Self {
+        if !range.contains(&value.chars().count()) {
+            self.add(
+                field,
+                format!("must be {} characters long", describe(&range)),
+            );
+        }
+        self
+    }
+
+    /// Check that `value` is in `range`.
+    pub fn range<T>(&mut self, field: &str, value: T, range: impl RangeBounds<T>) -> &mut Self
+    where
+        T: PartialOrd + Display,
+    {
+        if !range.contains(&value) {
+            self.add(field, format!("must be {}", describe(&range)));
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Violation> {
+        self.0.iter()
+    }
+
+    /// `Ok` if there are no violations, [`Error::Validation`] otherwise.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Validation(self))
+        }
+    }
+
+    /// A `422` response with a JSON body listing the violations, of the form
+    /// `{"error": "validation failed", "violations": [{"field": "...", "message": "..."}]}`.
+    pub fn to_response(&self) -> Result<Response> {
+        Ok(Response::from_json(&ViolationsBody {
+            error: "validation failed",
+            violations: self,
+        })?
+        .with_status(422))
+    }
+}
+
+impl Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, violation) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "`{}` {}", violation.field, violation.message)?;
+        }
+        Ok(())
+    }
+}
+
+fn describe<T: Display>(range: &impl RangeBounds<T>) -> String {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => format!("between {start} and {end}"),
+        (Bound::Included(start), Bound::Excluded(end)) => {
+            format!("at least {start} and less than {end}")
+        }
+        (Bound::Excluded(start), Bound::Included(end)) => {
+            format!("more than {start} and at most {end}")
+        }
+        (Bound::Excluded(start), Bound::Excluded(end)) => {
+            format!("more than {start} and less than {end}")
+        }
+        (Bound::Included(start), Bound::Unbounded) => format!("at least {start}"),
+        (Bound::Excluded(start), Bound::Unbounded) => format!("more than {start}"),
+        (Bound::Unbounded, Bound::Included(end)) => format!("at most {end}"),
+        (Bound::Unbounded, Bound::Excluded(end)) => format!("less than {end}"),
+        (Bound::Unbounded, Bound::Unbounded) => "any value".into(),
+    }
+}
+
+#[test]
+fn violations_work() {
+    let mut v = Violations::new();
+    v.length("name", "", 1..=64)
+        .range("age", 12, 13..)
+        .range("score", 0.5, 0.0..1.0)
+        .check("email", false, "must be an email address");
+    assert_eq!(
+        v.to_string(),
+        "`name` must be between 1 and 64 characters long, `age` must be at least 13, \
+         `email` must be an email address"
+    );
+    assert!(Violations::new().into_result().is_ok());
+}
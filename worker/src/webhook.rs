@@ -0,0 +1,245 @@
+//! Signature verification of the webhooks sent by [Stripe](https://stripe.com/docs/webhooks/signatures),
+//! [GitHub](https://docs.github.com/en/webhooks/using-webhooks/validating-webhook-deliveries) and
+//! [Slack](https://api.slack.com/authentication/verifying-requests-from-slack), which sign the raw
+//! body of their requests with an HMAC-SHA256 secret.
+//!
+//! Signatures are checked with `crypto.subtle.verify`, which compares them in constant time.
+//!
+//! ```no_run
+//! # use worker::*;
+//! use worker::webhook::{Verification, WebhookVerifier};
+//!
+//! # async fn example(mut req: Request, env: Env) -> Result<Response> {
+//! let verifier = WebhookVerifier::stripe(env.secret("STRIPE_WEBHOOK_SECRET")?.to_string());
+//! let body = match verifier.verify_request(&mut req).await? {
+//!     Verification::Valid(body) => body,
+//!     Verification::Invalid(rejection) => return Response::error(rejection.to_string(), 401),
+//! };
+//! let event: serde_json::Value = serde_json::from_slice(&body)?;
+//! # Response::ok("")
+//! # }
+//! ```
+
+use std::{fmt, time::Duration};
+
+use futures_util::{Stream, TryStreamExt};
+
+use crate::{crypto, Date, Headers, Request, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scheme {
+    Stripe,
+    GitHub,
+    Slack,
+}
+
+/// Verifies the signature of the webhooks of one provider, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct WebhookVerifier {
+    scheme: Scheme,
+    secret: Vec<u8>,
+    tolerance: Duration,
+}
+
+/// Why a webhook was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// A header required by the scheme is missing.
+    MissingHeader(&'static str),
+    /// A header required by the scheme can't be parsed.
+    MalformedHeader(&'static str),
+    /// The signed timestamp, in seconds, is further from now than the tolerance of the verifier,
+    /// which protects against replayed requests.
+    Expired(u64),
+    /// No signature matches the body.
+    InvalidSignature,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::MissingHeader(name) => write!(f, "missing {name} header"),
+            Rejection::MalformedHeader(name) => write!(f, "malformed {name} header"),
+            Rejection::Expired(timestamp) => {
+                write!(f, "webhook timestamp {timestamp} is outside the tolerance")
+            }
+            Rejection::InvalidSignature => write!(f, "invalid webhook signature"),
+        }
+    }
+}
+
+/// The result of verifying a webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// The signature is valid, with the raw body which was signed.
+    Valid(Vec<u8>),
+    Invalid(Rejection),
+}
+
+impl Verification {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Verification::Valid(_))
+    }
+}
+
+impl WebhookVerifier {
+    fn new(scheme: Scheme, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            scheme,
+            secret: secret.into(),
+            tolerance: Duration::from_secs(300),
+        }
+    }
+
+    /// Verify the `Stripe-Signature` header with the signing secret (`whsec_...`) of an endpoint.
+    pub fn stripe(secret: impl Into<Vec<u8>>) -> Self {
+        Self::new(Scheme::Stripe, secret)
+    }
+
+    /// Verify the `X-Hub-Signature-256` header with the secret of a webhook. GitHub doesn't sign a
+    /// timestamp, so these webhooks can't be checked for replays.
+    pub fn github(secret: impl Into<Vec<u8>>) -> Self {
+        Self::new(Scheme::GitHub, secret)
+    }
+
+    /// Verify the `X-Slack-Signature` header with the signing secret of an app.
+    pub fn slack(secret: impl Into<Vec<u8>>) -> Self {
+        Self::new(Scheme::Slack, secret)
+    }
+
+    /// How far the signed timestamp can be from now, 5 minutes by default.
+    #[must_use]
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Verify the headers and body of `req`, reading the body.
+    pub async fn verify_request(&self, req: &mut Request) -> Result<Verification> {
+        let headers = req.headers().clone();
+        self.verify(&headers, req.stream()?).await
+    }
+
+    /// Verify a webhook with `headers` and the raw, unparsed, `body`. The body is only read if the
+    /// headers are well-formed.
+    pub async fn verify<S>(&self, headers: &Headers, body: S) -> Result<Verification>
+    where
+        S: Stream<Item = Result<Vec<u8>>>,
+    {
+        let (timestamp, signatures) = match self.parse_headers(headers)? {
+            Ok(parsed) => parsed,
+            Err(rejection) => return Ok(Verification::Invalid(rejection)),
+        };
+        if let Some(timestamp) = timestamp {
+            let now = Date::now().as_millis() / 1000;
+            if timestamp.abs_diff(now) > self.tolerance.as_secs() {
+                return Ok(Verification::Invalid(Rejection::Expired(timestamp)));
+            }
+        }
+
+        let body: Vec<u8> = body.try_concat().await?;
+        let mut payload = match (self.scheme, timestamp) {
+            (Scheme::Stripe, Some(timestamp)) => format!("{timestamp}.").into_bytes(),
+            (Scheme::Slack, Some(timestamp)) => format!("v0:{timestamp}:").into_bytes(),
+            _ => Vec::new(),
+        };
+        payload.extend_from_slice(&body);
+
+        let algorithm = crypto::algorithm("HMAC", Some("SHA-256"));
+        let key = crypto::import_raw_key(&self.secret, &algorithm, &["verify"]).await?;
+        for signature in signatures {
+            if crypto::verify(&algorithm, &key, &signature, &payload).await? {
+                return Ok(Verification::Valid(body));
+            }
+        }
+        Ok(Verification::Invalid(Rejection::InvalidSignature))
+    }
+
+    /// The signed timestamp, if the scheme has one, and the candidate signatures.
+    #[allow(clippy::type_complexity)]
+    fn parse_headers(
+        &self,
+        headers: &Headers,
+    ) -> Result<std::result::Result<(Option<u64>, Vec<Vec<u8>>), Rejection>> {
+        let header = |name: &'static str| -> Result<std::result::Result<String, Rejection>> {
+            Ok(headers.get(name)?.ok_or(Rejection::MissingHeader(name)))
+        };
+        Ok(match self.scheme {
+            Scheme::Stripe => {
+                header("Stripe-Signature")?.and_then(|value| parse_stripe_signature(&value))
+            }
+            Scheme::GitHub => {
+                const NAME: &str = "X-Hub-Signature-256";
+                header(NAME)?.and_then(|value| {
+                    let signature = value
+                        .strip_prefix("sha256=")
+                        .and_then(decode_hex)
+                        .ok_or(Rejection::MalformedHeader(NAME))?;
+                    Ok((None, vec![signature]))
+                })
+            }
+            Scheme::Slack => {
+                const NAME: &str = "X-Slack-Signature";
+                const TIMESTAMP: &str = "X-Slack-Request-Timestamp";
+                header(NAME)?.and_then(|value| {
+                    let signature = value
+                        .strip_prefix("v0=")
+                        .and_then(decode_hex)
+                        .ok_or(Rejection::MalformedHeader(NAME))?;
+                    let timestamp = header(TIMESTAMP)
+                        .map_err(|_| Rejection::MalformedHeader(TIMESTAMP))??
+                        .parse::<u64>()
+                        .map_err(|_| Rejection::MalformedHeader(TIMESTAMP))?;
+                    Ok((Some(timestamp), vec![signature]))
+                })
+            }
+        })
+    }
+}
+
+/// Parses `t=<timestamp>,v1=<signature>,...`, which may carry several `v1` signatures while the
+/// secret of an endpoint is rolled.
+fn parse_stripe_signature(
+    value: &str,
+) -> std::result::Result<(Option<u64>, Vec<Vec<u8>>), Rejection> {
+    const NAME: &str = "Stripe-Signature";
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for (key, value) in value
+        .split(',')
+        .filter_map(|pair| pair.trim().split_once('='))
+    {
+        match key {
+            "t" => timestamp = value.parse().ok(),
+            "v1" => signatures.push(decode_hex(value).ok_or(Rejection::MalformedHeader(NAME))?),
+            _ => {}
+        }
+    }
+    match timestamp {
+        Some(timestamp) if !signatures.is_empty() => Ok((Some(timestamp), signatures)),
+        _ => Err(Rejection::MalformedHeader(NAME)),
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[test]
+fn parse_stripe_signature_works() {
+    let (timestamp, signatures) =
+        parse_stripe_signature("t=1492774577,v1=5257a869,v0=6ffbb59b,v1=00ff").unwrap();
+    assert_eq!(timestamp, Some(1492774577));
+    assert_eq!(signatures, [vec![0x52, 0x57, 0xa8, 0x69], vec![0x00, 0xff]]);
+    assert_eq!(
+        parse_stripe_signature("v1=5257a869"),
+        Err(Rejection::MalformedHeader("Stripe-Signature"))
+    );
+    assert_eq!(decode_hex("0g"), None);
+}
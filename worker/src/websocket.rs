@@ -1,4 +1,4 @@
-use crate::{Error, Method, Request, Result};
+use crate::{Delay, Error, Method, Request, Result};
 use futures_channel::mpsc::UnboundedReceiver;
 use futures_util::Stream;
 use serde::Serialize;
@@ -7,9 +7,12 @@ use worker_sys::ext::WebSocketExt;
 
 #[cfg(not(feature = "http"))]
 use crate::Fetch;
+use std::future::Future;
+use std::marker::PhantomData;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use wasm_bindgen::convert::FromWasmAbi;
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
@@ -138,6 +141,18 @@ impl WebSocket {
             .map_err(Error::from)
     }
 
+    /// Send the ping message of `keepalive`. The Workers runtime answers protocol-level pings
+    /// itself and doesn't let Workers send them, so pings are plain text messages the other end
+    /// has to answer with the pong message.
+    pub fn ping(&self, keepalive: &Keepalive) -> Result<()> {
+        self.send_with_str(&keepalive.ping)
+    }
+
+    /// Answer a [`ping`](WebSocket::ping) of the other end with the pong message of `keepalive`.
+    pub fn pong(&self, keepalive: &Keepalive) -> Result<()> {
+        self.send_with_str(&keepalive.pong)
+    }
+
     /// Sends raw binary data through the `WebSocket`.
     pub fn send_with_bytes<D: AsRef<[u8]>>(&self, bytes: D) -> Result<()> {
         let slice = bytes.as_ref();
@@ -202,6 +217,12 @@ impl WebSocket {
     /// Gets an implementation [`Stream`](futures::Stream) that yields events from the inner
     /// WebSocket.
     pub fn events(&self) -> Result<EventStream> {
+        self.clone().into_events()
+    }
+
+    /// Like [`WebSocket::events`], but the stream owns the socket, so it can be moved into a
+    /// spawned future.
+    pub fn into_events(self) -> Result<EventStream<'static>> {
         let (tx, rx) = futures_channel::mpsc::unbounded::<Result<WebsocketEvent>>();
         let tx = Rc::new(tx);
 
@@ -227,8 +248,10 @@ impl WebSocket {
 
         Ok(EventStream {
             ws: self,
+            marker: PhantomData,
             rx,
             closed: false,
+            keepalive: None,
             closures: Some((message_closure, error_closure, close_closure)),
         })
     }
@@ -256,7 +279,8 @@ type EvCallback<T> = Closure<dyn FnMut(T)>;
 
 /// A [`Stream`](futures::Stream) that yields [`WebsocketEvent`](crate::ws_events::WebsocketEvent)s
 /// emitted by the inner [`WebSocket`](crate::WebSocket). The stream is guaranteed to always yield a
-/// `WebsocketEvent::Close` as the final non-none item.
+/// `WebsocketEvent::Close` as the final non-none item, unless a [`Keepalive`] ends it with an
+/// error.
 ///
 /// # Example
 /// ```rust,ignore
@@ -275,16 +299,19 @@ type EvCallback<T> = Closure<dyn FnMut(T)>;
 ///         match event.expect("received error in websocket") {
 ///             WebsocketEvent::Message(msg) => console_log!("{:#?}", msg),
 ///             WebsocketEvent::Close(event) => console_log!("Closed!"),
+///             _ => {}
 ///         }
 ///     }
 /// });
 /// ```
 #[pin_project::pin_project(PinnedDrop)]
 pub struct EventStream<'ws> {
-    ws: &'ws WebSocket,
+    ws: WebSocket,
+    marker: PhantomData<&'ws WebSocket>,
     #[pin]
     rx: UnboundedReceiver<Result<WebsocketEvent>>,
     closed: bool,
+    keepalive: Option<KeepaliveState>,
     /// Once we have decided we need to finish the stream, we need to remove any listeners we
     /// registered with the websocket.
     closures: Option<(
@@ -294,6 +321,86 @@ pub struct EventStream<'ws> {
     )>,
 }
 
+/// Pings sent on an interval by an [`EventStream`] to detect dead connections, which would
+/// otherwise stay open until the runtime notices, and to keep proxies from closing idle
+/// connections.
+///
+/// Pings and pongs are text messages, `ping` and `pong` by default. Every message received counts
+/// as a sign of life: the socket is closed with code `1011` once `max_missed` pings in a row got
+/// no message back, and the stream ends with an error.
+///
+/// ```rust,ignore
+/// let mut events = server
+///     .events()?
+///     .with_keepalive(Keepalive::new(Duration::from_secs(30)));
+///
+/// while let Some(event) = events.next().await {
+///     match event? {
+///         WebsocketEvent::Message(msg) => console_log!("{:?}", msg.text()),
+///         WebsocketEvent::Pong => {}
+///         WebsocketEvent::Close(_) => break,
+///         _ => {}
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keepalive {
+    interval: Duration,
+    max_missed: u32,
+    ping: String,
+    pong: String,
+}
+
+impl Keepalive {
+    pub const DEFAULT_PING: &'static str = "ping";
+    pub const DEFAULT_PONG: &'static str = "pong";
+
+    /// Send a ping every `interval`, closing the socket after 2 unanswered pings.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            max_missed: 2,
+            ping: Self::DEFAULT_PING.into(),
+            pong: Self::DEFAULT_PONG.into(),
+        }
+    }
+
+    /// How many pings in a row may go unanswered before the socket is considered dead.
+    #[must_use]
+    pub fn max_missed(mut self, max_missed: u32) -> Self {
+        self.max_missed = max_missed.max(1);
+        self
+    }
+
+    /// The text of ping messages and of the pong messages answering them.
+    #[must_use]
+    pub fn messages(mut self, ping: impl Into<String>, pong: impl Into<String>) -> Self {
+        self.ping = ping.into();
+        self.pong = pong.into();
+        self
+    }
+}
+
+struct KeepaliveState {
+    options: Keepalive,
+    timer: Pin<Box<Delay>>,
+    missed: u32,
+}
+
+impl<'ws> EventStream<'ws> {
+    /// Send pings according to `keepalive` while the stream is polled, and yield
+    /// [`WebsocketEvent::Pong`] for the pong messages answering them.
+    #[must_use]
+    pub fn with_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(KeepaliveState {
+            timer: Box::pin(Delay::from(keepalive.interval)),
+            options: keepalive,
+            missed: 0,
+        });
+        self
+    }
+}
+
 impl<'ws> Stream for EventStream<'ws> {
     type Item = Result<WebsocketEvent>;
 
@@ -304,8 +411,32 @@ impl<'ws> Stream for EventStream<'ws> {
             return Poll::Ready(None);
         }
 
+        if let Some(keepalive) = this.keepalive {
+            while keepalive.timer.as_mut().poll(cx).is_ready() {
+                if keepalive.missed >= keepalive.options.max_missed {
+                    let _ = this.ws.close(Some(1011), Some("keepalive timeout"));
+                    *this.closed = true;
+                    return Poll::Ready(Some(Err(Error::RustError(
+                        "the WebSocket stopped answering pings".into(),
+                    ))));
+                }
+                // Failing to send means the socket is closing, which the close event reports.
+                let _ = this.ws.send_with_str(&keepalive.options.ping);
+                keepalive.missed += 1;
+                keepalive.timer = Box::pin(Delay::from(keepalive.options.interval));
+            }
+        }
+
         // Poll the inner receiver to check if theres any events from our event callbacks.
-        let item = futures_util::ready!(this.rx.poll_next(cx));
+        let mut item = futures_util::ready!(this.rx.poll_next(cx));
+
+        if let (Some(keepalive), Some(Ok(WebsocketEvent::Message(msg)))) = (this.keepalive, &item) {
+            keepalive.missed = 0;
+            let is_pong = msg.text().as_deref() == Some(keepalive.options.pong.as_str());
+            if is_pong {
+                item = Some(Ok(WebsocketEvent::Pong));
+            }
+        }
 
         // Mark the stream as closed if we get a close event and yield None next iteration.
         if let Some(item) = &item {
@@ -361,10 +492,15 @@ pub mod ws_events {
     use crate::Error;
 
     /// Events that can be yielded by a [`EventStream`](crate::EventStream).
+    ///
+    /// More kinds of events may be added, so matches need a wildcard arm.
     #[derive(Debug, Clone)]
+    #[non_exhaustive]
     pub enum WebsocketEvent {
         Message(MessageEvent),
         Close(CloseEvent),
+        /// A pong message answering a ping of a [`Keepalive`](crate::Keepalive).
+        Pong,
     }
 
     /// Wrapper/Utility struct for the `web_sys::MessageEvent`
@@ -433,6 +569,40 @@ pub mod ws_events {
         pub fn was_clean(&self) -> bool {
             self.event.was_clean()
         }
+
+        /// The code, reason and clean flag of the close frame as plain data.
+        pub fn frame(&self) -> CloseFrame {
+            CloseFrame {
+                code: self.code(),
+                reason: self.reason(),
+                was_clean: self.was_clean(),
+            }
+        }
+    }
+
+    /// The contents of a [`CloseEvent`], which unlike the event can be sent to other futures or
+    /// stored.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CloseFrame {
+        /// The close code, e.g. `1000` for a normal closure.
+        pub code: u16,
+        pub reason: String,
+        /// Whether the connection was closed with a closing handshake.
+        pub was_clean: bool,
+    }
+
+    impl CloseFrame {
+        /// Whether the connection was closed normally (code `1000`, or `1005` when no code was
+        /// sent).
+        pub fn is_normal(&self) -> bool {
+            matches!(self.code, 1000 | 1005)
+        }
+    }
+
+    impl From<CloseEvent> for CloseFrame {
+        fn from(event: CloseEvent) -> Self {
+            event.frame()
+        }
     }
 
     impl From<web_sys::CloseEvent> for CloseEvent {
@@ -0,0 +1,268 @@
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, de::IgnoredAny, Serialize};
+
+use crate::{Error, MessageEvent, Result, WebSocket, WebSocketIncomingMessage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+/// A message received through a [`WsCodec`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decoded<T> {
+    Message(T),
+    /// A well-formed message which isn't a `T`, e.g. a variant added by a newer version of the
+    /// peer, with the reason it couldn't be deserialized. It can usually be ignored.
+    Unknown(String),
+}
+
+/// Typed messages over a [`WebSocket`], shared by the two ends of a protocol such as a Durable
+/// Object and the Workers connecting to it.
+///
+/// The versions of the protocol are negotiated as WebSocket subprotocols named after the codec,
+/// e.g. `chat.v2`: the client offers the [`protocols`](WsCodec::protocols) it supports, the server
+/// accepts the newest one it supports too, and both then use the codec for the negotiated
+/// protocol: the server with [`for_protocol`](WsCodec::for_protocol) and the protocol it
+/// accepted, the client with [`for_socket`](WsCodec::for_socket). A peer which didn't negotiate a
+/// protocol is assumed to speak the oldest version.
+///
+/// Messages which are well-formed but aren't a `T` are [`Decoded::Unknown`] rather than errors,
+/// so that a peer can add messages without breaking the older ones.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use futures_util::StreamExt;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// #[serde(tag = "type")]
+/// enum Chat {
+///     Join { room: String },
+///     Say { text: String },
+/// }
+///
+/// # fn example(req: Request) -> Result<Response> {
+/// let codec = WsCodec::<Chat>::json("chat").versions(1, 2);
+/// WebSocketUpgrade::new()
+///     .protocols(codec.protocols())
+///     .upgrade(&req, move |mut ws| async move {
+///         let codec = codec.for_protocol(ws.protocol.as_deref())?;
+///         while let Some(event) = ws.events.next().await {
+///             if let WebsocketEvent::Message(msg) = event? {
+///                 if let Decoded::Message(Chat::Say { text }) = codec.decode(&msg)? {
+///                     codec.send(&ws.socket, &Chat::Say { text })?;
+///                 }
+///             }
+///         }
+///         Ok(())
+///     })
+/// # }
+/// ```
+pub struct WsCodec<T> {
+    name: String,
+    format: Format,
+    min_version: u32,
+    max_version: u32,
+    version: u32,
+    message: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for WsCodec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            format: self.format,
+            min_version: self.min_version,
+            max_version: self.max_version,
+            version: self.version,
+            message: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for WsCodec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WsCodec")
+            .field("name", &self.name)
+            .field("format", &self.format)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> WsCodec<T> {
+    /// A codec sending messages as JSON text, for version `1` of the protocol `name`.
+    pub fn json(name: impl Into<String>) -> Self {
+        Self::new(name.into(), Format::Json)
+    }
+
+    /// A codec sending messages as binary MessagePack, for version `1` of the protocol `name`.
+    ///
+    /// **Requires** `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    pub fn msgpack(name: impl Into<String>) -> Self {
+        Self::new(name.into(), Format::MessagePack)
+    }
+
+    fn new(name: String, format: Format) -> Self {
+        Self {
+            name,
+            format,
+            min_version: 1,
+            max_version: 1,
+            version: 1,
+            message: PhantomData,
+        }
+    }
+
+    /// Support the versions from `min` to `max` of the protocol, using `max` until one is
+    /// negotiated.
+    #[must_use]
+    pub fn versions(mut self, min: u32, max: u32) -> Self {
+        self.min_version = min.min(max);
+        self.max_version = max;
+        self.version = max;
+        self
+    }
+
+    /// The version of the protocol in use.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The subprotocols of the supported versions, newest first, to offer when connecting or to
+    /// give to [`WebSocketUpgrade::protocols`](crate::WebSocketUpgrade::protocols).
+    pub fn protocols(&self) -> Vec<String> {
+        (self.min_version..=self.max_version)
+            .rev()
+            .map(|version| self.protocol(version))
+            .collect()
+    }
+
+    fn protocol(&self, version: u32) -> String {
+        match self.format {
+            Format::Json => format!("{}.v{version}", self.name),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => format!("{}.v{version}.msgpack", self.name),
+        }
+    }
+
+    /// The codec for the negotiated subprotocol `protocol`, or for the oldest version if none
+    /// was. Fails if `protocol` isn't one of the [`protocols`](WsCodec::protocols).
+    pub fn for_protocol(&self, protocol: Option<&str>) -> Result<Self> {
+        let version = match protocol.filter(|protocol| !protocol.is_empty()) {
+            None => self.min_version,
+            Some(protocol) => (self.min_version..=self.max_version)
+                .find(|version| self.protocol(*version) == protocol)
+                .ok_or_else(|| {
+                    Error::RustError(format!(
+                        "subprotocol {protocol:?} is not a supported version of {}",
+                        self.name
+                    ))
+                })?,
+        };
+        Ok(Self {
+            version,
+            ..self.clone()
+        })
+    }
+
+    /// The codec for the subprotocol negotiated by `socket`, on the client end of the connection.
+    ///
+    /// The server end of a [`WebSocketPair`](crate::WebSocketPair) doesn't know the subprotocol
+    /// accepted in the response, so servers should use [`for_protocol`](WsCodec::for_protocol)
+    /// with the accepted protocol, e.g. the `protocol` of an
+    /// [`UpgradedWebSocket`](crate::UpgradedWebSocket).
+    pub fn for_socket(&self, socket: &WebSocket) -> Result<Self> {
+        self.for_protocol(Some(&socket.as_ref().protocol()))
+    }
+
+    /// Send `message` through `socket`.
+    pub fn send(&self, socket: &WebSocket, message: &T) -> Result<()> {
+        match self.format {
+            Format::Json => socket.send_with_str(serde_json::to_string(message)?),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => socket.send_with_bytes(crate::msgpack::encode(message)?),
+        }
+    }
+
+    /// Decode the message of a [`MessageEvent`].
+    pub fn decode(&self, event: &MessageEvent) -> Result<Decoded<T>> {
+        match event.text() {
+            Some(text) => self.decode_text(&text),
+            None => self.decode_bytes(&event.bytes().unwrap_or_default()),
+        }
+    }
+
+    /// Decode a message received by a hibernating Durable Object.
+    pub fn decode_incoming(&self, message: &WebSocketIncomingMessage) -> Result<Decoded<T>> {
+        match message {
+            WebSocketIncomingMessage::String(text) => self.decode_text(text),
+            WebSocketIncomingMessage::Binary(bytes) => self.decode_bytes(bytes),
+        }
+    }
+
+    /// Decode a text frame.
+    pub fn decode_text(&self, text: &str) -> Result<Decoded<T>> {
+        match self.format {
+            Format::Json => decode_json(text.as_bytes()),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => Err(Error::RustError(format!(
+                "expected a binary frame for {}",
+                self.name
+            ))),
+        }
+    }
+
+    /// Decode a binary frame.
+    pub fn decode_bytes(&self, bytes: &[u8]) -> Result<Decoded<T>> {
+        match self.format {
+            Format::Json => decode_json(bytes),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => match crate::msgpack::decode(bytes) {
+                Ok(message) => Ok(Decoded::Message(message)),
+                Err(e) => {
+                    crate::msgpack::decode::<IgnoredAny>(bytes)?;
+                    Ok(Decoded::Unknown(e.to_string()))
+                }
+            },
+        }
+    }
+}
+
+fn decode_json<T: DeserializeOwned>(json: &[u8]) -> Result<Decoded<T>> {
+    match serde_json::from_slice(json) {
+        Ok(message) => Ok(Decoded::Message(message)),
+        Err(e) if e.is_data() => Ok(Decoded::Unknown(e.to_string())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[test]
+fn ws_codec_works() {
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    #[serde(tag = "type")]
+    enum Chat {
+        Say { text: String },
+    }
+
+    let codec = WsCodec::<Chat>::json("chat").versions(1, 3);
+    assert_eq!(codec.protocols(), ["chat.v3", "chat.v2", "chat.v1"]);
+    assert_eq!(codec.version(), 3);
+    assert_eq!(codec.for_protocol(Some("chat.v2")).unwrap().version(), 2);
+    assert_eq!(codec.for_protocol(None).unwrap().version(), 1);
+    assert!(codec.for_protocol(Some("chat.v4")).is_err());
+
+    assert_eq!(
+        codec.decode_text(r#"{"type":"Say","text":"hi"}"#).unwrap(),
+        Decoded::Message(Chat::Say { text: "hi".into() })
+    );
+    assert!(matches!(
+        codec.decode_text(r#"{"type":"Leave"}"#).unwrap(),
+        Decoded::Unknown(_)
+    ));
+    assert!(codec.decode_text("{").is_err());
+}
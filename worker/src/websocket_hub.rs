@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::{Result, State, WebSocket};
+
+/// Keeps track of the [hibernatable WebSockets](https://developers.cloudflare.com/durable-objects/api/websockets/)
+/// accepted by a Durable Object, to send messages to all of them or to those with a tag.
+///
+/// The sockets are tracked by the runtime rather than in the memory of the object, so a `Hub`
+/// only borrows the [`State`] and survives hibernation. Sockets which are closing or closed are
+/// skipped, and sockets which can't be sent to are closed with `1011` so the runtime stops
+/// returning them.
+///
+/// ```no_run
+/// # use worker::*;
+/// # fn example(state: &State, pair: WebSocketPair, room: &str) -> Result<()> {
+/// let hub = Hub::new(state);
+/// hub.accept(&pair.server, &[room]);
+/// hub.send_to_tag(room, &serde_json::json!({ "joined": room }))?;
+/// hub.broadcast_str("someone joined a room");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Hub<'a> {
+    state: &'a State,
+}
+
+impl<'a> Hub<'a> {
+    pub fn new(state: &'a State) -> Self {
+        Self { state }
+    }
+
+    /// Accept `ws` as a hibernatable WebSocket with `tags`, which can be empty.
+    pub fn accept(&self, ws: &WebSocket, tags: &[&str]) {
+        self.state.accept_websocket_with_tags(ws, tags)
+    }
+
+    /// The sockets which are open.
+    pub fn sockets(&self) -> Vec<WebSocket> {
+        open(self.state.get_websockets())
+    }
+
+    /// The sockets with `tag` which are open.
+    pub fn sockets_with_tag(&self, tag: &str) -> Vec<WebSocket> {
+        open(self.state.get_websockets_with_tag(tag))
+    }
+
+    /// The tags `ws` was accepted with.
+    pub fn tags(&self, ws: &WebSocket) -> Vec<String> {
+        self.state.get_tags(ws)
+    }
+
+    /// The number of open sockets.
+    pub fn len(&self) -> usize {
+        self.sockets().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Send `msg` as JSON to every open socket, returning how many it was sent to.
+    pub fn broadcast<T: Serialize>(&self, msg: &T) -> Result<usize> {
+        Ok(self.broadcast_str(serde_json::to_string(msg)?))
+    }
+
+    /// Send `msg` to every open socket, returning how many it was sent to.
+    pub fn broadcast_str(&self, msg: impl AsRef<str>) -> usize {
+        send_all(self.sockets(), msg.as_ref())
+    }
+
+    /// Send `msg` as JSON to every open socket with `tag`, returning how many it was sent to.
+    pub fn send_to_tag<T: Serialize>(&self, tag: &str, msg: &T) -> Result<usize> {
+        Ok(self.send_to_tag_str(tag, serde_json::to_string(msg)?))
+    }
+
+    /// Send `msg` to every open socket with `tag`, returning how many it was sent to.
+    pub fn send_to_tag_str(&self, tag: &str, msg: impl AsRef<str>) -> usize {
+        send_all(self.sockets_with_tag(tag), msg.as_ref())
+    }
+}
+
+fn is_open(ws: &WebSocket) -> bool {
+    ws.as_ref().ready_state() == web_sys::WebSocket::OPEN
+}
+
+fn open(sockets: Vec<WebSocket>) -> Vec<WebSocket> {
+    sockets.into_iter().filter(is_open).collect()
+}
+
+fn send_all(sockets: Vec<WebSocket>, msg: &str) -> usize {
+    let mut sent = 0;
+    for ws in sockets {
+        if ws.send_with_str(msg).is_ok() {
+            sent += 1;
+        } else {
+            let _ = ws.close(Some(1011), Some("send failed"));
+        }
+    }
+    sent
+}
@@ -0,0 +1,361 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::Future,
+    pin::pin,
+    rc::Rc,
+    time::Duration,
+};
+
+use futures_channel::oneshot;
+use futures_util::{
+    future::{select, Either},
+    StreamExt,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Delay, Error, Result, WebSocket, WebSocketIncomingMessage, WebsocketEvent};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A frame of the protocol, sent as JSON text.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Frame {
+    Call {
+        id: u64,
+        method: String,
+        #[serde(default)]
+        params: Value,
+    },
+    Result {
+        id: u64,
+        result: Value,
+    },
+    Error {
+        id: u64,
+        error: String,
+    },
+    Cancel {
+        id: u64,
+        cancel: bool,
+    },
+}
+
+type Pending = Rc<RefCell<HashMap<u64, oneshot::Sender<std::result::Result<Value, String>>>>>;
+
+/// The client end of RPC calls over a [`WebSocket`], answered by an [`RpcServer`], usually in a
+/// Durable Object.
+///
+/// Each call is a JSON text message with an id, which the server's answer carries too, so that
+/// many calls can be made concurrently over the socket. A call which isn't answered within the
+/// timeout fails, and the server is told to cancel it, as it is when the call's future is
+/// dropped. Messages which aren't answers are ignored, and every pending call fails once the
+/// socket closes.
+///
+/// ```no_run
+/// # use worker::*;
+/// # async fn example(env: Env) -> Result<()> {
+/// let stub = env.durable_object("GAME")?.id_from_name("lobby")?.get_stub()?;
+/// let mut resp = stub.fetch_with_str("https://game/rpc").await?;
+/// let socket = resp.websocket().ok_or("not a WebSocket")?;
+/// socket.accept()?;
+///
+/// let rpc = RpcClient::new(socket)?;
+/// let players: Vec<String> = rpc.call("players", &()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RpcClient {
+    socket: WebSocket,
+    pending: Pending,
+    next_id: Rc<Cell<u64>>,
+    timeout: Duration,
+}
+
+impl RpcClient {
+    /// A client calling through the accepted `socket`, which it listens to for the answers.
+    pub fn new(socket: WebSocket) -> Result<Self> {
+        let pending = Pending::default();
+        let mut events = socket.clone().into_events()?;
+        let answers = pending.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            while let Some(event) = events.next().await {
+                let msg = match event {
+                    Ok(WebsocketEvent::Message(msg)) => msg,
+                    Ok(WebsocketEvent::Pong) => continue,
+                    Ok(WebsocketEvent::Close(_)) | Err(_) => break,
+                };
+                let Some(text) = msg.text() else {
+                    continue;
+                };
+                let (id, answer) = match serde_json::from_str(&text) {
+                    Ok(Frame::Result { id, result }) => (id, Ok(result)),
+                    Ok(Frame::Error { id, error }) => (id, Err(error)),
+                    _ => continue,
+                };
+                if let Some(tx) = answers.borrow_mut().remove(&id) {
+                    let _ = tx.send(answer);
+                }
+            }
+            // Dropping the senders fails the pending calls.
+            answers.borrow_mut().clear();
+        });
+
+        // Ids are unique across the clients of a server, and stay below 2^53 for JS clients.
+        let first_id = (js_sys::Math::random() * (1u64 << 52) as f64) as u64;
+        Ok(Self {
+            socket,
+            pending,
+            next_id: Rc::new(Cell::new(first_id)),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// How long calls wait for their answer, 30 seconds by default.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Call `method` with `params`, and deserialize its result.
+    pub async fn call<P, R>(&self, method: &str, params: &P) -> Result<R>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let frame = Frame::Call {
+            id,
+            method: method.into(),
+            params: serde_json::to_value(params)?,
+        };
+        let (tx, rx) = oneshot::channel();
+        self.pending.borrow_mut().insert(id, tx);
+        let _call = Call { client: self, id };
+        self.socket.send(&frame)?;
+
+        let answer = match select(rx, pin!(Delay::from(self.timeout))).await {
+            Either::Left((answer, _)) => answer,
+            Either::Right(_) => {
+                return Err(Error::RustError(format!(
+                    "RPC call {method} timed out after {:?}",
+                    self.timeout
+                )))
+            }
+        };
+        match answer {
+            Ok(Ok(result)) => Ok(serde_json::from_value(result)?),
+            Ok(Err(error)) => Err(Error::RustError(format!(
+                "RPC call {method} failed: {error}"
+            ))),
+            Err(_) => Err(Error::RustError(format!(
+                "the WebSocket closed before RPC call {method} was answered"
+            ))),
+        }
+    }
+
+    /// The number of calls waiting for their answer.
+    pub fn pending(&self) -> usize {
+        self.pending.borrow().len()
+    }
+}
+
+/// A call waiting for its answer, cancelled on the server if it's dropped before it's answered.
+struct Call<'a> {
+    client: &'a RpcClient,
+    id: u64,
+}
+
+impl Drop for Call<'_> {
+    fn drop(&mut self) {
+        // Answered calls were already removed.
+        if self.client.pending.borrow_mut().remove(&self.id).is_some() {
+            let _ = self.client.socket.send(&Frame::Cancel {
+                id: self.id,
+                cancel: true,
+            });
+        }
+    }
+}
+
+/// A call received by an [`RpcServer`].
+#[derive(Debug, Clone)]
+pub struct RpcRequest {
+    pub id: u64,
+    pub method: String,
+    params: Value,
+}
+
+impl RpcRequest {
+    /// Deserialize the parameters of the call.
+    pub fn params<P: DeserializeOwned>(&self) -> Result<P> {
+        Ok(serde_json::from_value(self.params.clone())?)
+    }
+}
+
+/// The server end of RPC calls over WebSockets made by [`RpcClient`]s, see its documentation
+/// for the protocol. A single server can answer the calls of every socket of a Durable Object,
+/// including hibernated ones.
+///
+/// ```no_run
+/// # use worker::*;
+/// # struct Game { rpc: RpcServer, state: State }
+/// # impl Game {
+/// async fn websocket_message(&self, ws: WebSocket, message: WebSocketIncomingMessage) -> Result<()> {
+///     self.rpc
+///         .handle(&ws, &message, |call| async move {
+///             match call.method.as_str() {
+///                 "players" => Ok(serde_json::json!(["alice", "bob"])),
+///                 method => Err(format!("unknown method {method}").into()),
+///             }
+///         })
+///         .await
+/// }
+/// # }
+/// ```
+#[derive(Default)]
+pub struct RpcServer {
+    in_flight: RefCell<Vec<InFlight>>,
+    serial: Cell<u64>,
+}
+
+/// A call being handled, which only the socket it came from can cancel.
+struct InFlight {
+    socket: web_sys::WebSocket,
+    id: u64,
+    /// Tells this call apart from a later call of the socket with the same id.
+    serial: u64,
+    cancel: oneshot::Sender<()>,
+}
+
+/// Forgets a call once its handling is done or dropped.
+struct Handling<'a> {
+    in_flight: &'a RefCell<Vec<InFlight>>,
+    serial: u64,
+}
+
+impl Drop for Handling<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.try_borrow_mut() {
+            in_flight.retain(|call| call.serial != self.serial);
+        }
+    }
+}
+
+impl RpcServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer the call in `message` through `ws` with the result of `handler`, or cancel the
+    /// call of `ws` that `message` asks to cancel. Messages which aren't calls are ignored. The
+    /// handler of a cancelled call is dropped, and its call isn't answered. A call whose id is
+    /// already in flight on `ws` is answered with an error without running `handler`.
+    pub async fn handle<F, Fut>(
+        &self,
+        ws: &WebSocket,
+        message: &WebSocketIncomingMessage,
+        handler: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(RpcRequest) -> Fut,
+        Fut: Future<Output = Result<Value>>,
+    {
+        let WebSocketIncomingMessage::String(text) = message else {
+            return Ok(());
+        };
+        let socket: &web_sys::WebSocket = ws.as_ref();
+        let (id, method, params) = match serde_json::from_str(text) {
+            Ok(Frame::Call { id, method, params }) => (id, method, params),
+            Ok(Frame::Cancel { id, .. }) => {
+                let mut in_flight = self.in_flight.borrow_mut();
+                if let Some(i) = in_flight.iter().position(|call| call.is(socket, id)) {
+                    let _ = in_flight.swap_remove(i).cancel.send(());
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        if self
+            .in_flight
+            .borrow()
+            .iter()
+            .any(|call| call.is(socket, id))
+        {
+            return ws.send(&Frame::Error {
+                id,
+                error: format!("call {id} is already in flight"),
+            });
+        }
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let serial = self.serial.get();
+        self.serial.set(serial + 1);
+        self.in_flight.borrow_mut().push(InFlight {
+            socket: socket.clone(),
+            id,
+            serial,
+            cancel: cancel_tx,
+        });
+        let _handling = Handling {
+            in_flight: &self.in_flight,
+            serial,
+        };
+        let request = RpcRequest { id, method, params };
+        let result = match select(pin!(handler(request)), cancel_rx).await {
+            Either::Left((result, _)) => result,
+            Either::Right((Ok(()), _)) => return Ok(()),
+            // The call was forgotten without being cancelled, so it's still answered.
+            Either::Right((Err(_), handler)) => handler.await,
+        };
+        let frame = match result {
+            Ok(result) => Frame::Result { id, result },
+            Err(e) => Frame::Error {
+                id,
+                error: e.to_string(),
+            },
+        };
+        ws.send(&frame)
+    }
+
+    /// The number of calls being handled.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.borrow().len()
+    }
+}
+
+impl InFlight {
+    fn is(&self, socket: &web_sys::WebSocket, id: u64) -> bool {
+        self.id == id && js_sys::Object::is(&self.socket, socket)
+    }
+}
+
+#[test]
+fn frames_work() {
+    let call: Frame = serde_json::from_str(r#"{"id":1,"method":"players"}"#).unwrap();
+    assert!(matches!(
+        call,
+        Frame::Call {
+            id: 1,
+            params: Value::Null,
+            ..
+        }
+    ));
+    let error: Frame = serde_json::from_str(r#"{"id":2,"error":"nope"}"#).unwrap();
+    assert!(matches!(error, Frame::Error { id: 2, .. }));
+    let cancel: Frame = serde_json::from_str(r#"{"id":3,"cancel":true}"#).unwrap();
+    assert!(matches!(cancel, Frame::Cancel { id: 3, .. }));
+    assert_eq!(
+        serde_json::to_string(&Frame::Result {
+            id: 4,
+            result: Value::Bool(true)
+        })
+        .unwrap(),
+        r#"{"id":4,"result":true}"#
+    );
+}
@@ -0,0 +1,137 @@
+use std::future::Future;
+
+use crate::{EventStream, Request, Response, Result, WebSocket, WebSocketPair};
+
+/// Accepts WebSocket upgrade requests: responds `426 Upgrade Required` to requests which aren't
+/// upgrades, negotiates the subprotocol, and hands the accepted socket to a handler running in the
+/// background.
+///
+/// ```no_run
+/// # use worker::*;
+/// # use futures_util::StreamExt;
+/// # fn example(req: Request) -> Result<Response> {
+/// WebSocketUpgrade::new()
+///     .protocols(["chat.v2", "chat.v1"])
+///     .upgrade(&req, |mut ws| async move {
+///         while let Some(event) = ws.events.next().await {
+///             if let WebsocketEvent::Message(msg) = event? {
+///                 ws.socket.send_with_str(msg.text().unwrap_or_default())?;
+///             }
+///         }
+///         Ok(())
+///     })
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketUpgrade {
+    protocols: Vec<String>,
+}
+
+/// A socket accepted by [`WebSocketUpgrade`].
+pub struct UpgradedWebSocket {
+    pub socket: WebSocket,
+    /// The events of the socket, listened to before it was accepted so that no message is missed.
+    pub events: EventStream<'static>,
+    /// The negotiated subprotocol, if any.
+    pub protocol: Option<String>,
+}
+
+impl WebSocketUpgrade {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The subprotocols the server supports, in order of preference. The most preferred one the
+    /// client offers is chosen, whatever order the client offers them in; when the client offers
+    /// none of them, the socket is accepted without a subprotocol, which the client may refuse.
+    #[must_use]
+    pub fn protocols<T: Into<String>>(mut self, protocols: impl IntoIterator<Item = T>) -> Self {
+        self.protocols.extend(protocols.into_iter().map(Into::into));
+        self
+    }
+
+    /// Whether `req` asks to be upgraded to a WebSocket.
+    pub fn is_upgrade(req: &Request) -> Result<bool> {
+        Ok(req
+            .headers()
+            .get("Upgrade")?
+            .map_or(false, |upgrade| upgrade.eq_ignore_ascii_case("websocket")))
+    }
+
+    /// The supported subprotocol the server prefers among those the client offers, if any.
+    pub fn select_protocol(&self, req: &Request) -> Result<Option<String>> {
+        let offered = req.headers().get("Sec-WebSocket-Protocol")?;
+        Ok(offered.and_then(|offered| select_protocol(&offered, &self.protocols)))
+    }
+
+    /// Accept `req` and run `handler` with the socket in the background, returning the `101`
+    /// response to send to the client. Errors of the handler are logged and close the socket with
+    /// code `1011`.
+    pub fn upgrade<F, Fut>(&self, req: &Request, handler: F) -> Result<Response>
+    where
+        F: FnOnce(UpgradedWebSocket) -> Fut + 'static,
+        Fut: Future<Output = Result<()>> + 'static,
+    {
+        if !Self::is_upgrade(req)? {
+            let mut resp = Response::error("Upgrade Required", 426)?;
+            resp.headers_mut().set("Upgrade", "websocket")?;
+            return Ok(resp);
+        }
+        let protocol = self.select_protocol(req)?;
+        let WebSocketPair { client, server } = WebSocketPair::new()?;
+        let events = server.clone().into_events()?;
+        server.accept()?;
+
+        let mut resp = Response::from_websocket(client)?;
+        if let Some(protocol) = &protocol {
+            resp.headers_mut().set("Sec-WebSocket-Protocol", protocol)?;
+        }
+        let socket = server.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let upgraded = UpgradedWebSocket {
+                socket,
+                events,
+                protocol,
+            };
+            if let Err(e) = handler(upgraded).await {
+                crate::console_error!("WebSocket handler failed: {}", e);
+                let _ = server.close(Some(1011), Some("internal error"));
+            }
+        });
+        Ok(resp)
+    }
+}
+
+impl WebSocket {
+    /// Accept `req` with [`WebSocketUpgrade`], without a subprotocol.
+    pub fn upgrade<F, Fut>(req: &Request, handler: F) -> Result<Response>
+    where
+        F: FnOnce(UpgradedWebSocket) -> Fut + 'static,
+        Fut: Future<Output = Result<()>> + 'static,
+    {
+        WebSocketUpgrade::new().upgrade(req, handler)
+    }
+}
+
+/// The first of `supported` listed in the `Sec-WebSocket-Protocol` header value `offered`.
+fn select_protocol(offered: &str, supported: &[String]) -> Option<String> {
+    let offered: Vec<&str> = offered.split(',').map(str::trim).collect();
+    supported
+        .iter()
+        .find(|protocol| offered.contains(&protocol.as_str()))
+        .cloned()
+}
+
+#[test]
+fn select_protocol_works() {
+    let supported = ["chat.v2".to_string(), "chat.v1".to_string()];
+    assert_eq!(
+        select_protocol("chat.v1, chat.v2", &supported).as_deref(),
+        Some("chat.v2")
+    );
+    assert_eq!(
+        select_protocol("chat.v1", &supported).as_deref(),
+        Some("chat.v1")
+    );
+    assert_eq!(select_protocol("mqtt", &supported), None);
+}